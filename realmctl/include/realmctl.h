@@ -0,0 +1,32 @@
+/* C API of realmctl, built with:
+ *   cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * Every function returns a JSON string, {"ok": ...} or {"error": "..."},
+ * that must be freed with realmctl_free_string. Arguments are UTF-8.
+ */
+#ifndef REALMCTL_H
+#define REALMCTL_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Create a workspace from a base with the default sharing rules */
+char *realmctl_create_workspace(const char *name, const char *base, const char *workspace_root);
+
+/* Launch a game of a config file without waiting for it: {"ok": {"pid": N}} */
+char *realmctl_launch(const char *config_path, const char *game);
+
+/* Bases and workspaces, as `realmctl list --json`; base_roots is PATH-separated or NULL */
+char *realmctl_list(const char *workspace_root, const char *base_roots);
+
+/* Fleet status, as `realmctl status --json`; config_path may be NULL */
+char *realmctl_status(const char *workspace_root, const char *config_path);
+
+void realmctl_free_string(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* REALMCTL_H */
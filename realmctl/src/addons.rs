@@ -0,0 +1,806 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::paths::long_path;
+
+/// Lockfile in an AddOns directory recording the addons installed by `addon install`
+///
+/// It lives next to the addons, so a shared AddOns directory has one lockfile for every
+/// workspace using it.
+pub const ADDON_LOCKFILE: &str = "realmctl-addons.lock";
+
+/// Prefix of the directory an archive is extracted to before its folders are moved in
+const STAGING_PREFIX: &str = ".realmctl-install-";
+
+/// Suffix of an AddOns directory parked by `launch --safe`
+const PARKED_SUFFIX: &str = ".realmctl-safe";
+
+/// Interface/AddOns of a client, next to the executable (the product directory of CASC clients)
+pub fn addons_dir(game_dir: &Path, executable: &str) -> PathBuf {
+    let client_dir = game_dir
+        .join(executable)
+        .parent()
+        .map_or_else(|| game_dir.to_path_buf(), Path::to_path_buf);
+    client_dir.join("Interface").join("AddOns")
+}
+
+/// Where [`park_addons`] moves an AddOns directory
+pub fn parked_dir(addons_dir: &Path) -> PathBuf {
+    let mut name = addons_dir.file_name().unwrap_or_default().to_os_string();
+    name.push(PARKED_SUFFIX);
+    addons_dir.with_file_name(name)
+}
+
+/// Move the AddOns directory aside so the client starts without addons
+///
+/// A shared AddOns symlink is moved as a link, its target is untouched. Returns false
+/// when there are no addons to park.
+pub fn park_addons(addons_dir: &Path) -> std::io::Result<bool> {
+    let parked = parked_dir(addons_dir);
+    if std::fs::symlink_metadata(&parked).is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+                "{} is left from an earlier --safe launch, move it back to AddOns first",
+                parked.display()
+            ),
+        ));
+    }
+    if std::fs::symlink_metadata(addons_dir).is_err() {
+        return Ok(false);
+    }
+    std::fs::rename(addons_dir, &parked)?;
+    Ok(true)
+}
+
+/// Move a parked AddOns directory back, returns false when none was parked
+///
+/// An empty AddOns directory the client created meanwhile is removed first; one with
+/// contents is kept and reported as an error so nothing is overwritten.
+pub fn restore_addons(addons_dir: &Path) -> std::io::Result<bool> {
+    let parked = parked_dir(addons_dir);
+    if std::fs::symlink_metadata(&parked).is_err() {
+        return Ok(false);
+    }
+    if let Ok(meta) = std::fs::symlink_metadata(addons_dir) {
+        let empty = meta.is_dir() && std::fs::read_dir(addons_dir)?.next().is_none();
+        if !empty {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "Both {} and {} exist, merge them by hand",
+                    addons_dir.display(),
+                    parked.display()
+                ),
+            ));
+        }
+        std::fs::remove_dir(addons_dir)?;
+    }
+    std::fs::rename(&parked, addons_dir)?;
+    Ok(true)
+}
+
+/// `## Interface:` number of a client version, e.g. 30300 for 3.3.5a (the patch level
+/// does not count)
+pub fn interface_version(client_version: &str) -> Option<u32> {
+    let mut parts = client_version.split('.');
+    let major: u32 = parts.next()?.parse().ok()?;
+    let minor: u32 = parts
+        .next()?
+        .trim_end_matches(char::is_alphabetic)
+        .parse()
+        .ok()?;
+    Some(major * 10000 + minor * 100)
+}
+
+/// Interface numbers listed by `## Interface:` lines of a `.toc` file
+pub fn toc_interfaces(content: &str) -> Vec<u32> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line
+                .trim_start_matches('\u{feff}')
+                .strip_prefix("##")?
+                .split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case("interface")
+                .then_some(value)
+        })
+        .flat_map(|value| {
+            value
+                .split(',')
+                .filter_map(|n| n.trim().parse().ok())
+                .collect::<Vec<u32>>()
+        })
+        .collect()
+}
+
+/// How an addon's interface numbers relate to the client's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compatibility {
+    /// Made for another expansion, its API calls likely fail
+    Incompatible,
+    /// Made for another patch of the same expansion, loaded only with "Load out of date AddOns"
+    OutOfDate,
+    /// No `## Interface:` line
+    Unknown,
+    Current,
+}
+
+impl Compatibility {
+    pub fn of(interfaces: &[u32], client: u32) -> Self {
+        // Flavor-specific .toc files list several numbers, the best match counts
+        interfaces
+            .iter()
+            .map(|&interface| {
+                if interface / 100 == client / 100 {
+                    Compatibility::Current
+                } else if interface / 10000 == client / 10000 {
+                    Compatibility::OutOfDate
+                } else {
+                    Compatibility::Incompatible
+                }
+            })
+            .max()
+            .unwrap_or(Compatibility::Unknown)
+    }
+}
+
+/// Interface check of one addon folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddonCheck {
+    pub folder: String,
+    pub interfaces: Vec<u32>,
+    pub compatibility: Compatibility,
+}
+
+/// Check every addon folder (one with a `.toc` file) in `addons_dir` against a client's
+/// interface number, worst first
+pub fn check_addons(addons_dir: &Path, client: u32) -> Vec<AddonCheck> {
+    let mut checks: Vec<AddonCheck> = std::fs::read_dir(addons_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|dir| {
+            let tocs: Vec<PathBuf> = std::fs::read_dir(&dir)
+                .ok()?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("toc"))
+                })
+                .collect();
+            if tocs.is_empty() {
+                return None;
+            }
+            let mut interfaces: Vec<u32> = tocs
+                .iter()
+                .filter_map(|toc| std::fs::read(toc).ok())
+                .flat_map(|bytes| toc_interfaces(&String::from_utf8_lossy(&bytes)))
+                .collect();
+            interfaces.sort();
+            interfaces.dedup();
+            Some(AddonCheck {
+                folder: dir.file_name()?.to_string_lossy().to_string(),
+                compatibility: Compatibility::of(&interfaces, client),
+                interfaces,
+            })
+        })
+        .collect();
+    checks.sort_by(|a, b| {
+        (a.compatibility, a.folder.to_lowercase()).cmp(&(b.compatibility, b.folder.to_lowercase()))
+    });
+    checks
+}
+
+/// Contents of [`ADDON_LOCKFILE`]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct AddonLock {
+    #[serde(default)]
+    pub addons: BTreeMap<String, InstalledAddon>,
+}
+
+/// One installed addon, which may consist of several folders (e.g. DBM-Core, DBM-GUI)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InstalledAddon {
+    /// URL or archive path it was installed from
+    pub source: String,
+    /// Release tag, version in the archive name, or the server's ETag for plain URLs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub folders: Vec<String>,
+    pub installed_at: u64,
+}
+
+impl AddonLock {
+    /// Name of the addon a folder was installed with
+    pub fn owner(&self, folder: &str) -> Option<&str> {
+        self.addons
+            .iter()
+            .find(|(_, addon)| addon.folders.iter().any(|f| f.eq_ignore_ascii_case(folder)))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Key of an installed addon, matched case-insensitively
+    pub fn find(&self, name: &str) -> Option<String> {
+        self.addons
+            .keys()
+            .find(|key| key.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+pub fn load_lock(addons_dir: &Path) -> Result<AddonLock> {
+    let path = addons_dir.join(ADDON_LOCKFILE);
+    match std::fs::read_to_string(&path) {
+        Ok(source) => {
+            toml::from_str(&source).with_context(|| format!("Failed to parse {}", path.display()))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(AddonLock::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+pub fn save_lock(addons_dir: &Path, lock: &AddonLock) -> Result<()> {
+    let path = addons_dir.join(ADDON_LOCKFILE);
+    std::fs::write(&path, toml::to_string_pretty(lock)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Where the addons of a game really live when that is outside the game directory,
+/// i.e. AddOns (or Interface) is a link into a shared directory
+pub fn shared_target(game_dir: &Path, addons_dir: &Path) -> Option<PathBuf> {
+    let existing = addons_dir.ancestors().find(|p| p.exists())?;
+    let resolved = existing
+        .canonicalize()
+        .ok()?
+        .join(addons_dir.strip_prefix(existing).ok()?);
+    let game_dir = game_dir.canonicalize().ok()?;
+    (!resolved.starts_with(&game_dir)).then_some(resolved)
+}
+
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("https://") || source.starts_with("http://")
+}
+
+/// A downloadable version of an addon
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: Option<String>,
+    pub download_url: String,
+}
+
+/// `owner/repo` of a GitHub repository URL such as `https://github.com/owner/repo`
+pub fn github_repo(source: &str) -> Option<String> {
+    let pattern = regex::Regex::new(
+        r"^https?://github\.com/([\w.-]+)/([\w.-]+?)(?:\.git)?(?:/releases/?)?/?$",
+    )
+    .unwrap();
+    let captures = pattern.captures(source)?;
+    Some(format!("{}/{}", &captures[1], &captures[2]))
+}
+
+/// Version number in a file name or URL, e.g. `1.4.2` in `Questie-v1.4.2.zip`
+pub fn version_tag(name: &str) -> Option<String> {
+    let file_name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    regex::Regex::new(r"\d+(?:\.\d+)+")
+        .unwrap()
+        .find_iter(file_name)
+        .last()
+        .map(|m| m.as_str().to_string())
+}
+
+/// Give up on downloads that stall, addon archives are small
+const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Fetch `url` with [`crate::fetch::curl`]
+fn curl(args: &[&str], url: &str) -> Result<String> {
+    crate::fetch::curl(url, args, FETCH_TIMEOUT).with_context(|| format!("Failed to fetch {url}"))
+}
+
+/// Latest release of a GitHub repository: its first zip asset, else the source archive
+fn github_release(repo: &str) -> Result<Release> {
+    let body = curl(
+        &["-H", "Accept: application/vnd.github+json"],
+        &format!("https://api.github.com/repos/{repo}/releases/latest"),
+    )
+    .with_context(|| format!("No release found for {repo}"))?;
+    let release: serde_json::Value = serde_json::from_str(&body)?;
+    let asset = release["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|asset| asset["browser_download_url"].as_str())
+        .find(|url| url.to_ascii_lowercase().ends_with(".zip"));
+    let download_url = asset
+        .or_else(|| release["zipball_url"].as_str())
+        .with_context(|| format!("The latest release of {repo} has no zip to download"))?;
+    Ok(Release {
+        version: release["tag_name"].as_str().map(str::to_string),
+        download_url: download_url.to_string(),
+    })
+}
+
+/// What the server reports for a plain archive URL: a version in the URL pins it,
+/// otherwise the ETag (or Last-Modified) tells versions apart
+fn url_release(url: &str) -> Result<Release> {
+    let version = match version_tag(url) {
+        Some(version) => Some(version),
+        None => {
+            let headers = curl(&["-I"], url)?;
+            // Headers of the last response after redirects win
+            let mut version = None;
+            for line in headers.lines() {
+                let Some((key, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let key = key.trim().to_ascii_lowercase();
+                if key == "etag" || (key == "last-modified" && version.is_none()) {
+                    version = Some(value.trim().trim_matches('"').to_string());
+                }
+            }
+            version
+        }
+    };
+    Ok(Release {
+        version,
+        download_url: url.to_string(),
+    })
+}
+
+/// Newest release available from an addon's source URL
+pub fn latest_release(source: &str) -> Result<Release> {
+    match github_repo(source) {
+        Some(repo) => github_release(&repo),
+        None => url_release(source),
+    }
+}
+
+/// Download `url` into `download_dir`
+fn download(url: &str, download_dir: &Path) -> Result<PathBuf> {
+    let file_name = url
+        .rsplit('/')
+        .next()
+        .filter(|name| {
+            let name = name.to_ascii_lowercase();
+            [".zip", ".tar.gz", ".tgz", ".tar"]
+                .iter()
+                .any(|ext| name.ends_with(ext))
+        })
+        .unwrap_or("addon.zip");
+    let path = download_dir.join(file_name);
+    let path_arg = path.to_string_lossy().to_string();
+    curl(&["-o", &path_arg], url)?;
+    Ok(path)
+}
+
+/// Extract a zip (or tar) archive into `dest` with the platform's unzip or tar
+fn extract_archive(archive: &Path, dest: &Path) -> Result<()> {
+    let is_zip = archive
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+    // The tar of Windows 10+ extracts zip archives as well
+    let mut command = if is_zip && !cfg!(windows) {
+        let mut unzip = std::process::Command::new("unzip");
+        unzip.arg("-qo").arg(archive).arg("-d").arg(dest);
+        unzip
+    } else {
+        let mut tar = std::process::Command::new("tar");
+        tar.arg("-xf").arg(archive).arg("-C").arg(dest);
+        tar
+    };
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {:?}", command.get_program()))?;
+    if !output.status.success() {
+        bail!(
+            "Failed to extract {}: {}",
+            archive.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Addon name from the `.toc` files in `dir`, preferring one named like the directory
+fn toc_name(dir: &Path) -> Option<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("toc"))
+        })
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect();
+    let dir_name = dir.file_name()?.to_string_lossy();
+    if let Some(name) = names
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(&dir_name))
+    {
+        return Some(name.clone());
+    }
+    // e.g. Questie-Classic.toc next to Questie.toc
+    names.sort_by_key(|name| (name.len(), name.clone()));
+    names.into_iter().next()
+}
+
+/// Addon folders in an extracted archive with the name each must have in AddOns
+///
+/// Archives made from a repository (`Questie-master/Questie.toc`) are renamed after their
+/// `.toc`, and a wrapping directory holding several addons is looked through.
+pub fn addon_folders(extracted: &Path) -> Vec<(PathBuf, String)> {
+    if let Some(name) = toc_name(extracted) {
+        return vec![(extracted.to_path_buf(), name)];
+    }
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(extracted)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    let mut folders = Vec::new();
+    for dir in dirs {
+        match toc_name(&dir) {
+            Some(name) => folders.push((dir, name)),
+            None => folders.extend(
+                std::fs::read_dir(&dir)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_dir())
+                    .filter_map(|path| Some((path.clone(), toc_name(&path)?))),
+            ),
+        }
+    }
+    folders.sort_by(|a, b| a.1.cmp(&b.1));
+    folders
+}
+
+/// Move the addon folders of an extracted archive into `addons_dir` and record them
+///
+/// Reinstalling an addon replaces its folders. A folder that exists but was not installed
+/// under `name` is only replaced with `force`.
+pub fn install_extracted(
+    addons_dir: &Path,
+    extracted: &Path,
+    source: &str,
+    version: Option<String>,
+    name: Option<&str>,
+    force: bool,
+) -> Result<(String, InstalledAddon)> {
+    let folders = addon_folders(extracted);
+    let Some((_, first)) = folders.first() else {
+        bail!("No addon (folder with a .toc file) in {source}");
+    };
+    let mut lock = load_lock(addons_dir)?;
+    let name = name.unwrap_or(first);
+    let name = lock.find(name).unwrap_or_else(|| name.to_string());
+
+    for (_, folder) in &folders {
+        match lock.owner(folder) {
+            Some(owner) if owner == name => {}
+            Some(owner) if !force => {
+                bail!("{folder} belongs to the addon {owner}, remove it first or pass --force")
+            }
+            None if addons_dir.join(folder).exists() && !force => bail!(
+                "{} already exists and was not installed by realmctl, pass --force to replace it",
+                addons_dir.join(folder).display()
+            ),
+            _ => {}
+        }
+    }
+
+    // Folders of the previous version the new one no longer has go as well
+    let mut stale: Vec<String> = lock
+        .addons
+        .remove(&name)
+        .map(|addon| addon.folders)
+        .unwrap_or_default();
+    stale.extend(folders.iter().map(|(_, folder)| folder.clone()));
+    for folder in &stale {
+        remove_folder(&addons_dir.join(folder))?;
+    }
+    for addon in lock.addons.values_mut() {
+        addon
+            .folders
+            .retain(|f| !stale.iter().any(|s| s.eq_ignore_ascii_case(f)));
+    }
+    lock.addons.retain(|_, addon| !addon.folders.is_empty());
+
+    for (dir, folder) in &folders {
+        let target = addons_dir.join(folder);
+        std::fs::rename(dir, &target)
+            .with_context(|| format!("Failed to move {folder} into {}", addons_dir.display()))?;
+    }
+    let installed = InstalledAddon {
+        source: source.to_string(),
+        version,
+        folders: folders.into_iter().map(|(_, folder)| folder).collect(),
+        installed_at: crate::state::now(),
+    };
+    lock.addons.insert(name.clone(), installed.clone());
+    save_lock(addons_dir, &lock)?;
+    Ok((name, installed))
+}
+
+/// Install an addon into `addons_dir`, see [`install_extracted`]
+///
+/// `source` is an archive path, a URL of an archive or a GitHub repository, whose latest
+/// release is installed.
+pub fn install_addon(
+    addons_dir: &Path,
+    source: &str,
+    name: Option<&str>,
+    force: bool,
+) -> Result<(String, InstalledAddon)> {
+    let release = if is_url(source) {
+        latest_release(source)?
+    } else {
+        let path = crate::paths::expand(source);
+        if !path.is_file() {
+            bail!("No such archive: {}", path.display());
+        }
+        Release {
+            version: version_tag(source),
+            download_url: path.to_string_lossy().to_string(),
+        }
+    };
+    install_release(addons_dir, source, &release, name, force)
+}
+
+fn install_release(
+    addons_dir: &Path,
+    source: &str,
+    release: &Release,
+    name: Option<&str>,
+    force: bool,
+) -> Result<(String, InstalledAddon)> {
+    std::fs::create_dir_all(addons_dir)
+        .with_context(|| format!("Failed to create {}", addons_dir.display()))?;
+    // Extracting next to the addons keeps the final move a rename on one filesystem
+    let staging = addons_dir.join(format!("{STAGING_PREFIX}{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(long_path(&staging));
+    let download_dir = staging.join("download");
+    let extracted = staging.join("extracted");
+    std::fs::create_dir_all(&download_dir)?;
+    std::fs::create_dir_all(&extracted)?;
+    let archive = if is_url(&release.download_url) {
+        download(&release.download_url, &download_dir)
+    } else {
+        Ok(PathBuf::from(&release.download_url))
+    };
+    let result = archive
+        .and_then(|archive| extract_archive(&archive, &extracted))
+        .and_then(|()| {
+            let version = release.version.clone();
+            install_extracted(addons_dir, &extracted, source, version, name, force)
+        });
+    let _ = std::fs::remove_dir_all(long_path(&staging));
+    result
+}
+
+/// Where [`update_addon`] keeps the previous version of each addon, next to AddOns
+pub fn backup_dir(addons_dir: &Path, name: &str) -> PathBuf {
+    let mut dir_name = addons_dir.file_name().unwrap_or_default().to_os_string();
+    dir_name.push(".realmctl-backup");
+    addons_dir.with_file_name(dir_name).join(name)
+}
+
+/// Install `release` over an installed addon, moving its folders to [`backup_dir`] first
+///
+/// The backup replaces the one of the previous update and is moved back when installing
+/// fails.
+pub fn update_addon(addons_dir: &Path, name: &str, release: &Release) -> Result<InstalledAddon> {
+    let lock = load_lock(addons_dir)?;
+    let installed = lock
+        .addons
+        .get(name)
+        .with_context(|| format!("{name} was not installed by realmctl"))?;
+    let backup = backup_dir(addons_dir, name);
+    remove_folder(&backup)?;
+    std::fs::create_dir_all(&backup)
+        .with_context(|| format!("Failed to create {}", backup.display()))?;
+    let mut moved = Vec::new();
+    for folder in &installed.folders {
+        let path = addons_dir.join(folder);
+        if path.exists() {
+            std::fs::rename(&path, backup.join(folder))
+                .with_context(|| format!("Failed to back up {}", path.display()))?;
+            moved.push(folder);
+        }
+    }
+    match install_release(addons_dir, &installed.source, release, Some(name), false) {
+        Ok((_, updated)) => Ok(updated),
+        Err(e) => {
+            for folder in moved {
+                let _ = remove_folder(&addons_dir.join(folder));
+                let _ = std::fs::rename(backup.join(folder), addons_dir.join(folder));
+            }
+            save_lock(addons_dir, &lock)?;
+            Err(e)
+        }
+    }
+}
+
+/// Remove an installed addon's folders and its lockfile entry
+pub fn remove_addon(addons_dir: &Path, name: &str) -> Result<(String, InstalledAddon)> {
+    let mut lock = load_lock(addons_dir)?;
+    let Some(key) = lock.find(name) else {
+        bail!(
+            "{name} was not installed by realmctl in {}",
+            addons_dir.display()
+        );
+    };
+    let addon = lock.addons.remove(&key).expect("key found above");
+    for folder in &addon.folders {
+        remove_folder(&addons_dir.join(folder))?;
+    }
+    save_lock(addons_dir, &lock)?;
+    Ok((key, addon))
+}
+
+/// Folders in `addons_dir` no lockfile entry accounts for
+pub fn unmanaged_folders(addons_dir: &Path, lock: &AddonLock) -> Vec<String> {
+    let mut folders: Vec<String> = std::fs::read_dir(addons_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|folder| !folder.starts_with('.') && lock.owner(folder).is_none())
+        .collect();
+    folders.sort();
+    folders
+}
+
+fn remove_folder(path: &Path) -> Result<()> {
+    let long = long_path(path);
+    let result = match std::fs::symlink_metadata(&long) {
+        Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(&long),
+        Ok(_) => std::fs::remove_file(&long),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    };
+    result.with_context(|| format!("Failed to remove {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_park_and_restore_addons() {
+        let temp = TempDir::new().unwrap();
+        let addons = addons_dir(temp.path(), "Wow.exe");
+        assert_eq!(addons, temp.path().join("Interface/AddOns"));
+        assert!(!park_addons(&addons).unwrap());
+
+        std::fs::create_dir_all(addons.join("Questie")).unwrap();
+        assert!(park_addons(&addons).unwrap());
+        assert!(!addons.exists());
+        assert!(parked_dir(&addons).join("Questie").is_dir());
+
+        // The client recreates an empty AddOns directory while running
+        std::fs::create_dir(&addons).unwrap();
+        assert!(park_addons(&addons).is_err());
+        assert!(restore_addons(&addons).unwrap());
+        assert!(addons.join("Questie").is_dir());
+        assert!(!restore_addons(&addons).unwrap());
+
+        // Addons installed during the session are not overwritten
+        assert!(park_addons(&addons).unwrap());
+        std::fs::create_dir_all(addons.join("New")).unwrap();
+        assert!(restore_addons(&addons).is_err());
+        assert!(parked_dir(&addons).join("Questie").is_dir());
+    }
+
+    #[test]
+    fn test_install_and_remove_addon() {
+        let temp = TempDir::new().unwrap();
+        let addons = temp.path().join("AddOns");
+        std::fs::create_dir_all(&addons).unwrap();
+
+        // A repository archive of an addon with two folders
+        let extracted = temp.path().join("extracted");
+        for (dir, toc) in [
+            ("DBM-main/DBM-Core", "DBM-Core"),
+            ("DBM-main/DBM-GUI", "DBM-GUI"),
+        ] {
+            std::fs::create_dir_all(extracted.join(dir)).unwrap();
+            std::fs::write(extracted.join(dir).join(format!("{toc}.toc")), "").unwrap();
+        }
+        let (name, installed) =
+            install_extracted(&addons, &extracted, "dbm.zip", None, None, false).unwrap();
+        assert_eq!(name, "DBM-Core");
+        assert_eq!(installed.folders, vec!["DBM-Core", "DBM-GUI"]);
+        assert!(addons.join("DBM-GUI/DBM-GUI.toc").is_file());
+
+        // A folder not installed by realmctl is left alone without --force
+        let questie = temp.path().join("questie/Questie-master");
+        std::fs::create_dir_all(&questie).unwrap();
+        std::fs::write(questie.join("Questie.toc"), "").unwrap();
+        std::fs::create_dir_all(addons.join("Questie")).unwrap();
+        let extracted = temp.path().join("questie");
+        assert!(install_extracted(&addons, &extracted, "questie.zip", None, None, false).is_err());
+        install_extracted(&addons, &extracted, "questie.zip", None, None, true).unwrap();
+        let lock = load_lock(&addons).unwrap();
+        assert_eq!(lock.owner("Questie"), Some("Questie"));
+        assert!(unmanaged_folders(&addons, &lock).is_empty());
+
+        remove_addon(&addons, "dbm-core").unwrap();
+        assert!(!addons.join("DBM-Core").exists());
+        assert!(!addons.join("DBM-GUI").exists());
+        assert!(remove_addon(&addons, "DBM-Core").is_err());
+        assert_eq!(load_lock(&addons).unwrap().addons.len(), 1);
+    }
+
+    #[test]
+    fn test_addon_sources() {
+        assert_eq!(
+            github_repo("https://github.com/Questie/Questie"),
+            Some("Questie/Questie".to_string())
+        );
+        assert_eq!(
+            github_repo("https://github.com/Zidras/DBM-Warmane.git"),
+            Some("Zidras/DBM-Warmane".to_string())
+        );
+        assert_eq!(github_repo("https://github.com/a/b/archive/main.zip"), None);
+        assert_eq!(
+            version_tag("https://example.org/v1.4/Questie-v1.4.2.zip"),
+            Some("1.4.2".to_string())
+        );
+        assert_eq!(version_tag("~/Downloads/pfQuest.zip"), None);
+    }
+
+    #[test]
+    fn test_check_addon_interfaces() {
+        assert_eq!(interface_version("3.3.5a"), Some(30300));
+        assert_eq!(interface_version("1.12"), Some(11200));
+        assert_eq!(interface_version("casc"), None);
+
+        let temp = TempDir::new().unwrap();
+        let addons = temp.path();
+        for (folder, toc) in [
+            (
+                "Current",
+                "\u{feff}## Interface: 30300\r\n## Title: Current",
+            ),
+            ("Older", "## Interface: 30200"),
+            ("Retail", "## Interface: 100200, 30400"),
+            ("Classic", "## Interface: 11503"),
+            ("NoVersion", "## Title: NoVersion"),
+        ] {
+            std::fs::create_dir_all(addons.join(folder)).unwrap();
+            std::fs::write(addons.join(folder).join(format!("{folder}.toc")), toc).unwrap();
+        }
+        std::fs::create_dir_all(addons.join("Textures")).unwrap();
+
+        let checks: Vec<(String, Compatibility)> = check_addons(addons, 30300)
+            .into_iter()
+            .map(|check| (check.folder, check.compatibility))
+            .collect();
+        assert_eq!(
+            checks,
+            vec![
+                ("Classic".to_string(), Compatibility::Incompatible),
+                ("Older".to_string(), Compatibility::OutOfDate),
+                ("Retail".to_string(), Compatibility::OutOfDate),
+                ("NoVersion".to_string(), Compatibility::Unknown),
+                ("Current".to_string(), Compatibility::Current),
+            ]
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::base::{FileRole, Profile};
+use crate::paths::long_path;
+use crate::retention::{RetainedItem, RetentionPolicy, expired};
+
+/// Extension of backup archives, gzipped like `delete --archive`
+const EXTENSION: &str = ".tar.gz";
+
+/// Commands that can back up a workspace before changing it
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupTrigger {
+    Clean,
+    Fix,
+    Upgrade,
+}
+
+impl BackupTrigger {
+    pub fn name(self) -> &'static str {
+        match self {
+            BackupTrigger::Clean => "clean",
+            BackupTrigger::Fix => "fix",
+            BackupTrigger::Upgrade => "upgrade",
+        }
+    }
+}
+
+/// The `[backup]` section of config.toml
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BackupConfig {
+    /// Where backups are written, `backups/` in the state directory by default
+    pub dir: Option<String>,
+    /// Commands that back up the workspace before changing it
+    #[serde(default)]
+    pub before: Vec<BackupTrigger>,
+}
+
+impl BackupConfig {
+    pub fn from_value(value: toml::Value) -> Result<Self, String> {
+        if let Some(key) = ["keep", "max_age_days", "max_size_mb"]
+            .into_iter()
+            .find(|key| value.get(key).is_some())
+        {
+            return Err(format!("[backup] {key} belongs in [retention.backups]"));
+        }
+        value.try_into().map_err(|e| format!("[backup] {e}"))
+    }
+
+    /// Directory holding a subdirectory of backups per workspace
+    pub fn dir(&self) -> PathBuf {
+        match &self.dir {
+            Some(dir) => crate::paths::resolve_in_root(crate::paths::expand(dir)),
+            None => crate::state::state_dir().join("backups"),
+        }
+    }
+}
+
+/// Limits on the backups of one workspace, `[retention.backups]`
+pub fn backup_policy(config_path: &str) -> RetentionPolicy {
+    crate::retention::load_retention(config_path).policy(&crate::retention::BACKUPS)
+}
+
+/// Read the `[backup]` section of a config file
+///
+/// A missing file or section means defaults; an invalid section is reported and ignored.
+pub fn load_backup(config_path: &str) -> BackupConfig {
+    let path = crate::paths::expand(config_path);
+    let Some(section) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|source| toml::from_str::<toml::Table>(&source).ok())
+        .and_then(|mut table| table.remove("backup"))
+    else {
+        return BackupConfig::default();
+    };
+    BackupConfig::from_value(section).unwrap_or_else(|e| {
+        tracing::warn!("⚠ Ignoring invalid [backup] section: {e}");
+        BackupConfig::default()
+    })
+}
+
+/// Files of a workspace that go into a backup, relative to it
+///
+/// UserConfig (WTF, addons) is taken wherever it lives, also behind links to shared
+/// directories; UserMedia only when stored in the workspace itself. Base data, caches and
+/// everything else are left out.
+pub fn backup_files(workspace_path: &Path, profile: &Profile) -> Vec<String> {
+    let workspace_path = &long_path(workspace_path);
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![workspace_path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        // Shared directories may link back into each other
+        if !visited.insert(std::fs::canonicalize(&dir).unwrap_or_else(|_| dir.clone())) {
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let rel_path = path
+                .strip_prefix(workspace_path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let role = profile.classify_path(&rel_path);
+            if file_type.is_symlink() {
+                if role != FileRole::UserConfig {
+                    continue;
+                }
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path.is_file() {
+                    files.push(rel_path);
+                }
+            } else if file_type.is_dir() {
+                if role != FileRole::Ephemeral {
+                    pending.push(path);
+                }
+            } else if matches!(role, FileRole::UserConfig | FileRole::UserMedia) {
+                files.push(rel_path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// A backup archive of a workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct Backup {
+    pub workspace: String,
+    pub path: PathBuf,
+    /// Unix timestamp the backup was made at
+    pub created: u64,
+    pub size_bytes: u64,
+}
+
+impl Backup {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Write a new backup of a workspace, named after its config entry
+pub fn create_backup(
+    name: &str,
+    workspace_path: &Path,
+    profile: &Profile,
+    config: &BackupConfig,
+) -> Result<Backup> {
+    let files = backup_files(workspace_path, profile);
+    if files.is_empty() {
+        bail!("{name} has no settings, addons or screenshots to back up");
+    }
+    let dir = config.dir().join(name);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    // Two backups within a second (an automatic one right before a manual one) get
+    // consecutive timestamps
+    let mut created = crate::state::now();
+    while dir.join(format!("{name}-{created}{EXTENSION}")).exists() {
+        created += 1;
+    }
+    let path = dir.join(format!("{name}-{created}{EXTENSION}"));
+    let partial = dir.join(format!("{name}-{created}{EXTENSION}.partial"));
+    crate::workspace::archive_files(workspace_path, &files, &partial).inspect_err(|_| {
+        let _ = std::fs::remove_file(&partial);
+    })?;
+    std::fs::rename(&partial, &path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(Backup {
+        workspace: name.to_string(),
+        size_bytes: path.metadata().map_or(0, |meta| meta.len()),
+        path,
+        created,
+    })
+}
+
+/// Backups of one workspace, or of all of them, oldest first
+pub fn list_backups(config: &BackupConfig, name: Option<&str>) -> Vec<Backup> {
+    let root = config.dir();
+    let names: Vec<String> = match name {
+        Some(name) => vec![name.to_string()],
+        None => std::fs::read_dir(&root)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect(),
+    };
+
+    let mut backups = Vec::new();
+    for name in names {
+        let Ok(entries) = std::fs::read_dir(root.join(&name)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(stamp) = file_name
+                .strip_prefix(&format!("{name}-"))
+                .and_then(|rest| rest.strip_suffix(EXTENSION))
+            else {
+                continue;
+            };
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let created = stamp.parse().unwrap_or_else(|_| {
+                meta.modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map_or(0, |age| age.as_secs())
+            });
+            backups.push(Backup {
+                workspace: name.clone(),
+                path: entry.path(),
+                created,
+                size_bytes: meta.len(),
+            });
+        }
+    }
+    backups.sort_by(|a, b| (&a.workspace, a.created).cmp(&(&b.workspace, b.created)));
+    backups
+}
+
+/// Remove the backups of a workspace beyond a policy's limits, or list them on a dry run;
+/// the newest always stays
+pub fn prune_backups(
+    config: &BackupConfig,
+    name: &str,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+) -> Result<Vec<Backup>> {
+    let backups = list_backups(config, Some(name));
+    let Some(newest) = backups.last().map(|backup| backup.path.clone()) else {
+        return Ok(Vec::new());
+    };
+    let items = backups
+        .iter()
+        .map(|backup| RetainedItem {
+            path: backup.path.clone(),
+            size: backup.size_bytes,
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(backup.created),
+        })
+        .collect();
+    let mut removed = Vec::new();
+    for item in expired(items, policy, SystemTime::now()) {
+        if item.path == newest {
+            continue;
+        }
+        if !dry_run {
+            std::fs::remove_file(&item.path)
+                .with_context(|| format!("Failed to remove {}", item.path.display()))?;
+        }
+        removed.extend(
+            backups
+                .iter()
+                .find(|backup| backup.path == item.path)
+                .cloned(),
+        );
+    }
+    Ok(removed)
+}
+
+/// Find a backup of a workspace by file name or path, the newest one for `None`
+pub fn find_backup(config: &BackupConfig, name: &str, backup: Option<&str>) -> Result<Backup> {
+    let backups = list_backups(config, Some(name));
+    let found = match backup {
+        None => backups.last().cloned(),
+        Some(wanted) => backups
+            .iter()
+            .find(|backup| {
+                backup.file_name() == wanted
+                    || backup.file_name() == format!("{wanted}{EXTENSION}")
+                    || backup.path == Path::new(wanted)
+            })
+            .cloned(),
+    };
+    match (found, backup) {
+        (Some(backup), _) => Ok(backup),
+        (None, Some(wanted)) if Path::new(wanted).is_file() => Ok(Backup {
+            workspace: name.to_string(),
+            path: PathBuf::from(wanted),
+            created: 0,
+            size_bytes: std::fs::metadata(wanted).map_or(0, |meta| meta.len()),
+        }),
+        (None, Some(wanted)) => bail!("No backup '{wanted}' of {name}, see `realmctl backup list`"),
+        (None, None) => bail!("{name} has no backups in {}", config.dir().display()),
+    }
+}
+
+/// Files in a backup archive, relative to the workspace
+pub fn backup_contents(archive: &Path) -> Result<Vec<String>> {
+    let output = std::process::Command::new("tar")
+        .arg("-tzf")
+        .arg(archive)
+        .output()
+        .context("Failed to run tar")?;
+    if !output.status.success() {
+        bail!(
+            "tar failed to read {} ({})",
+            archive.display(),
+            output.status
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.ends_with('/'))
+        .map(String::from)
+        .collect())
+}
+
+/// Extract a backup into a workspace, replacing the files it holds
+///
+/// Files through links to shared directories are written to the shared directory.
+pub fn restore_backup(archive: &Path, workspace_path: &Path) -> Result<()> {
+    let status = std::process::Command::new("tar")
+        .arg("-xzf")
+        .arg(archive)
+        .arg("-C")
+        .arg(workspace_path)
+        .status()
+        .context("Failed to run tar")?;
+    if !status.success() {
+        bail!("tar failed to restore {} ({status})", archive.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_backup_and_restore() {
+        let temp = TempDir::new().unwrap();
+        let ws = temp.path().join("main");
+        let shared = temp.path().join(".shared/global/Screenshots");
+        std::fs::create_dir_all(ws.join("WTF/Account/ME")).unwrap();
+        std::fs::create_dir_all(ws.join("Data")).unwrap();
+        std::fs::create_dir_all(ws.join("Cache/WDB")).unwrap();
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::write(ws.join("WTF/Config.wtf"), "SET gxWindow \"1\"").unwrap();
+        std::fs::write(ws.join("WTF/Account/ME/macros-cache.txt"), "macros").unwrap();
+        std::fs::write(ws.join("Data/common.MPQ"), "base").unwrap();
+        std::fs::write(ws.join("Cache/WDB/itemcache.wdb"), "cache").unwrap();
+        std::fs::write(shared.join("shot.jpg"), "shared shot").unwrap();
+        std::os::unix::fs::symlink(&shared, ws.join("Screenshots")).unwrap();
+        // Interface shared as well: UserConfig is followed into shared directories
+        let addons = temp.path().join(".shared/base/Interface");
+        std::fs::create_dir_all(addons.join("AddOns/Questie")).unwrap();
+        std::fs::write(addons.join("AddOns/Questie/Questie.toc"), "toc").unwrap();
+        std::os::unix::fs::symlink(&addons, ws.join("Interface")).unwrap();
+
+        let profile = Profile::chromie_335a();
+        assert_eq!(
+            backup_files(&ws, &profile),
+            vec![
+                "Interface/AddOns/Questie/Questie.toc",
+                "WTF/Account/ME/macros-cache.txt",
+                "WTF/Config.wtf",
+            ]
+        );
+
+        let config = BackupConfig {
+            dir: Some(temp.path().join("backups").to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let policy = RetentionPolicy {
+            keep: Some(2),
+            ..Default::default()
+        };
+        let first = create_backup("main", &ws, &profile, &config).unwrap();
+        let second = create_backup("main", &ws, &profile, &config).unwrap();
+        let third = create_backup("main", &ws, &profile, &config).unwrap();
+        assert!(first.created < second.created && second.created < third.created);
+        assert_eq!(list_backups(&config, None).len(), 3);
+        let planned = prune_backups(&config, "main", &policy, true).unwrap();
+        assert_eq!(list_backups(&config, None).len(), 3);
+        let removed = prune_backups(&config, "main", &policy, false).unwrap();
+        assert_eq!(removed.len(), planned.len());
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].path, first.path);
+        assert_eq!(find_backup(&config, "main", None).unwrap().path, third.path);
+        assert_eq!(
+            find_backup(&config, "main", Some(&second.file_name()))
+                .unwrap()
+                .path,
+            second.path
+        );
+        assert!(find_backup(&config, "main", Some("nope")).is_err());
+
+        std::fs::write(ws.join("WTF/Config.wtf"), "broken").unwrap();
+        std::fs::remove_file(addons.join("AddOns/Questie/Questie.toc")).unwrap();
+        assert_eq!(backup_contents(&third.path).unwrap().len(), 3);
+        restore_backup(&third.path, &ws).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(ws.join("WTF/Config.wtf")).unwrap(),
+            "SET gxWindow \"1\""
+        );
+        assert!(addons.join("AddOns/Questie/Questie.toc").is_file());
+        assert!(ws.join("Interface").is_symlink());
+    }
+
+    #[test]
+    fn test_backup_config() {
+        let config = BackupConfig::from_value(toml::Value::Table(
+            toml::from_str("before = [\"clean\", \"upgrade\"]").unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(
+            config.before,
+            vec![BackupTrigger::Clean, BackupTrigger::Upgrade]
+        );
+        // Limits are a retention category like the others
+        assert_eq!(
+            BackupConfig::from_value(toml::Value::Table(toml::from_str("keep = 3").unwrap())),
+            Err("[backup] keep belongs in [retention.backups]".to_string())
+        );
+        let retention = crate::retention::RetentionConfig::default();
+        assert_eq!(retention.policy(&crate::retention::BACKUPS).keep, Some(10));
+        assert!(
+            BackupConfig::from_value(toml::Value::Table(
+                toml::from_str("before = [\"launch\"]").unwrap()
+            ))
+            .is_err()
+        );
+    }
+}
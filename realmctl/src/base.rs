@@ -1,8 +1,10 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+use crate::progress::Progress;
+
 /// Role assigned to each file/directory in the WoW client
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum FileRole {
@@ -18,25 +20,80 @@ pub enum FileRole {
     UserConfig,
     /// Temporary files that can be deleted (Cache, Logs, Errors)
     Ephemeral,
+    /// Files realmctl itself rewrites (realmlist.wtf, manifest.toml, workspace.toml)
+    Managed,
     /// Other files not specifically classified
     Other,
 }
 
+impl FileRole {
+    /// Parse a role from its name as written in manifests, e.g. `"BaseData"`
+    pub fn from_name(name: &str) -> Option<Self> {
+        toml::Value::String(name.to_string()).try_into().ok()
+    }
+}
+
 /// Manifest describing a WoW base installation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Every key is optional so manifests from older releases or other tools still load.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BaseManifest {
     /// Profile name used for this base
+    #[serde(default)]
     pub profile: String,
     /// Base directory path
+    #[serde(default)]
     pub base_path: PathBuf,
     /// Timestamp when base was created
+    #[serde(default)]
     pub created_at: String,
-    /// Map of relative paths to their roles
-    pub file_roles: HashMap<String, FileRole>,
+    /// Map of relative paths to their roles, sorted so manifest.toml diffs stay small
+    #[serde(default, deserialize_with = "roles_or_other")]
+    pub file_roles: BTreeMap<String, FileRole>,
     /// Checksums for immutable files (BaseData)
-    pub checksums: HashMap<String, String>,
+    #[serde(default)]
+    pub checksums: BTreeMap<String, String>,
+    /// Algorithm of `checksums`, not recorded by releases that only knew crc32
+    #[serde(default)]
+    pub hash_algorithm: Option<String>,
     /// Version/notes
+    #[serde(default)]
     pub version: Option<String>,
+    /// Client locale (e.g. enUS) detected when the base was scanned
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+impl BaseManifest {
+    /// Algorithm the checksums were computed with
+    pub fn checksum_algorithm(&self) -> &str {
+        self.hash_algorithm.as_deref().unwrap_or("crc32")
+    }
+
+    /// BLAKE3 digest of the file roles and checksums
+    ///
+    /// Rescanning an unchanged base gives the same digest, new timestamps and paths aside.
+    pub fn digest(&self) -> String {
+        let mut hasher: Box<dyn ContentHasher> = Box::new(blake3::Hasher::new());
+        hasher.update(self.checksum_algorithm().as_bytes());
+        for (rel_path, role) in &self.file_roles {
+            let checksum = self.checksums.get(rel_path).map_or("", String::as_str);
+            hasher.update(format!("\n{rel_path}\t{role:?}\t{checksum}").as_bytes());
+        }
+        hasher.finish()
+    }
+}
+
+/// Roles by name, with names this release doesn't know (newer releases, other tools) as `Other`
+fn roles_or_other<'de, D>(deserializer: D) -> Result<BTreeMap<String, FileRole>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let names = BTreeMap::<String, String>::deserialize(deserializer)?;
+    Ok(names
+        .into_iter()
+        .map(|(path, name)| (path, FileRole::from_name(&name).unwrap_or(FileRole::Other)))
+        .collect())
 }
 
 /// Profile defining rules for a WoW version
@@ -48,8 +105,33 @@ pub struct Profile {
     pub required_dirs: Vec<String>,
     pub role_rules: Vec<RoleRule>,
     pub warnings: Vec<WarningRule>,
+    /// Glob patterns for files and directories that can always be deleted (logs, caches)
+    #[serde(default)]
+    pub ephemeral_patterns: Vec<String>,
+    /// Glob patterns for the realm-specific WDB cache, only removed on request
+    #[serde(default)]
+    pub wdb_patterns: Vec<String>,
+    /// Glob patterns for client files realmctl rewrites, besides its own [`TOOL_FILES`]
+    #[serde(default)]
+    pub managed_patterns: Vec<String>,
+    /// Rhai script with `classify(path)` and `warn(path)` functions, for rules regexes
+    /// can't express (needs the `scripting` feature)
+    #[serde(default)]
+    pub script: Option<String>,
 }
 
+/// Files written by realmctl in bases and workspaces
+pub const TOOL_FILES: &[&str] = &[
+    "manifest.toml",
+    "workspace.toml",
+    "workspace.log",
+    crate::SESSION_LOG,
+    crate::instance::LAUNCH_LOCK,
+    PROGRESS_FILE,
+    BASE_SNAPSHOT,
+    crate::realm::HISTORY_FILE,
+];
+
 /// Rule for assigning roles to files
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoleRule {
@@ -120,21 +202,6 @@ impl Profile {
                     role: FileRole::UserConfig,
                     is_regex: true,
                 },
-                RoleRule {
-                    pattern: r"^Cache($|/)".to_string(),
-                    role: FileRole::Ephemeral,
-                    is_regex: true,
-                },
-                RoleRule {
-                    pattern: r"^Logs($|/)".to_string(),
-                    role: FileRole::Ephemeral,
-                    is_regex: true,
-                },
-                RoleRule {
-                    pattern: r"^Errors($|/)".to_string(),
-                    role: FileRole::Ephemeral,
-                    is_regex: true,
-                },
             ],
             warnings: vec![
                 WarningRule {
@@ -150,6 +217,21 @@ impl Profile {
                     message: "Errors directory present in base - should be ephemeral".to_string(),
                 },
             ],
+            ephemeral_patterns: vec![
+                "Cache".to_string(),
+                "Logs".to_string(),
+                "Errors".to_string(),
+            ],
+            wdb_patterns: vec![
+                "Cache/WDB".to_string(),
+                "Data/*.wdb".to_string(),
+                "Data/*/*.wdb".to_string(),
+            ],
+            managed_patterns: vec![
+                "realmlist.wtf".to_string(),
+                "Data/*/realmlist.wtf".to_string(),
+            ],
+            script: None,
         }
     }
 
@@ -194,23 +276,90 @@ impl Profile {
                     role: FileRole::UserConfig,
                     is_regex: true,
                 },
+            ],
+            warnings: vec![
+                WarningRule {
+                    pattern: "Logs".to_string(),
+                    message: "Logs directory present in base - should be ephemeral".to_string(),
+                },
+                WarningRule {
+                    pattern: "Errors".to_string(),
+                    message: "Errors directory present in base - should be ephemeral".to_string(),
+                },
+            ],
+            ephemeral_patterns: vec!["Logs".to_string(), "Errors".to_string()],
+            wdb_patterns: vec![
+                "WDB".to_string(),
+                "Data/*.wdb".to_string(),
+                "Data/*/*.wdb".to_string(),
+            ],
+            managed_patterns: vec!["realmlist.wtf".to_string()],
+            script: None,
+        }
+    }
+
+    /// Get a builtin Burning Crusade 2.4.3 profile
+    ///
+    /// Locale MPQs live in `Data/<locale>/`, next to the realmlist.wtf the client reads.
+    pub fn tbc_243() -> Self {
+        let locales = crate::locale::KNOWN_LOCALES.join("|");
+        Profile {
+            name: "tbc-2.4.3".to_string(),
+            version: "2.4.3".to_string(),
+            required_files: vec![
+                "Wow.exe".to_string(),
+                "Data/common.MPQ".to_string(),
+                "Data/expansion.MPQ".to_string(),
+                "Data/*/realmlist.wtf".to_string(),
+            ],
+            required_dirs: vec!["Data".to_string()],
+            role_rules: vec![
+                RoleRule {
+                    pattern: "Wow.exe".to_string(),
+                    role: FileRole::Executable,
+                    is_regex: false,
+                },
+                RoleRule {
+                    pattern: r"^Data/(common|expansion)\.MPQ$".to_string(),
+                    role: FileRole::BaseData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^Data/patch.*\.MPQ$".to_string(),
+                    role: FileRole::MutableData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: format!(r"^Data/({locales})/patch-.*\.MPQ$"),
+                    role: FileRole::MutableData,
+                    is_regex: true,
+                },
                 RoleRule {
-                    pattern: r"^Logs($|/)".to_string(),
-                    role: FileRole::Ephemeral,
+                    pattern: format!(r"^Data/({locales})/.*\.MPQ$"),
+                    role: FileRole::BaseData,
                     is_regex: true,
                 },
                 RoleRule {
-                    pattern: r"^Errors($|/)".to_string(),
-                    role: FileRole::Ephemeral,
+                    pattern: r"^Screenshots($|/)".to_string(),
+                    role: FileRole::UserMedia,
                     is_regex: true,
                 },
                 RoleRule {
-                    pattern: r"^WDB($|/)".to_string(),
-                    role: FileRole::Ephemeral,
+                    pattern: r"^WTF($|/)".to_string(),
+                    role: FileRole::UserConfig,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^Interface($|/)".to_string(),
+                    role: FileRole::UserConfig,
                     is_regex: true,
                 },
             ],
             warnings: vec![
+                WarningRule {
+                    pattern: "Cache".to_string(),
+                    message: "Cache directory present in base - should be ephemeral".to_string(),
+                },
                 WarningRule {
                     pattern: "Logs".to_string(),
                     message: "Logs directory present in base - should be ephemeral".to_string(),
@@ -220,14 +369,320 @@ impl Profile {
                     message: "Errors directory present in base - should be ephemeral".to_string(),
                 },
             ],
+            ephemeral_patterns: vec![
+                "Cache".to_string(),
+                "Logs".to_string(),
+                "Errors".to_string(),
+            ],
+            wdb_patterns: vec![
+                "Cache/WDB".to_string(),
+                "Data/*.wdb".to_string(),
+                "Data/*/*.wdb".to_string(),
+            ],
+            managed_patterns: vec![
+                "realmlist.wtf".to_string(),
+                "Data/*/realmlist.wtf".to_string(),
+            ],
+            script: None,
         }
     }
 
+    /// Get a builtin Cataclysm 4.3.4 profile
+    pub fn cata_434() -> Self {
+        Profile::mpq_434_plus("cata-4.3.4", "4.3.4", "Data/expansion3.MPQ")
+    }
+
+    /// Get a builtin Mists of Pandaria 5.4.8 profile
+    pub fn mop_548() -> Self {
+        Profile::mpq_434_plus("mop-5.4.8", "5.4.8", "Data/expansion4.MPQ")
+    }
+
+    /// Layout shared by the last MPQ clients (before CASC in 6.0): `Wow.exe` with an optional
+    /// `Wow-64.exe`, base archives directly in `Data`, `wow-update-*.MPQ` patches and locale
+    /// archives plus realmlist.wtf in `Data/<locale>/`
+    fn mpq_434_plus(name: &str, version: &str, expansion_mpq: &str) -> Self {
+        let locales = crate::locale::KNOWN_LOCALES.join("|");
+        Profile {
+            name: name.to_string(),
+            version: version.to_string(),
+            required_files: vec![
+                "Wow.exe".to_string(),
+                "Data/art.MPQ".to_string(),
+                expansion_mpq.to_string(),
+                "Data/*/realmlist.wtf".to_string(),
+            ],
+            required_dirs: vec!["Data".to_string()],
+            role_rules: vec![
+                RoleRule {
+                    pattern: r"^Wow(-64)?\.exe$".to_string(),
+                    role: FileRole::Executable,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^Data/(wow-update|patch).*\.MPQ$".to_string(),
+                    role: FileRole::MutableData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^Data/[^/]+\.MPQ$".to_string(),
+                    role: FileRole::BaseData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: format!(r"^Data/({locales})/(wow-update|patch).*\.MPQ$"),
+                    role: FileRole::MutableData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: format!(r"^Data/({locales})/[^/]+\.MPQ$"),
+                    role: FileRole::BaseData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^Screenshots($|/)".to_string(),
+                    role: FileRole::UserMedia,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^WTF($|/)".to_string(),
+                    role: FileRole::UserConfig,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^Interface($|/)".to_string(),
+                    role: FileRole::UserConfig,
+                    is_regex: true,
+                },
+            ],
+            warnings: vec![
+                WarningRule {
+                    pattern: "Cache".to_string(),
+                    message: "Cache directory present in base - should be ephemeral".to_string(),
+                },
+                WarningRule {
+                    pattern: "Logs".to_string(),
+                    message: "Logs directory present in base - should be ephemeral".to_string(),
+                },
+                WarningRule {
+                    pattern: "Errors".to_string(),
+                    message: "Errors directory present in base - should be ephemeral".to_string(),
+                },
+            ],
+            // Cache holds WDB and the ADB client database caches; Data/Cache holds
+            // downloaded patch archives and is not ephemeral
+            ephemeral_patterns: vec![
+                "Cache".to_string(),
+                "Logs".to_string(),
+                "Errors".to_string(),
+            ],
+            wdb_patterns: vec!["Cache/WDB".to_string(), "Cache/ADB".to_string()],
+            managed_patterns: vec![
+                "realmlist.wtf".to_string(),
+                "Data/*/realmlist.wtf".to_string(),
+            ],
+            script: None,
+        }
+    }
+
+    /// Get the builtin profile for CASC clients (6.0 and later, retail and classic)
+    ///
+    /// Archives live in `Data/data` and are shared by every product directory next to
+    /// them (`_retail_`, `_classic_`, `_classic_era_`, ...), each with its own executable,
+    /// WTF, Interface and caches.
+    pub fn casc() -> Self {
+        let product = r"_[a-z_]+_";
+        Profile {
+            name: "casc".to_string(),
+            version: "casc".to_string(),
+            required_files: vec![".build.info".to_string()],
+            required_dirs: vec!["Data/data".to_string()],
+            role_rules: vec![
+                RoleRule {
+                    pattern: format!(r"^{product}/Wow[A-Za-z0-9-]*\.exe$"),
+                    role: FileRole::Executable,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^World of Warcraft Launcher\.exe$".to_string(),
+                    role: FileRole::Executable,
+                    is_regex: true,
+                },
+                // Index files are rewritten by every update, archives only grow
+                RoleRule {
+                    pattern: r"^Data/data/[^/]+\.idx$".to_string(),
+                    role: FileRole::MutableData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^Data/data/data\.[0-9]+$".to_string(),
+                    role: FileRole::BaseData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^Data/(config|indices)/".to_string(),
+                    role: FileRole::BaseData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: r"^\.(build\.info|product\.db|patch\.result)$".to_string(),
+                    role: FileRole::MutableData,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: format!(r"^{product}/Screenshots($|/)"),
+                    role: FileRole::UserMedia,
+                    is_regex: true,
+                },
+                RoleRule {
+                    pattern: format!(r"^{product}/(WTF|Interface)($|/)"),
+                    role: FileRole::UserConfig,
+                    is_regex: true,
+                },
+            ],
+            warnings: vec![
+                WarningRule {
+                    pattern: "_*_/Cache".to_string(),
+                    message: "Cache directory present in base - should be ephemeral".to_string(),
+                },
+                WarningRule {
+                    pattern: "_*_/Logs".to_string(),
+                    message: "Logs directory present in base - should be ephemeral".to_string(),
+                },
+                WarningRule {
+                    pattern: "_*_/Errors".to_string(),
+                    message: "Errors directory present in base - should be ephemeral".to_string(),
+                },
+            ],
+            // shmem is the running client's shared memory, recreated on start
+            ephemeral_patterns: vec![
+                "_*_/Cache".to_string(),
+                "_*_/Logs".to_string(),
+                "_*_/Errors".to_string(),
+                "Data/data/shmem".to_string(),
+            ],
+            wdb_patterns: vec!["_*_/Cache/WDB".to_string(), "_*_/Cache/ADB".to_string()],
+            managed_patterns: Vec::new(),
+            script: None,
+        }
+    }
+
+    /// Look up a builtin profile by name or version alias
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "chromie-3.3.5a" | "3.3.5a" | "335" | "335a" => Some(Profile::chromie_335a()),
+            "vanilla-1.12" | "1.12" | "112" => Some(Profile::vanilla_112()),
+            "tbc-2.4.3" | "2.4.3" | "243" => Some(Profile::tbc_243()),
+            "cata-4.3.4" | "4.3.4" | "434" => Some(Profile::cata_434()),
+            "mop-5.4.8" | "5.4.8" | "548" => Some(Profile::mop_548()),
+            "casc" | "retail" | "classic" => Some(Profile::casc()),
+            _ => None,
+        }
+    }
+
+    /// Load a custom profile from a TOML file
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read profile {}", path.display()))?;
+        let profile: Profile = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse profile {}", path.display()))?;
+        if let Some(script) = &profile.script {
+            #[cfg(feature = "scripting")]
+            crate::script::compiled(script)?;
+            #[cfg(not(feature = "scripting"))]
+            {
+                let _ = script;
+                anyhow::bail!(
+                    "Profile {} has a script, but realmctl was built without the `scripting` feature",
+                    profile.name
+                );
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Builtin profile by name, or a custom profile if `name` is a path to a TOML file
+    pub fn resolve(name: &str) -> Result<Self> {
+        if let Some(profile) = Profile::builtin(name) {
+            return Ok(profile);
+        }
+        let path = PathBuf::from(shellexpand::tilde(name).to_string());
+        if path.is_file() {
+            return Profile::load_file(&path);
+        }
+        anyhow::bail!("Unknown profile: {name}")
+    }
+
+    /// Guess the builtin profile of a client directory, defaulting to 3.3.5a
+    pub fn detect(game_dir: &Path) -> Self {
+        // Newer clients keep the archives of older expansions, check the newest marker first
+        [
+            Profile::casc(),
+            Profile::vanilla_112(),
+            Profile::chromie_335a(),
+            Profile::tbc_243(),
+            Profile::mop_548(),
+            Profile::cata_434(),
+        ]
+        .into_iter()
+        .find(|profile| profile.verify_requirements(game_dir).is_ok())
+        .unwrap_or_else(Profile::chromie_335a)
+    }
+
+    /// Existing ephemeral paths in a client directory, including the WDB cache if asked
+    pub fn ephemeral_paths(&self, game_dir: &Path, include_wdb: bool) -> Vec<PathBuf> {
+        let wdb: &[String] = if include_wdb { &self.wdb_patterns } else { &[] };
+        self.ephemeral_patterns
+            .iter()
+            .chain(wdb)
+            .flat_map(|pattern| crate::glob::expand_glob(game_dir, pattern))
+            .filter(|path| {
+                // Never clean up files realmctl manages, even if a broad pattern matches them
+                let rel_path = path.strip_prefix(game_dir).unwrap_or(path);
+                self.classify_path(&rel_path.to_string_lossy()) != FileRole::Managed
+            })
+            .collect()
+    }
+
+    /// Where the client reads its realmlist, from the profile's managed `realmlist.wtf` patterns
+    ///
+    /// `*` stands for the detected locale. Patterns inside `Data` come first, clients with a
+    /// locale directory ignore a realmlist.wtf in the root.
+    pub fn realmlist_path(&self, game_dir: &Path) -> Option<PathBuf> {
+        let mut patterns: Vec<&String> = self
+            .managed_patterns
+            .iter()
+            .filter(|pattern| pattern.rsplit('/').next() == Some("realmlist.wtf"))
+            .collect();
+        patterns.sort_by_key(|pattern| !pattern.contains('/'));
+
+        let locale = crate::locale::detect_locale(game_dir);
+        patterns.into_iter().find_map(|pattern| {
+            if !pattern.contains('*') {
+                return Some(game_dir.join(pattern));
+            }
+            let path = game_dir.join(pattern.replacen('*', locale.as_deref()?, 1));
+            path.parent()?.is_dir().then_some(path)
+        })
+    }
+
+    /// Existing WDB cache paths in a client directory
+    pub fn wdb_paths(&self, game_dir: &Path) -> Vec<PathBuf> {
+        self.wdb_patterns
+            .iter()
+            .flat_map(|pattern| crate::glob::expand_glob(game_dir, pattern))
+            .collect()
+    }
+
     /// Verify the directory meets requirements
     pub fn verify_requirements(&self, base_dir: &Path) -> Result<()> {
         for file in &self.required_files {
-            let path = base_dir.join(file);
-            if !path.exists() {
+            // Globs like Data/*/realmlist.wtf stand for a file in any locale directory
+            let found = if file.contains(['*', '?']) {
+                !crate::glob::expand_glob(base_dir, file).is_empty()
+            } else {
+                base_dir.join(file).exists()
+            };
+            if !found {
                 anyhow::bail!("Required file not found: {}", file);
             }
         }
@@ -246,16 +701,50 @@ impl Profile {
     pub fn check_warnings(&self, base_dir: &Path) -> Vec<String> {
         let mut warnings = Vec::new();
         for warning in &self.warnings {
-            let path = base_dir.join(&warning.pattern);
-            if path.exists() {
+            if !crate::glob::expand_glob(base_dir, &warning.pattern).is_empty() {
                 warnings.push(warning.message.clone());
             }
         }
+        #[cfg(feature = "scripting")]
+        if let Some(script) = self
+            .script
+            .as_deref()
+            .and_then(|s| crate::script::compiled(s).ok())
+        {
+            let mut paths = Vec::new();
+            collect_rel_paths(base_dir, base_dir, self, &mut paths);
+            warnings.extend(paths.iter().filter_map(|rel_path| script.warn(rel_path)));
+        }
         warnings
     }
 
     /// Classify a file path according to role rules
     pub fn classify_path(&self, rel_path: &str) -> FileRole {
+        if TOOL_FILES.contains(&rel_path)
+            || self
+                .managed_patterns
+                .iter()
+                .any(|pattern| crate::glob::glob_match(pattern, rel_path))
+        {
+            return FileRole::Managed;
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(role) = self
+            .script
+            .as_deref()
+            .and_then(|s| crate::script::compiled(s).ok())
+            .and_then(|script| script.classify(rel_path))
+        {
+            return role;
+        }
+        if self
+            .ephemeral_patterns
+            .iter()
+            .chain(&self.wdb_patterns)
+            .any(|pattern| crate::glob::glob_match(pattern, rel_path))
+        {
+            return FileRole::Ephemeral;
+        }
         for rule in &self.role_rules {
             if rule.is_regex {
                 if let Ok(re) = regex::Regex::new(&rule.pattern)
@@ -272,15 +761,219 @@ impl Profile {
     }
 }
 
+/// Relative paths under a directory for script warnings, without descending into ephemeral ones
+#[cfg(feature = "scripting")]
+fn collect_rel_paths(base_dir: &Path, dir: &Path, profile: &Profile, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(rel_path) = path.strip_prefix(base_dir) else {
+            continue;
+        };
+        let rel_path = rel_path.to_string_lossy().to_string();
+        if path.is_dir() && profile.classify_path(&rel_path) != FileRole::Ephemeral {
+            collect_rel_paths(base_dir, &path, profile, out);
+        }
+        out.push(rel_path);
+    }
+}
+
+/// Checksum algorithm used for BaseData files in the manifest
+pub const HASH_ALGORITHM: &str = "blake3";
+
+/// File next to manifest.toml holding hashing progress of an unfinished init-base
+pub const PROGRESS_FILE: &str = "manifest.progress.toml";
+
+/// Checksums of the base files a workspace links, as they were when it was created or
+/// last upgraded, next to its workspace.toml
+pub const BASE_SNAPSHOT: &str = "base-snapshot.toml";
+
+/// Checksums computed so far by an interrupted scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanProgress {
+    /// Profile the scan was started with, progress from another profile is not reused
+    pub profile: String,
+    /// Algorithm of the checksums, those of another algorithm are computed again
+    #[serde(default)]
+    pub algorithm: Option<String>,
+    pub hashed: BTreeMap<String, HashedFile>,
+}
+
+/// A hashed file with the size and modification time it had when it was hashed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashedFile {
+    pub checksum: String,
+    pub size: u64,
+    pub modified: u64,
+}
+
+/// Load the progress of an interrupted scan, if there is one
+pub fn load_progress(base_dir: &Path) -> Result<Option<ScanProgress>> {
+    let path = base_dir.join(PROGRESS_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)?;
+    let progress =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(progress))
+}
+
+fn save_progress(base_dir: &Path, progress: &ScanProgress) -> Result<()> {
+    let temp_path = base_dir.join(format!("{PROGRESS_FILE}.tmp"));
+    std::fs::write(&temp_path, toml::to_string(progress)?)?;
+    std::fs::rename(&temp_path, base_dir.join(PROGRESS_FILE))?;
+    Ok(())
+}
+
+/// Hashing progress of a running scan, saved periodically when persisted
+struct Tracker {
+    progress: ScanProgress,
+    persist: bool,
+    last_save: std::time::Instant,
+}
+
+impl Tracker {
+    /// Checksum of a file, reused from earlier progress when the file is unchanged
+    fn checksum(
+        &mut self,
+        base_dir: &Path,
+        rel_path: &str,
+        path: &Path,
+        bar: &Progress,
+    ) -> Result<String> {
+        let meta = std::fs::metadata(path)?;
+        let size = meta.len();
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+
+        if let Some(done) = self.progress.hashed.get(rel_path)
+            && done.size == size
+            && done.modified == modified
+        {
+            bar.add(1, size);
+            return Ok(done.checksum.clone());
+        }
+
+        let checksum = compute_file_hash(path, Some(bar))?;
+        bar.add(1, 0);
+        self.progress.hashed.insert(
+            rel_path.to_string(),
+            HashedFile {
+                checksum: checksum.clone(),
+                size,
+                modified,
+            },
+        );
+        if self.persist && self.last_save.elapsed().as_secs() >= 5 {
+            if let Err(e) = save_progress(base_dir, &self.progress) {
+                tracing::warn!("⚠ Failed to save scan progress: {e}");
+            }
+            self.last_save = std::time::Instant::now();
+        }
+        Ok(checksum)
+    }
+}
+
 /// Scan a directory and build a manifest
 pub fn scan_and_build_manifest(base_dir: &Path, profile: &Profile) -> Result<BaseManifest> {
+    let mut tracker = Tracker {
+        progress: ScanProgress::default(),
+        persist: false,
+        last_save: std::time::Instant::now(),
+    };
+    build_manifest(base_dir, profile, &mut tracker)
+}
+
+/// Scan a directory and build a manifest, saving hashing progress to [`PROGRESS_FILE`]
+///
+/// With `resume`, checksums of unchanged files are taken from the progress of an
+/// interrupted earlier run instead of hashing them again.
+pub fn scan_and_build_manifest_resumable(
+    base_dir: &Path,
+    profile: &Profile,
+    resume: bool,
+) -> Result<BaseManifest> {
+    let progress = if resume {
+        let Some(progress) = load_progress(base_dir)? else {
+            anyhow::bail!("No interrupted scan to resume in {}", base_dir.display());
+        };
+        if progress.profile != profile.name {
+            anyhow::bail!(
+                "Interrupted scan used profile {}, not {}",
+                progress.profile,
+                profile.name
+            );
+        }
+        let mut progress = progress;
+        if progress.algorithm.as_deref() != Some(HASH_ALGORITHM) {
+            progress.hashed.clear();
+            progress.algorithm = Some(HASH_ALGORITHM.to_string());
+        }
+        progress
+    } else {
+        ScanProgress {
+            profile: profile.name.clone(),
+            algorithm: Some(HASH_ALGORITHM.to_string()),
+            ..Default::default()
+        }
+    };
+
+    let mut tracker = Tracker {
+        progress,
+        persist: true,
+        last_save: std::time::Instant::now(),
+    };
+    match build_manifest(base_dir, profile, &mut tracker) {
+        Ok(manifest) => {
+            let _ = std::fs::remove_file(base_dir.join(PROGRESS_FILE));
+            Ok(manifest)
+        }
+        Err(e) => {
+            // Keep what was hashed so far for --resume, whatever stopped the scan
+            if let Err(save_error) = save_progress(base_dir, &tracker.progress) {
+                tracing::error!("✗ Failed to save scan progress: {save_error}");
+            }
+            Err(e)
+        }
+    }
+}
+
+fn build_manifest(
+    base_dir: &Path,
+    profile: &Profile,
+    tracker: &mut Tracker,
+) -> Result<BaseManifest> {
     use std::time::SystemTime;
 
-    let mut file_roles = HashMap::new();
-    let mut checksums = HashMap::new();
+    let mut file_roles = BTreeMap::new();
+    let mut checksums = BTreeMap::new();
 
     // Recursively scan directory
-    scan_directory(base_dir, base_dir, profile, &mut file_roles, &mut checksums)?;
+    let mut to_hash = Vec::new();
+    scan_directory(base_dir, base_dir, profile, &mut file_roles, &mut to_hash)?;
+
+    // Compute checksums for BaseData files, once their total size is known for the bar
+    let total_bytes = to_hash
+        .iter()
+        .map(|(_, path)| std::fs::metadata(path).map_or(0, |meta| meta.len()))
+        .sum();
+    let bar = Progress::new("Hashing", to_hash.len(), total_bytes);
+    for (rel_path, path) in to_hash {
+        match tracker.checksum(base_dir, &rel_path, &path, &bar) {
+            Ok(hash) => {
+                checksums.insert(rel_path, hash);
+            }
+            Err(e) if e.is::<crate::cancel::Cancelled>() => return Err(e),
+            Err(_) => {}
+        }
+    }
+    bar.finish();
 
     let created_at = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -294,7 +987,9 @@ pub fn scan_and_build_manifest(base_dir: &Path, profile: &Profile) -> Result<Bas
         created_at,
         file_roles,
         checksums,
+        hash_algorithm: Some(HASH_ALGORITHM.to_string()),
         version: Some(profile.version.clone()),
+        locale: crate::locale::detect_locale(base_dir),
     })
 }
 
@@ -302,10 +997,11 @@ fn scan_directory(
     base_dir: &Path,
     current_dir: &Path,
     profile: &Profile,
-    file_roles: &mut HashMap<String, FileRole>,
-    checksums: &mut HashMap<String, String>,
+    file_roles: &mut BTreeMap<String, FileRole>,
+    to_hash: &mut Vec<(String, PathBuf)>,
 ) -> Result<()> {
     for entry in std::fs::read_dir(current_dir)? {
+        crate::cancel::check()?;
         let entry = entry?;
         let path = entry.path();
         let rel_path = path
@@ -314,6 +1010,11 @@ fn scan_directory(
             .to_string_lossy()
             .to_string();
 
+        // Scan bookkeeping is not part of the client
+        if rel_path.starts_with(PROGRESS_FILE) {
+            continue;
+        }
+
         if path.is_dir() {
             // Classify directory
             let role = profile.classify_path(&rel_path);
@@ -321,52 +1022,627 @@ fn scan_directory(
 
             // Recursively scan subdirectories (skip ephemeral)
             if role != FileRole::Ephemeral {
-                scan_directory(base_dir, &path, profile, file_roles, checksums)?;
+                scan_directory(base_dir, &path, profile, file_roles, to_hash)?;
             }
         } else if path.is_file() {
             let role = profile.classify_path(&rel_path);
             file_roles.insert(rel_path.clone(), role.clone());
 
-            // Compute checksum for BaseData files
-            if role == FileRole::BaseData
-                && let Ok(hash) = compute_file_hash(&path) {
-                    checksums.insert(rel_path, hash);
-                }
+            // BaseData files are checksummed once the whole tree is known
+            if role == FileRole::BaseData {
+                to_hash.push((rel_path, path));
+            }
         }
     }
 
     Ok(())
 }
 
-fn compute_file_hash(path: &Path) -> Result<String> {
+/// Incremental checksum of file contents, so checks can use whichever hashes a source provides
+pub trait ContentHasher {
+    fn update(&mut self, data: &[u8]);
+    /// Lowercase hex digest
+    fn finish(self: Box<Self>) -> String;
+}
+
+impl ContentHasher for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        self.finalize().to_hex().to_string()
+    }
+}
+
+impl ContentHasher for crc32fast::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        crc32fast::Hasher::update(self, data);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("{:08x}", self.finalize())
+    }
+}
+
+impl ContentHasher for sha1_smol::Sha1 {
+    fn update(&mut self, data: &[u8]) {
+        sha1_smol::Sha1::update(self, data);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        self.digest().to_string()
+    }
+}
+
+/// Hasher for an algorithm name (`blake3`, `crc32` or `sha1`)
+pub fn hasher(algorithm: &str) -> Option<Box<dyn ContentHasher>> {
+    match algorithm {
+        "blake3" => Some(Box::new(blake3::Hasher::new())),
+        "crc32" => Some(Box::new(crc32fast::Hasher::new())),
+        "sha1" => Some(Box::new(sha1_smol::Sha1::new())),
+        _ => None,
+    }
+}
+
+/// Checksum of a file, counting the bytes read on `bar`
+pub(crate) fn compute_file_hash(path: &Path, bar: Option<&Progress>) -> Result<String> {
+    hash_file_with(path, HASH_ALGORITHM, bar)
+}
+
+/// Checksum of a file with a given algorithm, e.g. the one of an older manifest
+pub(crate) fn hash_file_with(
+    path: &Path,
+    algorithm: &str,
+    bar: Option<&Progress>,
+) -> Result<String> {
     use std::io::Read;
+    if algorithm == "blake3" {
+        // Memory-mapped and hashed on all cores; cancelling waits for the current file
+        crate::cancel::check()?;
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_mmap_rayon(path)?;
+        if let Some(bar) = bar {
+            bar.add(0, hasher.count());
+        }
+        return Ok(hasher.finalize().to_hex().to_string());
+    }
+    let mut hasher =
+        hasher(algorithm).with_context(|| format!("Unknown checksum algorithm {algorithm}"))?;
     let mut file = std::fs::File::open(path)?;
-    let mut hasher = crc32fast::Hasher::new();
     let mut buffer = [0u8; 8192];
 
     loop {
+        // Large MPQs take a while, stop between chunks rather than after the file
+        crate::cancel::check()?;
         let n = file.read(&mut buffer)?;
         if n == 0 {
             break;
         }
         hasher.update(&buffer[..n]);
+        if let Some(bar) = bar {
+            bar.add(0, n as u64);
+        }
+    }
+
+    Ok(hasher.finish())
+}
+
+/// Differences between a base and the checksums recorded in its manifest
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ManifestReport {
+    /// Files whose checksum still matches
+    pub verified: usize,
+    /// Bytes read to recompute the checksums
+    pub bytes: u64,
+    /// Files whose contents changed since the scan
+    pub modified: Vec<String>,
+    pub missing: Vec<String>,
+    /// BaseData files on disk without a recorded checksum (added after the scan)
+    pub extra: Vec<String>,
+}
+
+impl ManifestReport {
+    /// Extra files alone don't fail verification, the recorded files are intact
+    pub fn is_ok(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Recompute the checksums of a base's BaseData files and compare them with its manifest
+pub fn verify_manifest(base_dir: &Path) -> Result<ManifestReport> {
+    let manifest = load_manifest(base_dir)
+        .with_context(|| format!("No readable manifest.toml in {}", base_dir.display()))?;
+    let mut report = ManifestReport::default();
+    let algorithm = manifest.checksum_algorithm();
+    if hasher(algorithm).is_none() {
+        anyhow::bail!("Unknown checksum algorithm {algorithm} in manifest.toml");
     }
 
-    Ok(format!("{:08x}", hasher.finalize()))
+    let sizes: Vec<Option<u64>> = manifest
+        .checksums
+        .keys()
+        .map(|rel_path| {
+            std::fs::metadata(base_dir.join(rel_path))
+                .ok()
+                .filter(|meta| meta.is_file())
+                .map(|meta| meta.len())
+        })
+        .collect();
+    let total_bytes = sizes.iter().flatten().sum();
+    let bar = Progress::new("Verifying", manifest.checksums.len(), total_bytes);
+    for ((rel_path, expected), size) in manifest.checksums.iter().zip(sizes) {
+        let Some(size) = size else {
+            report.missing.push(rel_path.clone());
+            bar.add(1, 0);
+            continue;
+        };
+        match hash_file_with(&base_dir.join(rel_path), algorithm, Some(&bar)) {
+            Ok(actual) => {
+                report.bytes += size;
+                if actual == *expected {
+                    report.verified += 1;
+                } else {
+                    report.modified.push(rel_path.clone());
+                }
+            }
+            Err(e) if e.is::<crate::cancel::Cancelled>() => return Err(e),
+            Err(_) => report.missing.push(rel_path.clone()),
+        }
+        bar.add(1, 0);
+    }
+    bar.finish();
+
+    let profile = Profile::resolve(&manifest.profile).unwrap_or_else(|_| Profile::detect(base_dir));
+    let mut pending = vec![base_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            let path = entry.path();
+            let Ok(rel_path) = path.strip_prefix(base_dir) else {
+                continue;
+            };
+            // Same form as the manifest keys written by scan_directory
+            let rel_path = rel_path.to_string_lossy().to_string();
+            let role = profile.classify_path(&rel_path);
+            if path.is_dir() {
+                if role != FileRole::Ephemeral {
+                    pending.push(path);
+                }
+            } else if role == FileRole::BaseData && !manifest.checksums.contains_key(&rel_path) {
+                report.extra.push(rel_path);
+            }
+        }
+    }
+    report.extra.sort();
+    Ok(report)
+}
+
+/// What init-base would record for a directory, gathered without writing to it
+#[derive(Debug, Clone, Default)]
+pub struct Inspection {
+    /// Would-be manifest roles, ephemeral directories are not descended into
+    pub file_roles: BTreeMap<String, FileRole>,
+    /// Files worth a look before adopting the directory as a base, see [`crate::screen`]
+    pub red_flags: Vec<(String, String)>,
+}
+
+/// Classify a directory the way init-base would, without writing a manifest or following links
+pub fn inspect_directory(dir: &Path, profile: &Profile) -> Result<Inspection> {
+    let mut inspection = Inspection::default();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            let Ok(rel_path) = path.strip_prefix(dir) else {
+                continue;
+            };
+            let rel_path = rel_path.to_string_lossy().to_string();
+            let role = profile.classify_path(&rel_path);
+            if entry.file_type()?.is_dir() {
+                if role != FileRole::Ephemeral {
+                    pending.push(path);
+                }
+            } else if let Some(reason) = crate::screen::red_flag(&path, &rel_path, &role) {
+                inspection.red_flags.push((rel_path.clone(), reason));
+            }
+            inspection.file_roles.insert(rel_path, role);
+        }
+    }
+    inspection.red_flags.sort();
+    Ok(inspection)
 }
 
 /// Write manifest to disk
 pub fn write_manifest(manifest: &BaseManifest, base_dir: &Path) -> Result<()> {
     let manifest_path = base_dir.join("manifest.toml");
     let toml_string = toml::to_string_pretty(manifest)?;
-    std::fs::write(manifest_path, toml_string)?;
+    // Write then rename, so an interrupted write never leaves a truncated manifest
+    let temp_path = base_dir.join("manifest.toml.tmp");
+    std::fs::write(&temp_path, toml_string)?;
+    // Readable by every user of a shared base, whatever the umask of whoever ran init-base
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o644))?;
+    }
+    std::fs::rename(&temp_path, &manifest_path)?;
     Ok(())
 }
 
 /// Load manifest from disk
 pub fn load_manifest(base_dir: &Path) -> Result<BaseManifest> {
     let manifest_path = base_dir.join("manifest.toml");
-    let content = std::fs::read_to_string(manifest_path)?;
-    let manifest: BaseManifest = toml::from_str(&content)?;
+    let content = std::fs::read_to_string(&manifest_path)?;
+    let mut manifest: BaseManifest = crate::compat::parse_toml(&content, &manifest_path)?;
+    if manifest.base_path.as_os_str().is_empty() {
+        manifest.base_path = base_dir.to_path_buf();
+    }
     Ok(manifest)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_blake3_vectors() {
+        // Input of the official test vectors: bytes counting 0 to 250 over and over
+        let input = |len: usize| (0..len).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("common.MPQ");
+        for (len, expected) in [
+            (0, "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"),
+            (1025, "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444"),
+            (102400, "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085"),
+        ] {
+            std::fs::write(&path, input(len)).unwrap();
+            assert_eq!(compute_file_hash(&path, None).unwrap(), expected);
+        }
+        let mut abc = hasher("blake3").unwrap();
+        abc.update(b"abc");
+        assert_eq!(
+            abc.finish(),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+
+        // Large enough to be memory-mapped and split over threads, same hash as streamed
+        let data = input(4 * 1024 * 1024 + 4321);
+        std::fs::write(&path, &data).unwrap();
+        let mut streamed = hasher("blake3").unwrap();
+        streamed.update(&data);
+        assert_eq!(compute_file_hash(&path, None).unwrap(), streamed.finish());
+    }
+
+    #[test]
+    fn test_verify_manifest() {
+        let temp = TempDir::new().unwrap();
+        let base_dir = temp.path();
+        std::fs::create_dir_all(base_dir.join("Data")).unwrap();
+        std::fs::write(base_dir.join("Wow.exe"), b"exe").unwrap();
+        for name in ["common", "common-2", "lichking"] {
+            std::fs::write(base_dir.join(format!("Data/{name}.MPQ")), name).unwrap();
+        }
+        let mut manifest = scan_and_build_manifest(base_dir, &Profile::chromie_335a()).unwrap();
+        assert_eq!(manifest.checksum_algorithm(), "blake3");
+        write_manifest(&manifest, base_dir).unwrap();
+        assert!(verify_manifest(base_dir).unwrap().is_ok());
+
+        // Manifests of older releases hold crc32 checksums without naming the algorithm
+        for (rel_path, checksum) in manifest.checksums.iter_mut() {
+            *checksum = hash_file_with(&base_dir.join(rel_path), "crc32", None).unwrap();
+        }
+        manifest.hash_algorithm = None;
+        write_manifest(&manifest, base_dir).unwrap();
+        assert!(verify_manifest(base_dir).unwrap().is_ok());
+
+        std::fs::write(base_dir.join("Data/common.MPQ"), b"corrupt").unwrap();
+        std::fs::remove_file(base_dir.join("Data/lichking.MPQ")).unwrap();
+        std::fs::write(base_dir.join("Data/expansion.MPQ"), b"new").unwrap();
+        let report = verify_manifest(base_dir).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.verified, 1);
+        // "corrupt" and "common-2" were read, the missing file was not
+        assert_eq!(report.bytes, 15);
+        assert_eq!(report.modified, vec!["Data/common.MPQ"]);
+        assert_eq!(report.missing, vec!["Data/lichking.MPQ"]);
+        assert_eq!(report.extra, vec!["Data/expansion.MPQ"]);
+    }
+
+    #[test]
+    fn test_inspect_directory() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("Data")).unwrap();
+        std::fs::create_dir_all(dir.join("Interface/AddOns/Helper")).unwrap();
+        for name in [
+            "Wow.exe",
+            "Repair.exe",
+            "d3d9.dll",
+            "ijl15.dll",
+            "Data/common.MPQ",
+            "Interface/AddOns/Helper/run.bat",
+            "Interface/AddOns/Helper/hook.dll",
+        ] {
+            std::fs::write(dir.join(name), name).unwrap();
+        }
+
+        let inspection = inspect_directory(dir, &Profile::chromie_335a()).unwrap();
+        assert_eq!(inspection.file_roles["Wow.exe"], FileRole::Executable);
+        assert_eq!(inspection.file_roles["Data/common.MPQ"], FileRole::BaseData);
+        let flagged: Vec<&str> = inspection
+            .red_flags
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                "Interface/AddOns/Helper/hook.dll",
+                "Interface/AddOns/Helper/run.bat",
+                "d3d9.dll",
+            ]
+        );
+        assert!(!dir.join("manifest.toml").exists());
+    }
+
+    #[test]
+    fn test_resume_reuses_unchanged_checksums() {
+        let temp = TempDir::new().unwrap();
+        let base_dir = temp.path();
+        std::fs::create_dir_all(base_dir.join("Data")).unwrap();
+        std::fs::write(base_dir.join("Data/common.MPQ"), b"common").unwrap();
+        std::fs::write(base_dir.join("Data/common-2.MPQ"), b"common-2").unwrap();
+
+        let profile = Profile::chromie_335a();
+        let mut tracker = Tracker {
+            progress: ScanProgress {
+                profile: profile.name.clone(),
+                algorithm: Some(HASH_ALGORITHM.to_string()),
+                ..Default::default()
+            },
+            persist: false,
+            last_save: std::time::Instant::now(),
+        };
+        let path = base_dir.join("Data/common.MPQ");
+        tracker
+            .checksum(
+                base_dir,
+                "Data/common.MPQ",
+                &path,
+                &Progress::new("Hashing", 1, 0),
+            )
+            .unwrap();
+        // A checksum only a resumed scan can produce
+        tracker
+            .progress
+            .hashed
+            .get_mut("Data/common.MPQ")
+            .unwrap()
+            .checksum = "resumed".to_string();
+        save_progress(base_dir, &tracker.progress).unwrap();
+
+        let manifest = scan_and_build_manifest_resumable(base_dir, &profile, true).unwrap();
+        assert_eq!(manifest.checksums["Data/common.MPQ"], "resumed");
+        assert_ne!(manifest.checksums["Data/common-2.MPQ"], "resumed");
+        assert!(!base_dir.join(PROGRESS_FILE).exists());
+
+        assert!(scan_and_build_manifest_resumable(base_dir, &profile, true).is_err());
+    }
+
+    #[test]
+    fn test_manifest_serialization_is_sorted() {
+        let temp = TempDir::new().unwrap();
+        let base_dir = temp.path();
+        std::fs::create_dir_all(base_dir.join("Data")).unwrap();
+        for name in [
+            "Wow.exe",
+            "Data/patch.MPQ",
+            "Data/common.MPQ",
+            "Data/lichking.MPQ",
+        ] {
+            std::fs::write(base_dir.join(name), name).unwrap();
+        }
+
+        let manifest = scan_and_build_manifest(base_dir, &Profile::chromie_335a()).unwrap();
+        let keys: Vec<&String> = manifest.file_roles.keys().collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+
+        let first = toml::to_string_pretty(&manifest).unwrap();
+        let reloaded: BaseManifest = toml::from_str(&first).unwrap();
+        assert_eq!(toml::to_string_pretty(&reloaded).unwrap(), first);
+    }
+
+    #[test]
+    fn test_managed_files() {
+        let profile = Profile::chromie_335a();
+        assert_eq!(profile.classify_path("manifest.toml"), FileRole::Managed);
+        assert_eq!(profile.classify_path("workspace.toml"), FileRole::Managed);
+        assert_eq!(
+            profile.classify_path("Data/enUS/realmlist.wtf"),
+            FileRole::Managed
+        );
+        assert_eq!(
+            profile.classify_path("Data/enUS/patch-enUS.MPQ"),
+            FileRole::Other
+        );
+
+        // Broad ephemeral patterns never reach managed files
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("realmlist.wtf"), "set realmlist x").unwrap();
+        std::fs::write(temp.path().join("Wow.log"), "").unwrap();
+        let profile = Profile {
+            ephemeral_patterns: vec!["*".to_string()],
+            ..Profile::vanilla_112()
+        };
+        assert_eq!(
+            profile.ephemeral_paths(temp.path(), false),
+            vec![temp.path().join("Wow.log")]
+        );
+    }
+
+    #[test]
+    fn test_wdb_paths() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("Cache/WDB/enUS")).unwrap();
+        std::fs::create_dir_all(temp.path().join("Data/enUS")).unwrap();
+        std::fs::write(temp.path().join("Data/enUS/itemcache.wdb"), "").unwrap();
+        std::fs::write(temp.path().join("Data/enUS/patch-enUS.MPQ"), "").unwrap();
+
+        let mut paths = Profile::chromie_335a().wdb_paths(temp.path());
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                temp.path().join("Cache/WDB"),
+                temp.path().join("Data/enUS/itemcache.wdb"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tbc_profile() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("Data/enGB")).unwrap();
+        for file in ["Wow.exe", "Data/common.MPQ", "Data/expansion.MPQ"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        let profile = Profile::builtin("2.4.3").unwrap();
+        assert!(profile.verify_requirements(dir).is_err());
+        std::fs::write(dir.join("Data/enGB/realmlist.wtf"), "").unwrap();
+        assert!(profile.verify_requirements(dir).is_ok());
+        assert_eq!(Profile::detect(dir).name, "tbc-2.4.3");
+
+        assert_eq!(
+            profile.classify_path("Data/expansion.MPQ"),
+            FileRole::BaseData
+        );
+        assert_eq!(
+            profile.classify_path("Data/enGB/locale-enGB.MPQ"),
+            FileRole::BaseData
+        );
+        assert_eq!(
+            profile.classify_path("Data/enGB/patch-enGB-2.MPQ"),
+            FileRole::MutableData
+        );
+        assert_eq!(
+            profile.classify_path("Data/patch-2.MPQ"),
+            FileRole::MutableData
+        );
+        assert_eq!(
+            profile.classify_path("Data/enGB/realmlist.wtf"),
+            FileRole::Managed
+        );
+        assert_eq!(
+            profile.classify_path("Data/Custom/patch.MPQ"),
+            FileRole::Other
+        );
+
+        // A 3.3.5a client has expansion.MPQ too
+        std::fs::write(dir.join("Data/patch.MPQ"), "").unwrap();
+        std::fs::write(dir.join("Data/lichking.MPQ"), "").unwrap();
+        assert_eq!(Profile::detect(dir).name, "chromie-3.3.5a");
+    }
+
+    #[test]
+    fn test_cata_and_mop_profiles() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("Data/enUS")).unwrap();
+        for file in [
+            "Wow.exe",
+            "Data/art.MPQ",
+            "Data/expansion3.MPQ",
+            "Data/enUS/realmlist.wtf",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        assert_eq!(Profile::detect(dir).name, "cata-4.3.4");
+        std::fs::write(dir.join("Data/expansion4.MPQ"), "").unwrap();
+        assert_eq!(Profile::detect(dir).name, "mop-5.4.8");
+
+        let profile = Profile::builtin("5.4.8").unwrap();
+        assert_eq!(profile.classify_path("Wow-64.exe"), FileRole::Executable);
+        assert_eq!(profile.classify_path("Data/world.MPQ"), FileRole::BaseData);
+        assert_eq!(
+            profile.classify_path("Data/wow-update-base-18414.MPQ"),
+            FileRole::MutableData
+        );
+        assert_eq!(
+            profile.classify_path("Data/enUS/wow-update-enUS-18414.MPQ"),
+            FileRole::MutableData
+        );
+        assert_eq!(
+            profile.classify_path("Data/enUS/locale-enUS.MPQ"),
+            FileRole::BaseData
+        );
+        assert_eq!(profile.classify_path("Cache/ADB"), FileRole::Ephemeral);
+        assert_ne!(
+            profile.classify_path("Data/Cache/patch-base-1.MPQ"),
+            FileRole::Ephemeral
+        );
+    }
+
+    #[test]
+    fn test_realmlist_path_follows_profile() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("Data/deDE")).unwrap();
+
+        assert_eq!(
+            Profile::vanilla_112().realmlist_path(dir),
+            Some(dir.join("realmlist.wtf"))
+        );
+        assert_eq!(
+            Profile::chromie_335a().realmlist_path(dir),
+            Some(dir.join("Data/deDE/realmlist.wtf"))
+        );
+        assert_eq!(Profile::casc().realmlist_path(dir), None);
+
+        assert_eq!(
+            Profile::tbc_243().realmlist_path(dir),
+            Some(dir.join("Data/deDE/realmlist.wtf"))
+        );
+        std::fs::remove_dir(dir.join("Data/deDE")).unwrap();
+        assert_eq!(
+            Profile::tbc_243().realmlist_path(dir),
+            Some(dir.join("realmlist.wtf"))
+        );
+    }
+
+    #[test]
+    fn test_casc_profile() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("Data/data")).unwrap();
+        std::fs::create_dir_all(dir.join("_classic_/Cache")).unwrap();
+        std::fs::write(dir.join(".build.info"), "").unwrap();
+        assert_eq!(Profile::detect(dir).name, "casc");
+
+        let profile = Profile::builtin("retail").unwrap();
+        assert_eq!(
+            profile.check_warnings(dir),
+            vec!["Cache directory present in base - should be ephemeral"]
+        );
+        for (path, role) in [
+            ("_retail_/Wow.exe", FileRole::Executable),
+            ("_classic_era_/WowClassic.exe", FileRole::Executable),
+            ("Data/data/data.042", FileRole::BaseData),
+            ("Data/config/ab/cd/abcd1234", FileRole::BaseData),
+            ("Data/data/0a00000001.idx", FileRole::MutableData),
+            (".build.info", FileRole::MutableData),
+            ("_classic_/WTF", FileRole::UserConfig),
+            ("_retail_/Interface/AddOns", FileRole::UserConfig),
+            ("_retail_/Screenshots", FileRole::UserMedia),
+            ("_retail_/Cache", FileRole::Ephemeral),
+            ("_retail_/Cache/ADB", FileRole::Ephemeral),
+            ("Data/data/shmem", FileRole::Ephemeral),
+        ] {
+            assert_eq!(profile.classify_path(path), role, "{path}");
+        }
+    }
+}
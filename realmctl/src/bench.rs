@@ -0,0 +1,135 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Ways of bringing base files into a workspace compared by `bench-linking`, in order of
+/// preference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BenchStrategy {
+    /// Copy-on-write clones: no space up front, and writes never reach the base
+    Reflink,
+    /// Shared data; realmctl copies files it sees changed, in-place edits reach the base
+    Hardlink,
+    /// Links to the base path, break when the base moves
+    Symlink,
+    /// Full copies, the size of the base again
+    Copy,
+}
+
+impl BenchStrategy {
+    pub const ALL: [BenchStrategy; 4] = [
+        BenchStrategy::Reflink,
+        BenchStrategy::Hardlink,
+        BenchStrategy::Symlink,
+        BenchStrategy::Copy,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BenchStrategy::Reflink => "reflink",
+            BenchStrategy::Hardlink => "hardlink",
+            BenchStrategy::Symlink => "symlink",
+            BenchStrategy::Copy => "copy",
+        }
+    }
+
+    fn link(self, from: &Path, to: &Path) -> std::io::Result<()> {
+        match self {
+            BenchStrategy::Reflink => crate::reflink::reflink(from, to),
+            BenchStrategy::Hardlink => std::fs::hard_link(from, to),
+            #[cfg(unix)]
+            BenchStrategy::Symlink => std::os::unix::fs::symlink(from, to),
+            #[cfg(windows)]
+            BenchStrategy::Symlink => std::os::windows::fs::symlink_file(from, to),
+            BenchStrategy::Copy => std::fs::copy(from, to).map(|_| ()),
+        }
+    }
+}
+
+/// Outcome of one strategy
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub strategy: BenchStrategy,
+    pub elapsed: Duration,
+    /// Drop in free space on the filesystem, if it could be measured
+    pub bytes_used: Option<u64>,
+    /// Why the strategy does not work here
+    pub error: Option<String>,
+}
+
+/// Flush pending writes so free space reflects what was written
+fn sync_filesystems() {
+    #[cfg(unix)]
+    let _ = std::process::Command::new("sync").status();
+}
+
+/// Bring `files` (relative to `base`) into a fresh directory below `scratch` with every
+/// strategy, removing each directory again after measuring it
+///
+/// Ctrl-C fails the strategy being measured and the ones after it.
+pub fn bench(base: &Path, files: &[String], scratch: &Path) -> Vec<BenchResult> {
+    BenchStrategy::ALL
+        .iter()
+        .map(|&strategy| {
+            let dir = scratch.join(strategy.as_str());
+            sync_filesystems();
+            let free_before = crate::paths::free_space(scratch);
+            let start = Instant::now();
+            let outcome = files.iter().try_for_each(|rel_path| {
+                crate::cancel::check().map_err(std::io::Error::other)?;
+                let target = dir.join(rel_path);
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                strategy.link(&base.join(rel_path), &target)
+            });
+            let elapsed = start.elapsed();
+            sync_filesystems();
+            let free_after = crate::paths::free_space(scratch);
+            let _ = std::fs::remove_dir_all(&dir);
+            BenchResult {
+                strategy,
+                elapsed,
+                bytes_used: free_before
+                    .zip(free_after)
+                    .map(|(before, after)| before.saturating_sub(after)),
+                error: outcome.err().map(|e| e.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// The most preferred strategy that worked
+pub fn recommend(results: &[BenchResult]) -> Option<BenchStrategy> {
+    results
+        .iter()
+        .find(|result| result.error.is_none())
+        .map(|result| result.strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_tries_every_strategy_and_cleans_up() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let base = temp.path().join("base");
+        std::fs::create_dir_all(base.join("Data")).unwrap();
+        std::fs::write(base.join("Data/common.MPQ"), "mpq").unwrap();
+        std::fs::write(base.join("Wow.exe"), "exe").unwrap();
+        let scratch = temp.path().join("scratch");
+        std::fs::create_dir_all(&scratch).unwrap();
+
+        let files = vec!["Data/common.MPQ".to_string(), "Wow.exe".to_string()];
+        let results = bench(&base, &files, &scratch);
+        assert_eq!(results.len(), BenchStrategy::ALL.len());
+        let hardlink = &results[1];
+        assert_eq!(hardlink.strategy, BenchStrategy::Hardlink);
+        assert!(hardlink.error.is_none());
+        assert!(matches!(
+            recommend(&results),
+            Some(BenchStrategy::Reflink | BenchStrategy::Hardlink)
+        ));
+        assert_eq!(std::fs::read_dir(&scratch).unwrap().count(), 0);
+    }
+}
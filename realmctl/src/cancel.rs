@@ -0,0 +1,86 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(test)]
+thread_local! {
+    /// Ctrl-C for the current test only, the other tests share the process
+    static CANCELLED_IN_TEST: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Error returned by long-running operations stopped with Ctrl-C
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Interrupted")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Catch Ctrl-C so the current operation can stop at a safe point
+///
+/// A second Ctrl-C exits immediately.
+pub fn install_handler() {
+    let result = ctrlc::set_handler(|| {
+        if CANCELLED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        tracing::warn!("\nInterrupt received, stopping safely (press Ctrl-C again to abort now)");
+    });
+    if let Err(e) = result {
+        tracing::warn!("⚠ Failed to install Ctrl-C handler: {e}");
+    }
+}
+
+/// Whether Ctrl-C was pressed
+pub fn is_cancelled() -> bool {
+    #[cfg(test)]
+    if CANCELLED_IN_TEST.get() {
+        return true;
+    }
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// Fail with [`Cancelled`] once Ctrl-C was pressed
+pub fn check() -> Result<(), Cancelled> {
+    if is_cancelled() {
+        Err(Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Profile, hash_file_with, scan_and_build_manifest};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_stops_long_loops() -> anyhow::Result<()> {
+        let temp = TempDir::new()?;
+        std::fs::create_dir_all(temp.path().join("Data"))?;
+        std::fs::write(temp.path().join("Wow.exe"), "exe")?;
+        std::fs::write(temp.path().join("Data/common.MPQ"), vec![7u8; 64 * 1024])?;
+        let profile = Profile::chromie_335a();
+        assert!(check().is_ok());
+        scan_and_build_manifest(temp.path(), &profile)?;
+
+        CANCELLED_IN_TEST.set(true);
+        assert!(check().is_err());
+        // The scan stops between directory entries, chunked hashing between chunks
+        let e = scan_and_build_manifest(temp.path(), &profile).unwrap_err();
+        assert!(e.is::<Cancelled>());
+        let e = hash_file_with(&temp.path().join("Data/common.MPQ"), "crc32", None).unwrap_err();
+        assert!(e.is::<Cancelled>());
+        assert_eq!(e.to_string(), "Interrupted");
+
+        CANCELLED_IN_TEST.set(false);
+        assert!(check().is_ok());
+        Ok(())
+    }
+}
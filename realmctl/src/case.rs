@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Entries of one directory whose names differ only in case, e.g. `Interface` and `interface`
+///
+/// Case-insensitive lookups (sharing rules, Wine) only ever see one of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseCollision {
+    /// Directory holding the entries, relative to the scanned root
+    pub parent: PathBuf,
+    pub names: Vec<String>,
+    /// Whether every entry is a directory, which [`merge_collision`] can combine
+    pub all_dirs: bool,
+}
+
+impl CaseCollision {
+    /// Name the others are merged into: the capitalized spelling the client itself uses,
+    /// then the entry with the most contents
+    pub fn preferred(&self, root: &Path) -> &str {
+        let dir = root.join(&self.parent);
+        self.names
+            .iter()
+            .max_by_key(|name| {
+                let capitalized = name.starts_with(|c: char| c.is_ascii_uppercase());
+                let entries = std::fs::read_dir(dir.join(name)).map_or(0, |d| d.count());
+                (capitalized, entries)
+            })
+            .map_or("", String::as_str)
+    }
+}
+
+/// Find case collisions anywhere below `root`, without following symlinks
+pub fn find_case_collisions(root: &Path) -> std::io::Result<Vec<CaseCollision>> {
+    let mut collisions = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(rel_dir) = pending.pop() {
+        let mut groups: BTreeMap<String, Vec<(String, bool)>> = BTreeMap::new();
+        for entry in std::fs::read_dir(root.join(&rel_dir))? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry.file_type()?.is_dir();
+            if is_dir {
+                pending.push(rel_dir.join(&name));
+            }
+            groups
+                .entry(name.to_lowercase())
+                .or_default()
+                .push((name, is_dir));
+        }
+        for entries in groups.into_values().filter(|entries| entries.len() > 1) {
+            let mut names: Vec<String> = entries.iter().map(|(name, _)| name.clone()).collect();
+            names.sort();
+            collisions.push(CaseCollision {
+                parent: rel_dir.clone(),
+                names,
+                all_dirs: entries.iter().all(|(_, is_dir)| *is_dir),
+            });
+        }
+    }
+    collisions.sort_by(|a, b| a.parent.cmp(&b.parent).then(a.names.cmp(&b.names)));
+    Ok(collisions)
+}
+
+/// Move the contents of the other directories of a collision into the preferred one
+///
+/// Entries existing in both are left where they are and returned; a directory emptied by
+/// the merge is removed.
+pub fn merge_collision(root: &Path, collision: &CaseCollision) -> std::io::Result<Vec<PathBuf>> {
+    let parent = root.join(&collision.parent);
+    let into = parent.join(collision.preferred(root));
+    let mut conflicts = Vec::new();
+    for name in &collision.names {
+        let from = parent.join(name);
+        if from != into {
+            merge_dir(&from, &into, &mut conflicts)?;
+        }
+    }
+    Ok(conflicts)
+}
+
+fn merge_dir(from: &Path, into: &Path, conflicts: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let source = entry.path();
+        let target = into.join(entry.file_name());
+        match std::fs::symlink_metadata(&target) {
+            Err(_) => std::fs::rename(&source, &target)?,
+            Ok(meta) if meta.is_dir() && entry.file_type()?.is_dir() => {
+                merge_dir(&source, &target, conflicts)?
+            }
+            Ok(_) => conflicts.push(source),
+        }
+    }
+    if std::fs::read_dir(from)?.next().is_none() {
+        std::fs::remove_dir(from)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_merge_case_collisions() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("WTF")).unwrap();
+        // Nothing can collide on a case-insensitive filesystem
+        if root.join("wtf").exists() {
+            return;
+        }
+        std::fs::write(root.join("WTF/Config.wtf"), "a").unwrap();
+        std::fs::write(root.join("WTF/config.wtf"), "b").unwrap();
+        std::fs::create_dir_all(root.join("Interface/AddOns/Questie")).unwrap();
+        std::fs::create_dir_all(root.join("interface/AddOns/Bagnon")).unwrap();
+        std::fs::write(root.join("Interface/AddOns/Questie/Questie.toc"), "").unwrap();
+        std::fs::write(root.join("interface/AddOns/Bagnon/Bagnon.toc"), "").unwrap();
+        std::fs::write(root.join("interface/readme.txt"), "").unwrap();
+
+        let collisions = find_case_collisions(root).unwrap();
+        assert_eq!(collisions.len(), 2);
+        assert_eq!(collisions[0].names, vec!["Interface", "interface"]);
+        assert!(collisions[0].all_dirs);
+        assert_eq!(collisions[1].parent, PathBuf::from("WTF"));
+        assert!(!collisions[1].all_dirs);
+
+        assert_eq!(collisions[0].preferred(root), "Interface");
+        let conflicts = merge_collision(root, &collisions[0]).unwrap();
+        assert!(conflicts.is_empty());
+        assert!(!root.join("interface").exists());
+        assert!(root.join("Interface/readme.txt").is_file());
+        assert!(root.join("Interface/AddOns/Bagnon/Bagnon.toc").is_file());
+        assert!(root.join("Interface/AddOns/Questie/Questie.toc").is_file());
+    }
+}
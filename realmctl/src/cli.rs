@@ -1,366 +1,5797 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use std::fmt;
 use std::path::{Path, PathBuf};
 
-use crate::{launch, load_config, write_realmlist};
+use crate::hooks::{HookEvent, load_hooks, run_hooks};
+use crate::realm::{parse_realmlist, read_realmlist};
+use crate::{
+    Config, ConfigFile, current_hostname, load_config, out, outln, spawn_with_log, store_realmlist,
+    switch_realmlist, write_realmlist,
+};
+
+/// Process exit codes used by commands meant to be scripted
+pub mod exit_code {
+    /// The command line is invalid, as clap reports it
+    pub const USAGE: u8 = 2;
+    /// The config file or game entry could not be loaded
+    pub const CONFIG: u8 = 3;
+    /// The game has no realmlist path configured, or the file can't be read
+    pub const REALMLIST_MISSING: u8 = 4;
+    /// Writing the realmlist failed
+    pub const WRITE_FAILED: u8 = 5;
+    /// The realmlist on disk does not match the expected value
+    pub const MISMATCH: u8 = 6;
+    /// The realm's logon server is not reachable
+    pub const REALM_DOWN: u8 = 7;
+    /// Base files don't match their expected checksums
+    pub const CORRUPT: u8 = 8;
+    /// A client started from the game directory is still running
+    pub const ALREADY_RUNNING: u8 = 9;
+    /// The operation was stopped with Ctrl-C
+    pub const INTERRUPTED: u8 = 130;
+}
+
+/// Error that makes the process exit with a specific code
+#[derive(Debug)]
+pub struct ExitError {
+    pub code: u8,
+    pub message: String,
+}
+
+impl ExitError {
+    pub fn new(code: u8, message: impl Into<String>) -> Self {
+        ExitError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ExitError {}
 
 /// WoW Client Manager - manage multiple WoW clients with shared resources
 #[derive(Parser)]
 #[command(name = "realmctl")]
 #[command(version, about, long_about = None)]
 pub struct Cli {
+    /// Without a command, the [defaults] action is run for the [defaults] game
     #[command(subcommand)]
-    pub command: Commands,
+    pub command: Option<Commands>,
+    /// Load manifest.toml and workspace.toml from other tools or releases, skipping unreadable keys
+    #[arg(long, global = true)]
+    pub lenient: bool,
+    /// Keep config, state, bases and workspaces next to the realmctl executable, with
+    /// relative paths so the whole setup can move between machines (e.g. on a USB drive)
+    #[arg(long, global = true)]
+    pub portable: bool,
+    /// Print results as JSON on stdout (list, du, status, verify-base, realm verify, clean,
+    /// backup list, launch); other output goes to stderr
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Show more detail on stderr: -v for every file operation, -vv for everything
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Print only results, warnings and errors (realm commands: nothing on success)
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Also write a debug log to the logs directory in realmctl's state directory
+    #[arg(long, global = true)]
+    pub log_file: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// A game name instead of a command runs the [defaults] action for it
+    #[command(external_subcommand)]
+    Game(Vec<String>),
+    /// Launch a WoW workspace
+    Launch {
+        /// Workspace names to launch (as in your config file); several start side by side
+        #[arg(required_unless_present = "all")]
+        workspaces: Vec<String>,
+        /// Launch the games of a [groups.<name>] section of your config file
+        #[arg(long, value_name = "GROUP", conflicts_with = "workspaces")]
+        all: Option<String>,
+        /// Seconds between starting clients when launching several (default: the group's
+        /// stagger_secs, else 5)
+        #[arg(long, value_name = "SECS")]
+        stagger: Option<u64>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+        /// Use this realmlist (or [realms] preset) for one session only; waits for the client and restores the previous one
+        #[arg(long)]
+        realm: Option<String>,
+        /// Don't show the server news configured with news_url
+        #[arg(long)]
+        no_news: bool,
+        /// Use only this of the configured accounts (default: ask when there are several);
+        /// repeat to give each of several workspaces its own, in order
+        #[arg(long)]
+        account: Vec<String>,
+        /// Start without addons: moves Interface/AddOns aside until the client exits
+        #[arg(long)]
+        safe: bool,
+        /// Stay until the client exits: log its output to realmctl-session.log, report the
+        /// exit code and run on_exit hooks
+        #[arg(long)]
+        wait: bool,
+        /// Launch even while a client started from the same directory is running
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show which games have a client running
+    Ps {
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Stop the running client of a game, including its wine processes on Linux
+    Kill {
+        /// Workspace name (as in your config file)
+        workspace: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "tui")]
+    /// Pick a game from an interactive list and launch it
+    Tui {
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Run a command such as winetricks or regedit in a game's wine prefix, locked against
+    /// launches and other commands sharing the prefix
+    Prefix {
+        /// Game name (as in your config file)
+        game: String,
+        /// Command to run with WINEPREFIX set, e.g. `winetricks dxvk`
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+        /// Run even while the prefix's wineserver is up
+        #[arg(long)]
+        force: bool,
+    },
+    /// Initialize a base WoW installation for workspace creation
+    InitBase {
+        /// Path to the WoW directory to use as base
+        path: PathBuf,
+        /// Profile to use (e.g., chromie-3.3.5a) or path to a custom profile.toml
+        #[arg(long, default_value = "chromie-3.3.5a")]
+        profile: String,
+        /// Continue an interrupted scan instead of hashing everything again
+        #[arg(long)]
+        resume: bool,
+        /// Merge directories whose names differ only in case (e.g. interface/ into Interface/)
+        /// without asking
+        #[arg(long)]
+        merge_case: bool,
+    },
+    /// Show how a directory would be classified as a base, without writing to it
+    Inspect {
+        /// Path to the WoW directory, e.g. a freshly downloaded client
+        path: PathBuf,
+        /// Profile to use (default: detected from the directory)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Check a base's files against its manifest checksums or the client's .torrent
+    VerifyBase {
+        /// Path to the base (or the directory the torrent was downloaded to)
+        path: PathBuf,
+        /// Check against the piece hashes of the .torrent the client was distributed with
+        /// instead of the checksums in manifest.toml
+        #[arg(long)]
+        torrent: Option<PathBuf>,
+    },
+
+    #[cfg(feature = "workspaces")]
+    /// Create a new workspace from a base installation
+    Create {
+        /// Name of the workspace
+        workspace: String,
+        /// Path to the base installation (must have manifest.toml)
+        #[arg(long)]
+        base: String,
+        /// Sharing rules (format: key=value, e.g., screenshots=global)
+        #[arg(long = "share", value_name = "KEY=VALUE")]
+        share: Vec<String>,
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+        /// Path to your config.toml (for hooks)
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+        /// Hard link patch MPQs and other MutableData, copying them only once changed
+        #[arg(long)]
+        lazy_mutable: bool,
+        /// Realmlist address or [realms] preset recorded in workspace.toml, used when
+        /// config.toml sets none
+        #[arg(long)]
+        realm: Option<String>,
+        /// Account recorded in workspace.toml, used when config.toml sets none
+        #[arg(long)]
+        account: Option<String>,
+        /// Ways to bring in the files of a role, tried in order (repeatable, e.g.
+        /// Executable=hardlink,copy; methods: hardlink, reflink, symlink, copy)
+        #[arg(long = "fallback", value_name = "ROLE=METHODS", value_parser = crate::workspace::parse_fallback)]
+        fallbacks: Vec<(String, Vec<crate::workspace::LinkMethod>)>,
+        /// Print every directory, link and copy the workspace would get, creating nothing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Time each way of linking a base into a workspace on the workspace filesystem
+    BenchLinking {
+        /// Path to the base installation (must have manifest.toml)
+        base: String,
+        /// Workspace root directory, where the throwaway workspaces are created
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Clean ephemeral files (cache, logs) from a workspace
+    Clean {
+        /// Workspace name to clean (as in your config file)
+        workspace: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+        /// Also clean WDB cache files
+        #[arg(long)]
+        wdb: bool,
+        /// Print what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Create a workspace from another base (client version) and carry over macros, SavedVariables and screenshots
+    Migrate {
+        /// Workspace name to migrate (as in your config file)
+        workspace: String,
+        /// Base of the new workspace (must have manifest.toml)
+        #[arg(long)]
+        to_base: String,
+        /// Name of the new workspace (default: <workspace directory>-<new base profile>)
+        #[arg(long)]
+        name: Option<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Give a workspace its own copies of lazily linked files (run before patching in place),
+    /// or of everything with --standalone
+    Materialize {
+        /// Workspace name (as in your config file)
+        workspace: String,
+        /// Files to copy, relative to the workspace (default: all lazily linked files)
+        paths: Vec<String>,
+        /// Copy every file and directory linked from the base or the shared roots, making
+        /// the workspace a standalone install that works without them
+        #[arg(long, conflicts_with = "paths")]
+        standalone: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Check a workspace against its workspace.log, or show why a file was linked or copied
+    Audit {
+        /// Workspace name (as in your config file)
+        workspace: String,
+        /// Show the recorded history of this file, relative to the workspace
+        #[arg(long)]
+        path: Option<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Repair a workspace's shared links and directories
+    Fix {
+        /// Workspace name to fix (as in your config file)
+        workspace: String,
+        /// What to do with real directories where a shared link is expected, without asking:
+        /// keep-local, adopt or skip
+        #[arg(long, value_name = "CHOICE")]
+        assume: Option<crate::workspace::ConflictChoice>,
+        /// Print every repair (directories, links, moves) without making it
+        #[arg(long)]
+        dry_run: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Bring new and changed files of an updated base into a workspace
+    Upgrade {
+        /// Workspace name to upgrade (as in your config file)
+        workspace: String,
+        /// Only list what would change
+        #[arg(long)]
+        dry_run: bool,
+        /// Also replace files the workspace changed on its own
+        #[arg(long)]
+        force: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Delete a workspace, listing files that exist only in it first
+    Delete {
+        /// Workspace name to delete (as in your config file)
+        workspace: String,
+        /// Save files unique to the workspace to this .tar.gz before deleting, to the
+        /// archives directory of the state directory without a path
+        #[arg(long, value_name = "FILE")]
+        archive: Option<Option<String>>,
+        /// Don't ask for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+        /// Print what would be archived and removed without doing it
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Remove directories under .shared that no workspace links to any more
+    Gc {
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+        /// Save the orphaned directories to this .tar.gz before removing them, to the
+        /// archives directory of the state directory without a path
+        #[arg(long, value_name = "FILE")]
+        archive: Option<Option<String>>,
+        /// Don't ask for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+        /// Print what would be archived and removed without doing it
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Back up a workspace's settings, addons and own screenshots, or list and restore backups
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Backup {
+        /// Workspace name to back up (as in your config file)
+        #[arg(required = true)]
+        workspace: Option<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+        #[command(subcommand)]
+        action: Option<BackupAction>,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Recreate a workspace on another machine over ssh, linked to a base there
+    Push {
+        /// Workspace name to push (as in your config file)
+        workspace: String,
+        /// Remote workspace root, e.g. me@desktop:~/.local/share/wow_workspaces
+        target: String,
+        /// Base path on the remote machine (default: same as the local base)
+        #[arg(long)]
+        remote_base: Option<String>,
+        /// Workspace name on the remote machine (default: same as here)
+        #[arg(long)]
+        name: Option<String>,
+        /// realmctl command on the remote machine
+        #[arg(long, default_value = "realmctl")]
+        remote_realmctl: String,
+        /// Show what would be transferred without connecting
+        #[arg(long)]
+        dry_run: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Switch, read and verify a game's realmlist (scriptable)
+    #[command(alias = "realmlist")]
+    Realm {
+        #[command(subcommand)]
+        action: RealmAction,
+    },
+    /// Create, extend, launch and inspect [groups.<name>] of games sharing a prefix, addons
+    /// and CVars
+    Group {
+        #[command(subcommand)]
+        action: GroupAction,
+    },
+    /// Install, list and remove addons of a game
+    Addon {
+        #[command(subcommand)]
+        action: AddonAction,
+    },
+    /// Export and import a game's WTF settings (Config.wtf, SavedVariables, macros)
+    Settings {
+        #[command(subcommand)]
+        action: SettingsAction,
+    },
+    /// Inspect config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Manage encrypted account credentials (accounts.enc)
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Remove old logs, crash reports, history, backups and archives according to the [retention] policy
+    Maintain {
+        /// Show what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Reclaim space taken by screenshots and other media kept more than once
+    Media {
+        #[command(subcommand)]
+        action: MediaAction,
+    },
+    #[cfg(feature = "workspaces")]
+    /// List bases and workspaces with their profile, size and last launch
+    List {
+        /// Directory holding bases (or a base itself), repeatable
+        #[arg(long, default_value = "~/Games/WoW")]
+        base_root: Vec<String>,
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Sum up disk use and savings of all bases and workspaces, and suggest maintenance
+    Summary {
+        /// Directory holding bases (or a base itself), repeatable
+        #[arg(long, default_value = "~/Games/WoW")]
+        base_root: Vec<String>,
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Show apparent size, unique disk usage and savings of every base, workspace and
+    /// shared directory, counting hard-linked files once
+    Du {
+        /// Directory holding bases (or a base itself), repeatable
+        #[arg(long, default_value = "~/Games/WoW")]
+        base_root: Vec<String>,
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Show health, disk usage and activity of bases and workspaces
+    #[command(group(clap::ArgGroup::new("target").required(true).args(["game", "all"])))]
+    Status {
+        /// Game name (as in your config file)
+        game: Option<String>,
+        /// Every workspace under the workspace root and every configured game
+        #[arg(long)]
+        all: bool,
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    #[cfg(feature = "workspaces")]
+    /// Run in the background and serve opt-in services
+    Daemon {
+        /// Serve Prometheus metrics on this localhost address (e.g., 127.0.0.1:9898)
+        #[arg(long, value_name = "ADDR")]
+        metrics: Option<std::net::SocketAddr>,
+        /// Warn about or close clients idle for longer than the [watchdog] section allows
+        #[arg(long)]
+        watchdog: bool,
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RealmAction {
+    /// Write a new realmlist for a game and verify it was stored
+    Set {
+        /// Game name (as in your config file)
+        game: String,
+        /// Realmlist host or [realms] preset to write (e.g., logon.chromiecraft.com)
+        host: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+        /// Print the change as a unified diff instead of writing it
+        #[arg(long)]
+        diff: bool,
+    },
+    /// Write a new realmlist for every game derived from a base or carrying a tag
+    #[command(group(clap::ArgGroup::new("selector").required(true).multiple(true).args(["base", "tag"])))]
+    SetAll {
+        /// Realmlist host or [realms] preset to write
+        host: String,
+        /// Select games whose workspace was created from this base (profile name or path)
+        #[arg(long)]
+        base: Option<String>,
+        /// Select games carrying this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+        /// Print the changes as a unified diff instead of writing them
+        #[arg(long)]
+        diff: bool,
+    },
+    /// Print the realmlist currently written for a game
+    #[command(alias = "show")]
+    Get {
+        /// Game name (as in your config file)
+        game: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// List the realmlist switches recorded for a game, newest first
+    History {
+        /// Game name (as in your config file)
+        game: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+        /// Show at most this many switches
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Restore the realmlist written before the last recorded switch
+    Undo {
+        /// Game name (as in your config file)
+        game: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Check whether the realm's logon server is up, noting expected maintenance
+    Status {
+        /// Game name (as in your config file)
+        game: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Compare the realmlist on disk with the configured one for every game
+    Audit {
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Check that the realmlist on disk matches the expected host
+    Verify {
+        /// Game name (as in your config file)
+        game: String,
+        /// Expected host (defaults to the configured realmlist)
+        host: Option<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
 }
 
-#[derive(Subcommand)]
-pub enum Commands {
-    /// Launch a WoW workspace
-    Launch {
-        /// Workspace name to launch (as in your config file)
-        workspace: String,
-        /// Path to your config.toml
-        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
-        config: String,
-    },
-    /// Initialize a base WoW installation for workspace creation
-    InitBase {
-        /// Path to the WoW directory to use as base
-        path: PathBuf,
-        /// Profile to use (e.g., chromie-3.3.5a)
-        #[arg(long, default_value = "chromie-3.3.5a")]
-        profile: String,
-    },
+#[derive(Subcommand)]
+pub enum GroupAction {
+    /// Add a [groups.<name>] section to your config file and install its addon pack
+    Create {
+        /// Group name
+        name: String,
+        /// Games or workspaces in the group (as in your config file)
+        #[arg(required = true)]
+        games: Vec<String>,
+        /// Wine prefix all games of the group run in
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Seconds between starting one client and the next
+        #[arg(long, value_name = "SECS", default_value_t = crate::group::DEFAULT_STAGGER_SECS)]
+        stagger: u64,
+        /// Account for each game, in order (repeatable)
+        #[arg(long)]
+        account: Vec<String>,
+        /// Addon archive or URL installed into every game (repeatable)
+        #[arg(long)]
+        addon: Vec<String>,
+        /// CVar written to every game's Config.wtf before launching, as KEY=VALUE (repeatable)
+        #[arg(long, value_name = "KEY=VALUE")]
+        cvar: Vec<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Add games to a group and install its addon pack into them
+    Add {
+        /// Group name
+        name: String,
+        /// Games or workspaces to add (as in your config file)
+        #[arg(required = true)]
+        games: Vec<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Launch every game of a group, same as `launch --all`
+    Launch {
+        /// Group name
+        name: String,
+        /// Seconds between starting clients (default: the group's stagger_secs)
+        #[arg(long, value_name = "SECS")]
+        stagger: Option<u64>,
+        /// Launch even while a client started from the same directory is running
+        #[arg(long)]
+        force: bool,
+        /// Print the group's CVars as a unified diff of each Config.wtf instead of launching
+        #[arg(long)]
+        diff: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Show which games of a group run and whether they have its addons and CVars
+    Status {
+        /// Group name
+        name: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AddonAction {
+    /// Install an addon from a zip archive or its URL into the game's Interface/AddOns
+    Install {
+        /// Path or http(s) URL of the archive
+        source: String,
+        /// Game or workspace name (as in your config file)
+        #[arg(long, short)]
+        workspace: String,
+        /// Name to record the addon under (default: its first folder)
+        #[arg(long)]
+        name: Option<String>,
+        /// Replace folders that were not installed by realmctl or belong to another addon
+        #[arg(long)]
+        force: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// List installed addons and folders not installed by realmctl
+    List {
+        /// Game or workspace name (as in your config file)
+        #[arg(long, short)]
+        workspace: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Check the sources of installed addons for new versions and install them
+    Update {
+        /// Addon to update (default: all)
+        name: Option<String>,
+        /// Game or workspace name (as in your config file)
+        #[arg(long, short)]
+        workspace: String,
+        /// Only report available updates
+        #[arg(long)]
+        check: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Compare the `## Interface:` version of every addon with the client's
+    Check {
+        /// Game or workspace name (as in your config file)
+        #[arg(long, short)]
+        workspace: String,
+        /// Interface number to check against, e.g. 30300 (default: from the client version)
+        #[arg(long)]
+        interface: Option<u32>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Remove an addon installed with `addon install`
+    Remove {
+        /// Addon name (as shown by `addon list`)
+        name: String,
+        /// Game or workspace name (as in your config file)
+        #[arg(long, short)]
+        workspace: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SettingsAction {
+    /// Pack the game's WTF directory into a .tar.zst archive, written to stdout by default
+    Export {
+        /// Game or workspace name (as in your config file)
+        workspace: String,
+        /// Archive to write (.tar.zst, .tar.gz or .tar)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Rename account folders to ACCOUNT1, ACCOUNT2, ... and drop account names from Config.wtf
+        #[arg(long)]
+        strip_accounts: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Copy settings exported with `settings export` into the game's WTF directory
+    Import {
+        /// Game or workspace name (as in your config file)
+        workspace: String,
+        /// Archive to read (default: stdin)
+        archive: Option<PathBuf>,
+        /// Account folder to use for the account of a --strip-accounts archive
+        #[arg(long)]
+        account: Option<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// List all game entries and whether they are ready to launch
+    List {
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Report duplicate game keys and other config pitfalls
+    Lint {
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Check each game entry's keys, types and paths, with the line of every problem
+    Validate {
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Rewrite deprecated keys and values to their current form (keeps a .bak copy)
+    Upgrade {
+        /// Show what would change without writing the file
+        #[arg(long)]
+        dry_run: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Add a game entry, or update the given keys of an existing one, keeping comments
+    Add {
+        /// Game name, the section header
+        name: String,
+        /// Game or workspace directory (required for a new entry)
+        #[arg(long)]
+        directory: Option<String>,
+        /// Realmlist address or [realms] preset
+        #[arg(long)]
+        realmlist: Option<String>,
+        /// Executable name relative to the directory
+        #[arg(long)]
+        executable: Option<String>,
+        /// Account name
+        #[arg(long)]
+        account: Option<String>,
+        /// Path to your config.toml (created when missing)
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+}
+
+#[cfg(feature = "workspaces")]
+#[derive(Subcommand)]
+pub enum MediaAction {
+    /// Replace identical screenshots in workspaces and shared directories by hard links
+    Dedup {
+        /// Clone duplicates copy-on-write instead, so each stays a separate file (btrfs, XFS, APFS)
+        #[arg(long)]
+        reflink: bool,
+        /// Show what would be replaced without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Workspace root directory
+        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
+        workspace_root: String,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+}
+
+#[cfg(feature = "workspaces")]
+#[derive(Subcommand)]
+pub enum BackupAction {
+    /// List backups with their age and size, oldest first
+    List {
+        /// Only the backups of this workspace
+        workspace: Option<String>,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Put the files of a backup back into a workspace, backing up the current ones first
+    Restore {
+        /// Workspace name to restore (as in your config file)
+        workspace: String,
+        /// Backup file name from `backup list`, or a path (default: the newest)
+        backup: Option<String>,
+        /// Print the files that would be restored without restoring them
+        #[arg(long)]
+        dry_run: bool,
+        /// Path to your config.toml
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SecretsAction {
+    /// Decrypt accounts.enc into $EDITOR and re-encrypt it on save
+    Edit {
+        /// Path to your config.toml (accounts.enc lives next to it)
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Store a credential of a game, asking for its value without echo
+    Set {
+        /// Game name (as in your config file)
+        game: String,
+        /// account, password or accounts.<name>
+        key: String,
+        /// Keep the encryption key in the OS keyring (Secret Service, Keychain, DPAPI)
+        #[arg(long, conflicts_with = "passphrase")]
+        keyring: bool,
+        /// Encrypt with a master passphrase, asked for on every launch
+        #[arg(long)]
+        passphrase: bool,
+        /// Path to your config.toml (accounts.enc lives next to it)
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Print a stored credential of a game
+    Get {
+        /// Game name (as in your config file)
+        game: String,
+        /// account, password or accounts.<name>
+        key: String,
+        /// Path to your config.toml (accounts.enc lives next to it)
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+    /// Remove a stored credential of a game
+    Remove {
+        /// Game name (as in your config file)
+        game: String,
+        /// account, password or accounts.<name>
+        key: String,
+        /// Path to your config.toml (accounts.enc lives next to it)
+        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
+        config: String,
+    },
+}
+
+impl Cli {
+    pub fn run(self) -> Result<()> {
+        crate::compat::set_lenient(self.lenient);
+        if self.json {
+            crate::output::enable_json();
+        }
+        crate::logging::init(self.verbose, self.quiet, self.log_file);
+        let quiet = self.quiet;
+        if let Some(root) = crate::paths::executable_dir()
+            .filter(|dir| self.portable || dir.join(crate::paths::PORTABLE_MARKER).exists())
+        {
+            crate::paths::set_portable_root(root);
+        }
+        let Some(command) = self.command else {
+            return run_default(Vec::new(), self.lenient, self.portable, quiet);
+        };
+        match command {
+            Commands::Game(args) => {
+                run_default(args, self.lenient, self.portable, quiet)?;
+            }
+            Commands::Launch {
+                workspaces,
+                all,
+                stagger,
+                config,
+                realm,
+                no_news,
+                account,
+                safe,
+                wait,
+                force,
+            } => {
+                let options = LaunchOptions {
+                    realm: realm.map(|realm| resolve_realm(&config, &realm)),
+                    no_news,
+                    safe,
+                    wait,
+                    force,
+                    ..LaunchOptions::default()
+                };
+                match all {
+                    Some(name) => cmd_launch_group(&name, account, stagger, &config, options)?,
+                    None => cmd_launch_games(
+                        &workspaces,
+                        account,
+                        stagger.unwrap_or(crate::group::DEFAULT_STAGGER_SECS),
+                        &config,
+                        &options,
+                    )?,
+                }
+            }
+            Commands::Ps { config } => {
+                cmd_ps(&config)?;
+            }
+            Commands::Kill { workspace, config } => {
+                cmd_kill(&workspace, &config)?;
+            }
+            #[cfg(feature = "tui")]
+            Commands::Tui { config } => {
+                crate::tui::run(&config, |game| {
+                    cmd_launch(game, &config, &LaunchOptions::default())
+                })?;
+            }
+            Commands::Prefix {
+                game,
+                command,
+                config,
+                force,
+            } => {
+                cmd_prefix(&game, &command, &config, force)?;
+            }
+            Commands::InitBase {
+                path,
+                profile,
+                resume,
+                merge_case,
+            } => {
+                cmd_init_base(&path, &profile, resume, merge_case)?;
+            }
+            Commands::Inspect { path, profile } => {
+                cmd_inspect(&path, profile.as_deref())?;
+            }
+            Commands::VerifyBase { path, torrent } => {
+                cmd_verify_base(&path, torrent.as_deref())?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Create {
+                workspace,
+                base,
+                share,
+                workspace_root,
+                config,
+                lazy_mutable,
+                realm,
+                account,
+                fallbacks,
+                dry_run,
+            } => {
+                let options = crate::workspace::CreateOptions {
+                    lazy_mutable,
+                    realmlist: realm,
+                    account,
+                    link_fallbacks: fallbacks.into_iter().collect(),
+                };
+                cmd_create_workspace(
+                    &workspace,
+                    &base,
+                    &share,
+                    &workspace_root,
+                    &config,
+                    options,
+                    dry_run,
+                )?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::BenchLinking {
+                base,
+                workspace_root,
+            } => {
+                cmd_bench_linking(&base, &workspace_root)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Migrate {
+                workspace,
+                to_base,
+                name,
+                config,
+            } => {
+                cmd_migrate(&workspace, &to_base, name.as_deref(), &config)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Materialize {
+                workspace,
+                paths,
+                standalone,
+                config,
+            } => {
+                if standalone {
+                    cmd_materialize_standalone(&workspace, &config)?;
+                } else {
+                    cmd_materialize(&workspace, &paths, &config)?;
+                }
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Audit {
+                workspace,
+                path,
+                config,
+            } => {
+                cmd_audit(&workspace, path.as_deref(), &config)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Clean {
+                workspace,
+                config,
+                wdb,
+                dry_run,
+            } => {
+                cmd_clean(&workspace, &config, wdb, dry_run)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Fix {
+                workspace,
+                assume,
+                dry_run,
+                config,
+            } => {
+                cmd_fix(&workspace, assume, dry_run, &config)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Upgrade {
+                workspace,
+                dry_run,
+                force,
+                config,
+            } => {
+                cmd_upgrade(&workspace, dry_run, force, &config)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Delete {
+                workspace,
+                archive,
+                yes,
+                dry_run,
+                config,
+            } => {
+                let archive = archive.map(|path| archive_path(path.as_deref(), &workspace));
+                cmd_delete(&workspace, archive.as_deref(), yes, dry_run, &config)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Backup {
+                workspace,
+                config,
+                action,
+            } => match action {
+                None => {
+                    let workspace = workspace.context("Missing workspace name")?;
+                    cmd_backup(&workspace, &config)?;
+                }
+                Some(BackupAction::List { workspace, config }) => {
+                    cmd_backup_list(workspace.as_deref(), &config)?;
+                }
+                Some(BackupAction::Restore {
+                    workspace,
+                    backup,
+                    dry_run,
+                    config,
+                }) => {
+                    cmd_backup_restore(&workspace, backup.as_deref(), dry_run, &config)?;
+                }
+            },
+            #[cfg(feature = "workspaces")]
+            Commands::Gc {
+                workspace_root,
+                archive,
+                yes,
+                dry_run,
+            } => {
+                let archive = archive.map(|path| archive_path(path.as_deref(), "shared"));
+                cmd_gc(&workspace_root, archive.as_deref(), yes, dry_run)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Push {
+                workspace,
+                target,
+                remote_base,
+                name,
+                remote_realmctl,
+                dry_run,
+                config,
+            } => {
+                cmd_push(
+                    &workspace,
+                    &target,
+                    remote_base.as_deref(),
+                    name.as_deref(),
+                    &remote_realmctl,
+                    dry_run,
+                    &config,
+                )?;
+            }
+            Commands::Realm { action } => match action {
+                RealmAction::Set {
+                    game,
+                    host,
+                    config,
+                    diff,
+                } => {
+                    let host = resolve_realm(&config, &host);
+                    if diff {
+                        let (_, path) = realm_target(&game, &config)?;
+                        print_file_diff(&path, &crate::realmlist_content(&host))?;
+                    } else {
+                        cmd_realm_set(&game, &host, &config, quiet)?;
+                    }
+                }
+                RealmAction::SetAll {
+                    host,
+                    base,
+                    tag,
+                    config,
+                    diff,
+                } => {
+                    let host = resolve_realm(&config, &host);
+                    let selection = (base.as_deref(), tag.as_deref());
+                    cmd_realm_set_all(&host, selection, &config, quiet, diff)?;
+                }
+                RealmAction::Get { game, config } => {
+                    cmd_realm_get(&game, &config, quiet)?;
+                }
+                RealmAction::History {
+                    game,
+                    config,
+                    limit,
+                } => {
+                    cmd_realm_history(&game, &config, limit)?;
+                }
+                RealmAction::Undo { game, config } => {
+                    cmd_realm_undo(&game, &config, quiet)?;
+                }
+                RealmAction::Status { game, config } => {
+                    cmd_realm_status(&game, &config, quiet)?;
+                }
+                RealmAction::Audit { config } => {
+                    cmd_realm_audit(&config)?;
+                }
+                RealmAction::Verify { game, host, config } => {
+                    let host = host.map(|host| resolve_realm(&config, &host));
+                    cmd_realm_verify(&game, host.as_deref(), &config, quiet)?;
+                }
+            },
+            Commands::Group { action } => match action {
+                GroupAction::Create {
+                    name,
+                    games,
+                    prefix,
+                    stagger,
+                    account,
+                    addon,
+                    cvar,
+                    config,
+                } => {
+                    let mut group = crate::group::GroupConfig::new(games);
+                    group.prefix = prefix;
+                    group.stagger_secs = stagger;
+                    group.accounts = account;
+                    group.addons = addon;
+                    for cvar in cvar {
+                        let Some((key, value)) = cvar.split_once('=') else {
+                            anyhow::bail!("--cvar expects KEY=VALUE, got {cvar}");
+                        };
+                        group
+                            .cvars
+                            .insert(key.trim().to_string(), value.trim().to_string());
+                    }
+                    cmd_group_create(&name, group, &config)?;
+                }
+                GroupAction::Add {
+                    name,
+                    games,
+                    config,
+                } => {
+                    cmd_group_add(&name, &games, &config)?;
+                }
+                GroupAction::Launch {
+                    name,
+                    diff: true,
+                    config,
+                    ..
+                } => {
+                    cmd_group_diff(&name, &config)?;
+                }
+                GroupAction::Launch {
+                    name,
+                    stagger,
+                    force,
+                    diff: false,
+                    config,
+                } => {
+                    let options = LaunchOptions {
+                        force,
+                        ..LaunchOptions::default()
+                    };
+                    cmd_launch_group(&name, Vec::new(), stagger, &config, options)?;
+                }
+                GroupAction::Status { name, config } => {
+                    cmd_group_status(&name, &config)?;
+                }
+            },
+            Commands::Addon { action } => match action {
+                AddonAction::Install {
+                    source,
+                    workspace,
+                    name,
+                    force,
+                    config,
+                } => {
+                    cmd_addon_install(&source, &workspace, name.as_deref(), force, &config)?;
+                }
+                AddonAction::List { workspace, config } => {
+                    cmd_addon_list(&workspace, &config)?;
+                }
+                AddonAction::Update {
+                    name,
+                    workspace,
+                    check,
+                    config,
+                } => {
+                    cmd_addon_update(name.as_deref(), &workspace, check, &config)?;
+                }
+                AddonAction::Check {
+                    workspace,
+                    interface,
+                    config,
+                } => {
+                    cmd_addon_check(&workspace, interface, &config)?;
+                }
+                AddonAction::Remove {
+                    name,
+                    workspace,
+                    config,
+                } => {
+                    cmd_addon_remove(&name, &workspace, &config)?;
+                }
+            },
+            Commands::Settings { action } => match action {
+                SettingsAction::Export {
+                    workspace,
+                    output,
+                    strip_accounts,
+                    config,
+                } => {
+                    cmd_settings_export(&workspace, output.as_deref(), strip_accounts, &config)?;
+                }
+                SettingsAction::Import {
+                    workspace,
+                    archive,
+                    account,
+                    config,
+                } => {
+                    cmd_settings_import(&workspace, archive.as_deref(), account, &config)?;
+                }
+            },
+            Commands::Config { action } => match action {
+                ConfigAction::List { config } => {
+                    cmd_config_list(&config)?;
+                }
+                ConfigAction::Lint { config } => {
+                    cmd_config_lint(&config)?;
+                }
+                ConfigAction::Validate { config } => {
+                    cmd_config_validate(&config)?;
+                }
+                ConfigAction::Upgrade { dry_run, config } => {
+                    cmd_config_upgrade(dry_run, &config)?;
+                }
+                ConfigAction::Add {
+                    name,
+                    directory,
+                    realmlist,
+                    executable,
+                    account,
+                    config,
+                } => {
+                    let entry = GameEntry {
+                        directory,
+                        realmlist,
+                        executable,
+                        account,
+                    };
+                    cmd_config_add(&name, &entry, &config)?;
+                }
+            },
+            Commands::Secrets { action } => match action {
+                SecretsAction::Edit { config } => {
+                    cmd_secrets_edit(&config)?;
+                }
+                SecretsAction::Set {
+                    game,
+                    key,
+                    keyring,
+                    passphrase,
+                    config,
+                } => {
+                    cmd_secrets_set(&game, &key, keyring, passphrase, &config)?;
+                }
+                SecretsAction::Get { game, key, config } => {
+                    cmd_secrets_get(&game, &key, &config)?;
+                }
+                SecretsAction::Remove { game, key, config } => {
+                    cmd_secrets_remove(&game, &key, &config)?;
+                }
+            },
+            #[cfg(feature = "workspaces")]
+            Commands::Maintain {
+                dry_run,
+                workspace_root,
+                config,
+            } => {
+                cmd_maintain(dry_run, &workspace_root, &config)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Media { action } => match action {
+                MediaAction::Dedup {
+                    reflink,
+                    dry_run,
+                    workspace_root,
+                    config,
+                } => {
+                    let mode = if reflink {
+                        crate::media::DedupMode::Reflink
+                    } else {
+                        crate::media::DedupMode::Hardlink
+                    };
+                    cmd_media_dedup(mode, dry_run, &workspace_root, &config)?;
+                }
+            },
+            #[cfg(feature = "workspaces")]
+            Commands::Summary {
+                base_root,
+                workspace_root,
+            } => {
+                cmd_summary(&base_root, &workspace_root)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Du {
+                base_root,
+                workspace_root,
+            } => {
+                cmd_du(&base_root, &workspace_root)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::List {
+                base_root,
+                workspace_root,
+            } => {
+                cmd_list(&base_root, &workspace_root)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Status {
+                game,
+                all: _,
+                workspace_root,
+                config,
+            } => {
+                cmd_status(game.as_deref(), &workspace_root, &config)?;
+            }
+            #[cfg(feature = "workspaces")]
+            Commands::Daemon {
+                metrics,
+                watchdog,
+                workspace_root,
+                config,
+            } => {
+                cmd_daemon(metrics, watchdog, &workspace_root, &config)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run the `[defaults]` action for `realmctl <game> [ARGS]`, or for the default game of a
+/// bare `realmctl`
+fn run_default(args: Vec<String>, lenient: bool, portable: bool, quiet: bool) -> Result<()> {
+    use clap::CommandFactory;
+
+    let config_path = args
+        .iter()
+        .position(|arg| arg == "--config")
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|arg| arg.strip_prefix("--config=").map(str::to_string))
+        })
+        .unwrap_or_else(|| "~/.config/realmctl/config.toml".to_string());
+    let name = args.first().map(String::as_str);
+    let (defaults, is_game) = crate::defaults::load_defaults(&config_path, name);
+    if let Some(name) = name
+        && !is_game
+    {
+        return Err(ExitError::new(
+            exit_code::USAGE,
+            format!("'{name}' is neither a command nor a game in {config_path}, see --help"),
+        )
+        .into());
+    }
+    let Some(command_line) = defaults.command_line(&args) else {
+        Cli::command().print_help()?;
+        return Err(ExitError::new(
+            exit_code::USAGE,
+            "No command given and no [defaults] game configured",
+        )
+        .into());
+    };
+
+    let mut argv = vec!["realmctl".to_string()];
+    if lenient {
+        argv.push("--lenient".to_string());
+    }
+    if portable {
+        argv.push("--portable".to_string());
+    }
+    if quiet {
+        argv.push("--quiet".to_string());
+    }
+    argv.extend(command_line);
+    Cli::try_parse_from(argv).unwrap_or_else(|e| e.exit()).run()
+}
+
+/// Optional behaviour of [`cmd_launch`]
+#[derive(Debug, Clone, Default)]
+struct LaunchOptions {
+    /// Realmlist for this session only, see [`launch_with_realm`]
+    realm: Option<String>,
+    no_news: bool,
+    account: Option<String>,
+    /// Start without addons
+    safe: bool,
+    /// Supervise the client until it exits
+    wait: bool,
+    /// Ignore a client already running from the directory
+    force: bool,
+    /// WINEPREFIX shared by the games of a group
+    prefix: Option<PathBuf>,
+    /// Written to Config.wtf before starting, a group's CVar preset
+    cvars: Vec<(String, String)>,
+}
+
+fn cmd_launch(workspace: &str, config_path: &str, options: &LaunchOptions) -> Result<()> {
+    let LaunchOptions {
+        no_news,
+        safe,
+        wait,
+        force,
+        ..
+    } = *options;
+    let realm = options.realm.as_deref();
+    outln!("Loading configuration for:\n\t{workspace}");
+    let mut game_cfg = load_config(config_path, workspace)?;
+    // A second client in the directory would overwrite the first one's WTF and Cache
+    let launch_lock = match crate::instance::acquire(&game_cfg.directory, force) {
+        Ok(lock) => Some(lock),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err(ExitError::new(
+                exit_code::ALREADY_RUNNING,
+                format!("{workspace} is already running, {e}. Pass --force to launch anyway"),
+            )
+            .into());
+        }
+        Err(e) => {
+            tracing::warn!("⚠ Could not lock {}: {e}", game_cfg.directory.display());
+            None
+        }
+    };
+    let account = match &options.account {
+        Some(account) => Some(account.clone()),
+        None => pick_account(&game_cfg.account_names())?,
+    };
+    if let Some(account) = account {
+        game_cfg
+            .select_account(&account)
+            .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    }
+    if let Some(prefix) = &options.prefix {
+        game_cfg.env.insert(
+            "WINEPREFIX".to_string(),
+            prefix.to_string_lossy().to_string(),
+        );
+    }
+    if !options.cvars.is_empty() {
+        let config_wtf = crate::wtf::config_wtf_path(&game_cfg.directory, &game_cfg.executable);
+        let values: Vec<(&str, String)> = options
+            .cvars
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.clone()))
+            .collect();
+        if let Err(e) = crate::wtf::apply_values(&config_wtf, &values) {
+            tracing::warn!("⚠ Could not apply CVars to {}: {e}", config_wtf.display());
+        }
+    }
+    if let Some(url) = game_cfg.news_url.as_deref().filter(|_| !no_news) {
+        print_news(url);
+    }
+    if let Some((window, end)) =
+        crate::maintenance::active_window(&game_cfg.maintenance, crate::state::now())
+    {
+        tracing::warn!(
+            "⚠ {workspace} is in its maintenance window ({}) until {}, the realm may be down",
+            window.spec,
+            crate::maintenance::format_utc(end)
+        );
+    }
+    #[cfg(feature = "workspaces")]
+    {
+        print_launch_health(workspace, &game_cfg.directory);
+        match crate::workspace::check_lazy_files(&game_cfg.directory) {
+            Ok(warnings) => warnings.iter().for_each(|w| tracing::warn!("⚠ {w}")),
+            Err(e) => tracing::warn!("⚠ Failed to check lazily linked files: {e}"),
+        }
+    }
+
+    // The realm the client is about to connect to: the override, else what gets written,
+    // else what is already on disk
+    let target_realm = realm
+        .map(str::to_string)
+        .or(game_cfg.realmlist.clone())
+        .or_else(|| {
+            game_cfg
+                .realmlist_path()
+                .and_then(|path| read_realmlist(&path).ok().flatten())
+        });
+    if game_cfg.clear_wdb_on_realm_change != Some(false)
+        && let Some(target_realm) = &target_realm
+    {
+        clear_wdb_on_realm_change(&game_cfg.directory, target_realm);
+    }
+
+    let hooks = load_hooks(config_path);
+    let payload = serde_json::json!({
+        "game": workspace,
+        "directory": game_cfg.directory,
+        "executable": game_cfg.executable,
+        "realmlist": realm.or(game_cfg.realmlist.as_deref()),
+        "account": game_cfg.account_names().first(),
+    });
+    game_cfg.launch_cmd = game_cfg
+        .launch_cmd
+        .map(|command| crate::hooks::expand_template(&command, &payload));
+    let vars = [
+        ("WORKSPACE", workspace.to_string()),
+        (
+            "REALMLIST",
+            realm
+                .or(game_cfg.realmlist.as_deref())
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        (
+            "ACCOUNT",
+            game_cfg
+                .account_names()
+                .first()
+                .copied()
+                .unwrap_or_default()
+                .to_string(),
+        ),
+    ];
+    crate::hooks::run_game_commands(
+        "pre_launch",
+        &game_cfg.pre_launch,
+        &game_cfg.directory,
+        &vars,
+        &payload,
+    )
+    .context("pre_launch failed, not launching")?;
+
+    let addons = crate::addons::addons_dir(&game_cfg.directory, &game_cfg.executable);
+    // An earlier safe session ends here if realmctl did not see the client exit
+    match crate::addons::restore_addons(&addons) {
+        Ok(true) => tracing::warn!("⚠ Restored addons left disabled by an earlier --safe launch"),
+        Ok(false) => {}
+        Err(e) => tracing::warn!("⚠ {e}"),
+    }
+    if !safe
+        && let Some(client) =
+            crate::addons::interface_version(&game_profile(&game_cfg.directory).version)
+    {
+        let incompatible = crate::addons::check_addons(&addons, client)
+            .iter()
+            .filter(|check| check.compatibility == crate::addons::Compatibility::Incompatible)
+            .count();
+        if incompatible > 0 {
+            tracing::warn!(
+                "⚠ {incompatible} addon(s) made for another client version, see `realmctl addon check -w {workspace}`"
+            );
+        }
+    }
+    let parked = safe
+        && crate::addons::park_addons(&addons)
+            .with_context(|| format!("Failed to disable addons in {}", addons.display()))?;
+    if safe {
+        outln!(
+            "Safe mode, addons disabled for this session:\n\t{}",
+            addons.display()
+        );
+    }
+
+    // Two launches setting up a shared prefix at once leave it broken
+    let prefix = game_cfg.wine_prefix().filter(|_| cfg!(target_os = "linux"));
+    let prefix_lock = prefix.as_deref().and_then(|prefix| {
+        crate::prefix::lock_prefix(prefix)
+            .inspect_err(|e| tracing::warn!("⚠ Could not lock {}: {e}", prefix.display()))
+            .ok()
+    });
+
+    // Only attached launches keep realmctl running alongside the client
+    let attached = realm.is_some() || safe || wait || !game_cfg.post_exit.is_empty();
+    let watchdog = crate::watchdog::load_watchdog(config_path).filter(|_| attached);
+    let log = wait.then(|| game_cfg.directory.join(crate::SESSION_LOG));
+    let exit_payload = payload.clone();
+    let started_pid = std::cell::Cell::new(None);
+    let on_started = |pid| {
+        started_pid.set(Some(pid));
+        if let Some(Err(e)) = launch_lock.as_ref().map(|lock| lock.set_pid(pid)) {
+            tracing::warn!(
+                "⚠ Could not record the client in {}: {e}",
+                crate::instance::LAUNCH_LOCK
+            );
+        }
+        if let (Some(prefix), Some(lock)) = (&prefix, prefix_lock) {
+            if !crate::prefix::is_initialized(prefix) {
+                outln!("Waiting for wine to set up the prefix...");
+                crate::prefix::wait_for_initialization(prefix, crate::prefix::INIT_TIMEOUT);
+            }
+            drop(lock);
+        }
+        record_launch(&game_cfg.directory, target_realm.as_deref(), workspace, pid);
+        if let Some(watchdog) = watchdog {
+            crate::watchdog::spawn_watch(workspace, &game_cfg.directory, watchdog);
+        }
+        run_hooks(&hooks, HookEvent::Launch, payload)
+    };
+    let started_at = crate::state::now();
+    let result = match realm {
+        Some(realm) => launch_with_realm(&game_cfg, realm, log.as_deref(), on_started).map(Some),
+        None => launch_direct(&game_cfg, attached, log.as_deref(), on_started),
+    };
+    if attached {
+        forget_exited_client(&game_cfg.directory);
+        if let Some(lock) = launch_lock {
+            let _ = lock.release();
+        }
+    }
+
+    if parked {
+        match crate::addons::restore_addons(&addons) {
+            Ok(_) => outln!("Addons restored:\n\t{}", addons.display()),
+            Err(e) => tracing::error!("✗ Failed to restore addons: {e}"),
+        }
+    }
+    if crate::clipboard::is_pending() {
+        let secs = game_cfg
+            .clipboard_clear_secs
+            .unwrap_or(crate::clipboard::DEFAULT_CLEAR_SECS);
+        outln!("Clearing the password from the clipboard {secs}s after copying it...");
+        crate::clipboard::wait_for_clear();
+    }
+
+    let result = result?;
+    if crate::output::is_json() {
+        let mut report = exit_payload.clone();
+        report["pid"] = started_pid.get().into();
+        report["exit_code"] = result.and_then(|status| status.code()).into();
+        report["log"] = serde_json::json!(log);
+        crate::output::emit(&report)?;
+    }
+    let Some(status) = result else {
+        return Ok(());
+    };
+    let mut exit_payload = exit_payload;
+    exit_payload["exit_code"] = status.code().into();
+    exit_payload["log"] = serde_json::json!(log);
+    if wait {
+        match status.code() {
+            Some(0) => outln!("✓ Client exited normally"),
+            Some(code) => outln!("✗ Client exited with code {code}"),
+            None => outln!("✗ Client was terminated ({status})"),
+        }
+        run_hooks(&hooks, HookEvent::Exit, exit_payload.clone());
+    }
+    if !game_cfg.post_exit.is_empty() {
+        let mut vars = vars.to_vec();
+        vars.push((
+            "EXIT_CODE",
+            status.code().map(|c| c.to_string()).unwrap_or_default(),
+        ));
+        if let Err(e) = crate::hooks::run_game_commands(
+            "post_exit",
+            &game_cfg.post_exit,
+            &game_cfg.directory,
+            &vars,
+            &exit_payload,
+        ) {
+            tracing::warn!("⚠ post_exit failed: {e}");
+        }
+    }
+    let crashed = crate::crash::session_crashed(
+        status,
+        &game_cfg.directory,
+        &game_cfg.executable,
+        started_at,
+    );
+    if !check_crash_loop(&game_cfg.directory, crashed) {
+        return Ok(());
+    }
+    if safe {
+        outln!(
+            "\n⚠ The client crashed {} times within {} minutes even without addons, see its crash reports in Errors",
+            crate::crash::CRASH_LOOP_COUNT,
+            crate::crash::CRASH_LOOP_WINDOW / 60
+        );
+        return Ok(());
+    }
+    recover_crash_loop(workspace, config_path)
+}
+
+/// Record how an attached session ended, returns true when the client is crash looping
+fn check_crash_loop(dir: &Path, crashed: bool) -> bool {
+    let now = crate::state::now();
+    let mut looping = false;
+    crate::state::update_state(|state| {
+        let crashes = &mut state.directory_mut(dir).crashes;
+        if crashed {
+            crate::crash::record_crash(crashes, now);
+            looping = crate::crash::is_crash_loop(crashes, now);
+        } else {
+            crashes.clear();
+        }
+    });
+    looping
+}
+
+/// Stop and suggest the usual first steps after repeated crashes: no addons, no WDB cache
+///
+/// On a terminal, offers to take them right away.
+fn recover_crash_loop(workspace: &str, config_path: &str) -> Result<()> {
+    use std::io::{IsTerminal, Write};
+    outln!(
+        "\n⚠ The client crashed {} times within {} minutes, looks like a crash loop",
+        crate::crash::CRASH_LOOP_COUNT,
+        crate::crash::CRASH_LOOP_WINDOW / 60
+    );
+    outln!("Usual first steps: remove the WDB cache and start without addons:");
+    if cfg!(feature = "workspaces") {
+        outln!("\trealmctl clean {workspace} --wdb");
+    }
+    outln!("\trealmctl launch {workspace} --safe");
+    if !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+    out!("Clean the WDB cache and launch in safe mode now? [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        return Ok(());
+    }
+    let game_cfg = load_config(config_path, workspace)?;
+    outln!("Clearing WDB cache:");
+    clear_wdb(&game_cfg.directory);
+    let options = LaunchOptions {
+        no_news: true,
+        safe: true,
+        ..Default::default()
+    };
+    cmd_launch(workspace, config_path, &options)
+}
+
+/// Launch one game, or several side by side with `accounts` given out in order
+fn cmd_launch_games(
+    games: &[String],
+    accounts: Vec<String>,
+    stagger_secs: u64,
+    config_path: &str,
+    options: &LaunchOptions,
+) -> Result<()> {
+    if accounts.len() > games.len() {
+        anyhow::bail!("Got more accounts than workspaces to launch");
+    }
+    let accounts = crate::group::assign_accounts(games, &accounts);
+    match games {
+        [game] => {
+            let options = LaunchOptions {
+                account: accounts[0].clone(),
+                ..options.clone()
+            };
+            cmd_launch(game, config_path, &options)
+        }
+        _ => cmd_launch_many(
+            games,
+            &accounts,
+            config_path,
+            options,
+            std::time::Duration::from_secs(stagger_secs),
+        ),
+    }
+}
+
+/// Launch the games of a `[groups.<name>]` section with its accounts, prefix and CVars
+fn cmd_launch_group(
+    name: &str,
+    accounts: Vec<String>,
+    stagger: Option<u64>,
+    config_path: &str,
+    options: LaunchOptions,
+) -> Result<()> {
+    let group = crate::group::load_group(config_path, name)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e))?;
+    let accounts = if accounts.is_empty() {
+        group.accounts.clone()
+    } else {
+        accounts
+    };
+    let options = LaunchOptions {
+        prefix: group.prefix_path(),
+        cvars: group.cvars.clone().into_iter().collect(),
+        ..options
+    };
+    cmd_launch_games(
+        &group.games,
+        accounts,
+        stagger.unwrap_or(group.stagger_secs),
+        config_path,
+        &options,
+    )
+}
+
+/// Launch several games side by side (multiboxing), starting one every `stagger`
+fn cmd_launch_many(
+    games: &[String],
+    accounts: &[Option<String>],
+    config_path: &str,
+    options: &LaunchOptions,
+    stagger: std::time::Duration,
+) -> Result<()> {
+    let mut directories = Vec::new();
+    let mut prefixes = Vec::new();
+    let mut launches = Vec::new();
+    for (game, account) in games.iter().zip(accounts) {
+        let config = load_config(config_path, game)
+            .map_err(|e| ExitError::new(exit_code::CONFIG, format!("{game}: {e}")))?;
+        directories.push((game.clone(), config.directory.clone()));
+        if let Some(prefix) = config.wine_prefix().filter(|_| cfg!(target_os = "linux")) {
+            prefixes.push((game.clone(), prefix));
+        }
+        // Ask now, prompts of clients starting side by side would interleave
+        let account = match account {
+            Some(account) => Some(account.clone()),
+            None => pick_account(&config.account_names())?,
+        };
+        let options = LaunchOptions {
+            account,
+            ..options.clone()
+        };
+        launches.push((game, options));
+    }
+    let mut shared = crate::group::shared_paths(&directories);
+    // A group's prefix is shared on purpose
+    if options.prefix.is_none() {
+        shared.extend(crate::group::shared_paths(&prefixes));
+    }
+    if let Some((path, users)) = shared.first() {
+        return Err(ExitError::new(
+            exit_code::CONFIG,
+            format!(
+                "{} all use {}, clients launched together need their own directory and wine prefix",
+                users.join(", "),
+                path.display()
+            ),
+        )
+        .into());
+    }
+
+    outln!(
+        "Launching {} clients, {}s apart:\n\t{}",
+        games.len(),
+        stagger.as_secs(),
+        games.join(", ")
+    );
+    let results: Vec<(&String, Result<()>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = launches
+            .iter()
+            .enumerate()
+            .map(|(i, (game, options))| {
+                scope.spawn(move || {
+                    std::thread::sleep(stagger * i as u32);
+                    (*game, cmd_launch(game, config_path, options))
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("launch thread panicked"))
+            .collect()
+    });
+    let mut failed = Vec::new();
+    for (game, result) in results {
+        if let Err(e) = result {
+            tracing::error!("✗ {game}: {e:#}");
+            failed.push(game.as_str());
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{} of {} clients failed: {}",
+            failed.len(),
+            games.len(),
+            failed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Write the configured realmlist and launch, waiting for the client to exit if `attached`
+fn launch_direct(
+    config: &Config,
+    attached: bool,
+    log: Option<&Path>,
+    on_started: impl FnOnce(u32),
+) -> Result<Option<std::process::ExitStatus>> {
+    if let (Some(realmlist), Some(realmlist_path)) = (&config.realmlist, config.realmlist_path()) {
+        write_realmlist(&config.directory, &realmlist_path, realmlist)?;
+    }
+    let mut child = spawn_with_log(config, log)?;
+    on_started(child.id());
+    if !attached {
+        return Ok(None);
+    }
+    outln!("Waiting for the client to exit...");
+    let status = child.wait()?;
+    if !status.success() && log.is_none() {
+        tracing::warn!("⚠ Client exited with {status}");
+    }
+    Ok(Some(status))
+}
+
+fn cmd_prefix(game: &str, command: &[String], config_path: &str, force: bool) -> Result<()> {
+    let config = load_config(config_path, game)?;
+    let Some(prefix) = config.wine_prefix() else {
+        return Err(ExitError::new(
+            exit_code::CONFIG,
+            format!("{game} starts through launch_cmd, set WINEPREFIX in its env table"),
+        )
+        .into());
+    };
+    let _lock = crate::prefix::lock_prefix(&prefix)
+        .with_context(|| format!("Failed to lock {}", prefix.display()))?;
+    if crate::prefix::wineserver_running(&prefix) && !force {
+        anyhow::bail!(
+            "The wineserver of {} is running, close the client using it first (or pass --force)",
+            prefix.display()
+        );
+    }
+    outln!("Prefix:\n\t{}", prefix.display());
+    outln!("Running:\n\t{}", command.join(" "));
+    let status = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .envs(&config.env)
+        .env("WINEPREFIX", &prefix)
+        .stdout(crate::output::child_stdout())
+        .status()
+        .with_context(|| format!("Failed to run {}", command[0]))?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {status}", command[0]);
+    }
+    outln!("✓ Done");
+    Ok(())
+}
+
+/// Ask which account to launch with when several are configured
+///
+/// Without a terminal to ask on, nothing is selected and every account is shown.
+fn pick_account(names: &[&str]) -> Result<Option<String>> {
+    use std::io::{IsTerminal, Write};
+    if names.len() < 2 || !std::io::stdin().is_terminal() {
+        return Ok(None);
+    }
+    outln!("Accounts:");
+    for (i, name) in names.iter().enumerate() {
+        outln!("\t{}. {name}", i + 1);
+    }
+    loop {
+        out!("Account [1-{}, Enter for 1]: ", names.len());
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Ok(Some(names[0].to_string()));
+        }
+        match answer.parse::<usize>() {
+            Ok(i) if (1..=names.len()).contains(&i) => return Ok(Some(names[i - 1].to_string())),
+            _ => match names.iter().find(|name| name.eq_ignore_ascii_case(answer)) {
+                Some(name) => return Ok(Some(name.to_string())),
+                None => outln!(
+                    "✗ Enter a number from 1 to {} or an account name",
+                    names.len()
+                ),
+            },
+        }
+    }
+}
+
+/// Remember when and against which realm a game directory was last launched, and the
+/// client started from it
+fn record_launch(dir: &Path, realm: Option<&str>, game: &str, pid: u32) {
+    crate::state::update_state(|state| {
+        let directory = state.directory_mut(dir);
+        directory.last_launched = Some(crate::state::now());
+        if let Some(realm) = realm {
+            directory.last_realm = Some(realm.to_string());
+        }
+        directory.running = Some(crate::state::RunningClient {
+            game: game.to_string(),
+            pid,
+            started_at: crate::state::now(),
+        });
+    });
+}
+
+/// Processes of the client running from `dir`: the one `launch` recorded with its
+/// descendants, and programs started from the directory (e.g. the client under wine)
+fn client_pids(dir: &Path, state: &crate::state::State) -> Vec<u32> {
+    let recorded: Vec<u32> = state
+        .directory(dir)
+        .and_then(|directory| directory.running.as_ref())
+        .map(|running| running.pid)
+        .into_iter()
+        .collect();
+    let mut pids: Vec<u32> = crate::process::client_processes(dir, &recorded)
+        .iter()
+        .map(|process| process.pid)
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+    pids
+}
+
+/// Drop the recorded client of `dir` once nothing runs from it anymore
+fn forget_exited_client(dir: &Path) {
+    let state = crate::state::load_state();
+    let recorded = state.directory(dir).is_some_and(|d| d.running.is_some());
+    if recorded && client_pids(dir, &state).is_empty() {
+        crate::state::update_state(|state| state.directory_mut(dir).running = None);
+    }
+}
+
+fn cmd_ps(config_path: &str) -> Result<()> {
+    let file = ConfigFile::read(config_path)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    let state = crate::state::load_state();
+    let mut rows = Vec::new();
+    for (game, config) in file.games(&current_hostname()) {
+        let Ok(config) = config else {
+            continue;
+        };
+        let pids = client_pids(&config.directory, &state);
+        if pids.is_empty() {
+            forget_exited_client(&config.directory);
+            continue;
+        }
+        let started = state
+            .directory(&config.directory)
+            .and_then(|directory| directory.running.as_ref())
+            .map_or("-".to_string(), |running| {
+                crate::state::format_age(running.started_at)
+            });
+        let pids: Vec<String> = pids.iter().map(u32::to_string).collect();
+        rows.push((game, started, pids.join(" ")));
+    }
+    if rows.is_empty() {
+        outln!("No clients running");
+        return Ok(());
+    }
+    let width = rows
+        .iter()
+        .map(|(game, ..)| game.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    outln!("{:<width$}  {:<10}  PIDS", "GAME", "STARTED");
+    for (game, started, pids) in rows {
+        outln!("{game:<width$}  {started:<10}  {pids}");
+    }
+    Ok(())
+}
+
+fn cmd_kill(workspace: &str, config_path: &str) -> Result<()> {
+    let config = load_config(config_path, workspace)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    let pids = client_pids(&config.directory, &crate::state::load_state());
+    if pids.is_empty() {
+        outln!("{workspace} is not running");
+        forget_exited_client(&config.directory);
+        return Ok(());
+    }
+    let mut failed = false;
+    for pid in pids {
+        match crate::process::terminate(pid) {
+            Ok(()) => outln!("✓ Stopped process {pid}"),
+            Err(e) => {
+                tracing::error!("✗ {e}");
+                failed = true;
+            }
+        }
+    }
+
+    // Wine services of the prefix name no client file, only its wineserver knows them
+    if let Some(prefix) = config
+        .wine_prefix()
+        .filter(|prefix| cfg!(target_os = "linux") && crate::prefix::wineserver_running(prefix))
+    {
+        let state = crate::state::load_state();
+        let file = ConfigFile::read(config_path)?;
+        let sharing: Vec<String> = file
+            .games(&current_hostname())
+            .into_iter()
+            .filter_map(|(game, other)| Some((game, other.ok()?)))
+            .filter(|(_, other)| {
+                other.directory != config.directory
+                    && other.wine_prefix().as_ref() == Some(&prefix)
+                    && !client_pids(&other.directory, &state).is_empty()
+            })
+            .map(|(game, _)| game)
+            .collect();
+        if sharing.is_empty() {
+            match crate::prefix::kill_wineserver(&prefix, &config.env) {
+                Ok(()) => outln!("✓ Stopped the wine processes of:\n\t{}", prefix.display()),
+                Err(e) => {
+                    tracing::error!(
+                        "✗ Failed to stop the wine processes of {}: {e}",
+                        prefix.display()
+                    );
+                    failed = true;
+                }
+            }
+        } else {
+            outln!(
+                "⚠ Left the wine processes of {} running, {} uses the prefix too",
+                prefix.display(),
+                sharing.join(", ")
+            );
+        }
+    }
+
+    forget_exited_client(&config.directory);
+    if failed {
+        anyhow::bail!("Failed to stop {workspace} completely");
+    }
+    Ok(())
+}
+
+/// Remove WDB caches left by another realm; item and creature data cached from the
+/// previous server shows up as wrong names, icons and models
+fn clear_wdb_on_realm_change(dir: &Path, realm: &str) {
+    let last_realm = crate::state::load_state()
+        .directory(dir)
+        .and_then(|d| d.last_realm.clone());
+    let Some(last_realm) = last_realm.filter(|last| !last.eq_ignore_ascii_case(realm)) else {
+        return;
+    };
+    outln!("Realm changed from {last_realm} to {realm}, clearing WDB cache:");
+    clear_wdb(dir);
+}
+
+/// Remove the WDB cache of a game directory, printing each removed path
+fn clear_wdb(dir: &Path) {
+    for path in game_profile(dir).wdb_paths(dir) {
+        let rel_path = path.strip_prefix(dir).unwrap_or(&path).display();
+        match remove_path(&path) {
+            Some(Ok(())) => outln!("\t✓ Removed {rel_path}"),
+            Some(Err(e)) => tracing::error!("\t✗ Failed to remove {}: {}", path.display(), e),
+            None => {}
+        }
+    }
+}
+
+/// Remove a file or directory tree, `None` if it no longer exists
+fn remove_path(path: &Path) -> Option<std::io::Result<()>> {
+    let path = &crate::paths::long_path(path);
+    Some(match path.symlink_metadata().ok()? {
+        meta if meta.is_dir() => std::fs::remove_dir_all(path),
+        _ => std::fs::remove_file(path),
+    })
+}
+
+/// Print a server's latest news; failures are reported but never stop the launch
+fn print_news(url: &str) {
+    match crate::news::news(url) {
+        Ok(news) if news.lines.is_empty() => {}
+        Ok(news) => {
+            let stale = if news.stale {
+                " (cached, fetch failed)"
+            } else {
+                ""
+            };
+            outln!("News{stale}:");
+            for line in &news.lines {
+                outln!("\t{line}");
+            }
+        }
+        Err(e) => outln!("News:\n\t⚠ Failed to fetch {url}: {e:#}"),
+    }
+}
+
+/// Print a one-line workspace health summary, from the cached check when it is recent
+#[cfg(feature = "workspaces")]
+fn print_launch_health(game: &str, dir: &Path) {
+    use crate::state::{HealthSummary, load_state, now, update_state};
+    use crate::workspace::check_health;
+
+    const CACHE_SECS: u64 = 600;
+    const BUDGET: std::time::Duration = std::time::Duration::from_millis(5);
+
+    if !dir.join("workspace.toml").is_file() {
+        return;
+    }
+
+    let cached = load_state()
+        .directory(dir)
+        .and_then(|d| d.health.clone())
+        .filter(|health| now().saturating_sub(health.checked_at) < CACHE_SECS);
+    let (warnings, partial) = match cached {
+        Some(health) => (health.warnings, false),
+        None => match check_health(dir, Some(BUDGET)) {
+            Ok(report) => {
+                if report.complete {
+                    let summary = HealthSummary {
+                        checked_at: now(),
+                        warnings: report.warnings.clone(),
+                    };
+                    update_state(|state| state.directory_mut(dir).health = Some(summary));
+                }
+                (report.warnings, !report.complete)
+            }
+            Err(e) => {
+                outln!("Health:\n\t⚠ {e}");
+                return;
+            }
+        },
+    };
+
+    let partial = if partial { " (partial check)" } else { "" };
+    match warnings.len() {
+        0 => outln!("Health:\n\t✓ links OK{partial}"),
+        n => outln!(
+            "Health:\n\t⚠ {n} warning(s){partial}, e.g. {} — run `realmctl fix {game}`",
+            warnings[0]
+        ),
+    }
+}
+
+/// Turn a Ctrl-C cancellation into an exit code with a hint on how to continue
+fn interrupted(e: anyhow::Error, hint: &str) -> anyhow::Error {
+    if e.is::<crate::cancel::Cancelled>() {
+        ExitError::new(exit_code::INTERRUPTED, format!("Interrupted, {hint}")).into()
+    } else {
+        e
+    }
+}
+
+/// Launch attached with a temporary realmlist, restoring the previous file once the client exits
+fn launch_with_realm(
+    config: &Config,
+    realm: &str,
+    log: Option<&Path>,
+    on_started: impl FnOnce(u32),
+) -> Result<std::process::ExitStatus> {
+    let Some(path) = config.realmlist_path() else {
+        anyhow::bail!(
+            "No realmlist.wtf found in {}, set realmlist_path in the game's config",
+            config.directory.display()
+        );
+    };
+    // Keep the exact previous content, not just the host, so the file is restored byte for byte
+    let previous = match std::fs::read(&path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    // Restored afterwards, so not a switch for the realmlist history
+    store_realmlist(&path, realm).inspect_err(|e| {
+        tracing::error!("✗ Could not write {}: {e}", path.display());
+    })?;
+    outln!("Realmlist set to:\n\tset realmlist {realm} (this session only)");
+    let result = spawn_with_log(config, log).and_then(|mut child| {
+        on_started(child.id());
+        outln!("Waiting for the client to exit...");
+        child.wait()
+    });
+
+    let restored = match &previous {
+        Some(content) => std::fs::write(&path, content),
+        None => std::fs::remove_file(&path),
+    };
+    match &restored {
+        Ok(()) => match &previous {
+            Some(content) => match parse_realmlist(&String::from_utf8_lossy(content)) {
+                Some(host) => outln!("Realmlist restored to:\n\tset realmlist {host}"),
+                None => outln!("Realmlist restored:\n\t{}", path.display()),
+            },
+            None => outln!("Temporary realmlist removed:\n\t{}", path.display()),
+        },
+        Err(e) => tracing::error!("✗ Failed to restore {}: {e}", path.display()),
+    }
+
+    let status = result?;
+    if !status.success() && log.is_none() {
+        tracing::warn!("⚠ Client exited with {status}");
+    }
+    restored.with_context(|| format!("Failed to restore {}", path.display()))?;
+    Ok(status)
+}
+
+/// Address of a `[realms]` preset, or `realm` as given (also when the config can't be read,
+/// loading the game reports that)
+fn resolve_realm(config_path: &str, realm: &str) -> String {
+    ConfigFile::read(config_path)
+        .map(|file| file.resolve_realm(realm))
+        .unwrap_or_else(|_| realm.to_string())
+}
+
+/// Load a game config and locate its realmlist file, mapping failures to exit codes
+fn realm_target(game: &str, config_path: &str) -> Result<(Config, PathBuf)> {
+    let config = load_config(config_path, game)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    // A fresh workspace may have no realmlist.wtf yet, its profile knows where it belongs
+    let path = config
+        .realmlist_path()
+        .or_else(|| game_profile(&config.directory).realmlist_path(&config.directory))
+        .ok_or_else(|| {
+            ExitError::new(
+                exit_code::REALMLIST_MISSING,
+                format!("No realmlist.wtf found for '{game}' and no realmlist_path configured"),
+            )
+        })?;
+    Ok((config, path))
+}
+
+/// Read the realmlist of a game, mapping failures to exit codes
+fn realm_current(path: &Path) -> Result<Option<String>> {
+    let current = read_realmlist(path).map_err(|e| {
+        ExitError::new(
+            exit_code::REALMLIST_MISSING,
+            format!("Failed to read {}: {e}", path.display()),
+        )
+    })?;
+    Ok(current)
+}
+
+fn cmd_realm_set(game: &str, host: &str, config_path: &str, quiet: bool) -> Result<()> {
+    let (config, path) = realm_target(game, config_path)?;
+
+    switch_realmlist(&config.directory, &path, host).map_err(|e| {
+        ExitError::new(
+            exit_code::WRITE_FAILED,
+            format!("Failed to write {}: {e}", path.display()),
+        )
+    })?;
+
+    let current = realm_current(&path)?;
+    if current.as_deref() != Some(host) {
+        return Err(ExitError::new(
+            exit_code::MISMATCH,
+            format!(
+                "Realmlist verification failed for {}: expected {host}, found {}",
+                path.display(),
+                current.as_deref().unwrap_or("nothing")
+            ),
+        )
+        .into());
+    }
+
+    if !quiet {
+        outln!("Realmlist for {game} set to:\n\t{host}");
+    }
+    Ok(())
+}
+
+/// Print what writing `content` to `path` would change as a unified diff, writing nothing
+fn print_file_diff(path: &Path, content: &str) -> Result<()> {
+    let current = match std::fs::read(path) {
+        Ok(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    if let Some(diff) = crate::diff::unified_diff(path, current.as_deref(), content) {
+        out!("{diff}");
+    }
+    Ok(())
+}
+
+/// Whether a game's directory is a workspace created from the given base (profile name or path)
+#[cfg(feature = "workspaces")]
+fn derives_from_base(config: &Config, base: &str) -> bool {
+    let Ok(workspace) = crate::workspace::load_workspace_config(&config.directory) else {
+        return false;
+    };
+    let base_path = crate::paths::expand(base);
+    workspace.base_name.eq_ignore_ascii_case(base) || workspace.base_path == base_path
+}
+
+#[cfg(not(feature = "workspaces"))]
+fn derives_from_base(_config: &Config, _base: &str) -> bool {
+    false
+}
+
+fn cmd_realm_set_all(
+    host: &str,
+    (base, tag): (Option<&str>, Option<&str>),
+    config_path: &str,
+    quiet: bool,
+    diff: bool,
+) -> Result<()> {
+    let file = ConfigFile::read(config_path)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+
+    let mut written: Vec<PathBuf> = Vec::new();
+    let mut failures = 0;
+    for (key, config) in file.games(&current_hostname()) {
+        let Ok(config) = config else {
+            continue;
+        };
+        let base_match = base.is_some_and(|base| derives_from_base(&config, base));
+        let tag_match =
+            tag.is_some_and(|tag| config.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+        if !base_match && !tag_match {
+            continue;
+        }
+
+        let Some(path) = config.realmlist_path() else {
+            if !quiet {
+                outln!("⚠ {key}: no realmlist.wtf found, skipped");
+            }
+            continue;
+        };
+
+        // Several entries may share one workspace, write each file once
+        if written.contains(&path) {
+            continue;
+        }
+        if diff {
+            print_file_diff(&path, &crate::realmlist_content(host))?;
+            written.push(path);
+            continue;
+        }
+
+        let result =
+            switch_realmlist(&config.directory, &path, host).and_then(|_| read_realmlist(&path));
+        match result {
+            Ok(Some(current)) if current == host => {
+                if !quiet {
+                    outln!("✓ {key}: {}", path.display());
+                }
+                written.push(path);
+            }
+            Ok(_) => {
+                tracing::error!("✗ {key}: verification failed for {}", path.display());
+                failures += 1;
+            }
+            Err(e) => {
+                tracing::error!("✗ {key}: failed to write {}: {e}", path.display());
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(ExitError::new(
+            exit_code::WRITE_FAILED,
+            format!("{failures} realmlist(s) could not be updated"),
+        )
+        .into());
+    }
+    if written.is_empty() {
+        return Err(ExitError::new(exit_code::CONFIG, "No games matched the selection").into());
+    }
+    if !quiet && !diff {
+        outln!("\nRealmlist set to {host} for {} game(s)", written.len());
+    }
+    Ok(())
+}
+
+fn cmd_realm_get(game: &str, config_path: &str, quiet: bool) -> Result<()> {
+    let (_, path) = realm_target(game, config_path)?;
+    let current = realm_current(&path)?.ok_or_else(|| {
+        ExitError::new(
+            exit_code::REALMLIST_MISSING,
+            format!("No 'set realmlist' line in {}", path.display()),
+        )
+    })?;
+
+    if quiet {
+        outln!("{current}");
+    } else {
+        outln!("Realmlist for {game}:\n\t{current}");
+    }
+    Ok(())
+}
+
+fn cmd_realm_history(game: &str, config_path: &str, limit: usize) -> Result<()> {
+    use crate::realm::load_history;
+    use crate::state::format_age;
+
+    let config = load_config(config_path, game)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    let history = load_history(&config.directory)?;
+    if history.is_empty() {
+        outln!("No realmlist switches recorded for {game}");
+        return Ok(());
+    }
+
+    outln!("{:<10} {:<32} TO", "WHEN", "FROM");
+    for switch in history.iter().rev().take(limit) {
+        outln!(
+            "{:<10} {:<32} {}",
+            format_age(switch.at),
+            switch.from.as_deref().unwrap_or("-"),
+            switch.to
+        );
+    }
+    Ok(())
+}
+
+fn cmd_realm_undo(game: &str, config_path: &str, quiet: bool) -> Result<()> {
+    let (config, path) = realm_target(game, config_path)?;
+    let history = crate::realm::load_history(&config.directory)?;
+    let Some(last) = history.last() else {
+        return Err(ExitError::new(
+            exit_code::REALMLIST_MISSING,
+            format!("No realmlist switches recorded for {game}"),
+        )
+        .into());
+    };
+    let Some(previous) = &last.from else {
+        return Err(ExitError::new(
+            exit_code::REALMLIST_MISSING,
+            format!(
+                "{game} had no realmlist before {}, nothing to restore",
+                last.to
+            ),
+        )
+        .into());
+    };
+
+    let current = realm_current(&path).ok().flatten();
+    if current.as_deref() != Some(last.to.as_str()) && !quiet {
+        outln!(
+            "⚠ {} was changed outside realmctl since the last switch (now {})",
+            path.display(),
+            current.as_deref().unwrap_or("nothing")
+        );
+    }
+    // Recorded as a switch of its own, a second undo goes back again
+    switch_realmlist(&config.directory, &path, previous).map_err(|e| {
+        ExitError::new(
+            exit_code::WRITE_FAILED,
+            format!("Failed to write {}: {e}", path.display()),
+        )
+    })?;
+    if !quiet {
+        outln!("Realmlist for {game} restored to:\n\t{previous}");
+    }
+    Ok(())
+}
+
+fn cmd_realm_status(game: &str, config_path: &str, quiet: bool) -> Result<()> {
+    use crate::maintenance::{active_window, format_utc, realm_reachable};
+
+    let config = load_config(config_path, game)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    // The realmlist on disk is what the client connects to
+    let host = config
+        .realmlist_path()
+        .and_then(|path| read_realmlist(&path).ok().flatten())
+        .or(config.realmlist.clone())
+        .ok_or_else(|| {
+            ExitError::new(
+                exit_code::REALMLIST_MISSING,
+                format!("No realmlist found or configured for '{game}'"),
+            )
+        })?;
+
+    let reachable = realm_reachable(&host, std::time::Duration::from_secs(5));
+    let window = active_window(&config.maintenance, crate::state::now());
+    match (reachable, window) {
+        (Ok(()), _) => {
+            if !quiet {
+                outln!("✓ {host} is up");
+            }
+            Ok(())
+        }
+        (Err(e), Some((window, end))) => Err(ExitError::new(
+            exit_code::REALM_DOWN,
+            format!(
+                "{host} is down ({e}), expected: maintenance window {} until {}",
+                window.spec,
+                format_utc(end)
+            ),
+        )
+        .into()),
+        (Err(e), None) => Err(ExitError::new(
+            exit_code::REALM_DOWN,
+            format!("{host} is down ({e}), unexpected: no maintenance window is scheduled"),
+        )
+        .into()),
+    }
+}
+
+fn cmd_realm_verify(
+    game: &str,
+    expected: Option<&str>,
+    config_path: &str,
+    quiet: bool,
+) -> Result<()> {
+    let (config, path) = realm_target(game, config_path)?;
+    let expected = expected
+        .map(str::to_string)
+        .or(config.realmlist)
+        .ok_or_else(|| {
+            ExitError::new(
+                exit_code::CONFIG,
+                format!("No expected host given and no realmlist configured for '{game}'"),
+            )
+        })?;
+
+    let current = realm_current(&path)?;
+    if crate::output::is_json() {
+        crate::output::emit(&serde_json::json!({
+            "game": game,
+            "path": path,
+            "expected": expected,
+            "current": current,
+            "ok": current.as_deref() == Some(expected.as_str()),
+        }))?;
+    }
+    if current.as_deref() != Some(expected.as_str()) {
+        return Err(ExitError::new(
+            exit_code::MISMATCH,
+            format!(
+                "Realmlist for {game} is {}, expected {expected}",
+                current.as_deref().unwrap_or("not set")
+            ),
+        )
+        .into());
+    }
+
+    if !quiet {
+        outln!("✓ Realmlist for {game} is {expected}");
+    }
+    Ok(())
+}
+
+/// Realm a client connects to according to its files: realmlist.wtf, or the `portal`
+/// setting of Config.wtf for clients without one
+fn realm_on_disk(config: &Config) -> Option<String> {
+    let realmlist = config
+        .realmlist_path()
+        .or_else(|| game_profile(&config.directory).realmlist_path(&config.directory));
+    if let Some(path) = realmlist.filter(|path| path.is_file()) {
+        return read_realmlist(&path).ok().flatten();
+    }
+    let config_wtf = crate::wtf::config_wtf_path(&config.directory, &config.executable);
+    let content = std::fs::read_to_string(config_wtf).ok()?;
+    crate::wtf::get_value(&content, "portal")
+}
+
+fn cmd_realm_audit(config_path: &str) -> Result<()> {
+    let file = ConfigFile::read(config_path)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    let games = file.games(&current_hostname());
+    if games.is_empty() {
+        outln!("No games configured in {}", file.path.display());
+        return Ok(());
+    }
+
+    let width = games
+        .iter()
+        .map(|(key, _)| key.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+    outln!(
+        "{:<width$}  {:<28}  {:<28}  STATUS",
+        "GAME",
+        "CONFIGURED",
+        "ON DISK"
+    );
+    let mut drifted = 0;
+    for (key, config) in &games {
+        let config = match config {
+            Ok(config) => config,
+            Err(e) => {
+                outln!("{key:<width$}  {e}");
+                continue;
+            }
+        };
+        let actual = realm_on_disk(config);
+        let status = match (&config.realmlist, &actual) {
+            (None, _) => "- nothing configured",
+            (Some(_), None) => {
+                drifted += 1;
+                "✗ missing"
+            }
+            (Some(expected), Some(actual)) if expected.eq_ignore_ascii_case(actual) => "✓",
+            (Some(_), Some(_)) => {
+                drifted += 1;
+                "✗ drifted"
+            }
+        };
+        outln!(
+            "{:<width$}  {:<28}  {:<28}  {status}",
+            key,
+            config.realmlist.as_deref().unwrap_or("-"),
+            actual.as_deref().unwrap_or("-"),
+        );
+    }
+
+    if drifted > 0 {
+        return Err(ExitError::new(
+            exit_code::MISMATCH,
+            format!(
+                "{drifted} game(s) differ from config.toml; `realmctl launch` or `realmctl realm set` writes the configured realmlist again"
+            ),
+        )
+        .into());
+    }
+    outln!("\n✓ Every configured realmlist is on disk");
+    Ok(())
+}
+
+/// Write `[groups.<name>]` to the config file, keeping everything else as it is
+fn save_group(config_path: &str, name: &str, group: &crate::group::GroupConfig) -> Result<()> {
+    let path = crate::paths::expand(config_path);
+    let source = std::fs::read_to_string(&path)
+        .with_context(|| format!("Config file not found: {config_path}"))?;
+    let temp_path = path.with_extension("toml.tmp");
+    std::fs::write(&temp_path, crate::group::upsert_group(&source, name, group))?;
+    std::fs::rename(&temp_path, &path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Install the addons of a group's pack a game does not have from the same source yet
+///
+/// Games sharing an AddOns directory get each addon once.
+fn install_addon_pack(
+    group: &crate::group::GroupConfig,
+    games: &[String],
+    config_path: &str,
+) -> Result<()> {
+    let mut failed = 0;
+    for game in games {
+        let game_cfg = load_config(config_path, game)?;
+        let addons = crate::addons::addons_dir(&game_cfg.directory, &game_cfg.executable);
+        for source in &group.addons {
+            let lock = crate::addons::load_lock(&addons)?;
+            if lock.addons.values().any(|addon| addon.source == *source) {
+                continue;
+            }
+            match crate::addons::install_addon(&addons, source, None, false) {
+                Ok((name, _)) => outln!("✓ {game}: installed {name}"),
+                Err(e) => {
+                    outln!("✗ {game}: {source}: {e:#}");
+                    failed += 1;
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} addon(s) of the pack could not be installed");
+    }
+    Ok(())
+}
+
+fn cmd_group_create(name: &str, group: crate::group::GroupConfig, config_path: &str) -> Result<()> {
+    if let Some((key, _)) = crate::group::find_group(config_path, name)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e))?
+    {
+        return Err(ExitError::new(
+            exit_code::CONFIG,
+            format!("[groups.{key}] already exists, use `realmctl group add {key}` to extend it"),
+        )
+        .into());
+    }
+    if group.accounts.len() > group.games.len() {
+        anyhow::bail!("Got more accounts than games");
+    }
+    for game in &group.games {
+        load_config(config_path, game)
+            .map_err(|e| ExitError::new(exit_code::CONFIG, format!("{game}: {e}")))?;
+    }
+    save_group(config_path, name, &group)?;
+    outln!("✓ Created [groups.{name}] with {}", group.games.join(", "));
+    install_addon_pack(&group, &group.games, config_path)
+}
+
+fn cmd_group_add(name: &str, games: &[String], config_path: &str) -> Result<()> {
+    let (key, mut group) = crate::group::find_group(config_path, name)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e))?
+        .ok_or_else(|| ExitError::new(exit_code::CONFIG, format!("No [groups.{name}] section")))?;
+    let mut added = Vec::new();
+    for game in games {
+        if group.games.iter().any(|g| g.eq_ignore_ascii_case(game)) {
+            outln!("- {game} is already in the group");
+            continue;
+        }
+        load_config(config_path, game)
+            .map_err(|e| ExitError::new(exit_code::CONFIG, format!("{game}: {e}")))?;
+        group.games.push(game.clone());
+        added.push(game.clone());
+    }
+    if added.is_empty() {
+        return Ok(());
+    }
+    save_group(config_path, &key, &group)?;
+    outln!("✓ Added {} to [groups.{key}]", added.join(", "));
+    install_addon_pack(&group, &added, config_path)
+}
+
+fn cmd_group_status(name: &str, config_path: &str) -> Result<()> {
+    let group = crate::group::load_group(config_path, name)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e))?;
+    outln!(
+        "{} games, started {}s apart",
+        group.games.len(),
+        group.stagger_secs
+    );
+    if let Some(prefix) = group.prefix_path() {
+        outln!("Wine prefix:\n\t{}", prefix.display());
+    }
+
+    let width = group
+        .games
+        .iter()
+        .map(String::len)
+        .max()
+        .unwrap_or(4)
+        .max(4);
+    outln!(
+        "\n{:<width$}  {:<14}  {:<10}  CVARS",
+        "GAME",
+        "RUNNING",
+        "ADDONS"
+    );
+    for game in &group.games {
+        let game_cfg = match load_config(config_path, game) {
+            Ok(config) => config,
+            Err(e) => {
+                outln!("{game:<width$}  ✗ {e}");
+                continue;
+            }
+        };
+        let running = crate::instance::holder(&game_cfg.directory)
+            .map_or("-".to_string(), |pid| format!("PID {pid}"));
+        let addons = crate::addons::addons_dir(&game_cfg.directory, &game_cfg.executable);
+        let lock = crate::addons::load_lock(&addons).unwrap_or_default();
+        let installed = group
+            .addons
+            .iter()
+            .filter(|source| lock.addons.values().any(|addon| addon.source == **source))
+            .count();
+        let config_wtf = crate::wtf::config_wtf_path(&game_cfg.directory, &game_cfg.executable);
+        let content = std::fs::read_to_string(config_wtf).unwrap_or_default();
+        let differing = group
+            .cvars
+            .iter()
+            .filter(|(key, value)| crate::wtf::get_value(&content, key).as_ref() != Some(*value))
+            .count();
+        let cvars = match differing {
+            0 => "✓".to_string(),
+            n => format!("{n} to apply at launch"),
+        };
+        outln!(
+            "{game:<width$}  {running:<14}  {:<10}  {cvars}",
+            format!("{installed}/{}", group.addons.len())
+        );
+    }
+    Ok(())
+}
+
+/// Print the Config.wtf changes launching a group would make for its CVar preset
+fn cmd_group_diff(name: &str, config_path: &str) -> Result<()> {
+    let group = crate::group::load_group(config_path, name)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e))?;
+    let values: Vec<(&str, String)> = group
+        .cvars
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.clone()))
+        .collect();
+    for game in &group.games {
+        let game_cfg = load_config(config_path, game)
+            .map_err(|e| ExitError::new(exit_code::CONFIG, format!("{game}: {e}")))?;
+        let config_wtf = crate::wtf::config_wtf_path(&game_cfg.directory, &game_cfg.executable);
+        let content = std::fs::read_to_string(&config_wtf).unwrap_or_default();
+        print_file_diff(&config_wtf, &crate::wtf::set_values(&content, &values))?;
+    }
+    Ok(())
+}
+
+/// AddOns directory of a game, with a note when it is shared with other workspaces
+fn game_addons_dir(game: &str, config_path: &str) -> Result<PathBuf> {
+    let game_cfg = load_config(config_path, game)?;
+    let addons = crate::addons::addons_dir(&game_cfg.directory, &game_cfg.executable);
+    match crate::addons::shared_target(&game_cfg.directory, &addons) {
+        Some(shared) => outln!(
+            "AddOns (shared, changes apply to every workspace using it):\n\t{}",
+            shared.display()
+        ),
+        None => outln!("AddOns:\n\t{}", addons.display()),
+    }
+    Ok(addons)
+}
+
+fn cmd_addon_install(
+    source: &str,
+    game: &str,
+    name: Option<&str>,
+    force: bool,
+    config_path: &str,
+) -> Result<()> {
+    let addons = game_addons_dir(game, config_path)?;
+    let (name, installed) = crate::addons::install_addon(&addons, source, name, force)?;
+    let version = installed
+        .version
+        .as_deref()
+        .map(|version| format!(" {version}"))
+        .unwrap_or_default();
+    outln!(
+        "\n✓ Installed {name}{version} ({})",
+        installed.folders.join(", ")
+    );
+    Ok(())
+}
+
+fn cmd_addon_update(name: Option<&str>, game: &str, check: bool, config_path: &str) -> Result<()> {
+    use crate::addons::{backup_dir, is_url, latest_release, update_addon};
+
+    let addons = game_addons_dir(game, config_path)?;
+    let lock = crate::addons::load_lock(&addons)?;
+    let names: Vec<String> = match name {
+        Some(name) => vec![lock.find(name).with_context(|| {
+            format!(
+                "{name} was not installed by realmctl in {}",
+                addons.display()
+            )
+        })?],
+        None => lock.addons.keys().cloned().collect(),
+    };
+    if names.is_empty() {
+        outln!("\nNo addons installed by realmctl");
+        return Ok(());
+    }
+
+    outln!();
+    let (mut updated, mut failed) = (0, 0);
+    for name in names {
+        let installed = &lock.addons[&name];
+        let current = installed.version.as_deref().unwrap_or("unknown version");
+        if !is_url(&installed.source) {
+            outln!("- {name}: installed from a file, install a newer archive to update it");
+            continue;
+        }
+        let release = match latest_release(&installed.source) {
+            Ok(release) => release,
+            Err(e) => {
+                outln!("✗ {name}: {e:#}");
+                failed += 1;
+                continue;
+            }
+        };
+        let Some(latest) = release.version.as_deref() else {
+            outln!("- {name}: {} reports no version", installed.source);
+            continue;
+        };
+        if installed.version.as_deref() == Some(latest) {
+            outln!("✓ {name}: {current} is up to date");
+            continue;
+        }
+        if check {
+            outln!("⚠ {name}: {current} -> {latest} available");
+            updated += 1;
+            continue;
+        }
+        match update_addon(&addons, &name, &release) {
+            Ok(_) => {
+                outln!("✓ {name}: updated {current} -> {latest}");
+                updated += 1;
+            }
+            Err(e) => {
+                outln!("✗ {name}: {e:#}");
+                failed += 1;
+            }
+        }
+    }
+
+    if check {
+        outln!("\n{updated} update(s) available");
+    } else if updated > 0 {
+        outln!(
+            "\nPrevious versions were kept in:\n\t{}",
+            backup_dir(&addons, "").display()
+        );
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} addon(s) could not be checked or updated");
+    }
+    Ok(())
+}
+
+fn cmd_addon_list(game: &str, config_path: &str) -> Result<()> {
+    let addons = game_addons_dir(game, config_path)?;
+    let lock = crate::addons::load_lock(&addons)?;
+    if lock.addons.is_empty() {
+        outln!("\nNo addons installed by realmctl");
+    } else {
+        outln!(
+            "\n{:<24} {:<12} {:<16} SOURCE",
+            "NAME",
+            "VERSION",
+            "INSTALLED"
+        );
+        for (name, addon) in &lock.addons {
+            outln!(
+                "{:<24} {:<12} {:<16} {}",
+                name,
+                addon.version.as_deref().unwrap_or("-"),
+                crate::state::format_age(addon.installed_at),
+                addon.source
+            );
+            if addon.folders.len() > 1 || addon.folders[0] != *name {
+                outln!("  {}", addon.folders.join(", "));
+            }
+        }
+    }
+    let unmanaged = crate::addons::unmanaged_folders(&addons, &lock);
+    if !unmanaged.is_empty() {
+        outln!("\nNot installed by realmctl:");
+        for folder in unmanaged {
+            outln!("\t{folder}");
+        }
+    }
+    Ok(())
+}
+
+fn cmd_addon_check(game: &str, interface: Option<u32>, config_path: &str) -> Result<()> {
+    use crate::addons::{Compatibility, check_addons, interface_version};
+
+    let addons = game_addons_dir(game, config_path)?;
+    let game_cfg = load_config(config_path, game)?;
+    let version = game_profile(&game_cfg.directory).version;
+    let Some(client) = interface.or_else(|| interface_version(&version)) else {
+        return Err(ExitError::new(
+            exit_code::CONFIG,
+            format!("The interface version of {version} clients is not known, pass --interface"),
+        )
+        .into());
+    };
+    outln!("Client interface:\n\t{client}");
+
+    let checks = check_addons(&addons, client);
+    let current = checks
+        .iter()
+        .filter(|check| check.compatibility == Compatibility::Current)
+        .count();
+    let width = checks
+        .iter()
+        .map(|c| c.folder.len())
+        .max()
+        .unwrap_or(5)
+        .max(5);
+    let mut header = false;
+    for check in checks
+        .iter()
+        .filter(|c| c.compatibility != Compatibility::Current)
+    {
+        if !header {
+            outln!("\n{:<width$}  {:<14}  STATUS", "ADDON", "INTERFACE");
+            header = true;
+        }
+        let interfaces = match check.interfaces.as_slice() {
+            [] => "-".to_string(),
+            numbers => numbers
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        let status = match check.compatibility {
+            Compatibility::Incompatible => "✗ made for another expansion",
+            Compatibility::OutOfDate => "⚠ out of date",
+            Compatibility::Unknown => "⚠ no interface version",
+            Compatibility::Current => "✓",
+        };
+        outln!("{:<width$}  {:<14}  {status}", check.folder, interfaces);
+    }
+    outln!("\n✓ {current} of {} addons are up to date", checks.len());
+
+    let incompatible = checks
+        .iter()
+        .filter(|check| check.compatibility == Compatibility::Incompatible)
+        .count();
+    if incompatible > 0 {
+        anyhow::bail!("{incompatible} addon(s) are made for another client version");
+    }
+    Ok(())
+}
+
+fn cmd_addon_remove(name: &str, game: &str, config_path: &str) -> Result<()> {
+    let addons = game_addons_dir(game, config_path)?;
+    let (name, addon) = crate::addons::remove_addon(&addons, name)?;
+    outln!("\n✓ Removed {name} ({})", addon.folders.join(", "));
+    Ok(())
+}
+
+fn cmd_settings_export(
+    game: &str,
+    output: Option<&Path>,
+    strip_accounts: bool,
+    config_path: &str,
+) -> Result<()> {
+    use crate::settings::{pack, stage_export, staging_dir, wtf_dir};
+    use std::io::IsTerminal;
+
+    if output.is_none() && std::io::stdout().is_terminal() {
+        anyhow::bail!("Redirect the output to a file or pass --output settings.tar.zst");
+    }
+    let game_cfg = load_config(config_path, game)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    let wtf = wtf_dir(&game_cfg.directory, &game_cfg.executable);
+    let client = Some(game_profile(&game_cfg.directory).version).filter(|v| !v.is_empty());
+    let staging = staging_dir(&wtf)?;
+    let result = stage_export(&wtf, &staging, client, strip_accounts)
+        .and_then(|manifest| pack(&staging, output).map(|()| manifest));
+    let _ = std::fs::remove_dir_all(&staging);
+    let manifest = result?;
+    // The archive may be on stdout, so everything else goes to stderr
+    tracing::info!(
+        "✓ Exported the settings of {game} from:\n\t{}",
+        wtf.display()
+    );
+    if manifest.stripped_accounts > 0 {
+        tracing::warn!(
+            "⚠ Renamed {} account folder(s), SavedVariables may still mention account names",
+            manifest.stripped_accounts
+        );
+    }
+    Ok(())
+}
+
+fn cmd_settings_import(
+    game: &str,
+    archive: Option<&Path>,
+    account: Option<String>,
+    config_path: &str,
+) -> Result<()> {
+    use crate::settings::{backup_dir, import_settings, staging_dir, unpack, wtf_dir};
+
+    let game_cfg = load_config(config_path, game)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e.to_string()))?;
+    // The client writes its settings when it exits and would undo the import
+    if let Some(pid) = crate::instance::holder(&game_cfg.directory) {
+        return Err(ExitError::new(
+            exit_code::ALREADY_RUNNING,
+            format!("{game} is running (PID {pid}), close the client first"),
+        )
+        .into());
+    }
+    let wtf = wtf_dir(&game_cfg.directory, &game_cfg.executable);
+    let staging = staging_dir(&wtf)?;
+    let result = unpack(archive, &staging).and_then(|manifest| {
+        // The configured account stands in for a single stripped one
+        let account = account.or_else(|| {
+            let single = manifest.stripped_accounts == 1;
+            game_cfg.account.clone().filter(|_| single)
+        });
+        let report = import_settings(&staging, &manifest, &wtf, account.as_deref())?;
+        Ok((manifest, report, account))
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    let (manifest, report, account) = result?;
+
+    let version = game_profile(&game_cfg.directory).version;
+    if let Some(client) = manifest
+        .client
+        .as_deref()
+        .filter(|client| *client != version)
+    {
+        tracing::warn!("⚠ The settings were exported from a {client} client, {game} is {version}");
+    }
+    outln!(
+        "✓ Imported {} file(s) into:\n\t{}",
+        report.imported.len(),
+        wtf.display()
+    );
+    if report.replaced > 0 {
+        outln!(
+            "Replaced {} file(s), the previous versions are in:\n\t{}",
+            report.replaced,
+            backup_dir(&wtf).display()
+        );
+    }
+    if manifest.stripped_accounts > 0 && account.is_none() {
+        outln!(
+            "⚠ Account folders were imported as {}1..{}, rename them to your account names",
+            crate::settings::ACCOUNT_PLACEHOLDER,
+            manifest.stripped_accounts
+        );
+    }
+    Ok(())
+}
+
+fn cmd_config_list(config_path: &str) -> Result<()> {
+    use crate::config::Readiness;
+
+    let file = ConfigFile::read(config_path)?;
+    let games = file.games(&current_hostname());
+    if games.is_empty() {
+        outln!("No games configured in {}", file.path.display());
+        return Ok(());
+    }
+
+    let width = games
+        .iter()
+        .map(|(key, _)| key.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+    let mark = |ok: bool| if ok { "✓" } else { "✗" };
+
+    outln!(
+        "{:<width$}  DIR  EXE  {:<24}  ACCOUNTS  STATUS",
+        "GAME",
+        "REALMLIST"
+    );
+    let mut ready = 0;
+    for (key, config) in &games {
+        match config {
+            Ok(config) => {
+                let readiness = Readiness::check(config);
+                if readiness.is_launchable() {
+                    ready += 1;
+                }
+                outln!(
+                    "{:<width$}  {:<3}  {:<3}  {:<24}  {:<8}  {}",
+                    key,
+                    mark(readiness.directory),
+                    mark(readiness.executable),
+                    readiness.realmlist.as_deref().unwrap_or("-"),
+                    readiness.accounts,
+                    readiness.status(),
+                );
+            }
+            Err(e) => outln!("{key:<width$}  {e}"),
+        }
+    }
+
+    outln!("\n{ready}/{} games ready to launch", games.len());
+    Ok(())
+}
+
+fn cmd_config_lint(config_path: &str) -> Result<()> {
+    use crate::config::{Severity, lint};
+
+    let path = crate::paths::expand(config_path);
+    let source = std::fs::read_to_string(&path)
+        .with_context(|| format!("Config file not found: {config_path}"))?;
+
+    let issues = lint(&source);
+    for issue in &issues {
+        outln!("{issue}");
+    }
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        anyhow::bail!("{errors} error(s) found in {}", path.display());
+    }
+    if issues.is_empty() {
+        outln!("✓ No issues found in {}", path.display());
+    }
+    Ok(())
+}
+
+fn cmd_config_validate(config_path: &str) -> Result<()> {
+    use crate::config::{Severity, validate};
+
+    let path = crate::paths::expand(config_path);
+    let source = std::fs::read_to_string(&path)
+        .with_context(|| format!("Config file not found: {config_path}"))?;
+    let reports = validate(&source).map_err(|e| {
+        ExitError::new(
+            exit_code::CONFIG,
+            format!("{} is not valid TOML:\n{e}", path.display()),
+        )
+    })?;
+    if reports.is_empty() {
+        outln!("No games configured in {}", path.display());
+        return Ok(());
+    }
+
+    let mut errors = 0;
+    let mut failed = 0;
+    for report in &reports {
+        match report.line {
+            Some(line) => outln!("[{}] (line {line})", report.section),
+            None => outln!("[{}]", report.section),
+        }
+        if report.issues.is_empty() {
+            outln!("\t✓ OK");
+        }
+        for issue in &report.issues {
+            outln!("\t{issue}");
+        }
+        let section_errors = report
+            .issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .count();
+        errors += section_errors;
+        if section_errors > 0 {
+            failed += 1;
+        }
+    }
+    outln!("\n`realmctl config lint` checks duplicate keys, hooks, groups and other sections");
+    if errors > 0 {
+        return Err(ExitError::new(
+            exit_code::CONFIG,
+            format!(
+                "{errors} error(s) in {failed} of {} game(s) in {}",
+                reports.len(),
+                path.display()
+            ),
+        )
+        .into());
+    }
+    outln!("✓ {} game(s) valid in {}", reports.len(), path.display());
+    Ok(())
+}
+
+fn cmd_config_upgrade(dry_run: bool, config_path: &str) -> Result<()> {
+    use crate::compat::{deprecations, upgrade_source};
+
+    let path = crate::paths::expand(config_path);
+    let source = std::fs::read_to_string(&path)
+        .with_context(|| format!("Config file not found: {config_path}"))?;
+    let (upgraded, fixed) = upgrade_source(&source);
+
+    let verb = if dry_run { "Would fix" } else { "Fixed" };
+    for deprecation in &fixed {
+        outln!("✓ {verb} {deprecation}");
+    }
+    if !fixed.is_empty() && !dry_run {
+        let backup = path.with_extension("toml.bak");
+        std::fs::copy(&path, &backup)
+            .with_context(|| format!("Failed to back up {}", path.display()))?;
+        let temp_path = path.with_extension("toml.tmp");
+        std::fs::write(&temp_path, &upgraded)?;
+        std::fs::rename(&temp_path, &path)?;
+        outln!(
+            "\n✓ Upgraded {} (previous version in {})",
+            path.display(),
+            backup.display()
+        );
+    }
+
+    // Legacy keys in dotted or inline tables are mapped at load time but not rewritten
+    let left: Vec<_> = toml::from_str::<toml::Table>(&upgraded)
+        .map(|table| deprecations(&table))
+        .unwrap_or_default();
+    for deprecation in &left {
+        outln!("⚠ Not rewritten, fix by hand: {deprecation}");
+    }
+    if fixed.is_empty() && left.is_empty() {
+        outln!("✓ {} uses no deprecated keys", path.display());
+    }
+    Ok(())
+}
+
+/// Keys `config add` sets on a game entry
+struct GameEntry {
+    directory: Option<String>,
+    realmlist: Option<String>,
+    executable: Option<String>,
+    account: Option<String>,
+}
+
+fn cmd_config_add(name: &str, entry: &GameEntry, config_path: &str) -> Result<()> {
+    let path = crate::paths::expand(config_path);
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    let mut values: Vec<(&str, toml_edit::Value)> = Vec::new();
+    if let Some(directory) = &entry.directory {
+        let dir = crate::paths::expand(directory);
+        let dir = std::path::absolute(&dir).unwrap_or(dir);
+        if !dir.is_dir() {
+            tracing::warn!("⚠ {} is not a directory (yet)", dir.display());
+        }
+        let value = crate::paths::config_value(&dir).display().to_string();
+        values.push(("directory", value.into()));
+    }
+    for (key, value) in [
+        ("realmlist", &entry.realmlist),
+        ("executable", &entry.executable),
+        ("account", &entry.account),
+    ] {
+        if let Some(value) = value {
+            values.push((key, value.as_str().into()));
+        }
+    }
+    if values.is_empty() {
+        anyhow::bail!("Nothing to set, pass --directory, --realmlist, --executable or --account");
+    }
+
+    let (updated, existed) = crate::config::upsert_game(&source, name, &values)
+        .map_err(|e| ExitError::new(exit_code::CONFIG, e))?;
+    if !existed && entry.directory.is_none() {
+        return Err(ExitError::new(
+            exit_code::CONFIG,
+            format!(
+                "[{name}] is not in {}, a new entry needs --directory",
+                path.display()
+            ),
+        )
+        .into());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("toml.tmp");
+    std::fs::write(&temp_path, &updated)?;
+    std::fs::rename(&temp_path, &path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    let keys: Vec<&str> = values.iter().map(|(key, _)| *key).collect();
+    let verb = if existed { "Updated" } else { "Added" };
+    outln!(
+        "✓ {verb} [{name}] in {}: {}",
+        path.display(),
+        keys.join(", ")
+    );
+    Ok(())
+}
+
+fn cmd_secrets_edit(config_path: &str) -> Result<()> {
+    let config_path = crate::paths::expand(config_path);
+    crate::secrets::edit_secrets(&config_path)
+}
+
+/// Game key of config.toml matching `game`, read without decrypting the secrets
+fn secrets_game_key(config_path: &Path, game: &str) -> Result<String> {
+    let source = std::fs::read_to_string(config_path)
+        .with_context(|| format!("Config file not found: {}", config_path.display()))?;
+    let table: toml::Table = toml::from_str(&source).context("Failed to parse config file")?;
+    match crate::config::matching_keys(&table, game).as_slice() {
+        [key] => Ok(key.to_string()),
+        [] => anyhow::bail!("Config with key '{game}' not found (case-insensitive)"),
+        _ => anyhow::bail!("Config key '{game}' is ambiguous (run `realmctl config lint`)"),
+    }
+}
+
+fn cmd_secrets_set(
+    game: &str,
+    key: &str,
+    keyring: bool,
+    passphrase: bool,
+    config_path: &str,
+) -> Result<()> {
+    use crate::secrets::{Protection, read_secrets, set_secret, write_secrets};
+
+    let config_path = crate::paths::expand(config_path);
+    let game = secrets_game_key(&config_path, game)?;
+    let protection = if keyring {
+        Some(Protection::Keyring)
+    } else if passphrase {
+        Some(Protection::Passphrase)
+    } else {
+        None
+    };
+
+    let mut secrets = read_secrets(&config_path)?;
+    let value = rpassword::prompt_password(format!("{key} for {game}: "))?;
+    if value.is_empty() {
+        anyhow::bail!("Empty value, secrets left unchanged");
+    }
+    set_secret(&mut secrets, &game, key, &value)?;
+    write_secrets(&config_path, &secrets, protection)?;
+    outln!(
+        "✓ Stored {key} for {game} ({})",
+        crate::secrets::current_protection(&config_path)
+    );
+    Ok(())
+}
+
+fn cmd_secrets_get(game: &str, key: &str, config_path: &str) -> Result<()> {
+    let config_path = crate::paths::expand(config_path);
+    let secrets = crate::secrets::read_secrets(&config_path)?;
+    match crate::secrets::get_secret(&secrets, game, key)? {
+        Some(value) => {
+            outln!("{value}");
+            Ok(())
+        }
+        None => anyhow::bail!("No {key} stored for {game}"),
+    }
+}
+
+fn cmd_secrets_remove(game: &str, key: &str, config_path: &str) -> Result<()> {
+    use crate::secrets::{read_secrets, remove_secret, write_secrets};
+
+    let config_path = crate::paths::expand(config_path);
+    let mut secrets = read_secrets(&config_path)?;
+    if !remove_secret(&mut secrets, game, key)? {
+        anyhow::bail!("No {key} stored for {game}");
+    }
+    write_secrets(&config_path, &secrets, None)?;
+    outln!("✓ Removed {key} for {game}");
+    Ok(())
+}
+
+fn cmd_inspect(path: &Path, profile_name: Option<&str>) -> Result<()> {
+    use crate::base::{FileRole, Profile, inspect_directory};
+
+    let dir = crate::paths::expand(&path.to_string_lossy());
+    if !dir.is_dir() {
+        anyhow::bail!("Directory does not exist: {}", dir.display());
+    }
+    let profile = match profile_name {
+        Some(name) => Profile::resolve(name)?,
+        None => Profile::detect(&dir),
+    };
+
+    outln!("Directory:\n\t{}", dir.display());
+    outln!(
+        "Profile:\n\t{}{}",
+        profile.name,
+        if profile_name.is_none() {
+            " (detected)"
+        } else {
+            ""
+        }
+    );
+
+    outln!("\n=== Requirements ===");
+    match profile.verify_requirements(&dir) {
+        Ok(()) => outln!("✓ All required files and directories present"),
+        Err(e) => outln!("✗ {e}"),
+    }
+    for warning in profile.check_warnings(&dir) {
+        outln!("⚠ {warning}");
+    }
+
+    let inspection = inspect_directory(&dir, &profile)?;
+    outln!("\n=== Roles ===");
+    for role in [
+        FileRole::Executable,
+        FileRole::BaseData,
+        FileRole::MutableData,
+        FileRole::UserMedia,
+        FileRole::UserConfig,
+        FileRole::Ephemeral,
+        FileRole::Managed,
+        FileRole::Other,
+    ] {
+        let paths: Vec<&String> = inspection
+            .file_roles
+            .iter()
+            .filter(|(_, r)| **r == role)
+            .map(|(path, _)| path)
+            .collect();
+        if paths.is_empty() {
+            continue;
+        }
+        outln!("{:<12} {} path(s)", format!("{role:?}"), paths.len());
+        // Top-level entries are enough to see where a role comes from
+        for path in paths.iter().filter(|path| !path.contains('/')).take(10) {
+            outln!("\t{path}");
+        }
+    }
+
+    outln!("\n=== Red flags ===");
+    if inspection.red_flags.is_empty() {
+        outln!("✓ No unexpected executables, DLLs or scripts");
+    }
+    for (path, reason) in &inspection.red_flags {
+        outln!("⚠ {path}: {reason}");
+    }
+    outln!("\nNothing was written to {}", dir.display());
+    Ok(())
+}
+
+/// Warn about names in `dir` that differ only in case, returning them
+fn report_case_collisions(dir: &Path) -> Result<Vec<crate::case::CaseCollision>> {
+    let collisions = crate::case::find_case_collisions(dir)
+        .with_context(|| format!("Failed to scan {}", dir.display()))?;
+    if collisions.is_empty() {
+        return Ok(collisions);
+    }
+    outln!("\n⚠ Names differing only in case, sharing rules and Wine only ever see one of each:");
+    for collision in &collisions {
+        let names: Vec<String> = collision
+            .names
+            .iter()
+            .map(|name| collision.parent.join(name).display().to_string())
+            .collect();
+        outln!("  - {}", names.join(" and "));
+    }
+    Ok(collisions)
+}
+
+/// Merge the directories of case collisions into their capitalized spelling, asking first
+/// unless `merge` is set
+fn merge_case_collisions(
+    dir: &Path,
+    collisions: &[crate::case::CaseCollision],
+    merge: bool,
+) -> Result<()> {
+    use std::io::{IsTerminal, Write};
+
+    let mergeable: Vec<&crate::case::CaseCollision> =
+        collisions.iter().filter(|c| c.all_dirs).collect();
+    if mergeable.is_empty() {
+        return Ok(());
+    }
+    if !merge {
+        if !std::io::stdin().is_terminal() {
+            outln!("Run again with --merge-case to merge the directories");
+            return Ok(());
+        }
+        out!("Merge the directories into their capitalized spelling? [y/N] ");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+    for collision in mergeable {
+        let into = collision.parent.join(collision.preferred(dir));
+        let conflicts = crate::case::merge_collision(dir, collision)
+            .with_context(|| format!("Failed to merge into {}", into.display()))?;
+        outln!("✓ Merged into {}", into.display());
+        for conflict in conflicts {
+            outln!("  ⚠ Kept {}, it exists in both", conflict.display());
+        }
+    }
+    Ok(())
+}
+
+fn cmd_init_base(path: &Path, profile_name: &str, resume: bool, merge_case: bool) -> Result<()> {
+    use crate::base::{Profile, load_progress, scan_and_build_manifest_resumable, write_manifest};
+
+    outln!("Initializing base at: {}", path.display());
+    outln!("Using profile: {}", profile_name);
+
+    // Expand tilde in path
+    let expanded_path = shellexpand::tilde(&path.to_string_lossy()).to_string();
+    let base_dir = PathBuf::from(expanded_path);
+
+    if !base_dir.exists() {
+        anyhow::bail!("Directory does not exist: {}", base_dir.display());
+    }
+
+    // Load profile
+    let profile = Profile::resolve(profile_name)?;
+
+    let collisions = report_case_collisions(&base_dir)?;
+    merge_case_collisions(&base_dir, &collisions, merge_case)?;
+
+    outln!("\n=== Verifying Requirements ===");
+    profile.verify_requirements(&base_dir)?;
+    outln!("✓ All required files and directories present");
+
+    // Check warnings
+    let warnings = profile.check_warnings(&base_dir);
+    if !warnings.is_empty() {
+        outln!("\n⚠ Warnings:");
+        for warning in warnings {
+            outln!("  - {}", warning);
+        }
+    }
+
+    let red_flags = crate::base::inspect_directory(&base_dir, &profile)?.red_flags;
+    if !red_flags.is_empty() {
+        outln!("\n⚠ Red flags (see `realmctl inspect`):");
+        for (path, reason) in red_flags {
+            outln!("  - {path}: {reason}");
+        }
+    }
+
+    match &crate::locale::detect_locale(&base_dir) {
+        Some(locale) => outln!("Detected locale: {locale}"),
+        None => outln!("⚠ No locale directory found under Data"),
+    }
+
+    outln!("\n=== Scanning Directory ===");
+    match load_progress(&base_dir) {
+        Ok(Some(progress)) if resume => {
+            outln!("Resuming, {} files already hashed", progress.hashed.len())
+        }
+        Ok(Some(_)) => {
+            tracing::warn!("⚠ Discarding progress of an interrupted scan (use --resume to keep it)")
+        }
+        _ => {}
+    }
+    crate::cancel::install_handler();
+    let manifest = scan_and_build_manifest_resumable(&base_dir, &profile, resume).map_err(|e| {
+        interrupted(
+            e,
+            &format!(
+                "no manifest was written. Run `realmctl init-base {} --profile {} --resume` to continue",
+                path.display(),
+                profile.name
+            ),
+        )
+    })?;
+
+    let hashed_bytes: u64 = manifest
+        .checksums
+        .keys()
+        .filter_map(|rel_path| std::fs::metadata(base_dir.join(rel_path)).ok())
+        .map(|meta| meta.len())
+        .sum();
+    outln!("Found {} files/directories", manifest.file_roles.len());
+    outln!(
+        "Computed {} checksums for immutable files ({})",
+        manifest.checksums.len(),
+        crate::progress::format_bytes(hashed_bytes)
+    );
+
+    outln!("\n=== Writing Manifest ===");
+    write_manifest(&manifest, &base_dir)?;
+    outln!("✓ Manifest written to {}/manifest.toml", base_dir.display());
+
+    outln!("\n✓ Base initialization complete!");
+
+    Ok(())
+}
+
+fn cmd_verify_base(path: &Path, torrent_path: Option<&Path>) -> Result<()> {
+    let base_dir = crate::paths::expand(&path.to_string_lossy());
+    if !base_dir.is_dir() {
+        anyhow::bail!("Directory does not exist: {}", base_dir.display());
+    }
+    match torrent_path {
+        Some(torrent_path) => verify_base_torrent(&base_dir, torrent_path),
+        None => verify_base_manifest(&base_dir),
+    }
+}
+
+/// Result of `verify-base` for `--json`
+#[derive(serde::Serialize)]
+struct BaseVerification<'a, R> {
+    base: &'a Path,
+    /// manifest.toml or the .torrent file
+    against: &'a Path,
+    ok: bool,
+    #[serde(flatten)]
+    report: &'a R,
+}
+
+fn verify_base_manifest(base_dir: &Path) -> Result<()> {
+    outln!("Verifying:\n\t{}", base_dir.display());
+    outln!("Against:\n\t{}", base_dir.join("manifest.toml").display());
+
+    crate::cancel::install_handler();
+    let report = crate::base::verify_manifest(base_dir)
+        .map_err(|e| interrupted(e, "nothing was changed, run the verification again"))?;
+    if crate::output::is_json() {
+        crate::output::emit(&BaseVerification {
+            base: base_dir,
+            against: &base_dir.join("manifest.toml"),
+            ok: report.is_ok(),
+            report: &report,
+        })?;
+    }
+
+    for path in &report.missing {
+        outln!("✗ Missing: {path}");
+    }
+    for path in &report.modified {
+        outln!("✗ Modified: {path}");
+    }
+    for path in &report.extra {
+        outln!("⚠ Not in manifest: {path}");
+    }
+    outln!(
+        "\n{}/{} checksummed files verified ({} read)",
+        report.verified,
+        report.verified + report.modified.len() + report.missing.len(),
+        crate::progress::format_bytes(report.bytes)
+    );
+    if !report.extra.is_empty() {
+        outln!("Re-run `realmctl init-base` to record files added since the scan");
+    }
+    if !report.is_ok() {
+        record_verification(base_dir, false);
+        return Err(ExitError::new(
+            exit_code::CORRUPT,
+            format!("{} does not match its manifest", base_dir.display()),
+        )
+        .into());
+    }
+    outln!("✓ All files match the manifest");
+    record_verification(base_dir, true);
+    Ok(())
+}
+
+fn verify_base_torrent(base_dir: &Path, torrent_path: &Path) -> Result<()> {
+    use crate::torrent::{TorrentInfo, verify_torrent};
+
+    let torrent_path = crate::paths::expand(&torrent_path.to_string_lossy());
+    let torrent = TorrentInfo::load(&torrent_path)?;
+    let root = torrent.root_in(base_dir);
+    outln!("Verifying:\n\t{}", root.display());
+    outln!(
+        "Against:\n\t{} ({} files, {} pieces)",
+        torrent_path.display(),
+        torrent.files.iter().filter(|f| !f.padding).count(),
+        torrent.pieces.len()
+    );
+
+    crate::cancel::install_handler();
+    let report = verify_torrent(&root, &torrent)
+        .map_err(|e| interrupted(e, "nothing was changed, run the verification again"))?;
+    if crate::output::is_json() {
+        crate::output::emit(&BaseVerification {
+            base: &root,
+            against: &torrent_path,
+            ok: report.is_ok(),
+            report: &report,
+        })?;
+    }
+
+    for path in &report.missing {
+        outln!("✗ Missing: {path}");
+    }
+    for path in &report.wrong_size {
+        outln!("✗ Wrong size: {path}");
+    }
+    for path in &report.corrupt {
+        outln!("✗ Corrupt (in a piece that failed): {path}");
+    }
+    outln!(
+        "\n{}/{} pieces verified",
+        report.pieces_ok,
+        report.pieces_ok + report.pieces_bad
+    );
+    if !report.is_ok() {
+        if root == base_dir {
+            record_verification(base_dir, false);
+        }
+        return Err(ExitError::new(
+            exit_code::CORRUPT,
+            format!("{} does not match the torrent", root.display()),
+        )
+        .into());
+    }
+    outln!("✓ All files match the torrent");
+    if root == base_dir {
+        record_verification(base_dir, true);
+    }
+    Ok(())
+}
+
+/// Remember the outcome of a verification for `summary` and the metrics endpoint, keyed like
+/// `list` keys bases
+fn record_verification(base_dir: &Path, ok: bool) {
+    let base_dir = std::fs::canonicalize(base_dir).unwrap_or_else(|_| base_dir.to_path_buf());
+    crate::state::update_state(|state| {
+        let directory = state.directory_mut(&base_dir);
+        directory.last_verify_ok = Some(ok);
+        if ok {
+            directory.last_verified = Some(crate::state::now());
+        }
+    });
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_create_workspace(
+    name: &str,
+    base: &str,
+    share_args: &[String],
+    workspace_root: &str,
+    config_path: &str,
+    options: crate::workspace::CreateOptions,
+    dry_run: bool,
+) -> Result<()> {
+    use crate::workspace::{SharingStrategy, create_workspace_with, default_sharing_rules};
+
+    outln!("Creating workspace: {name}");
+    outln!("Base: {base}");
+
+    // Expand paths
+    let base_path = crate::paths::expand(base);
+    let base_path = std::path::absolute(&base_path).unwrap_or(base_path);
+    let ws_root = crate::paths::expand(workspace_root);
+
+    // Parse sharing rules
+    let mut sharing_rules = default_sharing_rules();
+    for arg in share_args {
+        let parts: Vec<&str> = arg.split('=').collect();
+        if parts.len() == 2 {
+            let key = parts[0].to_string();
+            let value = match parts[1] {
+                "global" => SharingStrategy::Global,
+                "base" => SharingStrategy::Base,
+                "workspace" => SharingStrategy::Workspace,
+                _ => anyhow::bail!("Invalid sharing strategy: {}", parts[1]),
+            };
+            sharing_rules.insert(key, value);
+        }
+    }
+
+    if !report_case_collisions(&base_path)?.is_empty() {
+        outln!(
+            "Merge them with `realmctl init-base {base} --merge-case` before creating workspaces"
+        );
+    }
+
+    outln!("\nSharing rules:");
+    for (key, value) in &sharing_rules {
+        outln!("  {} = {:?}", key, value);
+    }
+
+    if dry_run {
+        let plan =
+            crate::workspace::plan_workspace(name, &base_path, &ws_root, &sharing_rules, &options)?;
+        outln!("\n=== Would create {} ===", ws_root.join(name).display());
+        print_planned(plan.entries());
+        return Ok(());
+    }
+
+    outln!("\n=== Creating Workspace ===");
+    crate::cancel::install_handler();
+    let config = create_workspace_with(name, &base_path, &ws_root, sharing_rules, &options)
+        .map_err(|e| {
+        interrupted(
+            e,
+            &format!("the partial workspace was removed. Run `realmctl create {name} --base {base}` again"),
+        )
+    })?;
+
+    outln!(
+        "✓ Workspace created at: {}",
+        config.workspace_path.display()
+    );
+    // How each file was brought in, details in `realmctl audit`
+    use crate::journal::JournalAction;
+    let mut methods: std::collections::BTreeMap<&str, usize> = Default::default();
+    // Bytes of the files that share blocks with the base instead of being copied
+    let mut saved = 0;
+    for entry in crate::journal::load_journal(&config.workspace_path).unwrap_or_default() {
+        if entry.action == JournalAction::Mkdir {
+            continue;
+        }
+        *methods.entry(entry.action.as_str()).or_default() += 1;
+        if entry.action != JournalAction::Copy
+            && let Ok(meta) = std::fs::metadata(config.workspace_path.join(&entry.rel_path))
+            && meta.is_file()
+        {
+            saved += meta.len();
+        }
+    }
+    let methods: Vec<String> = methods
+        .iter()
+        .map(|(method, count)| format!("{count} {method}"))
+        .collect();
+    outln!("Files: {}", methods.join(", "));
+    outln!(
+        "Space saved vs. copying: {}",
+        crate::progress::format_bytes(saved)
+    );
+    outln!("\nAdd it to your config.toml to launch it:");
+    outln!(
+        "realmctl config add {name} --directory \"{}\"",
+        config.workspace_path.display()
+    );
+
+    run_hooks(
+        &load_hooks(config_path),
+        HookEvent::Create,
+        serde_json::json!({
+            "workspace": name,
+            "directory": config.workspace_path,
+            "base": config.base_path,
+            "profile": config.base_name,
+        }),
+    );
+    Ok(())
+}
+
+/// Print the journal entries of a planned operation, one "Would <action>" line each
+#[cfg(feature = "workspaces")]
+fn print_planned(entries: &[crate::journal::JournalEntry]) {
+    let mut counts: std::collections::BTreeMap<&str, usize> = Default::default();
+    for entry in entries {
+        *counts.entry(entry.action.as_str()).or_default() += 1;
+        match &entry.target {
+            Some(target) => outln!(
+                "Would {} {} -> {} ({})",
+                entry.action.as_str(),
+                entry.rel_path,
+                target.display(),
+                entry.reason
+            ),
+            None => outln!(
+                "Would {} {} ({})",
+                entry.action.as_str(),
+                entry.rel_path,
+                entry.reason
+            ),
+        }
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(action, count)| format!("{count} {action}"))
+        .collect();
+    outln!(
+        "\nDry run, nothing was changed. Planned: {}",
+        counts.join(", ")
+    );
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_bench_linking(base: &str, workspace_root: &str) -> Result<()> {
+    use crate::bench::{bench, recommend};
+
+    let base_path = crate::paths::expand(base);
+    let base_path = std::path::absolute(&base_path).unwrap_or(base_path);
+    let manifest = crate::base::load_manifest(&base_path).with_context(|| {
+        format!(
+            "No manifest.toml in {}, run init-base first",
+            base_path.display()
+        )
+    })?;
+    let files: Vec<String> = manifest
+        .file_roles
+        .keys()
+        .filter(|rel_path| base_path.join(rel_path).is_file())
+        .cloned()
+        .collect();
+    let ws_root = crate::paths::expand(workspace_root);
+    let scratch = ws_root.join(format!(".bench-linking-{}", std::process::id()));
+    std::fs::create_dir_all(&scratch)
+        .with_context(|| format!("Failed to create {}", scratch.display()))?;
+
+    outln!(
+        "Linking {} files of {} into:\n\t{}",
+        files.len(),
+        base_path.display(),
+        ws_root.display()
+    );
+    crate::cancel::install_handler();
+    let results = bench(&base_path, &files, &scratch);
+    let _ = std::fs::remove_dir_all(&scratch);
+    if crate::cancel::is_cancelled() {
+        return Err(ExitError::new(exit_code::INTERRUPTED, "Interrupted").into());
+    }
+
+    outln!("\n{:<10} {:>10} {:>12}", "STRATEGY", "TIME", "DISK USED");
+    for result in &results {
+        let time = format!("{:.2}s", result.elapsed.as_secs_f64());
+        match &result.error {
+            None => {
+                let used = result
+                    .bytes_used
+                    .map_or("?".to_string(), crate::status::format_bytes);
+                outln!("{:<10} {:>10} {:>12}", result.strategy.as_str(), time, used);
+            }
+            Some(error) => outln!("{:<10} ✗ {}", result.strategy.as_str(), error),
+        }
+    }
+
+    match recommend(&results) {
+        Some(strategy) => {
+            outln!("\nRecommended: {}", strategy.as_str());
+            outln!("(`realmctl create` currently hard links, falling back to symlinks)");
+        }
+        None => outln!("\n✗ No strategy worked on this filesystem"),
+    }
+    Ok(())
+}
+
+/// Ask what `fix` should do with a real directory where a shared link is expected
+///
+/// Skips without a terminal to ask on.
+#[cfg(feature = "workspaces")]
+fn ask_share_conflict(
+    conflict: &crate::workspace::ShareConflict,
+) -> crate::workspace::ConflictChoice {
+    use crate::workspace::ConflictChoice;
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        outln!(
+            "⚠ {} is a real directory instead of a shared link, run again with --assume keep-local or --assume adopt",
+            conflict.local.display()
+        );
+        return ConflictChoice::Skip;
+    }
+    outln!(
+        "\n⚠ {} is a real directory, expected a link to the {} shared directory:\n\t{}",
+        conflict.local.display(),
+        conflict.strategy.as_str(),
+        conflict.shared.display()
+    );
+    loop {
+        out!("[k]eep local, [a]dopt into shared, [s]kip, show [d]iff? ");
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+            return ConflictChoice::Skip;
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "k" | "keep" => return ConflictChoice::KeepLocal,
+            "a" | "adopt" => return ConflictChoice::Adopt,
+            "s" | "skip" | "" => return ConflictChoice::Skip,
+            "d" | "diff" => match crate::workspace::diff_dirs(&conflict.local, &conflict.shared) {
+                Ok(diff) => print_dir_diff(&diff),
+                Err(e) => outln!("✗ {e:#}"),
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(feature = "workspaces")]
+fn print_dir_diff(diff: &crate::workspace::DirDiff) {
+    const SHOWN: usize = 20;
+    for (label, files) in [
+        ("Only in the workspace", &diff.only_local),
+        ("Only in the shared directory", &diff.only_shared),
+        ("Different contents", &diff.differing),
+    ] {
+        if files.is_empty() {
+            continue;
+        }
+        outln!("{label} ({}):", files.len());
+        for file in files.iter().take(SHOWN) {
+            outln!("\t{file}");
+        }
+        if files.len() > SHOWN {
+            outln!("\t... and {} more", files.len() - SHOWN);
+        }
+    }
+    outln!("Identical in both: {}", diff.identical);
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_fix(
+    workspace: &str,
+    assume: Option<crate::workspace::ConflictChoice>,
+    dry_run: bool,
+    config_path: &str,
+) -> Result<()> {
+    outln!("Fixing workspace: {}", workspace);
+
+    let game_cfg = load_config(config_path, workspace)?;
+    if !dry_run {
+        backup_before(
+            crate::backup::BackupTrigger::Fix,
+            workspace,
+            &game_cfg.directory,
+            config_path,
+        )?;
+    }
+
+    // Perform fix/repair operations on the workspace
+    crate::cancel::install_handler();
+    let resolve = |conflict: &crate::workspace::ShareConflict| match assume {
+        Some(choice) => choice,
+        None if dry_run => {
+            outln!(
+                "Would ask whether to keep, adopt or skip {}",
+                conflict.local.display()
+            );
+            crate::workspace::ConflictChoice::Skip
+        }
+        None => ask_share_conflict(conflict),
+    };
+    crate::workspace::fix_workspace_with(&game_cfg.directory, resolve, dry_run).map_err(|e| {
+        interrupted(
+            e,
+            &format!("repairs so far were kept. Run `realmctl fix {workspace}` again to finish"),
+        )
+    })?;
+    if dry_run {
+        outln!("\nDry run, nothing was changed");
+        return Ok(());
+    }
+
+    outln!("\n✓ Fix operations completed (no user data was overridden)");
+    // Refresh the health summary shown at launch
+    if let Ok(report) = crate::workspace::check_health(&game_cfg.directory, None) {
+        crate::state::update_state(|state| {
+            state.directory_mut(&game_cfg.directory).health = Some(crate::state::HealthSummary {
+                checked_at: crate::state::now(),
+                warnings: report.warnings,
+            })
+        });
+    }
+    run_hooks(
+        &load_hooks(config_path),
+        HookEvent::Fix,
+        serde_json::json!({ "game": workspace, "directory": game_cfg.directory }),
+    );
+    Ok(())
+}
+
+/// Group unique files by addon, or by their top two directories
+#[cfg(feature = "workspaces")]
+fn deletion_group(rel_path: &str) -> String {
+    let parts: Vec<&str> = rel_path.split('/').collect();
+    let depth = if rel_path.starts_with("Interface/AddOns/") {
+        3
+    } else {
+        2
+    };
+    if parts.len() <= depth {
+        rel_path.to_string()
+    } else {
+        parts[..depth].join("/")
+    }
+}
+
+/// Where `--archive` writes: the given file, else a new archive named after `name` that
+/// `[retention.archives]` applies to
+#[cfg(feature = "workspaces")]
+fn archive_path(path: Option<&str>, name: &str) -> PathBuf {
+    match path {
+        Some(path) => crate::paths::expand(path),
+        None => crate::retention::default_archive(name),
+    }
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_delete(
+    workspace: &str,
+    archive: Option<&Path>,
+    yes: bool,
+    dry_run: bool,
+    config_path: &str,
+) -> Result<()> {
+    use crate::status::format_bytes;
+    use std::io::Write;
+
+    let game_cfg = load_config(config_path, workspace)?;
+    let dir = &game_cfg.directory;
+    if !crate::process::processes_in(dir).is_empty() {
+        anyhow::bail!("{workspace} is running, close the game before deleting it");
+    }
+    let report = crate::workspace::plan_deletion(dir)?;
+
+    outln!("Workspace:\n\t{}", dir.display());
+    outln!("\n=== Contents ===");
+    outln!(
+        "✓ {} linked from the base or shared directories (kept)",
+        report.linked
+    );
+    outln!("✓ {} copies of base files", report.copied);
+    outln!("✓ {} cache, log and realmctl files", report.disposable);
+    if report.unique.is_empty() {
+        outln!("✓ No files exist only in this workspace");
+    } else {
+        let total: u64 = report.unique.iter().map(|(_, size)| size).sum();
+        outln!(
+            "⚠ {} file(s) exist only in this workspace ({}):",
+            report.unique.len(),
+            format_bytes(total)
+        );
+        let mut groups: std::collections::BTreeMap<String, (usize, u64)> =
+            std::collections::BTreeMap::new();
+        for (rel_path, size) in &report.unique {
+            let group = groups.entry(deletion_group(rel_path)).or_default();
+            group.0 += 1;
+            group.1 += size;
+        }
+        for (group, (count, size)) in groups {
+            outln!("\t{group} ({count} file(s), {})", format_bytes(size));
+        }
+    }
+
+    if dry_run {
+        outln!();
+        if let Some(archive) = archive {
+            outln!(
+                "Would archive {} file(s) to {}",
+                report.unique.len(),
+                archive.display()
+            );
+        }
+        outln!("Would remove {} and everything in it", dir.display());
+        outln!("Would forget {workspace} in the state file");
+        outln!("\nDry run, nothing was changed");
+        return Ok(());
+    }
+    if let Some(archive) = archive {
+        let paths: Vec<String> = report.unique.iter().map(|(p, _)| p.clone()).collect();
+        crate::workspace::archive_files(dir, &paths, archive)?;
+        outln!(
+            "\n✓ Archived {} file(s) to {}",
+            paths.len(),
+            archive.display()
+        );
+    } else if !report.unique.is_empty() {
+        outln!("\nThese files are lost on deletion, keep them with --archive [<file.tar.gz>]");
+    }
+
+    if !yes {
+        out!("\nDelete {}? [y/N] ", dir.display());
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            outln!("Aborted, nothing was deleted");
+            return Ok(());
+        }
+    }
+
+    crate::workspace::delete_workspace(dir)?;
+    crate::state::update_state(|state| {
+        state.directories.remove(&crate::paths::state_key(dir));
+    });
+    outln!("\n✓ Deleted {workspace}");
+    outln!("Remove the [{workspace}] entry from {config_path} as well");
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_backup(workspace: &str, config_path: &str) -> Result<()> {
+    use crate::status::format_bytes;
+
+    let game_cfg = load_config(config_path, workspace)?;
+    let config = crate::backup::load_backup(config_path);
+    let backup = crate::backup::create_backup(
+        workspace,
+        &game_cfg.directory,
+        &game_profile(&game_cfg.directory),
+        &config,
+    )?;
+    outln!(
+        "✓ Backed up {workspace} ({}):\n\t{}",
+        format_bytes(backup.size_bytes),
+        backup.path.display()
+    );
+    let policy = crate::backup::backup_policy(config_path);
+    for removed in crate::backup::prune_backups(&config, workspace, &policy, false)? {
+        outln!("✓ Removed old backup {}", removed.file_name());
+    }
+    Ok(())
+}
+
+/// Back up a workspace before `trigger` changes it, if `[backup] before` asks for it
+#[cfg(feature = "workspaces")]
+fn backup_before(
+    trigger: crate::backup::BackupTrigger,
+    workspace: &str,
+    game_dir: &Path,
+    config_path: &str,
+) -> Result<()> {
+    let config = crate::backup::load_backup(config_path);
+    if !config.before.contains(&trigger) {
+        return Ok(());
+    }
+    let backup =
+        crate::backup::create_backup(workspace, game_dir, &game_profile(game_dir), &config)
+            .with_context(|| {
+                format!(
+                    "Backup before {} failed, nothing was changed",
+                    trigger.name()
+                )
+            })?;
+    tracing::info!("✓ Backed up {workspace} to {}", backup.path.display());
+    let policy = crate::backup::backup_policy(config_path);
+    for removed in crate::backup::prune_backups(&config, workspace, &policy, false)? {
+        tracing::debug!("Removed old backup {}", removed.path.display());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_backup_list(workspace: Option<&str>, config_path: &str) -> Result<()> {
+    use crate::state::format_age;
+    use crate::status::format_bytes;
+
+    let config = crate::backup::load_backup(config_path);
+    let backups = crate::backup::list_backups(&config, workspace);
+    if crate::output::is_json() {
+        return crate::output::emit(&backups);
+    }
+    if backups.is_empty() {
+        outln!("No backups in {}", config.dir().display());
+        return Ok(());
+    }
+    let width = backups
+        .iter()
+        .map(|backup| backup.workspace.len())
+        .max()
+        .unwrap_or(9)
+        .max(9);
+    outln!(
+        "{:<width$}  {:<10}  {:>10}  FILE",
+        "WORKSPACE",
+        "CREATED",
+        "SIZE"
+    );
+    for backup in &backups {
+        outln!(
+            "{:<width$}  {:<10}  {:>10}  {}",
+            backup.workspace,
+            format_age(backup.created),
+            format_bytes(backup.size_bytes),
+            backup.file_name()
+        );
+    }
+    outln!("\nBackups are in {}", config.dir().display());
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_backup_restore(
+    workspace: &str,
+    backup: Option<&str>,
+    dry_run: bool,
+    config_path: &str,
+) -> Result<()> {
+    let game_cfg = load_config(config_path, workspace)?;
+    let dir = &game_cfg.directory;
+    let config = crate::backup::load_backup(config_path);
+    let backup = crate::backup::find_backup(&config, workspace, backup)?;
+    let files = crate::backup::backup_contents(&backup.path)?;
+
+    outln!("Backup:\n\t{}", backup.path.display());
+    if dry_run {
+        for rel_path in &files {
+            outln!("Would restore {rel_path}");
+        }
+        outln!("\nDry run, nothing was changed");
+        return Ok(());
+    }
+    if !crate::process::processes_in(dir).is_empty() {
+        anyhow::bail!("{workspace} is running, close the game before restoring a backup");
+    }
+
+    // Restoring can be undone with the backup of what is there now
+    let current = crate::backup::create_backup(workspace, dir, &game_profile(dir), &config).ok();
+    crate::backup::restore_backup(&backup.path, dir)?;
+    outln!("✓ Restored {} file(s) into {workspace}", files.len());
+    if let Some(current) = current {
+        outln!(
+            "The files it replaced are in {}, restore that to undo",
+            current.file_name()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_gc(workspace_root: &str, archive: Option<&Path>, yes: bool, dry_run: bool) -> Result<()> {
+    use crate::status::{SharedOrphans, directory_size, format_bytes, orphaned_shared};
+    use std::io::Write;
+
+    let ws_root = crate::paths::expand(workspace_root);
+    let shared =
+        std::fs::canonicalize(ws_root.join(".shared")).unwrap_or_else(|_| ws_root.join(".shared"));
+    let SharedOrphans { orphans, unloaded } = orphaned_shared(&ws_root)?;
+    if !unloaded.is_empty() {
+        tracing::warn!(
+            "⚠ {} director(ies) under the workspace root have a missing or unreadable workspace.toml:",
+            unloaded.len()
+        );
+        for dir in &unloaded {
+            tracing::warn!("\t{}", dir.display());
+        }
+    }
+    if orphans.is_empty() {
+        outln!("✓ No orphaned shared directories in {}", shared.display());
+        return Ok(());
+    }
+
+    let rel = |path: &Path| {
+        path.strip_prefix(&shared)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    };
+    let mut total = 0;
+    outln!("=== Orphaned shared directories ===");
+    for orphan in &orphans {
+        let size = directory_size(orphan).on_disk;
+        total += size;
+        outln!("\t{} ({})", rel(orphan), format_bytes(size));
+    }
+    outln!(
+        "⚠ {} director(ies) no workspace links to ({})",
+        orphans.len(),
+        format_bytes(total)
+    );
+
+    // Links of a workspace that doesn't load may not all be found, removing anything could
+    // delete its settings
+    if !unloaded.is_empty() && !dry_run {
+        anyhow::bail!(
+            "Refusing to remove shared directories while workspaces fail to load, fix or delete them first"
+        );
+    }
+    if dry_run {
+        outln!();
+        if let Some(archive) = archive {
+            outln!(
+                "Would archive {} director(ies) to {}",
+                orphans.len(),
+                archive.display()
+            );
+        }
+        for orphan in &orphans {
+            outln!("Would remove {}", rel(orphan));
+        }
+        outln!("\nDry run, nothing was changed");
+        return Ok(());
+    }
+    if let Some(archive) = archive {
+        let paths: Vec<String> = orphans.iter().map(|orphan| rel(orphan)).collect();
+        crate::workspace::archive_files(&shared, &paths, archive)?;
+        outln!(
+            "\n✓ Archived {} director(ies) to {}",
+            paths.len(),
+            archive.display()
+        );
+    } else {
+        outln!("\nTheir contents are lost on removal, keep them with --archive [<file.tar.gz>]");
+    }
+
+    if !yes {
+        out!("\nRemove {} director(ies)? [y/N] ", orphans.len());
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            outln!("Aborted, nothing was removed");
+            return Ok(());
+        }
+    }
+
+    for orphan in &orphans {
+        std::fs::remove_dir_all(crate::paths::long_path(orphan))
+            .with_context(|| format!("Failed to remove {}", orphan.display()))?;
+        outln!("✓ Removed {}", rel(orphan));
+    }
+    outln!("\n✓ Freed {}", format_bytes(total));
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_push(
+    workspace: &str,
+    target: &str,
+    remote_base: Option<&str>,
+    name: Option<&str>,
+    remote_realmctl: &str,
+    dry_run: bool,
+    config_path: &str,
+) -> Result<()> {
+    use crate::push::{Remote, plan_push, push_workspace};
+    use crate::status::format_bytes;
+
+    let game_cfg = load_config(config_path, workspace)?;
+    let dir = &game_cfg.directory;
+    let remote = Remote::parse(target)?;
+    let plan = plan_push(dir, remote, name, remote_base, remote_realmctl)?;
+
+    outln!(
+        "Workspace:
+	{}",
+        dir.display()
+    );
+    outln!(
+        "Target:
+	{}:{}",
+        plan.remote.host,
+        plan.remote_dir()
+    );
+    outln!(
+        "Remote base:
+	{}",
+        plan.remote_base
+    );
+    outln!(
+        "\n{} file(s) exist only in this workspace ({})",
+        plan.files.len(),
+        format_bytes(plan.bytes)
+    );
+
+    if dry_run {
+        outln!("\n=== Remote commands ===");
+        outln!("ssh {} {}", plan.remote.host, plan.setup_command());
+        if !plan.files.is_empty() {
+            outln!("ssh {} {}", plan.remote.host, plan.unpack_command());
+        }
+        outln!("\nDry run, nothing was transferred");
+        return Ok(());
+    }
+
+    push_workspace(dir, &plan)?;
+    outln!(
+        "\n✓ Pushed {workspace} to {}:{}",
+        plan.remote.host,
+        plan.remote_dir()
+    );
+    outln!(
+        "Add a [{}] entry with directory = \"{}\" to the config.toml on {}",
+        plan.name,
+        plan.remote_dir(),
+        plan.remote.host
+    );
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_migrate(
+    workspace: &str,
+    to_base: &str,
+    name: Option<&str>,
+    config_path: &str,
+) -> Result<()> {
+    let game_cfg = load_config(config_path, workspace)?;
+    let to_base = crate::paths::expand(to_base);
+    let manifest = crate::base::load_manifest(&to_base)
+        .context("Failed to load base manifest - is this a valid base?")?;
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => {
+            let dir_name = game_cfg
+                .directory
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| workspace.to_string());
+            format!("{dir_name}-{}", manifest.profile)
+        }
+    };
+
+    outln!("Migrating workspace: {workspace}");
+    outln!("To base:\n\t{} ({})", to_base.display(), manifest.profile);
+    outln!("\n=== Creating Workspace ===");
+    crate::cancel::install_handler();
+    let report = crate::migrate::migrate_workspace(&game_cfg.directory, &name, &to_base)
+        .map_err(|e| interrupted(e, "the new workspace may be incomplete"))?;
+
+    outln!("\n=== Migrated ===");
+    for rel_path in &report.migrated {
+        outln!("✓ {rel_path}");
+    }
+    if !report.skipped.is_empty() {
+        outln!("\n=== Not migrated ===");
+        for (rel_path, reason) in &report.skipped {
+            outln!("⚠ {rel_path}: {reason}");
+        }
+    }
+
+    let new = &report.workspace;
+    outln!(
+        "\n✓ Workspace created at: {}\n\t{} file(s) migrated, {} left behind",
+        new.workspace_path.display(),
+        report.migrated.len(),
+        report.skipped.len()
+    );
+    outln!("\nAdd it to your config.toml:");
+    outln!(
+        "realmctl config add {name} --directory \"{}\"",
+        new.workspace_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_upgrade(workspace: &str, dry_run: bool, force: bool, config_path: &str) -> Result<()> {
+    let game_cfg = load_config(config_path, workspace)?;
+    let dir = &game_cfg.directory;
+    // The client holds its MPQs open, replacing them under it breaks the session
+    if !dry_run && let Some(pid) = crate::instance::holder(dir) {
+        return Err(ExitError::new(
+            exit_code::ALREADY_RUNNING,
+            format!("{workspace} is running (PID {pid}), close the client first"),
+        )
+        .into());
+    }
+    if crate::workspace::load_snapshot(dir)?.is_none() {
+        outln!(
+            "⚠ {workspace} has no base snapshot (created by an older release): only new files are linked, changed ones need --force"
+        );
+    }
+
+    if !dry_run {
+        backup_before(
+            crate::backup::BackupTrigger::Upgrade,
+            workspace,
+            dir,
+            config_path,
+        )?;
+    }
+
+    crate::cancel::install_handler();
+    let report = crate::workspace::upgrade_workspace(dir, dry_run, force)
+        .map_err(|e| interrupted(e, "files linked so far are kept. Run it again to finish"))?;
+    let (added, updated) = match dry_run {
+        true => ("Would add", "Would update"),
+        false => ("Added", "Updated"),
+    };
+    for rel_path in &report.added {
+        outln!("✓ {added}: {rel_path}");
+    }
+    for rel_path in &report.updated {
+        outln!("✓ {updated}: {rel_path}");
+    }
+    for rel_path in &report.diverged {
+        outln!("⚠ Changed in the workspace, kept: {rel_path} (--force replaces it)");
+    }
+    for rel_path in &report.removed {
+        outln!("⚠ No longer in the base, kept: {rel_path}");
+    }
+    if report.added.is_empty() && report.updated.is_empty() {
+        outln!("✓ {workspace} is up to date with its base");
+    } else if !dry_run {
+        outln!(
+            "✓ {workspace} upgraded: {} added, {} updated",
+            report.added.len(),
+            report.updated.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_materialize(workspace: &str, paths: &[String], config_path: &str) -> Result<()> {
+    let game_cfg = load_config(config_path, workspace)?;
+    crate::cancel::install_handler();
+    let copied = crate::workspace::materialize_files(&game_cfg.directory, paths)
+        .map_err(|e| interrupted(e, "files copied so far are kept"))?;
+    if copied.is_empty() {
+        outln!("No lazily linked files to copy");
+    }
+    for rel_path in &copied {
+        outln!("✓ Copied {rel_path}");
+    }
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_materialize_standalone(workspace: &str, config_path: &str) -> Result<()> {
+    use crate::status::format_bytes;
+
+    let game_cfg = load_config(config_path, workspace)?;
+    let dir = &game_cfg.directory;
+    let plan = crate::workspace::plan_detach(dir)?;
+    if !plan.is_empty() {
+        outln!(
+            "Copying {} hard-linked files and {} symlinks ({}) into:\n\t{}",
+            plan.hard_links.len(),
+            plan.symlinks.len(),
+            format_bytes(plan.bytes),
+            dir.display()
+        );
+    }
+    // Hard links already take no space of their own, copying them does
+    if let Some(free) = crate::paths::free_space(dir)
+        && plan.bytes > free
+    {
+        return Err(ExitError::new(
+            exit_code::WRITE_FAILED,
+            format!(
+                "Not enough space: {} needed, {} free",
+                format_bytes(plan.bytes),
+                format_bytes(free)
+            ),
+        )
+        .into());
+    }
+
+    crate::cancel::install_handler();
+    let mut copied = 0;
+    let mut reported = 0;
+    crate::workspace::detach_workspace(dir, &plan, |bytes| {
+        copied += bytes;
+        let percent = (copied * 100).checked_div(plan.bytes).unwrap_or(100);
+        if percent >= reported + 10 {
+            reported = percent - percent % 10;
+            outln!(
+                "  {reported}% ({} of {})",
+                format_bytes(copied),
+                format_bytes(plan.bytes)
+            );
+        }
+    })
+    .map_err(|e| interrupted(e, "files copied so far are kept. Run it again to finish"))?;
+    outln!("✓ {workspace} is standalone, it no longer needs its base or the shared directories");
+    Ok(())
+}
+
+#[cfg(feature = "workspaces")]
+fn cmd_audit(workspace: &str, path: Option<&str>, config_path: &str) -> Result<()> {
+    use crate::journal::{JournalEntry, audit_workspace, load_journal};
+
+    let game_cfg = load_config(config_path, workspace)?;
+    let describe = |entry: &JournalEntry| {
+        let target = entry
+            .target
+            .as_ref()
+            .map(|target| format!(" -> {}", target.display()))
+            .unwrap_or_default();
+        format!(
+            "{} {} {}{target}: {} ({})",
+            entry.operation,
+            entry.action.as_str(),
+            entry.rel_path,
+            entry.reason,
+            crate::state::format_age(entry.at)
+        )
+    };
+
+    let journal = load_journal(&game_cfg.directory)?;
+    if journal.is_empty() {
+        outln!(
+            "No workspace.log in {}; it is written by `create` and `fix` since this release",
+            game_cfg.directory.display()
+        );
+        return Ok(());
+    }
+    if let Some(path) = path {
+        let path = path.trim_start_matches("./").replace('\\', "/");
+        let history: Vec<&JournalEntry> = journal
+            .iter()
+            .filter(|entry| {
+                path == entry.rel_path || path.starts_with(&format!("{}/", entry.rel_path))
+            })
+            .collect();
+        if history.is_empty() {
+            outln!("{path} was never linked, copied or created by realmctl");
+        }
+        for entry in history {
+            outln!("{}", describe(entry));
+        }
+        return Ok(());
+    }
+
+    let findings = audit_workspace(&game_cfg.directory)?;
+    outln!(
+        "=== Audit of {workspace} ({} journal entries) ===",
+        journal.len()
+    );
+    if findings.is_empty() {
+        outln!("✓ Every recorded link, copy and directory is as it was made");
+        return Ok(());
+    }
+    for finding in &findings {
+        outln!("✗ {}: {}", finding.rel_path, finding.problem);
+        outln!("\t{}", describe(&finding.entry));
+    }
+    Err(ExitError::new(
+        exit_code::MISMATCH,
+        format!(
+            "{} path(s) of {workspace} changed since realmctl made them; `realmctl fix {workspace}` repairs shared links",
+            findings.len()
+        ),
+    )
+    .into())
+}
 
-    #[cfg(feature = "workspaces")]
-    /// Create a new workspace from a base installation
-    Create {
-        /// Name of the workspace
-        workspace: String,
-        /// Path to the base installation (must have manifest.toml)
-        #[arg(long)]
-        base: String,
-        /// Sharing rules (format: key=value, e.g., screenshots=global)
-        #[arg(long = "share", value_name = "KEY=VALUE")]
-        share: Vec<String>,
-        /// Workspace root directory
-        #[arg(long, default_value = "~/.local/share/wow_workspaces")]
-        workspace_root: String,
-    },
-    #[cfg(feature = "workspaces")]
-    /// Clean ephemeral files (cache, logs) from a workspace
-    Clean {
-        /// Workspace name to clean (as in your config file)
-        workspace: String,
-        /// Path to your config.toml
-        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
-        config: String,
-        /// Also clean WDB cache files
-        #[arg(long)]
-        wdb: bool,
-    },
-    #[cfg(feature = "workspaces")]
-    /// Repair a workspace's shared links and directories
-    Fix {
-        /// Workspace name to fix (as in your config file)
-        workspace: String,
-        /// Path to your config.toml
-        #[arg(long, default_value = "~/.config/realmctl/config.toml")]
-        config: String,
-    },
+/// Directories of every configured game plus every workspace under the workspace root
+#[cfg(feature = "workspaces")]
+fn known_game_dirs(workspace_root: &str, config_path: &str) -> Result<Vec<PathBuf>> {
+    let ws_root = crate::paths::expand(workspace_root);
+    let mut dirs: Vec<PathBuf> = match ConfigFile::read(config_path) {
+        Ok(file) => file
+            .games(&current_hostname())
+            .into_iter()
+            .filter_map(|(_, config)| Some(config.ok()?.directory))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("⚠ {e}");
+            Vec::new()
+        }
+    };
+    for workspace in crate::workspace::list_workspaces(&ws_root)? {
+        dirs.push(workspace.workspace_path);
+    }
+    dirs.sort();
+    dirs.dedup();
+    Ok(dirs)
 }
 
-impl Cli {
-    pub fn run(self) -> Result<()> {
-        match self.command {
-            Commands::Launch { workspace, config } => {
-                cmd_launch(&workspace, &config)?;
+#[cfg(feature = "workspaces")]
+fn cmd_maintain(dry_run: bool, workspace_root: &str, config_path: &str) -> Result<()> {
+    use crate::retention::{
+        CATEGORIES, Entries, RetainedItem, archives_dir, dir_items, expired, load_retention,
+        trim_lines,
+    };
+    use crate::status::format_bytes;
+
+    let retention = load_retention(config_path);
+    let now = std::time::SystemTime::now();
+    let game_dirs = known_game_dirs(workspace_root, config_path)?;
+    let (mut removed, mut freed, mut failed) = (0, 0, 0);
+    // Expired files and directories, removed once every category is planned
+    let mut doomed = Vec::new();
+    for category in CATEGORIES {
+        let policy = retention.policy(category);
+        match category.entries {
+            Entries::Dirs(dirs) => {
+                for game_dir in &game_dirs {
+                    for dir in dirs {
+                        doomed.extend(expired(dir_items(&game_dir.join(dir)), &policy, now));
+                    }
+                }
             }
-            Commands::InitBase { path, profile } => {
-                cmd_init_base(&path, &profile)?;
+            Entries::Archives => doomed.extend(expired(dir_items(&archives_dir()), &policy, now)),
+            Entries::Lines(file) => {
+                for game_dir in &game_dirs {
+                    let path = game_dir.join(file);
+                    match trim_lines(&path, &policy, now, dry_run) {
+                        Ok(trimmed) if trimmed.lines == 0 => {}
+                        Ok(trimmed) => {
+                            let verb = if dry_run { "Would trim" } else { "✓ Trimmed" };
+                            outln!(
+                                "{verb} {} old entries of {} ({})",
+                                trimmed.lines,
+                                path.display(),
+                                format_bytes(trimmed.bytes)
+                            );
+                            removed += trimmed.lines;
+                            freed += trimmed.bytes;
+                        }
+                        Err(e) => {
+                            tracing::error!("✗ Failed to trim {}: {e}", path.display());
+                            failed += 1;
+                        }
+                    }
+                }
             }
-            #[cfg(feature = "workspaces")]
-            Commands::Create {
-                workspace,
-                base,
-                share,
-                workspace_root,
-            } => {
-                cmd_create_workspace(&workspace, &base, &share, &workspace_root)?;
+            Entries::Backups => {
+                let config = crate::backup::load_backup(config_path);
+                let mut names: Vec<String> = crate::backup::list_backups(&config, None)
+                    .into_iter()
+                    .map(|backup| backup.workspace)
+                    .collect();
+                names.dedup();
+                for name in names {
+                    // Planned only, removed with the other expired files
+                    let backups = crate::backup::prune_backups(&config, &name, &policy, true)?;
+                    doomed.extend(backups.into_iter().map(|backup| RetainedItem {
+                        path: backup.path,
+                        size: backup.size_bytes,
+                        modified: std::time::SystemTime::UNIX_EPOCH,
+                    }));
+                }
             }
-            #[cfg(feature = "workspaces")]
-            Commands::Clean {
-                workspace,
-                config,
-                wdb,
-            } => {
-                cmd_clean(&workspace, &config, wdb)?;
+        }
+    }
+
+    for item in doomed {
+        if dry_run {
+            outln!(
+                "Would remove {} ({})",
+                item.path.display(),
+                format_bytes(item.size)
+            );
+            removed += 1;
+            freed += item.size;
+            continue;
+        }
+        let result = if item.path.symlink_metadata().is_ok_and(|m| m.is_dir()) {
+            std::fs::remove_dir_all(&item.path)
+        } else {
+            std::fs::remove_file(&item.path)
+        };
+        match result {
+            Ok(()) => {
+                outln!(
+                    "✓ Removed {} ({})",
+                    item.path.display(),
+                    format_bytes(item.size)
+                );
+                removed += 1;
+                freed += item.size;
             }
-            #[cfg(feature = "workspaces")]
-            Commands::Fix { workspace, config } => {
-                cmd_fix(&workspace, &config)?;
+            Err(e) => {
+                tracing::error!("✗ Failed to remove {}: {e}", item.path.display());
+                failed += 1;
             }
         }
-        Ok(())
     }
+
+    let verb = if dry_run { "Would free" } else { "Freed" };
+    outln!("\n{verb} {} in {removed} item(s)", format_bytes(freed));
+    if failed > 0 {
+        anyhow::bail!("{failed} item(s) could not be removed");
+    }
+    Ok(())
 }
 
-fn cmd_launch(workspace: &str, config_path: &str) -> Result<()> {
-    println!("Loading configuration for:\n\t{workspace}");
-    let game_cfg = load_config(config_path, workspace)?;
+#[cfg(feature = "workspaces")]
+fn cmd_media_dedup(
+    mode: crate::media::DedupMode,
+    dry_run: bool,
+    workspace_root: &str,
+    config_path: &str,
+) -> Result<()> {
+    use crate::media::{find_duplicates, media_roots, replace_duplicate};
+    use crate::status::format_bytes;
 
-    if let (Some(realmlist), Some(realmlist_rel_path)) =
-        (&game_cfg.realmlist, &game_cfg.realmlist_rel_path)
-    {
-        write_realmlist(&game_cfg.directory, realmlist_rel_path, realmlist)?;
+    let ws_root = crate::paths::expand(workspace_root);
+    let roots = media_roots(&ws_root, &known_game_dirs(workspace_root, config_path)?);
+    outln!("Scanning {} media directories", roots.len());
+    crate::cancel::install_handler();
+    let groups = find_duplicates(&roots).map_err(|e| interrupted(e, "nothing was replaced"))?;
+
+    let (mut replaced, mut reclaimed, mut failed) = (0, 0, 0);
+    for group in &groups {
+        for duplicate in &group.duplicates {
+            if dry_run {
+                outln!(
+                    "Would replace {} ({}) with {}",
+                    duplicate.display(),
+                    format_bytes(group.size),
+                    group.keep.display()
+                );
+                replaced += 1;
+                reclaimed += group.size;
+                continue;
+            }
+            match replace_duplicate(&group.keep, duplicate, mode) {
+                Ok(()) => {
+                    outln!("✓ {} ({})", duplicate.display(), format_bytes(group.size));
+                    replaced += 1;
+                    reclaimed += group.size;
+                }
+                Err(e) => {
+                    tracing::error!("✗ Failed to replace {}: {e}", duplicate.display());
+                    failed += 1;
+                }
+            }
+        }
     }
 
-    launch(&game_cfg)?;
+    let verb = if dry_run {
+        "Would reclaim"
+    } else {
+        "Reclaimed"
+    };
+    outln!(
+        "\n{verb} {} in {replaced} duplicate(s)",
+        format_bytes(reclaimed)
+    );
+    if failed > 0 {
+        anyhow::bail!("{failed} duplicate(s) could not be replaced (different filesystems?)");
+    }
     Ok(())
 }
 
-fn cmd_init_base(path: &Path, profile_name: &str) -> Result<()> {
-    use crate::base::{Profile, scan_and_build_manifest, write_manifest};
+#[cfg(feature = "workspaces")]
+fn cmd_status(game: Option<&str>, workspace_root: &str, config_path: &str) -> Result<()> {
+    use crate::state::format_age;
+    use crate::status::{collect_status, format_bytes};
 
-    println!("Initializing base at: {}", path.display());
-    println!("Using profile: {}", profile_name);
+    let mut status = match game {
+        Some(game) => {
+            let dir = load_config(config_path, game)?.directory;
+            let mut status = collect_status(None, &[(game.to_string(), dir)])?;
+            for ws in &mut status.workspaces {
+                match crate::status::diagnose(&ws.path) {
+                    Ok(diagnosis) => ws.diagnosis = Some(diagnosis),
+                    Err(e) => tracing::warn!("⚠ Could not diagnose {}: {e}", ws.name),
+                }
+            }
+            status
+        }
+        None => {
+            let ws_root = crate::paths::expand(workspace_root);
+            // Without a config every workspace under the root is still listed
+            let games: Vec<(String, PathBuf)> = match ConfigFile::read(config_path) {
+                Ok(file) => file
+                    .games(&current_hostname())
+                    .into_iter()
+                    .filter_map(|(key, config)| Some((key, config.ok()?.directory)))
+                    .collect(),
+                Err(e) => {
+                    tracing::warn!("⚠ {e}");
+                    Vec::new()
+                }
+            };
+            collect_status(Some(&ws_root), &games)?
+        }
+    };
 
-    // Expand tilde in path
-    let expanded_path = shellexpand::tilde(&path.to_string_lossy()).to_string();
-    let base_dir = PathBuf::from(expanded_path);
+    if crate::output::is_json() {
+        return crate::output::emit(&status);
+    }
 
-    if !base_dir.exists() {
-        anyhow::bail!("Directory does not exist: {}", base_dir.display());
+    outln!("=== Bases ===");
+    if status.bases.is_empty() {
+        outln!("(none)");
+    }
+    for base in &status.bases {
+        outln!(
+            "{}\n\t{} {}  {}  {}  {} workspace(s)",
+            base.path.display(),
+            base.profile.as_deref().unwrap_or("✗ manifest missing"),
+            base.version.as_deref().unwrap_or(""),
+            base.locale.as_deref().unwrap_or("-"),
+            format_bytes(base.size_bytes),
+            base.workspaces,
+        );
     }
 
-    // Load profile
-    let profile = match profile_name {
-        "chromie-3.3.5a" | "3.3.5a" | "335" | "335a" => Profile::chromie_335a(),
-        "vanilla-1.12" | "1.12" | "112" => Profile::vanilla_112(),
-        _ => anyhow::bail!("Unknown profile: {}", profile_name),
-    };
+    outln!("\n=== Workspaces ===");
+    if status.workspaces.is_empty() {
+        outln!("(none)");
+        return Ok(());
+    }
+    let width = status
+        .workspaces
+        .iter()
+        .map(|ws| ws.name.len())
+        .max()
+        .unwrap_or(9)
+        .max(9);
+    outln!(
+        "{:<width$}  {:<16}  HEALTH  {:>10}  {:>10}  RUNNING  LAST LAUNCHED",
+        "WORKSPACE",
+        "GAMES",
+        "OWN",
+        "LINKED"
+    );
+    for ws in &status.workspaces {
+        let games = if ws.games.is_empty() {
+            "-".to_string()
+        } else {
+            ws.games.join(",")
+        };
+        let health = if ws.healthy {
+            "✓".to_string()
+        } else {
+            format!("⚠ {}", ws.warnings.len())
+        };
+        outln!(
+            "{:<width$}  {:<16}  {:<6}  {:>10}  {:>10}  {:<7}  {}",
+            ws.name,
+            games,
+            health,
+            format_bytes(ws.own_bytes),
+            format_bytes(ws.linked_bytes),
+            if ws.running { "yes" } else { "no" },
+            ws.last_launched.map_or("never".to_string(), format_age),
+        );
+    }
+    for ws in status.workspaces.iter().filter(|ws| !ws.healthy) {
+        outln!("\n⚠ {}:", ws.name);
+        for warning in &ws.warnings {
+            outln!("\t{warning}");
+        }
+    }
+    for ws in &mut status.workspaces {
+        if let Some(diagnosis) = ws.diagnosis.take() {
+            print_diagnosis(&diagnosis);
+        }
+    }
+    Ok(())
+}
 
-    println!("\n=== Verifying Requirements ===");
-    profile.verify_requirements(&base_dir)?;
-    println!("✓ All required files and directories present");
+#[cfg(feature = "workspaces")]
+fn print_diagnosis(diagnosis: &crate::status::Diagnosis) {
+    use crate::status::format_bytes;
 
-    // Check warnings
-    let warnings = profile.check_warnings(&base_dir);
-    if !warnings.is_empty() {
-        println!("\n⚠ Warnings:");
-        for warning in warnings {
-            println!("  - {}", warning);
+    outln!("\n=== Diagnosis ===");
+    let findings: [(&str, Vec<String>); 5] = [
+        ("Broken symlinks", diagnosis.broken_links.clone()),
+        ("Base files missing", diagnosis.missing.clone()),
+        (
+            "Base files copied instead of linked",
+            diagnosis.unlinked.clone(),
+        ),
+        (
+            "Base files changed in the workspace",
+            diagnosis.drifted.clone(),
+        ),
+        (
+            "Shared directories no workspace links to",
+            diagnosis
+                .orphaned_shared
+                .iter()
+                .map(|dir| dir.display().to_string())
+                .collect(),
+        ),
+    ];
+    for (label, paths) in findings {
+        if paths.is_empty() {
+            outln!("✓ {label}: none");
+            continue;
+        }
+        outln!("✗ {label}: {}", paths.len());
+        for path in paths {
+            outln!("\t{path}");
         }
     }
+    outln!(
+        "Cache, logs and WDB:\n\t{}",
+        format_bytes(diagnosis.cache_bytes)
+    );
+    if let Some(change) = &diagnosis.base_change {
+        outln!("⚠ {change}");
+    }
+    if let Some(warning) = &diagnosis.path_limit {
+        outln!("⚠ {warning}");
+    }
 
-    println!("\n=== Scanning Directory ===");
-    let manifest = scan_and_build_manifest(&base_dir, &profile)?;
+    let mut hints = Vec::new();
+    if !diagnosis.broken_links.is_empty() {
+        hints.push("`realmctl fix <game>` recreates shared links");
+    }
+    if !(diagnosis.missing.is_empty() && diagnosis.drifted.is_empty()) {
+        hints.push(
+            "Missing or changed base files come back by recreating the workspace from its base",
+        );
+    }
+    if !diagnosis.orphaned_shared.is_empty() {
+        hints.push("Orphaned shared directories are left by deleted workspaces, `realmctl gc` archives or removes them");
+    }
+    if !hints.is_empty() {
+        outln!("\n{}", hints.join("\n"));
+    }
+}
 
-    println!("Found {} files/directories", manifest.file_roles.len());
-    println!(
-        "Computed {} checksums for immutable files",
-        manifest.checksums.len()
-    );
+#[cfg(feature = "workspaces")]
+fn cmd_summary(base_roots: &[String], workspace_root: &str) -> Result<()> {
+    use crate::state::format_age;
+    use crate::status::{
+        IDLE_AFTER_DAYS, VERIFY_AFTER_DAYS, collect_listing, disk_usage, format_bytes, summarize,
+    };
 
-    println!("\n=== Writing Manifest ===");
-    write_manifest(&manifest, &base_dir)?;
-    println!("✓ Manifest written to {}/manifest.toml", base_dir.display());
+    let ws_root = crate::paths::expand(workspace_root);
+    let base_roots: Vec<PathBuf> = base_roots
+        .iter()
+        .map(|root| crate::paths::expand(root))
+        .collect();
+    let listing = collect_listing(&ws_root, &base_roots)?;
+    let (shared_own, shared_linked) = disk_usage(&ws_root.join(".shared"));
+    let summary = summarize(&listing, shared_own + shared_linked);
 
-    println!("\n✓ Base initialization complete!");
+    outln!("=== Summary ===");
+    outln!("Bases:\n\t{}", summary.bases);
+    outln!("Workspaces:\n\t{}", summary.workspaces);
+    outln!("Disk used:\n\t{}", format_bytes(summary.managed_bytes));
+    let saved = summary.naive_bytes - summary.managed_bytes;
+    if saved > 0 {
+        outln!(
+            "Saved by linking:\n\t{} ({}% less than full copies)",
+            format_bytes(saved),
+            saved * 100 / summary.naive_bytes
+        );
+    }
 
+    outln!("\n=== Suggestions ===");
+    let mut suggestions = 0;
+    if let Some(path) = &summary.oldest_unverified {
+        let verified = summary
+            .oldest_verified_at
+            .map_or("never verified".to_string(), |at| {
+                format!("last verified {}", format_age(at))
+            });
+        outln!(
+            "⚠ {} was {verified}, check it with `realmctl verify-base {}`",
+            path.display(),
+            path.display()
+        );
+        suggestions += 1;
+    }
+    for (name, own_bytes) in &summary.idle_workspaces {
+        outln!(
+            "⚠ {name} was not launched for over {IDLE_AFTER_DAYS} days, deleting it frees about {}",
+            format_bytes(*own_bytes)
+        );
+        suggestions += 1;
+    }
+    if suggestions == 0 {
+        outln!(
+            "✓ Nothing to do (bases verified within {VERIFY_AFTER_DAYS} days, no idle workspaces)"
+        );
+    }
     Ok(())
 }
 
 #[cfg(feature = "workspaces")]
-fn cmd_create_workspace(
-    name: &str,
-    base: &str,
-    share_args: &[String],
-    workspace_root: &str,
-) -> Result<()> {
-    use crate::workspace::{SharingStrategy, create_workspace, default_sharing_rules};
+fn cmd_du(base_roots: &[String], workspace_root: &str) -> Result<()> {
+    use crate::status::{collect_usage, format_bytes};
 
-    println!("Creating workspace: {name}");
-    println!("Base: {base}");
+    let ws_root = crate::paths::expand(workspace_root);
+    let base_roots: Vec<PathBuf> = base_roots
+        .iter()
+        .map(|root| crate::paths::expand(root))
+        .collect();
+    let report = collect_usage(&ws_root, &base_roots)?;
 
-    // Expand paths
-    let expanded_base = shellexpand::tilde(base).to_string();
-    let base_path = PathBuf::from(expanded_base);
-
-    let expanded_root = shellexpand::tilde(workspace_root).to_string();
-    let ws_root = PathBuf::from(expanded_root);
+    if crate::output::is_json() {
+        return crate::output::emit(&report);
+    }
 
-    // Parse sharing rules
-    let mut sharing_rules = default_sharing_rules();
-    for arg in share_args {
-        let parts: Vec<&str> = arg.split('=').collect();
-        if parts.len() == 2 {
-            let key = parts[0].to_string();
-            let value = match parts[1] {
-                "global" => SharingStrategy::Global,
-                "base" => SharingStrategy::Base,
-                "workspace" => SharingStrategy::Workspace,
-                _ => anyhow::bail!("Invalid sharing strategy: {}", parts[1]),
-            };
-            sharing_rules.insert(key, value);
+    let width = report
+        .trees
+        .iter()
+        .map(|tree| tree.name.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+    for (kind, title) in [
+        ("base", "Bases"),
+        ("workspace", "Workspaces"),
+        ("shared", "Shared directories"),
+    ] {
+        outln!("=== {title} ===");
+        let trees: Vec<_> = report
+            .trees
+            .iter()
+            .filter(|tree| tree.kind == kind)
+            .collect();
+        if trees.is_empty() {
+            outln!("(none)\n");
+            continue;
         }
+        outln!(
+            "{:<width$}  {:>10}  {:>10}  {:>10}  PATH",
+            "NAME",
+            "APPARENT",
+            "UNIQUE",
+            "SAVED"
+        );
+        for tree in trees {
+            outln!(
+                "{:<width$}  {:>10}  {:>10}  {:>10}  {}",
+                tree.name,
+                format_bytes(tree.apparent_bytes),
+                format_bytes(tree.unique_bytes),
+                format_bytes(tree.saved_bytes),
+                tree.path.display(),
+            );
+        }
+        outln!();
     }
 
-    println!("\nSharing rules:");
-    for (key, value) in &sharing_rules {
-        println!("  {} = {:?}", key, value);
+    outln!("=== Total ===");
+    outln!("Apparent size:\n\t{}", format_bytes(report.apparent_bytes));
+    outln!("On disk:\n\t{}", format_bytes(report.on_disk_bytes));
+    let saved = report.apparent_bytes - report.on_disk_bytes;
+    if saved > 0 {
+        outln!(
+            "Saved by hard links:\n\t{} ({}% less than separate copies)",
+            format_bytes(saved),
+            saved * 100 / report.apparent_bytes
+        );
     }
+    Ok(())
+}
 
-    println!("\n=== Creating Workspace ===");
-    let config = create_workspace(name, &base_path, &ws_root, sharing_rules)?;
+#[cfg(feature = "workspaces")]
+fn cmd_list(base_roots: &[String], workspace_root: &str) -> Result<()> {
+    use crate::state::format_age;
+    use crate::status::{collect_listing, format_bytes};
 
-    println!(
-        "✓ Workspace created at: {}",
-        config.workspace_path.display()
-    );
-    println!("\nYou can now launch this workspace by updating your config.toml:");
-    println!("[{}]", name);
-    println!("directory = \"{}\"", config.workspace_path.display());
-    println!("# ... other settings ...");
+    let ws_root = crate::paths::expand(workspace_root);
+    let base_roots: Vec<PathBuf> = base_roots
+        .iter()
+        .map(|root| crate::paths::expand(root))
+        .collect();
+    let listing = collect_listing(&ws_root, &base_roots)?;
 
+    if crate::output::is_json() {
+        return crate::output::emit(&listing);
+    }
+
+    let last_launched = |time: Option<u64>| time.map_or("never".to_string(), format_age);
+    outln!("=== Bases ===");
+    if listing.bases.is_empty() {
+        outln!("(none)");
+    } else {
+        let width = listing
+            .bases
+            .iter()
+            .map(|b| b.name.len())
+            .max()
+            .unwrap_or(4)
+            .max(4);
+        outln!(
+            "{:<width$}  {:<16}  {:>10}  WORKSPACES  LAST LAUNCHED  PATH",
+            "BASE",
+            "PROFILE",
+            "SIZE"
+        );
+        for base in &listing.bases {
+            outln!(
+                "{:<width$}  {:<16}  {:>10}  {:>10}  {:<13}  {}",
+                base.name,
+                base.profile.as_deref().unwrap_or("✗ no manifest"),
+                format_bytes(base.size_bytes),
+                base.workspaces,
+                last_launched(base.last_launched),
+                base.path.display(),
+            );
+        }
+    }
+
+    outln!("\n=== Workspaces ===");
+    if listing.workspaces.is_empty() {
+        outln!("(none)");
+        return Ok(());
+    }
+    let width = listing
+        .workspaces
+        .iter()
+        .map(|ws| ws.name.len())
+        .max()
+        .unwrap_or(9)
+        .max(9);
+    outln!(
+        "{:<width$}  {:<16}  {:<16}  {:>10}  LAST LAUNCHED",
+        "WORKSPACE",
+        "BASE",
+        "PROFILE",
+        "OWN"
+    );
+    for ws in &listing.workspaces {
+        outln!(
+            "{:<width$}  {:<16}  {:<16}  {:>10}  {}",
+            ws.name,
+            ws.base,
+            ws.profile.as_deref().unwrap_or("✗ no manifest"),
+            format_bytes(ws.own_bytes),
+            last_launched(ws.last_launched),
+        );
+    }
     Ok(())
 }
 
 #[cfg(feature = "workspaces")]
-fn cmd_fix(workspace: &str, config_path: &str) -> Result<()> {
-    println!("Fixing workspace: {}", workspace);
+fn cmd_daemon(
+    metrics: Option<std::net::SocketAddr>,
+    watchdog: bool,
+    workspace_root: &str,
+    config_path: &str,
+) -> Result<()> {
+    let ws_root = crate::paths::expand(workspace_root);
 
-    let game_cfg = load_config(config_path, workspace)?;
+    let watchdog = match watchdog {
+        true => Some(
+            crate::watchdog::load_watchdog(config_path).with_context(|| {
+                format!("--watchdog needs a [watchdog] section with idle_hours in {config_path}")
+            })?,
+        ),
+        false => None,
+    };
+    if metrics.is_none() && watchdog.is_none() {
+        anyhow::bail!("No daemon services enabled (use --metrics <ADDR> or --watchdog)");
+    }
 
-    // Perform fix/repair operations on the workspace
-    crate::workspace::fix_workspace(&game_cfg.directory)?;
+    let dirs = {
+        let workspace_root = workspace_root.to_string();
+        let config_path = config_path.to_string();
+        move || known_game_dirs(&workspace_root, &config_path).unwrap_or_default()
+    };
+    if let Some(watchdog) = watchdog {
+        let dirs = dirs.clone();
+        let action = match watchdog.action {
+            crate::watchdog::IdleAction::Warn => "warning about",
+            crate::watchdog::IdleAction::Close => "closing",
+        };
+        outln!(
+            "Watchdog: {action} clients idle for more than {}h",
+            watchdog.idle_hours
+        );
+        if metrics.is_none() {
+            crate::watchdog::watch(dirs, &watchdog);
+            return Ok(());
+        }
+        std::thread::spawn(move || crate::watchdog::watch(dirs, &watchdog));
+    }
+    match metrics {
+        Some(addr) => crate::metrics::serve_metrics(addr, &ws_root, dirs),
+        None => Ok(()),
+    }
+}
 
-    println!("\n✓ Fix operations completed (no user data was overridden)");
-    Ok(())
+/// Profile of a game directory: its base's profile for workspaces, else guessed from its files
+fn game_profile(game_dir: &Path) -> crate::base::Profile {
+    use crate::base::Profile;
+
+    #[cfg(feature = "workspaces")]
+    if let Some(profile) = crate::workspace::load_workspace_config(game_dir)
+        .and_then(|workspace| crate::base::load_manifest(&workspace.base_path))
+        .ok()
+        .and_then(|manifest| Profile::builtin(&manifest.profile))
+    {
+        return profile;
+    }
+    Profile::detect(game_dir)
 }
 
 #[cfg(feature = "workspaces")]
-fn cmd_clean(workspace: &str, config_path: &str, clean_wdb: bool) -> Result<()> {
-    println!("Cleaning workspace: {}", workspace);
+fn cmd_clean(workspace: &str, config_path: &str, clean_wdb: bool, dry_run: bool) -> Result<()> {
+    outln!("Cleaning workspace: {}", workspace);
 
     let game_cfg = load_config(config_path, workspace)?;
     let workspace_dir = &game_cfg.directory;
+    if !dry_run {
+        backup_before(
+            crate::backup::BackupTrigger::Clean,
+            workspace,
+            workspace_dir,
+            config_path,
+        )?;
+    }
 
+    let profile = game_profile(workspace_dir);
     let mut cleaned_items = Vec::new();
+    let mut failed = Vec::new();
 
-    // Clean Cache directory
-    let cache_dir = workspace_dir.join("Cache");
-    if cache_dir.exists() {
-        match std::fs::remove_dir_all(&cache_dir) {
-            Ok(_) => {
-                cleaned_items.push("Cache");
-                println!("✓ Removed Cache directory");
-            }
-            Err(e) => {
-                eprintln!("✗ Failed to remove Cache: {}", e);
+    for path in profile.ephemeral_paths(workspace_dir, clean_wdb) {
+        let rel_path = path.strip_prefix(workspace_dir).unwrap_or(&path).display();
+        if dry_run {
+            // Skip paths that don't exist or go together with a listed parent directory
+            if path.symlink_metadata().is_ok()
+                && !cleaned_items.iter().any(|parent| path.starts_with(parent))
+            {
+                outln!("Would remove {rel_path}");
+                cleaned_items.push(path);
             }
+            continue;
         }
-    }
-
-    // Clean Logs directory
-    let logs_dir = workspace_dir.join("Logs");
-    if logs_dir.exists() {
-        match std::fs::remove_dir_all(&logs_dir) {
+        // Already removed together with a matching parent directory
+        let Some(result) = remove_path(&path) else {
+            continue;
+        };
+        match result {
             Ok(_) => {
-                cleaned_items.push("Logs");
-                println!("✓ Removed Logs directory");
+                outln!("✓ Removed {rel_path}");
+                cleaned_items.push(path);
             }
             Err(e) => {
-                eprintln!("✗ Failed to remove Logs: {}", e);
+                tracing::error!("✗ Failed to remove {}: {}", path.display(), e);
+                failed.push(serde_json::json!({ "path": path, "error": e.to_string() }));
             }
         }
     }
-
-    // Clean Errors directory
-    let errors_dir = workspace_dir.join("Errors");
-    if errors_dir.exists() {
-        match std::fs::remove_dir_all(&errors_dir) {
-            Ok(_) => {
-                cleaned_items.push("Errors");
-                println!("✓ Removed Errors directory");
-            }
-            Err(e) => {
-                eprintln!("✗ Failed to remove Errors: {}", e);
-            }
-        }
-    }
-
-    // Clean WDB cache files if requested
-    if clean_wdb {
-        let data_dir = workspace_dir.join("Data");
-        if data_dir.exists()
-            && let Ok(entries) = std::fs::read_dir(&data_dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if let Some(ext) = path.extension()
-                        && ext == "wdb" {
-                            match std::fs::remove_file(&path) {
-                                Ok(_) => {
-                                    println!(
-                                        "✓ Removed WDB cache: {}",
-                                        path.file_name().unwrap().to_string_lossy()
-                                    );
-                                    cleaned_items.push("WDB files");
-                                }
-                                Err(e) => {
-                                    eprintln!("✗ Failed to remove {}: {}", path.display(), e);
-                                }
-                            }
-                        }
-                }
-            }
-
-        // Also check locale directories for WDB files
-        if let Ok(entries) = std::fs::read_dir(&data_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir()
-                    && let Some(dir_name) = path.file_name() {
-                        let dir_name = dir_name.to_string_lossy();
-                        // Check if it's a locale directory (enUS, enGB, etc.)
-                        if dir_name.len() == 4 && dir_name.chars().all(|c| c.is_alphabetic())
-                            && let Ok(locale_entries) = std::fs::read_dir(&path) {
-                                for locale_entry in locale_entries.flatten() {
-                                    let locale_path = locale_entry.path();
-                                    if let Some(ext) = locale_path.extension()
-                                        && ext == "wdb" {
-                                            match std::fs::remove_file(&locale_path) {
-                                                Ok(_) => {
-                                                    println!(
-                                                        "✓ Removed WDB cache: {}/{}",
-                                                        dir_name,
-                                                        locale_path
-                                                            .file_name()
-                                                            .unwrap()
-                                                            .to_string_lossy()
-                                                    );
-                                                }
-                                                Err(e) => {
-                                                    eprintln!(
-                                                        "✗ Failed to remove {}: {}",
-                                                        locale_path.display(),
-                                                        e
-                                                    );
-                                                }
-                                            }
-                                        }
-                                }
-                            }
-                    }
-            }
-        }
+    if crate::output::is_json() {
+        crate::output::emit(&serde_json::json!({
+            "game": workspace,
+            "directory": workspace_dir,
+            "dry_run": dry_run,
+            "removed": cleaned_items,
+            "failed": failed,
+        }))?;
     }
 
+    if dry_run {
+        outln!("\nDry run, nothing was removed");
+        return Ok(());
+    }
     if cleaned_items.is_empty() {
-        println!("\nNo files to clean (workspace is already clean)");
+        outln!("\nNo files to clean (workspace is already clean)");
     } else {
-        println!("\n✓ Workspace cleaned successfully!");
+        outln!("\n✓ Workspace cleaned successfully!");
     }
 
+    run_hooks(
+        &load_hooks(config_path),
+        HookEvent::Clean,
+        serde_json::json!({
+            "game": workspace,
+            "directory": workspace_dir,
+            "removed": cleaned_items,
+        }),
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn exit_code_of(e: &anyhow::Error) -> Option<u8> {
+        e.downcast_ref::<ExitError>().map(|e| e.code)
+    }
+
+    #[test]
+    fn test_realm_set_all() -> Result<()> {
+        let temp = TempDir::new()?;
+        let dir = |name: &str| temp.path().join(name).to_string_lossy().replace('\\', "/");
+        for name in ["a", "b", "c", "untagged"] {
+            std::fs::create_dir_all(temp.path().join(name))?;
+            std::fs::write(
+                temp.path().join(name).join("realmlist.wtf"),
+                "set realmlist old.example.org",
+            )?;
+        }
+        let config_path = temp.path().join("config.toml");
+        let config = format!(
+            r#"
+[A]
+directory = "{a}"
+realmlist_path = "realmlist.wtf"
+tags = ["fleet"]
+
+[B]
+directory = "{b}"
+realmlist_path = "realmlist.wtf"
+tags = ["Fleet"]
+
+[Untagged]
+directory = "{untagged}"
+realmlist_path = "realmlist.wtf"
+"#,
+            a = dir("a"),
+            b = dir("b"),
+            untagged = dir("untagged"),
+        );
+        std::fs::write(&config_path, &config)?;
+        let config_path = config_path.to_string_lossy().to_string();
+        let realmlist = |name: &str| read_realmlist(&temp.path().join(name).join("realmlist.wtf"));
+
+        cmd_realm_set_all(
+            "new.example.org",
+            (None, Some("fleet")),
+            &config_path,
+            true,
+            false,
+        )?;
+        assert_eq!(realmlist("a")?.as_deref(), Some("new.example.org"));
+        assert_eq!(realmlist("b")?.as_deref(), Some("new.example.org"));
+        assert_eq!(realmlist("untagged")?.as_deref(), Some("old.example.org"));
+
+        let e = cmd_realm_set_all(
+            "new.example.org",
+            (None, Some("none")),
+            &config_path,
+            true,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(exit_code_of(&e), Some(exit_code::CONFIG));
+
+        // A realmlist that can't be written fails the command, the others are still written
+        std::fs::write(temp.path().join("c/Data"), "a file, not a directory")?;
+        std::fs::write(
+            temp.path().join("config.toml"),
+            format!(
+                "{config}\n[C]\ndirectory = \"{c}\"\nrealmlist_path = \"Data/realmlist.wtf\"\ntags = [\"fleet\"]\n",
+                c = dir("c"),
+            ),
+        )?;
+        let e = cmd_realm_set_all(
+            "other.example.org",
+            (None, Some("fleet")),
+            &config_path,
+            true,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(exit_code_of(&e), Some(exit_code::WRITE_FAILED));
+        assert!(
+            e.to_string()
+                .contains("1 realmlist(s) could not be updated")
+        );
+        assert_eq!(realmlist("a")?.as_deref(), Some("other.example.org"));
+        assert_eq!(realmlist("b")?.as_deref(), Some("other.example.org"));
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_launch_with_realm_restores_realmlist() -> Result<()> {
+        let temp = TempDir::new()?;
+        let game = temp.path().join("game");
+        std::fs::create_dir_all(&game)?;
+        std::fs::write(game.join("Wow.exe"), "")?;
+        let path = game.join("realmlist.wtf");
+        let original = b"set realmlist old.example.org\r\nset patchlist old.example.org\r\n";
+        std::fs::write(&path, original)?;
+        let seen = temp.path().join("seen.wtf");
+        let config = |launch_cmd: &str| -> Config {
+            toml::from_str(&format!(
+                "directory = {:?}\nrealmlist_path = \"realmlist.wtf\"\nlaunch_cmd = {launch_cmd:?}\n",
+                game.to_string_lossy()
+            ))
+            .unwrap()
+        };
+        let copy_cmd = format!("cp '{}' '{}'", path.display(), seen.display());
+
+        // The client sees the session realmlist, the original comes back byte for byte
+        let mut started = None;
+        let status = launch_with_realm(&config(&copy_cmd), "new.example.org", None, |pid| {
+            started = Some(pid)
+        })?;
+        assert!(status.success());
+        assert!(started.is_some());
+        assert_eq!(
+            std::fs::read_to_string(&seen)?,
+            "set realmlist new.example.org"
+        );
+        assert_eq!(std::fs::read(&path)?, original);
+
+        // Also when the client fails
+        let status = launch_with_realm(&config("exit 3"), "new.example.org", None, |_| {})?;
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(std::fs::read(&path)?, original);
+
+        // And when it can't be started at all
+        std::fs::remove_file(game.join("Wow.exe"))?;
+        let e = launch_with_realm(&config("true"), "new.example.org", None, |_| {}).unwrap_err();
+        assert!(e.to_string().contains("Executable not found"));
+        assert_eq!(std::fs::read(&path)?, original);
+
+        // A realmlist that did not exist before is removed again
+        std::fs::write(game.join("Wow.exe"), "")?;
+        std::fs::remove_file(&path)?;
+        launch_with_realm(&config(&copy_cmd), "new.example.org", None, |_| {})?;
+        assert!(!path.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Seconds a copied password stays on the clipboard when `clipboard_clear_secs` is unset
+pub const DEFAULT_CLEAR_SECS: u64 = 30;
+
+/// Thread holding the clipboard until it is cleared, see [`wait_for_clear`]
+static PENDING: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+/// Copy a secret to the clipboard and clear it after `clear_after`, unless something
+/// else was copied meanwhile
+///
+/// On X11 and Wayland the clipboard is served by the process that set it, so a thread
+/// keeps it until it is cleared; with a zero duration it is never cleared and only stays
+/// available through a clipboard manager.
+///
+/// The secret is marked for clipboard managers and the OS clipboard history to leave
+/// out, see [`set_secret`].
+pub fn copy_secret(secret: &str, clear_after: Duration) -> Result<(), String> {
+    let secret = secret.to_string();
+    let (copied, result) = mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        let mut clipboard = match arboard::Clipboard::new() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                let _ = copied.send(Err(e.to_string()));
+                return;
+            }
+        };
+        if let Err(e) = set_secret(&mut clipboard, &secret, clear_after) {
+            let _ = copied.send(Err(e.to_string()));
+            return;
+        }
+        let _ = copied.send(Ok(()));
+        if clear_after.is_zero() {
+            return;
+        }
+        std::thread::sleep(clear_after);
+        if clipboard.get_text().is_ok_and(|text| text == secret) {
+            let _ = clipboard.clear();
+        }
+    });
+    let copied = result
+        .recv()
+        .unwrap_or_else(|_| Err("clipboard thread failed".to_string()));
+    if copied.is_ok()
+        && let Ok(mut pending) = PENDING.lock()
+    {
+        *pending = Some(handle);
+    }
+    copied
+}
+
+/// Put a secret on the clipboard with the hint clipboard history tools honour:
+/// `x-kde-passwordManagerHint` on X11 and Wayland (Klipper, CopyQ, GPaste),
+/// `ExcludeClipboardContentFromMonitorProcessing` on Windows (history and cloud clipboard)
+/// and `org.nspasteboard.ConcealedType` on macOS
+///
+/// On Linux a secret that is never cleared is left unmarked, a clipboard manager is then
+/// the only thing keeping it once realmctl exits.
+fn set_secret(
+    clipboard: &mut arboard::Clipboard,
+    secret: &str,
+    clear_after: Duration,
+) -> Result<(), arboard::Error> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        use arboard::SetExtLinux;
+        if clear_after.is_zero() {
+            clipboard.set_text(secret)
+        } else {
+            clipboard.set().exclude_from_history().text(secret)
+        }
+    }
+    #[cfg(windows)]
+    {
+        use arboard::SetExtWindows;
+        let _ = clear_after;
+        clipboard.set().exclude_from_monitoring().text(secret)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        use arboard::SetExtApple;
+        let _ = clear_after;
+        clipboard.set().exclude_from_history().text(secret)
+    }
+}
+
+/// Whether a copied password is still waiting to be cleared
+pub fn is_pending() -> bool {
+    PENDING
+        .lock()
+        .is_ok_and(|pending| pending.as_ref().is_some_and(|handle| !handle.is_finished()))
+}
+
+/// Block until a password copied by [`copy_secret`] has been cleared
+///
+/// Returns right away when nothing is pending.
+pub fn wait_for_clear() {
+    let handle = PENDING.lock().ok().and_then(|mut pending| pending.take());
+    if let Some(handle) = handle {
+        let _ = handle.join();
+    }
+}
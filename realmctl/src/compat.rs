@@ -0,0 +1,402 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::RESERVED_KEYS;
+
+/// Game keys renamed since they were introduced, as (old, new)
+pub const RENAMED_KEYS: &[(&str, &str)] = &[("realmlist_rel_path", "realmlist_path")];
+
+/// Boolean game keys older configs wrote as strings or numbers
+const BOOL_KEYS: &[&str] = &["clear_cache", "clear_wdb_on_realm_change"];
+
+/// A legacy key or value in a game entry, and what it maps to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deprecation {
+    /// Section the key is in, e.g. `Chromie` or `host.laptop.Chromie`
+    pub section: String,
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.section, self.key, self.message)
+    }
+}
+
+fn legacy_bool(value: &toml::Value) -> Option<bool> {
+    match value {
+        toml::Value::String(s) => match s.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(true),
+            "false" | "no" | "off" | "0" => Some(false),
+            _ => None,
+        },
+        toml::Value::Integer(0) => Some(false),
+        toml::Value::Integer(1) => Some(true),
+        _ => None,
+    }
+}
+
+/// Host of a realmlist value that was pasted as a whole `set realmlist <host>` line
+fn legacy_realmlist(value: &toml::Value) -> Option<String> {
+    let value = value.as_str()?.trim();
+    let (set, rest) = value.split_once(char::is_whitespace)?;
+    let (name, host) = rest.trim_start().split_once(char::is_whitespace)?;
+    (set.eq_ignore_ascii_case("set") && name.eq_ignore_ascii_case("realmlist"))
+        .then(|| host.trim().trim_matches('"').to_string())
+}
+
+/// Rewrite legacy keys and values of a game entry (or host override) to their current form
+pub fn upgrade_entry(section: &str, entry: &mut toml::Table) -> Vec<Deprecation> {
+    let mut deprecations = Vec::new();
+    let mut deprecate = |key: &str, message: String| {
+        deprecations.push(Deprecation {
+            section: section.to_string(),
+            key: key.to_string(),
+            message,
+        })
+    };
+
+    for (old, new) in RENAMED_KEYS {
+        let Some(value) = entry.remove(*old) else {
+            continue;
+        };
+        if entry.contains_key(*new) {
+            deprecate(old, format!("renamed to {new}, which is also set and wins"));
+        } else {
+            entry.insert(new.to_string(), value);
+            deprecate(old, format!("renamed to {new}"));
+        }
+    }
+
+    if let Some(host) = entry.get("realmlist").and_then(legacy_realmlist) {
+        deprecate(
+            "realmlist",
+            format!("write just the host (\"{host}\"), `set realmlist` is added when writing"),
+        );
+        entry.insert("realmlist".to_string(), toml::Value::String(host));
+    }
+
+    for key in BOOL_KEYS {
+        if let Some((old, value)) = entry
+            .get(*key)
+            .and_then(|old| Some((old.clone(), legacy_bool(old)?)))
+        {
+            deprecate(key, format!("use {value} instead of {old}"));
+            entry.insert(key.to_string(), toml::Value::Boolean(value));
+        }
+    }
+    deprecations
+}
+
+/// Print each deprecation once per process, on stderr so scripted output stays clean
+pub fn warn_once(deprecations: &[Deprecation]) {
+    static WARNED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+    let Ok(mut warned) = WARNED.lock() else {
+        return;
+    };
+    for deprecation in deprecations {
+        if warned.insert(deprecation.to_string()) {
+            tracing::warn!("⚠ Deprecated config: {deprecation} (run `realmctl config upgrade`)");
+        }
+    }
+}
+
+/// Name of a `[table]` header as dotted segments, `None` for other lines
+fn header_segments(line: &str) -> Option<Vec<String>> {
+    let name = line.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    if name.starts_with('[') {
+        return None;
+    }
+    // Reuse the TOML parser for quoted segments like [host."my-pc".Chromie]
+    let parsed: toml::Table = toml::from_str(&format!("[{name}]")).ok()?;
+    let mut segments = Vec::new();
+    let mut table = &parsed;
+    while let Some((key, value)) = table.iter().next() {
+        segments.push(key.clone());
+        match value.as_table() {
+            Some(inner) => table = inner,
+            None => break,
+        }
+    }
+    Some(segments)
+}
+
+/// Section name if a header opens a game entry or a host override of one
+fn game_section(segments: &[String]) -> Option<String> {
+    match segments {
+        [game] if !RESERVED_KEYS.contains(&game.as_str()) => Some(game.clone()),
+        [host, name, game] if host == "host" => Some(format!("host.{name}.{game}")),
+        _ => None,
+    }
+}
+
+/// Rewrite legacy keys in config.toml source, keeping everything else as written
+///
+/// Only `key = value` lines directly under a game or host override header are rewritten;
+/// the returned deprecations are the ones that were fixed.
+pub fn upgrade_source(source: &str) -> (String, Vec<Deprecation>) {
+    let full: toml::Table = toml::from_str(source).unwrap_or_default();
+    let mut output = Vec::new();
+    let mut fixed = Vec::new();
+    let mut section: Option<(String, toml::Table)> = None;
+
+    for line in source.lines() {
+        if let Some(segments) = header_segments(line) {
+            section = game_section(&segments).map(|name| {
+                // Keys of the whole section, to tell whether a renamed key's new name is set
+                let keys = segments
+                    .iter()
+                    .try_fold(&full, |table, segment| table.get(segment)?.as_table())
+                    .cloned()
+                    .unwrap_or_default();
+                (name, keys)
+            });
+            output.push(line.to_string());
+            continue;
+        }
+        let Some((name, keys)) = &section else {
+            output.push(line.to_string());
+            continue;
+        };
+        let Ok(mut entry) = toml::from_str::<toml::Table>(line) else {
+            output.push(line.to_string());
+            continue;
+        };
+        if entry.len() != 1 || entry.values().any(toml::Value::is_table) {
+            output.push(line.to_string());
+            continue;
+        }
+
+        let key = entry.keys().next().cloned().unwrap_or_default();
+        if let Some((_, new)) = RENAMED_KEYS.iter().find(|(old, _)| *old == key)
+            && keys.contains_key(*new)
+        {
+            fixed.push(Deprecation {
+                section: name.clone(),
+                key,
+                message: format!("removed, {new} is also set and wins"),
+            });
+            continue;
+        }
+        let deprecations = upgrade_entry(name, &mut entry);
+        if deprecations.is_empty() {
+            output.push(line.to_string());
+            continue;
+        }
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let rewritten = toml::to_string(&entry).unwrap_or_default();
+        output.push(format!("{indent}{}", rewritten.trim_end()));
+        fixed.extend(deprecations);
+    }
+
+    let mut upgraded = output.join("\n");
+    if source.ends_with('\n') {
+        upgraded.push('\n');
+    }
+    (upgraded, fixed)
+}
+
+/// Deprecations in a whole config file, including host overrides
+pub fn deprecations(table: &toml::Table) -> Vec<Deprecation> {
+    let mut found = Vec::new();
+    for (key, value) in table {
+        if RESERVED_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        if let Some(entry) = value.as_table() {
+            found.extend(upgrade_entry(key, &mut entry.clone()));
+        }
+    }
+    if let Some(hosts) = table.get("host").and_then(|h| h.as_table()) {
+        for (host, games) in hosts {
+            for (game, entry) in games.as_table().into_iter().flatten() {
+                if let Some(entry) = entry.as_table() {
+                    found.extend(upgrade_entry(
+                        &format!("host.{host}.{game}"),
+                        &mut entry.clone(),
+                    ));
+                }
+            }
+        }
+    }
+    found
+}
+
+static LENIENT: AtomicBool = AtomicBool::new(false);
+
+/// Load manifest.toml and workspace.toml as far as they can be read (`--lenient`)
+pub fn set_lenient(lenient: bool) {
+    LENIENT.store(lenient, Ordering::SeqCst);
+}
+
+pub fn is_lenient() -> bool {
+    LENIENT.load(Ordering::SeqCst)
+}
+
+/// Parse a file realmctl wrote, possibly by another release or tool
+///
+/// Missing keys take their serde defaults. In lenient mode, keys (and entries of tables
+/// like `file_roles`) with unreadable values are skipped with a warning instead of
+/// failing the whole file.
+pub fn parse_toml<T>(content: &str, path: &Path) -> Result<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let error = match toml::from_str::<T>(content) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    if !is_lenient() {
+        anyhow::bail!(
+            "Failed to parse {}: {error}\nRun with --lenient to skip what can't be read",
+            path.display()
+        );
+    }
+    // Broken TOML syntax leaves nothing to salvage
+    let source: toml::Table =
+        toml::from_str(content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    let (value, skipped) = salvage::<T>(source)?;
+    for key in skipped {
+        tracing::warn!("⚠ {}: skipped unreadable {key}", path.display());
+    }
+    Ok(value)
+}
+
+fn salvage<T>(source: toml::Table) -> Result<(T, Vec<String>)>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let defaults = toml::Table::try_from(T::default())?;
+    let readable = |key: &str, value: toml::Value| {
+        let mut candidate = defaults.clone();
+        candidate.insert(key.to_string(), value);
+        toml::Value::Table(candidate).try_into::<T>().is_ok()
+    };
+
+    let mut table = defaults.clone();
+    let mut skipped = Vec::new();
+    for (key, value) in source {
+        if readable(&key, value.clone()) {
+            table.insert(key, value);
+            continue;
+        }
+        let toml::Value::Table(entries) = value else {
+            skipped.push(key);
+            continue;
+        };
+        // Entries of a map are checked one by one, a bad one shouldn't drop the others
+        let mut kept = toml::Table::new();
+        for (entry_key, entry) in entries {
+            let single = toml::Table::from_iter([(entry_key.clone(), entry.clone())]);
+            if readable(&key, toml::Value::Table(single)) {
+                kept.insert(entry_key, entry);
+            } else {
+                skipped.push(format!("{key}.{entry_key}"));
+            }
+        }
+        if readable(&key, toml::Value::Table(kept.clone())) {
+            table.insert(key, toml::Value::Table(kept));
+        } else {
+            skipped.push(key);
+        }
+    }
+    Ok((toml::Value::Table(table).try_into()?, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_entry() {
+        let mut entry: toml::Table = toml::from_str(
+            r#"
+realmlist = "set realmlist logon.example.org"
+realmlist_rel_path = "Data/enUS/realmlist.wtf"
+clear_cache = "yes"
+"#,
+        )
+        .unwrap();
+        let deprecations = upgrade_entry("Chromie", &mut entry);
+        assert_eq!(deprecations.len(), 3);
+        assert_eq!(entry["realmlist"].as_str(), Some("logon.example.org"));
+        assert_eq!(
+            entry["realmlist_path"].as_str(),
+            Some("Data/enUS/realmlist.wtf")
+        );
+        assert_eq!(entry["clear_cache"].as_bool(), Some(true));
+        assert!(upgrade_entry("Chromie", &mut entry).is_empty());
+    }
+
+    #[test]
+    fn test_upgrade_source_keeps_layout() {
+        let source = r#"# my games
+[Chromie]
+directory = "~/Games/wow335"  # the main client
+realmlist_rel_path = "Data/enUS/realmlist.wtf"
+clear_cache = 1
+
+[host.laptop.Chromie]
+realmlist_rel_path = "Data/enGB/realmlist.wtf"
+realmlist_path = "Data/enGB/realmlist.wtf"
+"#;
+        let (upgraded, fixed) = upgrade_source(source);
+        assert_eq!(fixed.len(), 3);
+        assert_eq!(
+            upgraded,
+            r#"# my games
+[Chromie]
+directory = "~/Games/wow335"  # the main client
+realmlist_path = "Data/enUS/realmlist.wtf"
+clear_cache = true
+
+[host.laptop.Chromie]
+realmlist_path = "Data/enGB/realmlist.wtf"
+"#
+        );
+        let table: toml::Table = toml::from_str(&upgraded).unwrap();
+        assert!(deprecations(&table).is_empty());
+    }
+
+    #[test]
+    fn test_salvage_skips_unreadable_keys() {
+        let source: toml::Table = toml::from_str(
+            r#"
+profile = "chromie-3.3.5a"
+created_at = 1700000000
+future_key = "ignored"
+
+[file_roles]
+"Wow.exe" = "Executable"
+"Data/new.MPQ" = "StreamedData"
+"Data" = { role = "BaseData" }
+
+[checksums]
+"Data/common.MPQ" = "0a1b2c3d"
+"Data/patch.MPQ" = 42
+"#,
+        )
+        .unwrap();
+        let (manifest, skipped) = salvage::<crate::base::BaseManifest>(source).unwrap();
+        assert_eq!(manifest.profile, "chromie-3.3.5a");
+        assert_eq!(manifest.created_at, "");
+        // Unknown role names are read as Other even without --lenient
+        assert_eq!(
+            manifest.file_roles["Data/new.MPQ"],
+            crate::base::FileRole::Other
+        );
+        assert_eq!(manifest.file_roles.len(), 2);
+        assert_eq!(manifest.checksums.len(), 1);
+        assert_eq!(
+            skipped,
+            vec!["created_at", "file_roles.Data", "checksums.Data/patch.MPQ"]
+        );
+    }
+}
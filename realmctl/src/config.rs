@@ -0,0 +1,775 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::hooks::{HOOK_EVENTS, HookCommands};
+use crate::locale::find_realmlist;
+use crate::{Config, RESERVED_KEYS};
+
+/// Keys understood in a game entry
+pub const GAME_KEYS: &[&str] = &[
+    "directory",
+    "executable",
+    "launch_cmd",
+    "runner",
+    "proton_path",
+    "compat_data_path",
+    "gamescope",
+    "gamemode",
+    "env",
+    "realmlist",
+    "realmlist_path",
+    "account",
+    "password",
+    "accounts",
+    "arguments",
+    "clear_cache",
+    "auto_login",
+    "realm_name",
+    "character_index",
+    "copy_password",
+    "clipboard_clear_secs",
+    "cache_password",
+    "password_cache_secs",
+    "clear_wdb_on_realm_change",
+    "tags",
+    "news_url",
+    "maintenance",
+    "pre_launch",
+    "post_exit",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A single problem found in config.toml
+#[derive(Debug, Clone)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = match self.severity {
+            Severity::Warning => "⚠",
+            Severity::Error => "✗",
+        };
+        write!(f, "{marker} {}", self.message)
+    }
+}
+
+/// Line (1-based) of the `[key]` header defining a top-level table, if it has one
+pub fn header_line(source: &str, key: &str) -> Option<usize> {
+    source.lines().enumerate().find_map(|(i, line)| {
+        let name = line.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+        if name.starts_with('[') {
+            return None;
+        }
+        let name = name.trim_matches('"').trim_matches('\'');
+        (name == key).then_some(i + 1)
+    })
+}
+
+/// Describe a game key with its location, e.g. `[Chromie] (line 3)`
+pub fn describe_key(source: &str, key: &str) -> String {
+    match header_line(source, key) {
+        Some(line) => format!("[{key}] (line {line})"),
+        None => format!("[{key}]"),
+    }
+}
+
+/// Game keys matching `game` case-insensitively, in file order
+pub fn matching_keys<'a>(table: &'a toml::Table, game: &str) -> Vec<&'a str> {
+    table
+        .keys()
+        .filter(|key| !RESERVED_KEYS.contains(&key.as_str()))
+        .filter(|key| key.eq_ignore_ascii_case(game))
+        .map(String::as_str)
+        .collect()
+}
+
+/// Check a config file for duplicate keys and other silent pitfalls
+pub fn lint(source: &str) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    let table: toml::Table = match toml::from_str(source) {
+        Ok(table) => table,
+        Err(e) => {
+            issues.push(LintIssue {
+                severity: Severity::Error,
+                message: format!("Failed to parse config file: {e}"),
+            });
+            return issues;
+        }
+    };
+
+    let games: Vec<&String> = table
+        .keys()
+        .filter(|key| !RESERVED_KEYS.contains(&key.as_str()))
+        .collect();
+
+    // Keys that only differ in case shadow each other
+    let mut reported: Vec<String> = Vec::new();
+    for key in &games {
+        let lower = key.to_lowercase();
+        if reported.contains(&lower) {
+            continue;
+        }
+        let duplicates = matching_keys(&table, key);
+        if duplicates.len() > 1 {
+            let locations: Vec<String> = duplicates
+                .iter()
+                .map(|dup| describe_key(source, dup))
+                .collect();
+            issues.push(LintIssue {
+                severity: Severity::Error,
+                message: format!(
+                    "{} differ only in case; lookup is case-insensitive so one shadows the other",
+                    locations.join(" and ")
+                ),
+            });
+            reported.push(lower);
+        }
+    }
+
+    for key in &games {
+        let location = describe_key(source, key);
+
+        if RESERVED_KEYS.iter().any(|r| r.eq_ignore_ascii_case(key)) {
+            issues.push(LintIssue {
+                severity: Severity::Warning,
+                message: format!(
+                    "{location} resembles the reserved section name '{}'",
+                    key.to_lowercase()
+                ),
+            });
+        }
+
+        let Some(entry) = table[key.as_str()].as_table() else {
+            issues.push(LintIssue {
+                severity: Severity::Error,
+                message: format!("{location} is not a table"),
+            });
+            continue;
+        };
+
+        for field in entry.keys() {
+            let renamed = crate::compat::RENAMED_KEYS
+                .iter()
+                .any(|(old, _)| old == field);
+            if !GAME_KEYS.contains(&field.as_str()) && !renamed {
+                issues.push(LintIssue {
+                    severity: Severity::Warning,
+                    message: format!("{location} unknown key '{field}' is ignored"),
+                });
+            }
+        }
+
+        let realmlist_found = entry
+            .get("directory")
+            .and_then(|d| d.as_str())
+            .is_some_and(|d| find_realmlist(Path::new(&*shellexpand::tilde(d))).is_some());
+        if entry.contains_key("realmlist")
+            && !entry.contains_key("realmlist_path")
+            && !entry.contains_key("realmlist_rel_path")
+            && !realmlist_found
+        {
+            issues.push(LintIssue {
+                severity: Severity::Warning,
+                message: format!(
+                    "{location} sets 'realmlist' without 'realmlist_path' and no realmlist.wtf was found; the realmlist is never written"
+                ),
+            });
+        }
+
+        for spec in entry
+            .get("maintenance")
+            .and_then(|m| m.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let error = match spec.as_str() {
+                Some(spec) => crate::maintenance::MaintenanceWindow::parse(spec).err(),
+                None => Some("entries must be strings".to_string()),
+            };
+            if let Some(error) = error {
+                issues.push(LintIssue {
+                    severity: Severity::Error,
+                    message: format!("{location} maintenance: {error}"),
+                });
+            }
+        }
+
+        if let (Some(account), Some(accounts)) = (
+            entry.get("account").and_then(|a| a.as_str()),
+            entry.get("accounts").and_then(|a| a.as_table()),
+        ) && accounts.contains_key(account)
+        {
+            issues.push(LintIssue {
+                severity: Severity::Warning,
+                message: format!("{location} account '{account}' is listed twice"),
+            });
+        }
+    }
+
+    for deprecation in crate::compat::deprecations(&table) {
+        issues.push(LintIssue {
+            severity: Severity::Warning,
+            message: format!("{deprecation} (fix with `realmctl config upgrade`)"),
+        });
+    }
+
+    if let Some(hooks) = table.get("hooks").and_then(|h| h.as_table()) {
+        for (event, commands) in hooks {
+            if !HOOK_EVENTS.contains(&event.as_str()) {
+                issues.push(LintIssue {
+                    severity: Severity::Warning,
+                    message: format!(
+                        "[hooks] unknown hook '{event}' never runs (expected one of {})",
+                        HOOK_EVENTS.join(", ")
+                    ),
+                });
+            } else if commands.clone().try_into::<HookCommands>().is_err() {
+                issues.push(LintIssue {
+                    severity: Severity::Error,
+                    message: format!("[hooks] {event} must be a command or a list of commands"),
+                });
+            }
+        }
+    }
+
+    #[cfg(feature = "workspaces")]
+    if let Some(retention) = table.get("retention") {
+        let error = match retention.as_table() {
+            Some(section) => crate::retention::RetentionConfig::from_table(section).err(),
+            None => Some("[retention] must be a table".to_string()),
+        };
+        if let Some(message) = error {
+            issues.push(LintIssue {
+                severity: Severity::Error,
+                message,
+            });
+        }
+    }
+
+    #[cfg(feature = "workspaces")]
+    if let Some(backup) = table.get("backup")
+        && let Err(message) = crate::backup::BackupConfig::from_value(backup.clone())
+    {
+        issues.push(LintIssue {
+            severity: Severity::Error,
+            message,
+        });
+    }
+
+    if let Some(watchdog) = table.get("watchdog")
+        && let Err(message) = crate::watchdog::WatchdogConfig::from_value(watchdog.clone())
+    {
+        issues.push(LintIssue {
+            severity: Severity::Error,
+            message,
+        });
+    }
+
+    if let Some(defaults) = table.get("defaults") {
+        match crate::defaults::DefaultsConfig::from_value(defaults.clone()) {
+            Ok(defaults) => {
+                if let Some(game) = defaults.game
+                    && matching_keys(&table, &game).is_empty()
+                {
+                    issues.push(LintIssue {
+                        severity: Severity::Error,
+                        message: format!("[defaults] game '{game}' does not exist"),
+                    });
+                }
+            }
+            Err(message) => issues.push(LintIssue {
+                severity: Severity::Error,
+                message,
+            }),
+        }
+    }
+
+    if let Some(groups) = table.get("groups") {
+        match groups.as_table() {
+            Some(groups) => {
+                for (name, group) in groups {
+                    match crate::group::GroupConfig::from_value(name, group.clone()) {
+                        Ok(group) => {
+                            for game in &group.games {
+                                if matching_keys(&table, game).is_empty() {
+                                    issues.push(LintIssue {
+                                        severity: Severity::Error,
+                                        message: format!(
+                                            "[groups.{name}] game '{game}' does not exist"
+                                        ),
+                                    });
+                                }
+                            }
+                        }
+                        Err(message) => issues.push(LintIssue {
+                            severity: Severity::Error,
+                            message,
+                        }),
+                    }
+                }
+            }
+            None => issues.push(LintIssue {
+                severity: Severity::Error,
+                message: "[groups] must be a table".to_string(),
+            }),
+        }
+    }
+
+    if let Some(realms) = table.get("realms") {
+        match realms.as_table() {
+            Some(presets) => {
+                for (name, address) in presets {
+                    if !address.is_str() {
+                        issues.push(LintIssue {
+                            severity: Severity::Error,
+                            message: format!("[realms] {name} must be a realmlist address"),
+                        });
+                    }
+                }
+            }
+            None => issues.push(LintIssue {
+                severity: Severity::Error,
+                message: "[realms] must be a table".to_string(),
+            }),
+        }
+    }
+
+    // Host overrides for games that do not exist never apply
+    if let Some(hosts) = table.get("host").and_then(|h| h.as_table()) {
+        for (host, overrides) in hosts {
+            let Some(overrides) = overrides.as_table() else {
+                continue;
+            };
+            for game in overrides.keys() {
+                if matching_keys(&table, game).is_empty() {
+                    issues.push(LintIssue {
+                        severity: Severity::Warning,
+                        message: format!(
+                            "[host.{host}.{game}] overrides a game that does not exist"
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+/// Whether a game entry can be launched right now
+#[derive(Debug, Clone)]
+pub struct Readiness {
+    pub directory: bool,
+    pub executable: bool,
+    /// Realmlist that launch writes, if realmlist is set and a realmlist file was found
+    pub realmlist: Option<String>,
+    pub accounts: usize,
+}
+
+impl Readiness {
+    pub fn check(config: &Config) -> Self {
+        let realmlist = match (&config.realmlist, config.realmlist_path()) {
+            (Some(realmlist), Some(_)) => Some(realmlist.clone()),
+            _ => None,
+        };
+        let accounts = config.account.iter().count()
+            + config
+                .accounts
+                .as_ref()
+                .map_or(0, |accounts| accounts.len());
+
+        Readiness {
+            directory: config.directory.is_dir(),
+            executable: config.directory.join(&config.executable).is_file(),
+            realmlist,
+            accounts,
+        }
+    }
+
+    pub fn is_launchable(&self) -> bool {
+        self.directory && self.executable
+    }
+
+    /// Short reason why the game can't be launched, or "ready"
+    pub fn status(&self) -> &'static str {
+        if !self.directory {
+            "directory missing"
+        } else if !self.executable {
+            "executable missing"
+        } else {
+            "ready"
+        }
+    }
+}
+
+/// Findings about one game entry of config.toml, see [`validate`]
+#[derive(Debug, Clone)]
+pub struct SectionReport {
+    pub section: String,
+    /// Line (1-based) of the section header
+    pub line: Option<usize>,
+    pub issues: Vec<LintIssue>,
+}
+
+/// Line (1-based) a byte offset of `source` is on
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// Check every game entry against the config schema and the file system
+///
+/// Reports all keys of wrong type, unknown keys, and directories, executables and realmlist
+/// files that don't exist, each with the line it is on. Fails with the parser's message
+/// (line, column and the offending text) when the file isn't valid TOML.
+pub fn validate(source: &str) -> Result<Vec<SectionReport>, String> {
+    let table: toml::Table = toml::from_str(source).map_err(|e| e.to_string())?;
+    let spans = toml_edit::ImDocument::parse(source).map_err(|e| e.to_string())?;
+    let key_line = |section: &str, field: &str| {
+        spans
+            .get(section)?
+            .as_table_like()?
+            .get(field)?
+            .span()
+            .map(|span| line_of(source, span.start))
+    };
+    let at = |section: &str, field: &str| match key_line(section, field) {
+        Some(line) => format!("line {line}: '{field}'"),
+        None => format!("'{field}'"),
+    };
+
+    let mut reports = Vec::new();
+    for (key, value) in &table {
+        if RESERVED_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let mut report = SectionReport {
+            section: key.clone(),
+            line: header_line(source, key),
+            issues: Vec::new(),
+        };
+        let error = |message: String| LintIssue {
+            severity: Severity::Error,
+            message,
+        };
+        let Some(entry) = value.as_table() else {
+            report.issues.push(error(format!(
+                "is not a table, write it as a [{key}] section"
+            )));
+            reports.push(report);
+            continue;
+        };
+
+        for field in entry.keys() {
+            let renamed = crate::compat::RENAMED_KEYS
+                .iter()
+                .any(|(old, _)| old == field);
+            if !GAME_KEYS.contains(&field.as_str()) && !renamed {
+                report.issues.push(LintIssue {
+                    severity: Severity::Warning,
+                    message: format!("{} is not a known key and is ignored", at(key, field)),
+                });
+            }
+        }
+
+        // Serde stops at the first bad key, drop each one and try again to find them all
+        let mut entry = entry.clone();
+        crate::compat::upgrade_entry(key, &mut entry);
+        let mut removed = Vec::new();
+        let config = loop {
+            let e = match toml::Value::Table(entry.clone()).try_into::<Config>() {
+                Ok(config) => break Some(config),
+                Err(e) => e,
+            };
+            let field = e
+                .to_string()
+                .lines()
+                .find_map(|line| {
+                    line.strip_prefix("in `")?
+                        .strip_suffix('`')
+                        .map(str::to_string)
+                })
+                .and_then(|path| path.split('.').next().map(str::to_string));
+            match field.filter(|field| entry.contains_key(field)) {
+                Some(field) => {
+                    report
+                        .issues
+                        .push(error(format!("{}: {}", at(key, &field), e.message())));
+                    entry.remove(&field);
+                    removed.push(field);
+                }
+                None => {
+                    // A required key already reported with the wrong type is now missing
+                    let reported = removed
+                        .iter()
+                        .any(|field| e.message().contains(&format!("`{field}`")));
+                    if !reported {
+                        report.issues.push(error(e.message().to_string()));
+                    }
+                    break None;
+                }
+            }
+        };
+
+        if let Some(mut config) = config {
+            config.directory = crate::paths::resolve_in_root(std::path::PathBuf::from(
+                shellexpand::tilde(&config.directory.to_string_lossy()).to_string(),
+            ));
+            if !config.directory.is_dir() {
+                report.issues.push(error(format!(
+                    "{} {} does not exist; fix the path or create the game directory",
+                    at(key, "directory"),
+                    config.directory.display()
+                )));
+            } else if config.launch_cmd.is_none()
+                && !config.directory.join(&config.executable).is_file()
+            {
+                let field = if entry.contains_key("executable") {
+                    "executable"
+                } else {
+                    "directory"
+                };
+                report.issues.push(error(format!(
+                    "{} {} is not in {}; set 'executable' to the client's file name",
+                    at(key, field),
+                    config.executable,
+                    config.directory.display()
+                )));
+            }
+            if config.directory.is_dir() {
+                match config.realmlist_path() {
+                    Some(path) if !path.is_file() => report.issues.push(LintIssue {
+                        severity: Severity::Warning,
+                        message: format!(
+                            "{} {} does not exist; the path is relative to the game directory",
+                            at(key, "realmlist_path"),
+                            path.display()
+                        ),
+                    }),
+                    None if config.realmlist.is_some() => report.issues.push(LintIssue {
+                        severity: Severity::Warning,
+                        message: format!(
+                            "{} is set but no realmlist.wtf was found; add 'realmlist_path'",
+                            at(key, "realmlist")
+                        ),
+                    }),
+                    _ => {}
+                }
+            }
+            if let Some(path) = &config.proton_path
+                && !Path::new(&*shellexpand::tilde(path)).is_dir()
+            {
+                report.issues.push(error(format!(
+                    "{} {path} is not a directory",
+                    at(key, "proton_path")
+                )));
+            }
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+/// config.toml source with the `[name]` game entry set to `values`, or appended when there
+/// is none; returns whether an entry was updated
+///
+/// Keys not in `values`, comments and the rest of the file are kept as written. The entry is
+/// matched case-insensitively like at launch.
+pub fn upsert_game(
+    source: &str,
+    name: &str,
+    values: &[(&str, toml_edit::Value)],
+) -> Result<(String, bool), String> {
+    if RESERVED_KEYS
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name))
+    {
+        return Err(format!("'{name}' is a reserved section, pick another name"));
+    }
+    let mut doc: toml_edit::DocumentMut = source
+        .parse()
+        .map_err(|e| format!("Failed to parse config file: {e}"))?;
+    let keys: Vec<String> = doc
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| key.eq_ignore_ascii_case(name))
+        .collect();
+    let (key, updated) = match keys.as_slice() {
+        [] => (name.to_string(), false),
+        [key] => (key.clone(), true),
+        _ => {
+            return Err(format!(
+                "Config key '{name}' is ambiguous (run `realmctl config lint`)"
+            ));
+        }
+    };
+    if !updated {
+        doc.insert(&key, toml_edit::Item::Table(toml_edit::Table::new()));
+    }
+    let table = doc[&key]
+        .as_table_like_mut()
+        .ok_or_else(|| format!("'{key}' is not a table"))?;
+    for (field, value) in values {
+        match table.get_mut(field).and_then(|item| item.as_value_mut()) {
+            // Keep the comment after the old value
+            Some(old) => {
+                let decor = old.decor().clone();
+                *old = value.clone();
+                *old.decor_mut() = decor;
+            }
+            None => {
+                table.insert(field, toml_edit::value(value.clone()));
+            }
+        }
+    }
+    Ok((doc.to_string(), updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_reports_case_duplicates_with_lines() {
+        let source = r#"
+[Chromie]
+directory = "/a"
+
+[chromie]
+directory = "/b"
+"#;
+        let issues = lint(source);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert!(issues[0].message.contains("[Chromie] (line 2)"));
+        assert!(issues[0].message.contains("[chromie] (line 5)"));
+    }
+
+    #[test]
+    fn test_lint_reports_unknown_keys_and_dead_overrides() {
+        let source = r#"
+[Local]
+directory = "/a"
+username = "user"
+realmlist = "127.0.0.1"
+
+[host.laptop.Missing]
+directory = "/b"
+"#;
+        let messages: Vec<String> = lint(source).into_iter().map(|i| i.message).collect();
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.contains("unknown key 'username'"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.contains("without 'realmlist_path'"))
+        );
+        assert!(messages.iter().any(|m| m.contains("[host.laptop.Missing]")));
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem_with_lines() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let game = temp.path().join("game");
+        std::fs::create_dir_all(&game).unwrap();
+        std::fs::write(game.join("Wow.exe"), "exe").unwrap();
+        let source = format!(
+            r#"[Good]
+directory = "{dir}"
+
+[Bad]
+directory = "{dir}"
+clear_cache = 3
+character_index = "first"
+speling = true
+realmlist_path = "Data/enUS/realmlist.wtf"
+
+[Missing]
+directory = "{dir}/gone"
+"#,
+            dir = game.display()
+        );
+        let reports = validate(&source).unwrap();
+        assert!(reports[0].issues.is_empty());
+
+        assert_eq!(reports[1].line, Some(4));
+        let messages: Vec<&str> = reports[1]
+            .issues
+            .iter()
+            .map(|issue| issue.message.as_str())
+            .collect();
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.starts_with("line 6: 'clear_cache'"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.starts_with("line 7: 'character_index'"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.starts_with("line 8: 'speling' is not a known key"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.contains("realmlist.wtf does not exist"))
+        );
+
+        assert!(reports[2].issues[0].message.contains("gone does not exist"));
+        assert!(
+            validate("[Broken]\ndirectory = \n")
+                .unwrap_err()
+                .contains("line 2")
+        );
+    }
+
+    #[test]
+    fn test_upsert_game_keeps_comments() {
+        let source = r#"# My games
+[Chromie]
+directory = "/a" # the main install
+realmlist = "old.example"
+
+# Local server
+[Local]
+directory = "/b"
+"#;
+        let (updated, existed) =
+            upsert_game(source, "chromie", &[("realmlist", "logon.example".into())]).unwrap();
+        assert!(existed);
+        assert!(updated.contains("# My games\n[Chromie]\ndirectory = \"/a\" # the main install\n"));
+        assert!(updated.contains("realmlist = \"logon.example\""));
+        assert!(updated.contains("# Local server\n[Local]"));
+
+        let (added, existed) = upsert_game(
+            &updated,
+            "Warmane",
+            &[
+                ("directory", "/c".into()),
+                ("realmlist", "logon.warmane.com".into()),
+            ],
+        )
+        .unwrap();
+        assert!(!existed);
+        let table: toml::Table = toml::from_str(&added).unwrap();
+        assert_eq!(table["Warmane"]["directory"].as_str(), Some("/c"));
+        assert_eq!(table["Local"]["directory"].as_str(), Some("/b"));
+
+        assert!(upsert_game(source, "realms", &[]).is_err());
+    }
+}
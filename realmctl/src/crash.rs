@@ -0,0 +1,81 @@
+use std::path::Path;
+
+/// Crashes within [`CRASH_LOOP_WINDOW`] seconds that make a crash loop
+pub const CRASH_LOOP_COUNT: usize = 3;
+
+/// Seconds in which [`CRASH_LOOP_COUNT`] crashes count as a loop
+pub const CRASH_LOOP_WINDOW: u64 = 600;
+
+/// Modification time of the newest crash report in a client's Errors directory
+///
+/// CASC clients write theirs to the product directory next to the executable.
+pub fn newest_crash_report(game_dir: &Path, executable: &str) -> Option<u64> {
+    let client_dir = game_dir
+        .join(executable)
+        .parent()
+        .map_or_else(|| game_dir.to_path_buf(), Path::to_path_buf);
+    std::fs::read_dir(client_dir.join("Errors"))
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            entry
+                .metadata()
+                .ok()?
+                .modified()
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs())
+        })
+        .max()
+}
+
+/// Whether an attached session ended in a crash: the client exited with an error or
+/// wrote a crash report after `started_at`
+pub fn session_crashed(
+    status: std::process::ExitStatus,
+    game_dir: &Path,
+    executable: &str,
+    started_at: u64,
+) -> bool {
+    !status.success()
+        || newest_crash_report(game_dir, executable).is_some_and(|modified| modified >= started_at)
+}
+
+/// Add a crash at `at` to the recorded ones, dropping those outside the window
+pub fn record_crash(crashes: &mut Vec<u64>, at: u64) {
+    crashes.retain(|crash| at.saturating_sub(*crash) < CRASH_LOOP_WINDOW);
+    crashes.push(at);
+}
+
+/// Whether the recorded crashes form a crash loop at `now`
+pub fn is_crash_loop(crashes: &[u64], now: u64) -> bool {
+    crashes
+        .iter()
+        .filter(|crash| now.saturating_sub(**crash) < CRASH_LOOP_WINDOW)
+        .count()
+        >= CRASH_LOOP_COUNT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_loop() {
+        let mut crashes = vec![100];
+        record_crash(&mut crashes, 1000);
+        assert_eq!(crashes, vec![1000]);
+        record_crash(&mut crashes, 1100);
+        assert!(!is_crash_loop(&crashes, 1100));
+        record_crash(&mut crashes, 1200);
+        assert!(is_crash_loop(&crashes, 1200));
+        assert!(!is_crash_loop(&crashes, 1000 + CRASH_LOOP_WINDOW));
+
+        let temp = tempfile::TempDir::new().unwrap();
+        assert_eq!(newest_crash_report(temp.path(), "Wow.exe"), None);
+        std::fs::create_dir_all(temp.path().join("_retail_/Errors")).unwrap();
+        std::fs::write(temp.path().join("_retail_/Errors/crash.txt"), "").unwrap();
+        assert!(newest_crash_report(temp.path(), "_retail_/Wow.exe").is_some());
+    }
+}
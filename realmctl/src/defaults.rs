@@ -0,0 +1,102 @@
+use serde::Deserialize;
+
+/// What `realmctl <game>` does when no command is given
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultAction {
+    #[default]
+    Launch,
+    /// `realmctl status <game>`
+    Status,
+}
+
+impl DefaultAction {
+    /// Command run for the game
+    pub fn command(self) -> &'static str {
+        match self {
+            DefaultAction::Launch => "launch",
+            DefaultAction::Status => "status",
+        }
+    }
+}
+
+/// The `[defaults]` section of config.toml
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DefaultsConfig {
+    /// Game used by a bare `realmctl`
+    pub game: Option<String>,
+    #[serde(default)]
+    pub action: DefaultAction,
+}
+
+impl DefaultsConfig {
+    pub fn from_value(value: toml::Value) -> Result<Self, String> {
+        value.try_into().map_err(|e| format!("[defaults] {e}"))
+    }
+
+    /// Command line for arguments that name no command: `args` starts with a game, or is
+    /// empty for the default game. `None` when there is no game to run the action for.
+    pub fn command_line(&self, args: &[String]) -> Option<Vec<String>> {
+        let mut command_line = vec![self.action.command().to_string()];
+        if args.is_empty() {
+            command_line.push(self.game.clone()?);
+        }
+        command_line.extend(args.iter().cloned());
+        Some(command_line)
+    }
+}
+
+/// Read the `[defaults]` section of a config file and whether `name` is one of its games
+///
+/// A missing file or section gives the defaults; an invalid section is reported and ignored.
+pub fn load_defaults(config_path: &str, name: Option<&str>) -> (DefaultsConfig, bool) {
+    let path = crate::paths::expand(config_path);
+    let Some(mut table) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|source| toml::from_str::<toml::Table>(&source).ok())
+    else {
+        return (DefaultsConfig::default(), false);
+    };
+    let is_game = name.is_some_and(|name| {
+        !crate::RESERVED_KEYS.contains(&name)
+            && !crate::config::matching_keys(&table, name).is_empty()
+    });
+    let defaults = table
+        .remove("defaults")
+        .and_then(|section| {
+            DefaultsConfig::from_value(section)
+                .inspect_err(|e| tracing::warn!("⚠ Ignoring invalid [defaults] section: {e}"))
+                .ok()
+        })
+        .unwrap_or_default();
+    (defaults, is_game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_command_line() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let defaults = DefaultsConfig::from_value(toml::toml! { game = "Chromie" }.into()).unwrap();
+        assert_eq!(defaults.action, DefaultAction::Launch);
+        assert_eq!(
+            defaults.command_line(&[]),
+            Some(args(&["launch", "Chromie"]))
+        );
+        assert_eq!(
+            defaults.command_line(&args(&["Alt", "--realm", "ptr"])),
+            Some(args(&["launch", "Alt", "--realm", "ptr"]))
+        );
+
+        let status = DefaultsConfig::from_value(toml::toml! { action = "status" }.into()).unwrap();
+        assert_eq!(status.command_line(&[]), None);
+        assert_eq!(
+            status.command_line(&args(&["Alt"])),
+            Some(args(&["status", "Alt"]))
+        );
+        assert!(DefaultsConfig::from_value(toml::toml! { action = "delete" }.into()).is_err());
+    }
+}
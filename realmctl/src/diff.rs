@@ -0,0 +1,143 @@
+use std::path::Path;
+
+/// Lines of unchanged context around each change
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Line by line edit script from `old` to `new` (longest common subsequence)
+fn edit_script<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut script = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            script.push((Op::Keep, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            // Removals first, as diff prints them
+            script.push((Op::Remove, old[i]));
+            i += 1;
+        } else {
+            script.push((Op::Add, new[j]));
+            j += 1;
+        }
+    }
+    script
+}
+
+fn range(start: usize, len: usize) -> String {
+    match len {
+        1 => format!("{}", start + 1),
+        // An empty range names the line before it
+        0 => format!("{start},0"),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Unified diff of a text file, as `diff -u` and `patch` know it, or None when nothing changes
+///
+/// `old` is None for a file that does not exist yet. A missing final newline is marked
+/// the way diff marks it.
+pub fn unified_diff(path: &Path, old: Option<&str>, new: &str) -> Option<String> {
+    if old == Some(new) {
+        return None;
+    }
+    let old_lines: Vec<&str> = old.unwrap_or("").split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let script = edit_script(&old_lines, &new_lines);
+
+    // Group the changes into hunks, merging those whose context would overlap
+    let changes: Vec<usize> = (0..script.len())
+        .filter(|&i| script[i].0 != Op::Keep)
+        .collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(script.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let label = path.display();
+    let mut out = match old {
+        Some(_) => format!("--- {label}\n+++ {label}\n"),
+        None => format!("--- /dev/null\n+++ {label}\n"),
+    };
+    for (start, end) in hunks {
+        let before = &script[..start];
+        let old_start = before.iter().filter(|(op, _)| *op != Op::Add).count();
+        let new_start = before.iter().filter(|(op, _)| *op != Op::Remove).count();
+        let hunk = &script[start..end];
+        let old_len = hunk.iter().filter(|(op, _)| *op != Op::Add).count();
+        let new_len = hunk.iter().filter(|(op, _)| *op != Op::Remove).count();
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_len),
+            range(new_start, new_len)
+        ));
+        for (op, line) in hunk {
+            out.push(match op {
+                Op::Keep => ' ',
+                Op::Remove => '-',
+                Op::Add => '+',
+            });
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let path = Path::new("realmlist.wtf");
+        assert_eq!(unified_diff(path, Some("same\n"), "same\n"), None);
+        assert_eq!(
+            unified_diff(path, Some("set realmlist old\n"), "set realmlist new").unwrap(),
+            "--- realmlist.wtf\n+++ realmlist.wtf\n@@ -1 +1 @@\n-set realmlist old\n\
+             +set realmlist new\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            unified_diff(path, None, "a\n").unwrap(),
+            "--- /dev/null\n+++ realmlist.wtf\n@@ -0,0 +1 @@\n+a\n"
+        );
+
+        // Changes far apart get their own hunks with three lines of context
+        let old: String = (1..=12).map(|n| format!("{n}\n")).collect();
+        let new: String = (1..=12)
+            .map(|n| match n {
+                2 => "two\n".to_string(),
+                11 => "eleven\n".to_string(),
+                n => format!("{n}\n"),
+            })
+            .collect();
+        let diff = unified_diff(path, Some(&old), &new).unwrap();
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n"));
+        assert!(diff.contains("@@ -8,5 +8,5 @@\n 8\n 9\n 10\n-11\n+eleven\n 12\n"));
+    }
+}
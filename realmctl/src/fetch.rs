@@ -0,0 +1,21 @@
+//! Downloads through curl, which ships with Linux distributions, macOS and Windows 10+
+
+use anyhow::{Context, Result, bail};
+use std::process::Command;
+use std::time::Duration;
+
+/// Run `curl -fsSL` with extra `args` on `url` and return what it printed
+///
+/// HTTP errors fail with curl's message; the whole transfer is limited to `timeout`.
+pub fn curl(url: &str, args: &[&str], timeout: Duration) -> Result<String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", &timeout.as_secs().to_string()])
+        .args(args)
+        .arg(url)
+        .output()
+        .context("Failed to run curl")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
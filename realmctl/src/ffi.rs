@@ -0,0 +1,191 @@
+//! C API for front-ends that embed realmctl instead of running it
+//!
+//! Every function returns a JSON string owned by the caller: `{"ok": <value>}` on success or
+//! `{"error": "<message>"}` on failure. Free it with [`realmctl_free_string`]. Arguments are
+//! NUL-terminated UTF-8 strings; paths are expanded as on the command line. The header is
+//! `include/realmctl.h`.
+
+use std::ffi::{CStr, CString, c_char};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+
+/// Read a required string argument
+///
+/// # Safety
+/// `ptr` is null or points to a NUL-terminated string.
+unsafe fn arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str> {
+    if ptr.is_null() {
+        anyhow::bail!("{name} is null");
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .with_context(|| format!("{name} is not UTF-8"))
+}
+
+/// Read an optional string argument, None when null or empty
+///
+/// # Safety
+/// `ptr` is null or points to a NUL-terminated string.
+unsafe fn optional_arg<'a>(ptr: *const c_char, name: &str) -> Result<Option<&'a str>> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    let value = unsafe { arg(ptr, name) }?;
+    Ok((!value.is_empty()).then_some(value))
+}
+
+/// Run `f` and hand its result to C as a JSON envelope; panics become errors
+fn respond(f: impl FnOnce() -> Result<Value>) -> *mut c_char {
+    let envelope = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => json!({ "ok": value }),
+        Ok(Err(e)) => json!({ "error": format!("{e:#}") }),
+        Err(_) => json!({ "error": "realmctl panicked" }),
+    };
+    // serde_json escapes NUL, so the string has no interior NUL
+    CString::new(envelope.to_string())
+        .expect("JSON has no NUL bytes")
+        .into_raw()
+}
+
+/// Create workspace `name` from the base at `base` under `workspace_root`, with the default
+/// sharing rules. Returns the workspace's workspace.toml as JSON.
+///
+/// # Safety
+/// Every argument is a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn realmctl_create_workspace(
+    name: *const c_char,
+    base: *const c_char,
+    workspace_root: *const c_char,
+) -> *mut c_char {
+    respond(|| {
+        let name = unsafe { arg(name, "name") }?;
+        let base = crate::paths::expand(unsafe { arg(base, "base") }?);
+        let base = std::path::absolute(&base).unwrap_or(base);
+        let ws_root = crate::paths::expand(unsafe { arg(workspace_root, "workspace_root") }?);
+        let config = crate::workspace::create_workspace(
+            name,
+            &base,
+            &ws_root,
+            crate::workspace::default_sharing_rules(),
+        )?;
+        Ok(serde_json::to_value(config)?)
+    })
+}
+
+/// Launch `game` from the config file at `config_path` without waiting for it.
+/// Returns `{"pid": <pid>}`.
+///
+/// # Safety
+/// Every argument is a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn realmctl_launch(
+    config_path: *const c_char,
+    game: *const c_char,
+) -> *mut c_char {
+    respond(|| {
+        let config_path = unsafe { arg(config_path, "config_path") }?;
+        let game = unsafe { arg(game, "game") }?;
+        let config = crate::load_config(config_path, game)?;
+        let child = crate::spawn(&config)?;
+        Ok(json!({ "pid": child.id() }))
+    })
+}
+
+/// List the workspaces under `workspace_root` and the bases found under `base_roots`, as
+/// `realmctl list --json` does. `base_roots` holds directories separated like PATH, and may be
+/// null.
+///
+/// # Safety
+/// `workspace_root` is a NUL-terminated string; `base_roots` is one or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn realmctl_list(
+    workspace_root: *const c_char,
+    base_roots: *const c_char,
+) -> *mut c_char {
+    respond(|| {
+        let ws_root = crate::paths::expand(unsafe { arg(workspace_root, "workspace_root") }?);
+        let base_roots: Vec<PathBuf> = match unsafe { optional_arg(base_roots, "base_roots") }? {
+            Some(roots) => std::env::split_paths(roots)
+                .map(|root| crate::paths::expand(&root.to_string_lossy()))
+                .collect(),
+            None => Vec::new(),
+        };
+        let listing = crate::status::collect_listing(&ws_root, &base_roots)?;
+        Ok(serde_json::to_value(listing)?)
+    })
+}
+
+/// Status of every workspace under `workspace_root` and every game of the config file at
+/// `config_path`, as `realmctl status --json` does. `config_path` may be null.
+///
+/// # Safety
+/// `workspace_root` is a NUL-terminated string; `config_path` is one or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn realmctl_status(
+    workspace_root: *const c_char,
+    config_path: *const c_char,
+) -> *mut c_char {
+    respond(|| {
+        let ws_root = crate::paths::expand(unsafe { arg(workspace_root, "workspace_root") }?);
+        let games: Vec<(String, PathBuf)> =
+            match unsafe { optional_arg(config_path, "config_path") }? {
+                Some(path) => crate::ConfigFile::read(path)?
+                    .games(&crate::current_hostname())
+                    .into_iter()
+                    .filter_map(|(key, config)| Some((key, config.ok()?.directory)))
+                    .collect(),
+                None => Vec::new(),
+            };
+        let status = crate::status::collect_status(Some(&ws_root), &games)?;
+        Ok(serde_json::to_value(status)?)
+    })
+}
+
+/// Free a string returned by the functions above
+///
+/// # Safety
+/// `s` is null or a string returned by this library that was not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn realmctl_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Call `f` and parse the JSON it returns
+    fn call(f: impl FnOnce() -> *mut c_char) -> Value {
+        let s = f();
+        let value = serde_json::from_str(unsafe { CStr::from_ptr(s) }.to_str().unwrap()).unwrap();
+        unsafe { realmctl_free_string(s) };
+        value
+    }
+
+    #[test]
+    fn test_ffi_envelopes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = CString::new(temp.path().to_str().unwrap()).unwrap();
+
+        let listing = call(|| unsafe { realmctl_list(root.as_ptr(), std::ptr::null()) });
+        assert_eq!(listing["ok"]["workspaces"], json!([]));
+
+        let missing = call(|| unsafe { realmctl_list(std::ptr::null(), std::ptr::null()) });
+        assert_eq!(missing["error"], "workspace_root is null");
+
+        let name = CString::new("alt").unwrap();
+        let base = CString::new(temp.path().join("no-base").to_str().unwrap()).unwrap();
+        let created = call(|| unsafe {
+            realmctl_create_workspace(name.as_ptr(), base.as_ptr(), root.as_ptr())
+        });
+        assert!(created["ok"].is_null());
+        assert!(created["error"].as_str().unwrap().contains("valid base"));
+
+        unsafe { realmctl_free_string(std::ptr::null_mut()) };
+    }
+}
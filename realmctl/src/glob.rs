@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+
+/// Regex body for a single glob segment (`*`, `?`, literals)
+fn segment_regex(segment: &str) -> String {
+    let mut re = String::new();
+    for c in segment.chars() {
+        match c {
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re
+}
+
+/// Convert a glob over `/`-separated relative paths to an anchored regex
+///
+/// `*` and `?` stay within a path segment, `**` spans any number of segments.
+pub fn glob_to_regex(pattern: &str) -> String {
+    let segments: Vec<&str> = pattern.split('/').collect();
+    let mut re = String::from("^");
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        if *segment == "**" {
+            re.push_str(if last { ".*" } else { "(?:.*/)?" });
+        } else {
+            re.push_str(&segment_regex(segment));
+            if !last {
+                re.push('/');
+            }
+        }
+    }
+    re.push('$');
+    re
+}
+
+/// Whether a relative path matches a glob pattern
+pub fn glob_match(pattern: &str, rel_path: &str) -> bool {
+    regex::Regex::new(&glob_to_regex(pattern)).is_ok_and(|re| re.is_match(rel_path))
+}
+
+/// Existing paths under `root` matching a glob pattern
+///
+/// Only directories named by the pattern are read, so a pattern like `*.log` never
+/// walks into subdirectories (or a wine prefix).
+pub fn expand_glob(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let mut current = vec![root.to_path_buf()];
+    for (i, segment) in segments.iter().enumerate() {
+        let mut next = Vec::new();
+        for dir in &current {
+            if *segment == "**" {
+                // A leading or inner `**` also matches zero segments
+                if i + 1 < segments.len() {
+                    next.push(dir.clone());
+                }
+                collect_descendants(dir, &mut next);
+            } else if segment.contains(['*', '?']) {
+                let Ok(re) = regex::Regex::new(&format!("^{}$", segment_regex(segment))) else {
+                    continue;
+                };
+                let Ok(entries) = std::fs::read_dir(dir) else {
+                    continue;
+                };
+                let mut matches: Vec<PathBuf> = entries
+                    .flatten()
+                    .filter(|entry| re.is_match(&entry.file_name().to_string_lossy()))
+                    .map(|entry| entry.path())
+                    .collect();
+                matches.sort();
+                next.extend(matches);
+            } else {
+                let path = dir.join(segment);
+                if path.symlink_metadata().is_ok() {
+                    next.push(path);
+                }
+            }
+        }
+        current = next;
+    }
+    current.retain(|path| path != root);
+    current
+}
+
+fn collect_descendants(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        out.push(path.clone());
+        // Do not follow symlinks, shared directories belong to other workspaces too
+        if path.symlink_metadata().is_ok_and(|m| m.is_dir()) {
+            collect_descendants(&path, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("Data/*/*.wdb", "Data/enUS/creaturecache.wdb"));
+        assert!(!glob_match("Data/*/*.wdb", "Data/creaturecache.wdb"));
+        assert!(glob_match("*.log", "Wow.log"));
+        assert!(!glob_match("*.log", "Logs/Wow.log"));
+        assert!(glob_match("WDB/**", "WDB/enUS/itemcache.wdb"));
+        assert!(glob_match("Cache", "Cache"));
+        assert!(!glob_match("Cache", "Cache/WDB"));
+        assert!(glob_match("**/*.wdb", "Data/enUS/x.wdb"));
+        assert!(glob_match("**/*.wdb", "x.wdb"));
+    }
+
+    #[test]
+    fn test_expand_glob() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("Data/enUS")).unwrap();
+        std::fs::create_dir_all(root.join("Logs")).unwrap();
+        std::fs::write(root.join("Data/enUS/itemcache.wdb"), "").unwrap();
+        std::fs::write(root.join("Data/enUS/realmlist.wtf"), "").unwrap();
+        std::fs::write(root.join("Wow.log"), "").unwrap();
+        std::fs::write(root.join("Logs/nested.log"), "").unwrap();
+
+        assert_eq!(
+            expand_glob(root, "Data/*/*.wdb"),
+            vec![root.join("Data/enUS/itemcache.wdb")]
+        );
+        assert_eq!(expand_glob(root, "*.log"), vec![root.join("Wow.log")]);
+        assert_eq!(expand_glob(root, "Logs"), vec![root.join("Logs")]);
+        assert!(expand_glob(root, "Cache").is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Seconds between starting one client and the next when launching several
+pub const DEFAULT_STAGGER_SECS: u64 = 5;
+
+/// A `[groups.<name>]` section of config.toml: games launched together with `launch --all`
+/// and the resources they share
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GroupConfig {
+    pub games: Vec<String>,
+    /// Seconds between starting one client and the next
+    #[serde(default = "default_stagger_secs")]
+    pub stagger_secs: u64,
+    /// Account for each game, in the order of `games`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<String>,
+    /// Wine prefix all games of the group run in, instead of their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// Addon pack: sources (URLs or archives) installed into every game of the group
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addons: Vec<String>,
+    /// CVars written to every game's Config.wtf before launching
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub cvars: IndexMap<String, String>,
+}
+
+fn default_stagger_secs() -> u64 {
+    DEFAULT_STAGGER_SECS
+}
+
+impl GroupConfig {
+    pub fn new(games: Vec<String>) -> Self {
+        GroupConfig {
+            games,
+            stagger_secs: DEFAULT_STAGGER_SECS,
+            accounts: Vec::new(),
+            prefix: None,
+            addons: Vec::new(),
+            cvars: IndexMap::new(),
+        }
+    }
+
+    /// Expanded path of the group's wine prefix
+    pub fn prefix_path(&self) -> Option<PathBuf> {
+        self.prefix.as_deref().map(crate::paths::expand)
+    }
+
+    pub fn from_value(name: &str, value: toml::Value) -> Result<Self, String> {
+        let group: GroupConfig = value
+            .try_into()
+            .map_err(|e| format!("[groups.{name}] {e}"))?;
+        if group.games.is_empty() {
+            return Err(format!("[groups.{name}] games must not be empty"));
+        }
+        if group.accounts.len() > group.games.len() {
+            return Err(format!("[groups.{name}] has more accounts than games"));
+        }
+        Ok(group)
+    }
+}
+
+/// Read `[groups.<name>]` from a config file, matched case-insensitively
+pub fn load_group(config_path: &str, name: &str) -> Result<GroupConfig, String> {
+    find_group(config_path, name)?
+        .map(|(_, group)| group)
+        .ok_or_else(|| {
+            format!(
+                "No [groups.{name}] section in {}",
+                crate::paths::expand(config_path).display()
+            )
+        })
+}
+
+/// `[groups.<name>]` of a config file with its key as written, if there is one
+pub fn find_group(config_path: &str, name: &str) -> Result<Option<(String, GroupConfig)>, String> {
+    let path = crate::paths::expand(config_path);
+    let source = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let table: toml::Table = toml::from_str(&source).map_err(|e| e.to_string())?;
+    let Some((key, value)) = table
+        .get("groups")
+        .and_then(|groups| groups.as_table())
+        .and_then(|groups| {
+            groups
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        })
+    else {
+        return Ok(None);
+    };
+    Ok(Some((
+        key.clone(),
+        GroupConfig::from_value(key, value.clone())?,
+    )))
+}
+
+/// Components of a `[table.header]` line, without quotes
+fn header_components(line: &str) -> Option<Vec<String>> {
+    let inner = line.trim().strip_prefix('[')?;
+    if inner.starts_with('[') {
+        return None;
+    }
+    let (name, rest) = inner.split_once(']')?;
+    // A comment may follow the header
+    let rest = rest.trim();
+    if !(rest.is_empty() || rest.starts_with('#')) {
+        return None;
+    }
+    Some(
+        name.split('.')
+            .map(|part| part.trim().trim_matches('"').trim_matches('\'').to_string())
+            .collect(),
+    )
+}
+
+/// config.toml source with the `[groups.<name>]` section (and its subtables) replaced by
+/// `group`, or appended when there is none; the rest of the file keeps its formatting
+pub fn upsert_group(source: &str, name: &str, group: &GroupConfig) -> String {
+    let mut groups = toml::Table::new();
+    groups.insert(
+        name.to_string(),
+        toml::Value::try_from(group).expect("group serializes"),
+    );
+    let mut root = toml::Table::new();
+    root.insert("groups".to_string(), toml::Value::Table(groups));
+    let section = toml::to_string(&root).expect("group serializes");
+
+    let in_section = |line: &str| {
+        header_components(line).is_some_and(|parts| {
+            parts.len() >= 2 && parts[0] == "groups" && parts[1].eq_ignore_ascii_case(name)
+        })
+    };
+    let lines: Vec<&str> = source.lines().collect();
+    let Some(start) = lines.iter().position(|line| in_section(line)) else {
+        let mut updated = source.to_string();
+        if !updated.is_empty() && !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        if !updated.is_empty() {
+            updated.push('\n');
+        }
+        updated.push_str(&section);
+        return updated;
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| header_components(line).is_some() && !in_section(line))
+        .map_or(lines.len(), |i| start + 1 + i);
+
+    let mut updated: Vec<&str> = lines[..start].to_vec();
+    updated.extend(section.lines());
+    if end < lines.len() {
+        updated.push("");
+        updated.extend(&lines[end..]);
+    }
+    let mut updated = updated.join("\n");
+    updated.push('\n');
+    updated
+}
+
+/// Give `accounts` to `games` in order; games past the end of the list get none
+pub fn assign_accounts(games: &[String], accounts: &[String]) -> Vec<Option<String>> {
+    (0..games.len()).map(|i| accounts.get(i).cloned()).collect()
+}
+
+/// Paths used by more than one of the games, with the games using each
+///
+/// Clients sharing a wine prefix share its wineserver, and two clients in one directory
+/// overwrite each other's WTF and Cache.
+pub fn shared_paths(paths: &[(String, PathBuf)]) -> Vec<(PathBuf, Vec<String>)> {
+    let mut users: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for (game, path) in paths {
+        users
+            .entry(crate::paths::normalize(path))
+            .or_default()
+            .push(game.clone());
+    }
+    users
+        .into_iter()
+        .filter(|(_, games)| games.len() > 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_accounts_and_shared_paths() {
+        let value: toml::Value = toml::from_str(
+            "games = [\"Main\", \"Alt1\", \"Alt2\"]\naccounts = [\"tank\", \"healer\"]",
+        )
+        .unwrap();
+        let group = GroupConfig::from_value("raid", value).unwrap();
+        assert_eq!(group.stagger_secs, DEFAULT_STAGGER_SECS);
+        assert_eq!(
+            assign_accounts(&group.games, &group.accounts),
+            vec![Some("tank".to_string()), Some("healer".to_string()), None]
+        );
+        let too_many: toml::Value =
+            toml::from_str("games = [\"Main\"]\naccounts = [\"a\", \"b\"]").unwrap();
+        assert!(GroupConfig::from_value("raid", too_many).is_err());
+
+        let shared = shared_paths(&[
+            ("Main".to_string(), PathBuf::from("/wow/main/.wine")),
+            ("Alt1".to_string(), PathBuf::from("/wow/pfx")),
+            ("Alt2".to_string(), PathBuf::from("/wow/./pfx")),
+        ]);
+        assert_eq!(
+            shared,
+            vec![(
+                PathBuf::from("/wow/pfx"),
+                vec!["Alt1".to_string(), "Alt2".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_upsert_group_keeps_the_rest_of_the_file() {
+        let source = "# my games\n[Main]\ndirectory = \"/wow/main\"\n\n[groups.raid]\ngames = [\"Main\"]\n\n[groups.raid.cvars]\nmaxfps = \"30\"\n\n[Alt1] # second box\ndirectory = \"/wow/alt1\"\n";
+        let mut group = GroupConfig::new(vec!["Main".to_string(), "Alt1".to_string()]);
+        group.prefix = Some("~/wow/pfx".to_string());
+        group.cvars.insert("gxWindow".to_string(), "1".to_string());
+        let updated = upsert_group(source, "Raid", &group);
+        assert!(updated.starts_with("# my games\n[Main]\n"));
+        assert!(updated.ends_with("\n[Alt1] # second box\ndirectory = \"/wow/alt1\"\n"));
+        assert!(!updated.contains("maxfps"));
+
+        let table: toml::Table = toml::from_str(&updated).unwrap();
+        let value = table["groups"]["Raid"].clone();
+        assert_eq!(GroupConfig::from_value("Raid", value).unwrap(), group);
+
+        let appended = upsert_group("[Main]\ndirectory = \"/wow\"", "dungeon", &group);
+        assert!(appended.starts_with("[Main]\ndirectory = \"/wow\"\n\n[groups.dungeon]\n"));
+    }
+}
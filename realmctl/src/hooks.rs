@@ -0,0 +1,309 @@
+use serde::Deserialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Hook names accepted in the `[hooks]` section
+pub const HOOK_EVENTS: &[&str] = &["on_create", "on_launch", "on_exit", "on_clean", "on_fix"];
+
+/// One command or a list of commands to run for an event
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum HookCommands {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl HookCommands {
+    fn commands(&self) -> &[String] {
+        match self {
+            HookCommands::One(command) => std::slice::from_ref(command),
+            HookCommands::Many(commands) => commands,
+        }
+    }
+}
+
+/// User scripts run after realmctl events, from the `[hooks]` section of config.toml
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Hooks {
+    pub on_create: Option<HookCommands>,
+    pub on_launch: Option<HookCommands>,
+    /// After a client started with `launch --wait` exited
+    pub on_exit: Option<HookCommands>,
+    pub on_clean: Option<HookCommands>,
+    pub on_fix: Option<HookCommands>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    Create,
+    Launch,
+    Exit,
+    Clean,
+    Fix,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::Create => "create",
+            HookEvent::Launch => "launch",
+            HookEvent::Exit => "exit",
+            HookEvent::Clean => "clean",
+            HookEvent::Fix => "fix",
+        }
+    }
+}
+
+impl Hooks {
+    fn commands(&self, event: HookEvent) -> &[String] {
+        let hook = match event {
+            HookEvent::Create => &self.on_create,
+            HookEvent::Launch => &self.on_launch,
+            HookEvent::Exit => &self.on_exit,
+            HookEvent::Clean => &self.on_clean,
+            HookEvent::Fix => &self.on_fix,
+        };
+        hook.as_ref().map_or(&[], HookCommands::commands)
+    }
+}
+
+/// Read the `[hooks]` section of a config file
+///
+/// A missing config file or section means no hooks; an invalid section is reported and ignored
+/// so a typo in a hook never blocks launching.
+pub fn load_hooks(config_path: &str) -> Hooks {
+    let path = crate::paths::expand(config_path);
+    let Ok(source) = std::fs::read_to_string(path) else {
+        return Hooks::default();
+    };
+    let Some(hooks) = toml::from_str::<toml::Table>(&source)
+        .ok()
+        .and_then(|mut table| table.remove("hooks"))
+    else {
+        return Hooks::default();
+    };
+    hooks.try_into().unwrap_or_else(|e| {
+        tracing::warn!("⚠ Ignoring invalid [hooks] section: {e}");
+        Hooks::default()
+    })
+}
+
+/// Variables substituted in hook commands and `launch_cmd`, with the payload field each
+/// is taken from
+pub const TEMPLATE_VARIABLES: &[(&str, &str)] = &[
+    ("account", "account"),
+    ("realm", "realmlist"),
+    ("workspace", "game"),
+    ("directory", "directory"),
+];
+
+/// Replace `{account}`, `{realm}`, `{workspace}` and `{directory}` in a command
+///
+/// Values are quoted for the shell running the command, so spaces or quotes in them can't
+/// change what runs; missing values become an empty argument. Other braces are kept as is.
+pub fn expand_template(command: &str, values: &serde_json::Value) -> String {
+    let mut expanded = command.to_string();
+    for (name, field) in TEMPLATE_VARIABLES {
+        let placeholder = format!("{{{name}}}");
+        if !expanded.contains(&placeholder) {
+            continue;
+        }
+        let value = match &values[field] {
+            serde_json::Value::String(value) => value.clone(),
+            serde_json::Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        expanded = expanded.replace(&placeholder, &shell_quote(&value));
+    }
+    expanded
+}
+
+/// Quote a value as one argument for `sh -c`, or `cmd /C` on Windows
+pub(crate) fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Event payload passed to hooks on stdin
+pub fn payload(event: HookEvent, mut fields: serde_json::Value) -> serde_json::Value {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    if let Some(object) = fields.as_object_mut() {
+        object.insert("event".to_string(), event.name().into());
+        object.insert("timestamp".to_string(), timestamp.into());
+    }
+    fields
+}
+
+/// Run the hooks of an event, writing the JSON payload to each command's stdin
+///
+/// Hooks run one after another and their failures are only reported, never fatal.
+pub fn run_hooks(hooks: &Hooks, event: HookEvent, fields: serde_json::Value) {
+    let commands = hooks.commands(event);
+    if commands.is_empty() {
+        return;
+    }
+    let payload = payload(event, fields);
+    let values = payload.clone();
+    let payload = payload.to_string();
+
+    for command in commands {
+        let command = expand_template(&shellexpand::tilde(command), &values);
+        tracing::info!("Running on_{} hook:\n\t{command}", event.name());
+        if let Err(e) = run_hook(&command, event, &payload) {
+            tracing::warn!("⚠ Hook failed: {e}");
+        }
+    }
+}
+
+/// `sh -c <command>`, or `cmd /C <command>` on Windows
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command).stdout(crate::output::child_stdout());
+    shell
+}
+
+/// Run a game's `pre_launch` or `post_exit` commands in its directory, with `vars` in
+/// their environment
+///
+/// Commands run one after another and stop at the first one that fails.
+pub fn run_game_commands(
+    name: &str,
+    commands: &[String],
+    dir: &std::path::Path,
+    vars: &[(&str, String)],
+    values: &serde_json::Value,
+) -> std::io::Result<()> {
+    for command in commands {
+        let command = expand_template(&shellexpand::tilde(command), values);
+        tracing::info!("Running {name}:\n\t{command}");
+        let status = shell(&command)
+            .current_dir(dir)
+            .envs(vars.iter().map(|(key, value)| (key, value)))
+            .status()?;
+        if !status.success() {
+            return Err(std::io::Error::other(format!(
+                "{command} exited with {status}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn run_hook(command: &str, event: HookEvent, payload: &str) -> std::io::Result<()> {
+    let mut child = shell(command)
+        .env("REALMCTL_EVENT", event.name())
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores its input closes the pipe early, that's fine
+        let _ = stdin.write_all(payload.as_bytes());
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!(
+            "{command} exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_accept_one_or_many_commands() {
+        let table: toml::Table = toml::from_str(
+            r#"
+on_launch = "obs-cli scene switch WoW"
+on_clean = ["backup.sh", "notify-send cleaned"]
+"#,
+        )
+        .unwrap();
+        let hooks: Hooks = toml::Value::Table(table).try_into().unwrap();
+        assert_eq!(
+            hooks.commands(HookEvent::Launch),
+            ["obs-cli scene switch WoW"]
+        );
+        assert_eq!(hooks.commands(HookEvent::Clean).len(), 2);
+        assert!(hooks.commands(HookEvent::Fix).is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_expand_template_quotes_values() {
+        let values = serde_json::json!({
+            "game": "Chromie",
+            "directory": "/games/it's here",
+            "realmlist": "logon.example.org; rm -rf ~",
+        });
+        assert_eq!(
+            expand_template(
+                "login-helper {workspace} {directory} {realm} {account} {other}",
+                &values
+            ),
+            r"login-helper 'Chromie' '/games/it'\''s here' 'logon.example.org; rm -rf ~' '' {other}"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_game_commands_run_in_directory_and_stop_on_failure() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let commands = vec![
+            "echo \"$WORKSPACE\" > out.txt".to_string(),
+            "false".to_string(),
+            "touch never".to_string(),
+        ];
+        let vars = [("WORKSPACE", "Chromie".to_string())];
+        let result = run_game_commands(
+            "pre_launch",
+            &commands,
+            temp.path(),
+            &vars,
+            &serde_json::json!({}),
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            std::fs::read_to_string(temp.path().join("out.txt")).unwrap(),
+            "Chromie\n"
+        );
+        assert!(!temp.path().join("never").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_hook_receives_payload_on_stdin() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let out = temp.path().join("payload.json");
+        let hooks = Hooks {
+            on_fix: Some(HookCommands::One(format!("cat > '{}'", out.display()))),
+            ..Default::default()
+        };
+
+        run_hooks(
+            &hooks,
+            HookEvent::Fix,
+            serde_json::json!({ "game": "Chromie" }),
+        );
+
+        let payload: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out).unwrap()).unwrap();
+        assert_eq!(payload["event"], "fix");
+        assert_eq!(payload["game"], "Chromie");
+    }
+}
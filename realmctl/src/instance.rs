@@ -0,0 +1,101 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Lock file in a game directory while a client started by realmctl runs from it
+pub const LAUNCH_LOCK: &str = "realmctl-launch.lock";
+
+/// Claim on a game directory by one launch
+///
+/// Two clients in one directory overwrite each other's WTF and Cache. The lock holds the
+/// PID of the client, so it outlives realmctl for launches that don't wait for the client
+/// and is recognized as stale once that process is gone.
+#[derive(Debug)]
+pub struct LaunchLock {
+    path: PathBuf,
+}
+
+impl LaunchLock {
+    /// Record the PID of the started client
+    pub fn set_pid(&self, pid: u32) -> std::io::Result<()> {
+        std::fs::write(&self.path, pid.to_string())
+    }
+
+    /// Remove the lock once the client has exited
+    pub fn release(self) -> std::io::Result<()> {
+        std::fs::remove_file(&self.path)
+    }
+}
+
+/// PID recorded in the lock of `dir`, if a client started from it still runs
+pub fn holder(dir: &Path) -> Option<u32> {
+    let pid: u32 = std::fs::read_to_string(dir.join(LAUNCH_LOCK))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    // The recorded process may be a launcher that exited while the client it started runs
+    (crate::process::is_running(pid) || !crate::process::client_processes(dir, &[]).is_empty())
+        .then_some(pid)
+}
+
+/// Lock a game directory for a launch
+///
+/// Fails with [`std::io::ErrorKind::AlreadyExists`] while a client started from it runs,
+/// unless `force` is set. Stale locks are taken over.
+pub fn acquire(dir: &Path, force: bool) -> std::io::Result<LaunchLock> {
+    let path = dir.join(LAUNCH_LOCK);
+    loop {
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                // Until the client is started the lock belongs to this realmctl
+                write!(file, "{}", std::process::id())?;
+                return Ok(LaunchLock { path });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                if let Some(pid) = holder(dir).filter(|_| !force) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::AlreadyExists,
+                        format!(
+                            "a client started from {} is running (PID {pid})",
+                            dir.display()
+                        ),
+                    ));
+                }
+                match std::fs::remove_file(&path) {
+                    Ok(()) => {}
+                    // Another launch took over the stale lock first, look at its lock
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_lock() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let lock = acquire(dir, false).unwrap();
+        assert_eq!(holder(dir), Some(std::process::id()));
+        let err = acquire(dir, false).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+        // A lock of an exited client is stale
+        lock.set_pid(u32::MAX).unwrap();
+        assert_eq!(holder(dir), None);
+        let lock = acquire(dir, false).unwrap();
+        assert!(acquire(dir, true).is_ok());
+        lock.release().unwrap();
+        assert!(!dir.join(LAUNCH_LOCK).exists());
+    }
+}
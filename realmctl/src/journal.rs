@@ -0,0 +1,287 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Journal of a workspace, one tab-separated `<timestamp> <operation> <action> <path> <reason>`
+/// line per structural change made by `create` and `fix`, symlinks adding their target
+pub const JOURNAL_FILE: &str = "workspace.log";
+
+/// What was done to a workspace path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JournalAction {
+    /// Hard link to the base file
+    Hardlink,
+    /// Hard link to the base file, copied once changed (see `lazy_files`)
+    Lazy,
+    /// Symlink to a shared directory or, when hard linking failed, to the base file
+    Symlink,
+    /// Private copy of the base file
+    Copy,
+    /// Private copy-on-write clone of the base file, sharing its blocks until written
+    Reflink,
+    /// Empty directory
+    Mkdir,
+}
+
+impl JournalAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JournalAction::Hardlink => "hardlink",
+            JournalAction::Lazy => "lazy",
+            JournalAction::Symlink => "symlink",
+            JournalAction::Copy => "copy",
+            JournalAction::Reflink => "reflink",
+            JournalAction::Mkdir => "mkdir",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "hardlink" => JournalAction::Hardlink,
+            "lazy" => JournalAction::Lazy,
+            "symlink" => JournalAction::Symlink,
+            "copy" => JournalAction::Copy,
+            "reflink" => JournalAction::Reflink,
+            "mkdir" => JournalAction::Mkdir,
+            _ => return None,
+        })
+    }
+}
+
+/// A recorded change
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    /// `create`, `fix` or `materialize`
+    pub operation: String,
+    pub action: JournalAction,
+    /// Path relative to the workspace
+    pub rel_path: String,
+    /// Why this action was chosen
+    pub reason: String,
+    /// Target of a symlink
+    pub target: Option<PathBuf>,
+}
+
+/// Changes collected during one operation, written with [`Journal::append`]
+#[derive(Debug, Clone)]
+pub struct Journal {
+    operation: &'static str,
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn new(operation: &'static str) -> Self {
+        Journal {
+            operation,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, action: JournalAction, rel_path: &str, reason: impl Into<String>) {
+        self.push(action, rel_path, reason.into(), None);
+    }
+
+    pub fn record_symlink(&mut self, rel_path: &str, target: &Path, reason: impl Into<String>) {
+        self.push(
+            JournalAction::Symlink,
+            rel_path,
+            reason.into(),
+            Some(target.to_path_buf()),
+        );
+    }
+
+    fn push(
+        &mut self,
+        action: JournalAction,
+        rel_path: &str,
+        reason: String,
+        target: Option<PathBuf>,
+    ) {
+        // Tabs and newlines would split the line
+        let clean = |value: &str| value.replace(['\t', '\n', '\r'], " ");
+        tracing::debug!("{}: {action:?} {rel_path} ({reason})", self.operation);
+        self.entries.push(JournalEntry {
+            at: crate::state::now(),
+            operation: self.operation.to_string(),
+            action,
+            rel_path: clean(rel_path),
+            reason: clean(&reason),
+            target,
+        });
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Append the collected changes to the journal of a workspace
+    pub fn append(&self, workspace_path: &Path) -> std::io::Result<()> {
+        use std::io::Write;
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for entry in &self.entries {
+            lines.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}",
+                entry.at,
+                entry.operation,
+                entry.action.as_str(),
+                entry.rel_path,
+                entry.reason
+            ));
+            if let Some(target) = &entry.target {
+                lines.push_str(&format!("\t{}", target.display()));
+            }
+            lines.push('\n');
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(workspace_path.join(JOURNAL_FILE))?
+            .write_all(lines.as_bytes())
+    }
+}
+
+/// Recorded changes of a workspace, oldest first
+///
+/// Unreadable lines are skipped, a missing journal is empty (workspaces created before
+/// it was kept).
+pub fn load_journal(workspace_path: &Path) -> std::io::Result<Vec<JournalEntry>> {
+    let content = match std::fs::read_to_string(workspace_path.join(JOURNAL_FILE)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(6, '\t');
+            Some(JournalEntry {
+                at: fields.next()?.parse().ok()?,
+                operation: fields.next()?.to_string(),
+                action: JournalAction::parse(fields.next()?)?,
+                rel_path: fields.next()?.to_string(),
+                reason: fields.next()?.to_string(),
+                target: fields.next().map(PathBuf::from),
+            })
+        })
+        .collect())
+}
+
+/// A journaled path that no longer looks the way it was made
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditFinding {
+    pub rel_path: String,
+    /// Last recorded change of the path
+    pub entry: JournalEntry,
+    pub problem: String,
+}
+
+/// Replay the journal of a workspace against the disk
+///
+/// Only the last entry of each path counts. Lazily linked files that were copied since
+/// are expected to differ and are not reported.
+pub fn audit_workspace(workspace_path: &Path) -> Result<Vec<AuditFinding>> {
+    let config = crate::workspace::load_workspace_config(workspace_path)?;
+    let mut latest: BTreeMap<String, JournalEntry> = BTreeMap::new();
+    for entry in load_journal(workspace_path)? {
+        latest.insert(entry.rel_path.clone(), entry);
+    }
+
+    let mut findings = Vec::new();
+    for (rel_path, entry) in latest {
+        let path = workspace_path.join(&rel_path);
+        let base_file = config.base_path.join(&rel_path);
+        let meta = std::fs::symlink_metadata(&path).ok();
+        let problem = match (entry.action, &meta) {
+            (_, None) => Some("missing".to_string()),
+            (JournalAction::Symlink, Some(meta)) if !meta.file_type().is_symlink() => {
+                Some("no longer a symlink, a real file or directory replaced it".to_string())
+            }
+            (JournalAction::Symlink, Some(_)) => {
+                let target = std::fs::read_link(&path).unwrap_or_default();
+                if entry
+                    .target
+                    .as_ref()
+                    .is_some_and(|expected| *expected != target)
+                {
+                    Some(format!("points to {} instead", target.display()))
+                } else if !path.exists() {
+                    Some("target is missing".to_string())
+                } else {
+                    None
+                }
+            }
+            (_, Some(meta)) if meta.file_type().is_symlink() => {
+                Some("replaced by a symlink".to_string())
+            }
+            (JournalAction::Hardlink, Some(_))
+                if !crate::workspace::same_file(&path, &base_file) =>
+            {
+                Some(
+                    "no longer shared with the base, it was replaced or the base changed"
+                        .to_string(),
+                )
+            }
+            (JournalAction::Lazy, Some(_))
+                if !crate::workspace::same_file(&path, &base_file)
+                    && config.lazy_files.contains_key(&rel_path) =>
+            {
+                Some("no longer shared with the base".to_string())
+            }
+            (JournalAction::Copy | JournalAction::Reflink, Some(_))
+                if crate::workspace::same_file(&path, &base_file) =>
+            {
+                Some("hard-linked to the base, changes would reach the base".to_string())
+            }
+            (JournalAction::Mkdir, Some(meta)) if !meta.is_dir() => {
+                Some("not a directory".to_string())
+            }
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            findings.push(AuditFinding {
+                rel_path,
+                entry,
+                problem,
+            });
+        }
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_roundtrip() {
+        let temp = tempfile::TempDir::new().unwrap();
+        assert!(load_journal(temp.path()).unwrap().is_empty());
+
+        let mut journal = Journal::new("create");
+        journal.record(JournalAction::Copy, "Wow.exe", "hard link failed:\tdenied");
+        journal.record_symlink(
+            "Screenshots",
+            Path::new("/shared/Screenshots"),
+            "sharing: global",
+        );
+        journal.append(temp.path()).unwrap();
+        Journal::new("fix").append(temp.path()).unwrap();
+
+        let entries = load_journal(temp.path()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, "create");
+        assert_eq!(entries[0].action, JournalAction::Copy);
+        assert_eq!(entries[0].reason, "hard link failed: denied");
+        assert_eq!(entries[0].target, None);
+        assert_eq!(entries[1].rel_path, "Screenshots");
+        assert_eq!(
+            entries[1].target,
+            Some(PathBuf::from("/shared/Screenshots"))
+        );
+    }
+}
@@ -1,11 +1,96 @@
+pub mod addons;
 pub mod base;
+pub mod cancel;
+pub mod case;
 pub mod cli;
+pub mod clipboard;
+pub mod compat;
+pub mod config;
+pub mod crash;
+pub mod defaults;
+pub mod diff;
+pub mod fetch;
+pub mod glob;
+pub mod group;
+pub mod hooks;
+pub mod instance;
+pub mod locale;
+pub mod logging;
+pub mod maintenance;
+pub mod news;
+pub mod output;
+pub mod paths;
+pub mod prefix;
+pub mod process;
+pub mod progress;
+pub mod realm;
+pub mod screen;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod secrets;
+pub mod settings;
+pub mod state;
+pub mod torrent;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod watchdog;
+pub mod wtf;
 
+#[cfg(feature = "workspaces")]
+pub mod backup;
+#[cfg(feature = "workspaces")]
+pub mod bench;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "workspaces")]
+pub mod journal;
+#[cfg(feature = "workspaces")]
+pub mod media;
+#[cfg(feature = "workspaces")]
+pub mod metrics;
+#[cfg(feature = "workspaces")]
+pub mod migrate;
+#[cfg(feature = "workspaces")]
+pub mod push;
+#[cfg(feature = "workspaces")]
+pub mod reflink;
+#[cfg(feature = "workspaces")]
+pub mod retention;
+#[cfg(feature = "workspaces")]
+pub mod status;
 #[cfg(feature = "workspaces")]
 pub mod workspace;
 
+use indexmap::IndexMap;
 use serde::Deserialize;
-use std::collections::HashMap;
+
+/// Client output of a `launch --wait` session, in the game directory
+pub const SESSION_LOG: &str = "realmctl-session.log";
+
+/// Compatibility layer running the client on Linux when no `launch_cmd` is set
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Runner {
+    /// `wine` with the prefix in `<directory>/.wine`
+    #[default]
+    Wine,
+    /// A Proton build's `proton run`, see `proton_path`
+    Proton,
+    /// `umu-run` from umu-launcher
+    Umu,
+}
+
+/// The `gamescope` table of a game: run the client inside a gamescope session on Linux
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Gamescope {
+    /// Output width in pixels (`-W`)
+    pub width: Option<u32>,
+    /// Output height in pixels (`-H`)
+    pub height: Option<u32>,
+    /// Start fullscreen (`-f`)
+    pub fullscreen: Option<bool>,
+}
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
@@ -13,100 +98,633 @@ pub struct Config {
     #[serde(default = "default_executable")]
     pub executable: String,
     pub launch_cmd: Option<String>,
+    /// How to start the client on Linux when `launch_cmd` is unset (default: wine)
+    pub runner: Option<Runner>,
+    /// Proton installation (the directory holding the `proton` script); required by the
+    /// proton runner, optional `PROTONPATH` for umu
+    pub proton_path: Option<String>,
+    /// Prefix directory for proton (`STEAM_COMPAT_DATA_PATH`) and umu (`WINEPREFIX`),
+    /// default `<directory>/.proton`
+    pub compat_data_path: Option<String>,
+    /// Wrap the Linux launch command in gamescope
+    pub gamescope: Option<Gamescope>,
+    /// Wrap the Linux launch command in `gamemoderun` from Feral GameMode
+    pub gamemode: Option<bool>,
+    /// Environment variables set for the client, e.g. `DXVK_HUD` or `WINEDLLOVERRIDES`
+    #[serde(default)]
+    pub env: IndexMap<String, String>,
     pub realmlist: Option<String>,
-    pub realmlist_rel_path: Option<String>,
+    /// Realmlist file relative to `directory`, detected from the locale if unset
+    pub realmlist_path: Option<String>,
     pub account: Option<String>,
     pub password: Option<String>,
-    pub accounts: Option<HashMap<String, String>>,
+    pub accounts: Option<IndexMap<String, String>>,
     pub arguments: Option<String>,
     pub clear_cache: Option<bool>,
+    /// Prefill the login screen through WTF/Config.wtf with the selected account
+    pub auto_login: Option<bool>,
+    /// Realm selected on login when `auto_login` is on (Config.wtf `realmName`)
+    pub realm_name: Option<String>,
+    /// Character selected on login when `auto_login` is on, counting from 0
+    pub character_index: Option<u32>,
+    /// Copy the selected account's password to the clipboard instead of printing it
+    pub copy_password: Option<bool>,
+    /// Seconds until a copied password is cleared from the clipboard, 0 to keep it
+    /// (default: [`clipboard::DEFAULT_CLEAR_SECS`])
+    pub clipboard_clear_secs: Option<u64>,
+    /// Keep passwords typed at the prompt in the OS keyring, so the next launches don't ask
+    pub cache_password: Option<bool>,
+    /// Seconds a cached password is used before prompting again
+    /// (default: [`secrets::DEFAULT_PASSWORD_CACHE_SECS`])
+    pub password_cache_secs: Option<u64>,
+    /// Clear WDB caches when launching against another realm than last time (default: true)
+    pub clear_wdb_on_realm_change: Option<bool>,
+    /// Free-form labels used to select several games at once
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Server news page or RSS/Atom feed shown before launching
+    pub news_url: Option<String>,
+    /// Recurring server downtime, see [`maintenance::MaintenanceWindow`]
+    #[serde(default)]
+    pub maintenance: Vec<String>,
+    /// Commands run in the game directory before launching; one failing cancels the launch
+    #[serde(default)]
+    pub pre_launch: Vec<String>,
+    /// Commands run in the game directory after the client exited (launch waits for it)
+    #[serde(default)]
+    pub post_exit: Vec<String>,
+}
+
+impl Config {
+    /// Realmlist file of this game: `realmlist_path` if set, else detected from the locale
+    pub fn realmlist_path(&self) -> Option<std::path::PathBuf> {
+        match &self.realmlist_path {
+            Some(rel_path) => Some(self.directory.join(rel_path)),
+            None => locale::find_realmlist(&self.directory),
+        }
+    }
+
+    /// Prefix directory of proton and umu, see `compat_data_path`
+    fn compat_data(&self) -> std::path::PathBuf {
+        self.compat_data_path.as_deref().map_or_else(
+            || self.directory.join(".proton"),
+            |path| std::path::PathBuf::from(shellexpand::tilde(path).to_string()),
+        )
+    }
+
+    /// `WINEPREFIX` from `env`, also set by a group's `prefix`
+    fn env_prefix(&self) -> Option<std::path::PathBuf> {
+        self.env
+            .get("WINEPREFIX")
+            .map(|prefix| std::path::PathBuf::from(shellexpand::tilde(prefix).to_string()))
+    }
+
+    /// Prefix directory [`Config::runner_command`] hands to the runner: `WINEPREFIX` from
+    /// `env`, else `<directory>/.wine` for wine and the compat data for proton and umu
+    fn runner_prefix(&self) -> std::path::PathBuf {
+        self.env_prefix()
+            .unwrap_or_else(|| match self.runner.unwrap_or_default() {
+                Runner::Wine => self.directory.join(".wine"),
+                Runner::Proton | Runner::Umu => self.compat_data(),
+            })
+    }
+
+    /// Wine prefix the client runs in on Linux, the one [`Config::runner_command`] uses
+    ///
+    /// For a custom `launch_cmd` only `WINEPREFIX` from `env` is known.
+    pub fn wine_prefix(&self) -> Option<std::path::PathBuf> {
+        if self.launch_cmd.is_some() {
+            return self.env_prefix();
+        }
+        let prefix = self.runner_prefix();
+        Some(match self.runner.unwrap_or_default() {
+            // Proton keeps the wine prefix in pfx below its compat data
+            Runner::Proton => prefix.join("pfx"),
+            Runner::Wine | Runner::Umu => prefix,
+        })
+    }
+
+    /// Shell command starting the client with the configured runner, for Linux
+    pub fn runner_command(&self) -> std::io::Result<String> {
+        let executable_path = self.directory.join(&self.executable);
+        let executable = executable_path.to_string_lossy();
+        let expand = |path: &str| shellexpand::tilde(path).to_string();
+        let prefix = self.runner_prefix().to_string_lossy().to_string();
+        Ok(match self.runner.unwrap_or_default() {
+            Runner::Wine => format!("WINEPREFIX=\"{prefix}\" wine \"{executable}\""),
+            Runner::Proton => {
+                let proton = self.proton_path.as_deref().map(expand).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "runner = \"proton\" needs proton_path, the directory of a Proton build",
+                    )
+                })?;
+                let steam = expand("~/.steam/steam");
+                format!(
+                    "STEAM_COMPAT_DATA_PATH=\"{prefix}\" STEAM_COMPAT_CLIENT_INSTALL_PATH=\"{steam}\" \"{proton}/proton\" run \"{executable}\""
+                )
+            }
+            Runner::Umu => {
+                let proton = self
+                    .proton_path
+                    .as_deref()
+                    .map(|path| format!("PROTONPATH=\"{}\" ", expand(path)))
+                    .unwrap_or_default();
+                format!(
+                    "WINEPREFIX=\"{prefix}\" GAMEID=umu-default {proton}umu-run \"{executable}\""
+                )
+            }
+        })
+    }
+
+    /// Wrap a Linux launch command in gamescope and/or gamemode as configured
+    ///
+    /// The command runs through `sh -c` inside the wrappers, so environment assignments
+    /// of runners and hand-written `launch_cmd`s keep working.
+    pub fn wrap_command(&self, command: String) -> String {
+        let gamemode = self.gamemode == Some(true);
+        if self.gamescope.is_none() && !gamemode {
+            return command;
+        }
+        let mut wrapped = Vec::new();
+        if gamemode {
+            wrapped.push("gamemoderun".to_string());
+        }
+        if let Some(gamescope) = &self.gamescope {
+            wrapped.push("gamescope".to_string());
+            if let Some(width) = gamescope.width {
+                wrapped.push(format!("-W {width}"));
+            }
+            if let Some(height) = gamescope.height {
+                wrapped.push(format!("-H {height}"));
+            }
+            if gamescope.fullscreen == Some(true) {
+                wrapped.push("-f".to_string());
+            }
+            wrapped.push("--".to_string());
+        }
+        wrapped.push(format!("sh -c {}", hooks::shell_quote(&command)));
+        wrapped.join(" ")
+    }
+
+    /// Names of the configured accounts, `account` first, then `accounts` in file order
+    pub fn account_names(&self) -> Vec<&str> {
+        self.account
+            .iter()
+            .map(String::as_str)
+            .chain(
+                self.accounts
+                    .iter()
+                    .flat_map(|accounts| accounts.keys().map(String::as_str)),
+            )
+            .collect()
+    }
+
+    /// Keep only the named account, so launching uses just its credentials
+    pub fn select_account(&mut self, name: &str) -> std::io::Result<()> {
+        if self.account.as_deref() == Some(name) {
+            self.accounts = None;
+            return Ok(());
+        }
+        let password = self
+            .accounts
+            .as_ref()
+            .and_then(|accounts| accounts.get(name))
+            .cloned()
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!(
+                        "No account '{name}' configured, expected one of: {}",
+                        self.account_names().join(", ")
+                    ),
+                )
+            })?;
+        self.account = Some(name.to_string());
+        self.password = Some(password);
+        self.accounts = None;
+        Ok(())
+    }
 }
 
 fn default_executable() -> String {
     "Wow.exe".to_string()
 }
 
-/// Load the whole config file (TOML)
-pub fn load_config(path_str: &str, game: &str) -> std::io::Result<Config> {
-    let config_path = shellexpand::tilde(path_str).to_string();
-    let config_path = std::path::PathBuf::from(config_path);
+/// Top-level config keys that are not game entries
+pub const RESERVED_KEYS: &[&str] = &[
+    "backup",
+    "defaults",
+    "groups",
+    "host",
+    "hooks",
+    "realms",
+    "retention",
+    "watchdog",
+];
 
-    let s = std::fs::read_to_string(config_path).map_err(|_| {
-        std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            format!("Config file not found: {path_str}"),
-        )
-    })?;
+/// Hostname used to select `[host.<name>]` override sections
+///
+/// `REALMCTL_HOST` takes precedence over the system hostname.
+pub fn current_hostname() -> String {
+    std::env::var("REALMCTL_HOST")
+        .ok()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| gethostname::gethostname().to_string_lossy().to_string())
+}
 
-    let configs: std::collections::HashMap<String, Config> = toml::from_str(&s).map_err(|_| {
-        std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
-            "Failed to parse config file",
-        )
-    })?;
+/// A parsed config file; game entries keep the order they have in the file
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: std::path::PathBuf,
+    pub source: String,
+    table: toml::Table,
+    secrets: Option<toml::Table>,
+}
 
-    let config = configs
-        .iter()
-        .find(|(key, _)| key.eq_ignore_ascii_case(game))
-        .map(|(_, value)| value)
-        .ok_or_else(|| {
+impl ConfigFile {
+    /// Read and parse a config file, decrypting accounts.enc if present
+    pub fn read(path_str: &str) -> std::io::Result<Self> {
+        let path = paths::expand(path_str);
+
+        let source = std::fs::read_to_string(&path).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Config file not found: {path_str}"),
+            )
+        })?;
+
+        let table: toml::Table = toml::from_str(&source).map_err(|e| {
             std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to parse config file {}: {e}", path.display()),
+            )
+        })?;
+
+        // Merge credentials from the encrypted companion file, if present
+        let secrets = secrets::load_secrets(&path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{e:#}")))?;
+
+        Ok(ConfigFile {
+            path,
+            source,
+            table,
+            secrets,
+        })
+    }
+
+    /// Game keys in file order
+    pub fn game_keys(&self) -> Vec<&str> {
+        self.table
+            .keys()
+            .filter(|key| !RESERVED_KEYS.contains(&key.as_str()))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Resolve a game key case-insensitively, failing if several keys match
+    pub fn find_game(&self, game: &str) -> std::io::Result<&str> {
+        match config::matching_keys(&self.table, game).as_slice() {
+            [] => Err(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
                 format!("Config with key '{game}' not found (case-insensitive)"),
+            )),
+            [key] => Ok(key),
+            keys => {
+                let locations: Vec<String> = keys
+                    .iter()
+                    .map(|key| config::describe_key(&self.source, key))
+                    .collect();
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "Config key '{game}' is ambiguous: {} differ only in case (run `realmctl config lint`)",
+                        locations.join(" and ")
+                    ),
+                ))
+            }
+        }
+    }
+
+    /// Build the config for a game, applying `[host.<hostname>.<game>]` overrides and secrets
+    pub fn game(&self, game: &str, hostname: &str) -> std::io::Result<Config> {
+        let key = self.find_game(game)?;
+
+        let mut value = self.table[key].clone();
+        if let Some(entry) = value.as_table_mut() {
+            compat::warn_once(&compat::upgrade_entry(key, entry));
+        }
+        if let Some(toml::Value::Table(hosts)) = self.table.get("host") {
+            apply_host_overrides(&mut value, hosts, key, hostname);
+        }
+        if let Some(secrets) = &self.secrets {
+            secrets::apply_secrets(&mut value, secrets, key);
+        }
+
+        let mut config: Config = value.try_into().map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to parse config for '{key}': {e}"),
             )
         })?;
 
-    let mut config = config.clone();
+        // Expand ~ in the directory path, relative ones are inside the portable root
+        // $HOME, $USER are NOT expanded
+        config.directory = paths::resolve_in_root(std::path::PathBuf::from(
+            shellexpand::tilde(&config.directory.to_string_lossy()).to_string(),
+        ));
+
+        // A workspace carries the realm and account it is meant for, config.toml wins
+        #[cfg(feature = "workspaces")]
+        if let Ok(workspace) = workspace::load_workspace_config(&config.directory) {
+            config.realmlist = config.realmlist.or(workspace.realmlist);
+            config.account = config.account.or(workspace.account);
+        }
+
+        // A game's realmlist may name a preset instead of an address
+        config.realmlist = config
+            .realmlist
+            .map(|realmlist| self.resolve_realm(&realmlist));
+
+        Ok(config)
+    }
+
+    /// Address of a `[realms]` preset, matched case-insensitively
+    pub fn realm_preset(&self, name: &str) -> Option<&str> {
+        self.table
+            .get("realms")?
+            .as_table()?
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))?
+            .1
+            .as_str()
+    }
+
+    /// A `[realms]` preset's address, or `realm` itself when it names no preset
+    pub fn resolve_realm(&self, realm: &str) -> String {
+        self.realm_preset(realm).unwrap_or(realm).to_string()
+    }
+
+    /// All games in file order, with the outcome of building each config
+    pub fn games(&self, hostname: &str) -> Vec<(String, std::io::Result<Config>)> {
+        self.game_keys()
+            .into_iter()
+            .map(|key| (key.to_string(), self.game(key, hostname)))
+            .collect()
+    }
+}
 
-    // Expand ~ in the directory path
-    // $HOME, $USER are NOT expanded
-    config.directory = std::path::PathBuf::from(
-        shellexpand::tilde(&config.directory.to_string_lossy()).to_string(),
-    );
+/// Load the whole config file (TOML)
+pub fn load_config(path_str: &str, game: &str) -> std::io::Result<Config> {
+    load_config_for_host(path_str, game, &current_hostname())
+}
 
-    Ok(config.clone())
+/// Load a game config, applying `[host.<hostname>.<game>]` overrides for the given host
+pub fn load_config_for_host(path_str: &str, game: &str, hostname: &str) -> std::io::Result<Config> {
+    ConfigFile::read(path_str)?.game(game, hostname)
 }
 
-/// Overwrite the realmlist file to point at the desired server
+/// Merge the `[host.<hostname>.<game>]` table (if any) over the game table
+fn apply_host_overrides(
+    game_value: &mut toml::Value,
+    hosts: &toml::Table,
+    game: &str,
+    hostname: &str,
+) {
+    let overrides = hosts
+        .iter()
+        .find(|(host, _)| host.eq_ignore_ascii_case(hostname))
+        .and_then(|(host, games)| Some((host, games.as_table()?)))
+        .and_then(|(host, games)| {
+            games
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(game))
+                .map(|(key, value)| (format!("host.{host}.{key}"), value))
+        });
+
+    if let Some((section, overrides)) = overrides {
+        // Upgrade before merging, so a legacy key can't shadow the game's current one
+        let mut overrides = overrides.clone();
+        if let Some(entry) = overrides.as_table_mut() {
+            compat::warn_once(&compat::upgrade_entry(&section, entry));
+        }
+        merge_values(game_value, &overrides);
+    }
+}
+
+/// Recursively merge `overlay` into `base`; tables are merged, other values replaced
+fn merge_values(base: &mut toml::Value, overlay: &toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// Overwrite the realmlist file to point at the desired server, recording the switch
 pub fn write_realmlist(
-    game_folder: &std::path::Path,
-    rel_path: &str,
+    game_dir: &std::path::Path,
+    realmlist_path: &std::path::Path,
     realmlist: &str,
 ) -> std::io::Result<()> {
-    let realmlist_path = game_folder.join(rel_path);
     let realmlist_fmt = format!("set realmlist {realmlist}");
-    std::fs::write(&realmlist_path, &realmlist_fmt).inspect_err(|e| {
-        eprintln!(
-            "{e} Realmlist not writable, check path: {}",
-            realmlist_path.display()
-        );
+    switch_realmlist(game_dir, realmlist_path, realmlist).inspect_err(|e| {
+        tracing::error!("✗ Could not write {}: {e}", realmlist_path.display());
     })?;
-    println!("Realmlist set to:\n\t{realmlist_fmt}");
+    outln!("Realmlist set to:\n\t{realmlist_fmt}");
+    Ok(())
+}
+
+/// Write a realmlist like [`store_realmlist`] and add the switch to the game's
+/// [`realm::HISTORY_FILE`], if the host changes
+pub fn switch_realmlist(
+    game_dir: &std::path::Path,
+    path: &std::path::Path,
+    realmlist: &str,
+) -> std::io::Result<()> {
+    let previous = realm::read_realmlist(path).ok().flatten();
+    store_realmlist(path, realmlist)?;
+    if previous.as_deref() != Some(realmlist)
+        && let Err(e) = realm::record_switch(game_dir, previous.as_deref(), realmlist)
+    {
+        tracing::warn!("⚠ Failed to record the realmlist switch: {e}");
+    }
     Ok(())
 }
 
+/// Content [`store_realmlist`] writes for a realmlist
+pub fn realmlist_content(realmlist: &str) -> String {
+    format!("set realmlist {realmlist}")
+}
+
+/// Write `set realmlist <realmlist>` to a realmlist file without printing anything
+///
+/// A read-only realmlist (marked so by the client, a launcher or by hand) is made writable
+/// for the write and read-only again afterwards.
+pub fn store_realmlist(path: &std::path::Path, realmlist: &str) -> std::io::Result<()> {
+    write_keeping_readonly(path, realmlist_content(realmlist).as_bytes())
+        .map_err(|e| std::io::Error::new(e.kind(), describe_write_error(&e)))
+}
+
+fn write_keeping_readonly(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    let original = match std::fs::metadata(path) {
+        Ok(meta) if meta.permissions().readonly() => meta.permissions(),
+        _ => return std::fs::write(path, contents),
+    };
+    let mut writable = original.clone();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        writable.set_mode(writable.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    writable.set_readonly(false);
+    std::fs::set_permissions(path, writable)?;
+    let written = std::fs::write(path, contents);
+    let restored = std::fs::set_permissions(path, original);
+    written?;
+    restored
+}
+
+/// Explain why a realmlist write failed in terms of what the user can do about it
+fn describe_write_error(e: &std::io::Error) -> String {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    if cfg!(windows) && matches!(e.raw_os_error(), Some(32 | 33)) {
+        return format!("file is locked by another program, close the game or launcher ({e})");
+    }
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => {
+            format!("permission denied, the file or its folder belongs to another user ({e})")
+        }
+        std::io::ErrorKind::NotFound => {
+            format!("folder does not exist, check realmlist_path in config.toml ({e})")
+        }
+        _ => e.to_string(),
+    }
+}
+
 fn clear_cache(game_dir: &std::path::Path) -> std::io::Result<()> {
     let cache_dir = game_dir.join("Cache");
     match cache_dir.try_exists() {
         Ok(true) => {
-            println!("Cache directory exists, removing...");
+            tracing::info!("Cache directory exists, removing...");
             std::fs::remove_dir_all(&cache_dir)?;
         }
         Ok(false) => {
-            println!("Cache directory does not exist, nothing to remove.");
+            tracing::info!("Cache directory does not exist, nothing to remove.");
         }
         Err(e) => {
-            eprintln!("Failed to check if cache directory exists: {e}");
+            tracing::error!("✗ Failed to check if cache directory exists: {e}");
             return Err(e);
         }
     }
     Ok(())
 }
 
+/// Password value that asks for the password at launch instead of storing it
+pub const PASSWORD_PROMPT: &str = "prompt";
+
+/// Return the stored password, or prompt for it (without echo) when absent or set to "prompt"
+fn resolve_password(
+    config: &Config,
+    account: &str,
+    password: Option<&str>,
+) -> std::io::Result<String> {
+    resolve_password_with(config, account, password, rpassword::prompt_password)
+}
+
+/// [`resolve_password`] with the prompt passed in; with `cache_password`, a password typed
+/// earlier is taken from the OS keyring and a newly typed one stored there
+fn resolve_password_with(
+    config: &Config,
+    account: &str,
+    password: Option<&str>,
+    prompt: impl FnOnce(String) -> std::io::Result<String>,
+) -> std::io::Result<String> {
+    if let Some(password) = password.filter(|password| *password != PASSWORD_PROMPT) {
+        return Ok(password.to_string());
+    }
+    let cache = config.cache_password == Some(true);
+    if cache && let Some(password) = secrets::cached_password(&config.directory, account) {
+        return Ok(password);
+    }
+    let password = prompt(format!("Password for {account}: ")).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!("No password configured for '{account}' and prompting failed: {e}"),
+        )
+    })?;
+    if cache {
+        let secs = config
+            .password_cache_secs
+            .unwrap_or(secrets::DEFAULT_PASSWORD_CACHE_SECS);
+        if let Err(e) = secrets::cache_password(
+            &config.directory,
+            account,
+            &password,
+            std::time::Duration::from_secs(secs),
+        ) {
+            tracing::warn!("⚠ Could not cache the password: {e:#}");
+        }
+    }
+    Ok(password)
+}
+
+/// Passwords typed at the prompt are not echoed back to the terminal
+fn display_password<'a>(config: &Config, account: &str, password: &'a str) -> &'a str {
+    let stored = if config.account.as_deref() == Some(account) {
+        config.password.as_deref()
+    } else {
+        config
+            .accounts
+            .as_ref()
+            .and_then(|accounts| accounts.get(account))
+            .map(String::as_str)
+    };
+    match stored {
+        Some(stored) if stored != PASSWORD_PROMPT => password,
+        _ => "********",
+    }
+}
+
 /// Launches the game executable
 /// On Linux, it supports launching the game using a custom command or Wine with a local `.wine` configuration.
 /// On Windows, it directly runs the executable.
 pub fn launch(config: &Config) -> std::io::Result<()> {
+    spawn(config)?;
+    Ok(())
+}
+
+/// Print the variables of the `env` table a client is started with
+fn print_env(config: &Config) {
+    if config.env.is_empty() {
+        return;
+    }
+    outln!("Environment:");
+    for (name, value) in &config.env {
+        outln!("\t{name}={value}");
+    }
+}
+
+/// Launch the game and return the client process, so callers can wait for it to exit
+pub fn spawn(config: &Config) -> std::io::Result<std::process::Child> {
+    spawn_with_log(config, None)
+}
+
+/// Launch the game like [`spawn`], writing the client's stdout and stderr to `log`
+pub fn spawn_with_log(
+    config: &Config,
+    log: Option<&std::path::Path>,
+) -> std::io::Result<std::process::Child> {
     // Clear cache if specified
     if config.clear_cache == Some(true) {
         clear_cache(&config.directory)?;
@@ -121,20 +739,48 @@ pub fn launch(config: &Config) -> std::io::Result<()> {
         ));
     }
 
-    // Collect all accounts
+    // Collect all accounts, prompting for passwords that are not stored
     let mut all_accounts: Vec<(String, String)> = vec![];
-    if let (Some(account), Some(password)) = (&config.account, &config.password) {
-        all_accounts.push((account.clone(), password.clone()));
+    if let Some(account) = &config.account {
+        all_accounts.push((
+            account.clone(),
+            resolve_password(config, account, config.password.as_deref())?,
+        ));
     }
     if let Some(accounts) = &config.accounts {
         for (account, password) in accounts {
-            all_accounts.push((account.clone(), password.clone()));
+            all_accounts.push((
+                account.clone(),
+                resolve_password(config, account, Some(password))?,
+            ));
+        }
+    }
+    // The first account is the one that gets copied, as for auto_login
+    let mut copied = None;
+    if config.copy_password == Some(true)
+        && let Some((account, password)) = all_accounts.first()
+    {
+        let clear_secs = config
+            .clipboard_clear_secs
+            .unwrap_or(clipboard::DEFAULT_CLEAR_SECS);
+        match clipboard::copy_secret(password, std::time::Duration::from_secs(clear_secs)) {
+            Ok(()) => copied = Some(account.clone()),
+            Err(e) => tracing::warn!("⚠ Could not copy the password to the clipboard: {e}"),
         }
     }
+    let display = |account: &str, password: &'_ str| -> String {
+        if copied.as_deref() == Some(account) {
+            "(copied to clipboard)".to_string()
+        } else {
+            display_password(config, account, password).to_string()
+        }
+    };
+
     // Display accounts and passwords
     if all_accounts.len() == 1 {
         let (account, password) = &all_accounts[0];
-        println!("Account\n\t{account} / {password}");
+        let password = display(account, password);
+        outln!("Account\n\t{account} / {password}");
     } else if !all_accounts.is_empty() {
         let default_account_width = 12;
         let max_account_len = all_accounts
@@ -142,58 +788,435 @@ pub fn launch(config: &Config) -> std::io::Result<()> {
             .map(|(account, _)| account.len())
             .max()
             .unwrap_or(default_account_width);
-        println!("Accounts:");
+        outln!("Accounts:");
         for (i, (account, password)) in all_accounts.iter().enumerate() {
-            println!(
+            outln!(
                 "\t{}. {:<width$} / {}",
                 i + 1,
                 account,
-                password,
+                display(account, password),
                 width = max_account_len,
             );
         }
     }
 
+    // The first account is the one the login screen gets
+    if config.auto_login == Some(true)
+        && let Some((account, _)) = all_accounts.first()
+    {
+        let config_wtf = wtf::config_wtf_path(&config.directory, &config.executable);
+        match wtf::apply_auto_login(
+            &config_wtf,
+            account,
+            config.realm_name.as_deref(),
+            config.character_index,
+        ) {
+            Ok(()) => outln!("Login prefilled for:\n\t{account}"),
+            Err(e) => tracing::warn!("⚠ Could not prefill {}: {e}", config_wtf.display()),
+        }
+    }
+
     // Launch the game
-    match std::env::consts::OS {
+    let mut command = match std::env::consts::OS {
         "linux" => {
-            let mut command: String = config.launch_cmd.clone().unwrap_or_else(|| {
-                let wine_prefix_path = config.directory.join(".wine");
-                format!(
-                    "WINEPREFIX=\"{}\" wine \"{}\"",
-                    wine_prefix_path.to_string_lossy(),
-                    executable_path.to_string_lossy()
-                )
-            });
+            let mut command = match &config.launch_cmd {
+                Some(launch_cmd) => launch_cmd.clone(),
+                None => config.runner_command()?,
+            };
             if let Some(args) = &config.arguments
                 && !args.trim().is_empty() {
                     command = format!("{command} {args}");
                 }
-            println!("Launching with command:\n\t{command}");
-            std::process::Command::new("setsid")
-                .arg("sh")
-                .arg("-c")
-                .arg(command)
-                .spawn()?;
-        }
-        "windows" => {
-            if let Some(args) = &config.arguments {
-                if !args.trim().is_empty() {
-                    let cmd_string = format!("\"{}\" {}", executable_path.to_string_lossy(), args);
-                    std::process::Command::new("cmd")
-                        .arg("/C")
-                        .arg(cmd_string)
-                        .spawn()?;
-                } else {
-                    std::process::Command::new(executable_path).spawn()?;
-                }
-            } else {
-                std::process::Command::new(executable_path).spawn()?;
+            let command = config.wrap_command(command);
+            outln!("Launching with command:\n\t{command}");
+            let mut setsid = std::process::Command::new("setsid");
+            setsid.arg("sh").arg("-c").arg(command);
+            setsid
+        }
+        "windows" => match config
+            .arguments
+            .as_deref()
+            .filter(|args| !args.trim().is_empty())
+        {
+            Some(args) => {
+                let cmd_string = format!("\"{}\" {}", executable_path.to_string_lossy(), args);
+                let mut cmd = std::process::Command::new("cmd");
+                cmd.arg("/C").arg(cmd_string);
+                cmd
+            }
+            None => std::process::Command::new(executable_path),
+        },
+        _ => return Err(std::io::Error::other("Unsupported platform")),
+    };
+    print_env(config);
+    command.stdout(crate::output::child_stdout());
+    if let Some(log) = log {
+        let file = std::fs::File::create(log)?;
+        command.stdout(file.try_clone()?).stderr(file);
+        outln!("Client output:\n\t{}", log.display());
+    }
+    command.envs(&config.env).spawn()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CONFIG: &str = r#"
+[Chromie]
+directory = "/games/wow335"
+realmlist = "logon.chromiecraft.com"
+launch_cmd = "lutris lutris:rungameid/1"
+accounts.main = "password1"
+
+[host.laptop.chromie]
+directory = "/mnt/games/wow335"
+accounts.alt = "password2"
+"#;
+
+    fn write_config(dir: &TempDir) -> String {
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, CONFIG).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_host_override_applied() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_config(&dir);
+
+        let config = load_config_for_host(&path, "chromie", "LAPTOP")?;
+        assert_eq!(
+            config.directory,
+            std::path::PathBuf::from("/mnt/games/wow335")
+        );
+        assert_eq!(config.realmlist.as_deref(), Some("logon.chromiecraft.com"));
+
+        // Tables are merged rather than replaced
+        let accounts = config.accounts.unwrap();
+        assert_eq!(accounts.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_realm_presets() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+[realms]
+warmane = "logon.warmane.com"
+localhost = "127.0.0.1"
+
+[Wotlk]
+directory = "/games/wow335"
+realmlist = "Warmane"
+"#,
+        )?;
+        let file = ConfigFile::read(&path.to_string_lossy())?;
+        assert_eq!(file.game_keys(), vec!["Wotlk"]);
+        assert_eq!(file.resolve_realm("LOCALHOST"), "127.0.0.1");
+        assert_eq!(file.resolve_realm("logon.example.org"), "logon.example.org");
+        assert_eq!(
+            file.game("wotlk", "desktop")?.realmlist.as_deref(),
+            Some("logon.warmane.com")
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "workspaces")]
+    #[test]
+    fn test_workspace_defaults() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let workspace = dir.path().join("main");
+        std::fs::create_dir(&workspace)?;
+        std::fs::write(
+            workspace.join("workspace.toml"),
+            "base_path = \"/games/base\"\nrealmlist = \"warmane\"\naccount = \"me\"\n",
+        )?;
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            format!(
+                "[realms]\nwarmane = \"logon.warmane.com\"\n\n[Main]\ndirectory = {:?}\n\n[Alt]\ndirectory = {:?}\naccount = \"alt\"\n",
+                workspace, workspace
+            ),
+        )?;
+
+        let file = ConfigFile::read(&path.to_string_lossy())?;
+        let main = file.game("main", "desktop")?;
+        assert_eq!(main.realmlist.as_deref(), Some("logon.warmane.com"));
+        assert_eq!(main.account.as_deref(), Some("me"));
+        assert_eq!(file.game("alt", "desktop")?.account.as_deref(), Some("alt"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_games_keep_file_order() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+[Zeta]
+directory = "/z"
+accounts.second = "b"
+accounts.first = "a"
+
+[Alpha]
+directory = "/a"
+
+[Mid]
+directory = "/m"
+"#,
+        )?;
+
+        let file = ConfigFile::read(&path.to_string_lossy())?;
+        assert_eq!(file.game_keys(), vec!["Zeta", "Alpha", "Mid"]);
+
+        let zeta = file.game("zeta", "desktop")?;
+        let accounts: Vec<_> = zeta.accounts.unwrap().into_keys().collect();
+        assert_eq!(accounts, vec!["second", "first"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_runner_command() {
+        let config = |extra: &str| -> Config {
+            toml::from_str(&format!("directory = \"/games/wow\"\n{extra}")).unwrap()
+        };
+        assert_eq!(
+            config("").runner_command().unwrap(),
+            "WINEPREFIX=\"/games/wow/.wine\" wine \"/games/wow/Wow.exe\""
+        );
+        assert!(config("runner = \"proton\"").runner_command().is_err());
+        assert!(
+            config("runner = \"proton\"\nproton_path = \"/opt/GE-Proton9\"")
+                .runner_command()
+                .unwrap()
+                .ends_with("\"/opt/GE-Proton9/proton\" run \"/games/wow/Wow.exe\"")
+        );
+        assert_eq!(
+            config("runner = \"umu\"\ncompat_data_path = \"/prefixes/wow\"")
+                .runner_command()
+                .unwrap(),
+            "WINEPREFIX=\"/prefixes/wow\" GAMEID=umu-default umu-run \"/games/wow/Wow.exe\""
+        );
+        assert_eq!(
+            config("runner = \"proton\"\ncompat_data_path = \"/prefixes/wow\"").wine_prefix(),
+            Some(std::path::PathBuf::from("/prefixes/wow/pfx"))
+        );
+        assert_eq!(config("launch_cmd = \"lutris\"").wine_prefix(), None);
+    }
+
+    #[test]
+    fn test_wine_prefix_matches_runner_command() {
+        for runner in ["wine", "proton", "umu"] {
+            for env in ["", "env.WINEPREFIX = \"/prefixes/raid\""] {
+                let config: Config = toml::from_str(&format!(
+                    "directory = \"/games/wow\"\nrunner = \"{runner}\"\nproton_path = \"/opt/GE-Proton9\"\n{env}"
+                ))
+                .unwrap();
+                let command = config.runner_command().unwrap();
+                let prefix = config.wine_prefix().unwrap();
+                // Proton is given the compat data directory and uses its pfx
+                let (variable, dir) = match runner {
+                    "proton" => ("STEAM_COMPAT_DATA_PATH", prefix.parent().unwrap()),
+                    _ => ("WINEPREFIX", prefix.as_path()),
+                };
+                assert!(
+                    command.starts_with(&format!("{variable}=\"{}\"", dir.display())),
+                    "{runner} {env}: {command}"
+                );
             }
         }
-        _ => {
-            return Err(std::io::Error::other("Unsupported platform"));
+    }
+
+    #[test]
+    fn test_runner_command_uses_env_prefix() {
+        let config = |extra: &str| -> Config {
+            toml::from_str(&format!(
+                "directory = \"/games/wow\"\nproton_path = \"/opt/GE-Proton9\"\n{extra}\nenv.WINEPREFIX = \"/prefixes/raid\""
+            ))
+            .unwrap()
+        };
+        assert_eq!(
+            config("").runner_command().unwrap(),
+            "WINEPREFIX=\"/prefixes/raid\" wine \"/games/wow/Wow.exe\""
+        );
+        assert!(
+            config("runner = \"proton\"")
+                .runner_command()
+                .unwrap()
+                .starts_with("STEAM_COMPAT_DATA_PATH=\"/prefixes/raid\" ")
+        );
+        assert_eq!(
+            config("runner = \"umu\"\ncompat_data_path = \"/prefixes/wow\"")
+                .runner_command()
+                .unwrap(),
+            "WINEPREFIX=\"/prefixes/raid\" GAMEID=umu-default PROTONPATH=\"/opt/GE-Proton9\" umu-run \"/games/wow/Wow.exe\""
+        );
+        assert_eq!(
+            config("launch_cmd = \"lutris\"").wine_prefix(),
+            Some(std::path::PathBuf::from("/prefixes/raid"))
+        );
+    }
+
+    #[test]
+    fn test_env_merges_host_overrides() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[Main]\ndirectory = \"/m\"\nenv = { DXVK_HUD = \"fps\", WINEDLLOVERRIDES = \"d3d9=n\" }\n\n[host.laptop.Main]\nenv.DXVK_HUD = \"0\"\n",
+        )?;
+        let config = load_config_for_host(&path.to_string_lossy(), "Main", "laptop")?;
+        let env: Vec<(&str, &str)> = config
+            .env
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(env, vec![("DXVK_HUD", "0"), ("WINEDLLOVERRIDES", "d3d9=n")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_command() {
+        let config = |extra: &str| -> Config {
+            toml::from_str(&format!("directory = \"/games/wow\"\n{extra}")).unwrap()
+        };
+        assert_eq!(
+            config("").wrap_command("wine Wow.exe".into()),
+            "wine Wow.exe"
+        );
+        assert_eq!(
+            config("gamemode = true").wrap_command("wine Wow.exe".into()),
+            format!("gamemoderun sh -c {}", hooks::shell_quote("wine Wow.exe"))
+        );
+        assert_eq!(
+            config(
+                "gamemode = true\ngamescope = { width = 2560, height = 1440, fullscreen = true }"
+            )
+            .wrap_command("WINEPREFIX=/p wine Wow.exe".into()),
+            format!(
+                "gamemoderun gamescope -W 2560 -H 1440 -f -- sh -c {}",
+                hooks::shell_quote("WINEPREFIX=/p wine Wow.exe")
+            )
+        );
+        assert!(toml::from_str::<Config>("directory = \"/w\"\ngamescope = { depth = 1 }").is_err());
+    }
+
+    #[test]
+    fn test_select_account() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[Main]\ndirectory = \"/m\"\naccount = \"me\"\npassword = \"p\"\naccounts.alt = \"a\"\naccounts.bank = \"b\"\n",
+        )?;
+        let config = load_config(&path.to_string_lossy(), "Main")?;
+        assert_eq!(config.account_names(), vec!["me", "alt", "bank"]);
+
+        let mut bank = config.clone();
+        bank.select_account("bank")?;
+        assert_eq!(bank.account_names(), vec!["bank"]);
+        assert_eq!(bank.password.as_deref(), Some("b"));
+
+        let mut me = config.clone();
+        me.select_account("me")?;
+        assert_eq!(me.account_names(), vec!["me"]);
+        assert_eq!(me.password.as_deref(), Some("p"));
+
+        assert!(config.clone().select_account("nobody").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_override_ignored_for_other_hosts() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_config(&dir);
+
+        let config = load_config_for_host(&path, "Chromie", "desktop")?;
+        assert_eq!(config.directory, std::path::PathBuf::from("/games/wow335"));
+        assert_eq!(config.accounts.unwrap().len(), 1);
+
+        // The host table itself is not a game
+        assert!(load_config_for_host(&path, "host", "desktop").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_realmlist_keeps_read_only() -> std::io::Result<()> {
+        let temp = TempDir::new()?;
+        let path = temp.path().join("realmlist.wtf");
+        std::fs::write(&path, "set realmlist old.example.org")?;
+        let mut permissions = std::fs::metadata(&path)?.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions)?;
+
+        store_realmlist(&path, "new.example.org")?;
+        assert_eq!(
+            std::fs::read_to_string(&path)?,
+            "set realmlist new.example.org"
+        );
+        assert!(std::fs::metadata(&path)?.permissions().readonly());
+
+        let missing = temp.path().join("Data/enUS/realmlist.wtf");
+        let e = store_realmlist(&missing, "new.example.org").unwrap_err();
+        assert!(e.to_string().contains("realmlist_path"));
+        Ok(())
+    }
+
+    fn account_config(extra: &str) -> Config {
+        toml::from_str(&format!(
+            "directory = \"/games/wow335\"\naccount = \"me\"\n{extra}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_password_prompts() -> std::io::Result<()> {
+        let stored = account_config("password = \"secret\"");
+        let typed = resolve_password_with(&stored, "me", stored.password.as_deref(), |_| {
+            panic!("a stored password is not prompted for")
+        })?;
+        assert_eq!(typed, "secret");
+
+        for config in [account_config(""), account_config("password = \"prompt\"")] {
+            let typed =
+                resolve_password_with(&config, "me", config.password.as_deref(), |prompt| {
+                    assert_eq!(prompt, "Password for me: ");
+                    Ok("typed".to_string())
+                })?;
+            assert_eq!(typed, "typed");
         }
+
+        let missing = account_config("");
+        let e = resolve_password_with(&missing, "me", None, |_| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no terminal",
+            ))
+        })
+        .unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+        assert!(e.to_string().contains("No password configured for 'me'"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_password_masks_typed_passwords() {
+        let config = account_config(
+            "password = \"secret\"\naccounts.alt = \"prompt\"\naccounts.bank = \"gold\"",
+        );
+        assert_eq!(display_password(&config, "me", "secret"), "secret");
+        assert_eq!(display_password(&config, "bank", "gold"), "gold");
+        assert_eq!(display_password(&config, "alt", "typed"), "********");
+        assert_eq!(display_password(&config, "unknown", "typed"), "********");
+
+        let prompted = account_config("");
+        assert_eq!(display_password(&prompted, "me", "typed"), "********");
     }
-    Ok(())
 }
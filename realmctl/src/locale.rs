@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+
+/// Locale codes used by WoW clients for `Data/<locale>` directories
+pub const KNOWN_LOCALES: &[&str] = &[
+    "enUS", "enGB", "enCN", "enTW", "deDE", "esES", "esMX", "frFR", "itIT", "koKR", "ptBR", "ptPT",
+    "ruRU", "zhCN", "zhTW",
+];
+
+/// Extract the locale from WTF/Config.wtf content (`SET locale "enUS"`)
+pub fn parse_config_locale(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let set = parts.next()?;
+        let key = parts.next()?;
+        if !set.eq_ignore_ascii_case("set") || !key.eq_ignore_ascii_case("locale") {
+            return None;
+        }
+        let value = parts.next()?.trim_matches('"');
+        canonical_locale(value)
+    })
+}
+
+/// Known locale code matching `name` case-insensitively, in its canonical casing
+pub fn canonical_locale(name: &str) -> Option<String> {
+    KNOWN_LOCALES
+        .iter()
+        .find(|locale| locale.eq_ignore_ascii_case(name))
+        .map(|locale| locale.to_string())
+}
+
+/// Locale directories present under `Data`, as named on disk
+pub fn locale_dirs(game_dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(game_dir.join("Data")) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| canonical_locale(name).is_some())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Locale directory of a client, as named on disk
+///
+/// The locale set in WTF/Config.wtf wins when its directory exists, otherwise the
+/// first locale directory found under `Data` is used.
+pub fn detect_locale(game_dir: &Path) -> Option<String> {
+    let dirs = locale_dirs(game_dir);
+    let configured = std::fs::read_to_string(game_dir.join("WTF").join("Config.wtf"))
+        .ok()
+        .and_then(|content| parse_config_locale(&content));
+
+    if let Some(configured) = configured
+        && let Some(dir) = dirs.iter().find(|d| d.eq_ignore_ascii_case(&configured))
+    {
+        return Some(dir.clone());
+    }
+    dirs.into_iter().next()
+}
+
+/// Realmlist file of a client: `Data/<locale>/realmlist.wtf`, else `realmlist.wtf` in the root
+pub fn find_realmlist(game_dir: &Path) -> Option<PathBuf> {
+    if let Some(locale) = detect_locale(game_dir) {
+        let path = game_dir.join("Data").join(locale).join("realmlist.wtf");
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+    let path = game_dir.join("realmlist.wtf");
+    path.is_file().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_config_locale() {
+        assert_eq!(
+            parse_config_locale("SET gxResolution \"1920x1080\"\nSET locale \"deDE\"\n"),
+            Some("deDE".to_string())
+        );
+        assert_eq!(
+            parse_config_locale("set locale enus"),
+            Some("enUS".to_string())
+        );
+        assert_eq!(parse_config_locale("SET locale \"xxXX\""), None);
+    }
+
+    #[test]
+    fn test_detect_locale_prefers_config_wtf() {
+        let temp = TempDir::new().unwrap();
+        let game_dir = temp.path();
+        std::fs::create_dir_all(game_dir.join("Data/enUS")).unwrap();
+        std::fs::create_dir_all(game_dir.join("Data/frFR")).unwrap();
+        std::fs::create_dir_all(game_dir.join("Data/Interface")).unwrap();
+        std::fs::create_dir_all(game_dir.join("WTF")).unwrap();
+
+        assert_eq!(locale_dirs(game_dir), vec!["enUS", "frFR"]);
+        assert_eq!(detect_locale(game_dir), Some("enUS".to_string()));
+
+        std::fs::write(game_dir.join("WTF/Config.wtf"), "SET locale \"frFR\"\n").unwrap();
+        assert_eq!(detect_locale(game_dir), Some("frFR".to_string()));
+
+        assert_eq!(find_realmlist(game_dir), None);
+        std::fs::write(game_dir.join("Data/frFR/realmlist.wtf"), "set realmlist x").unwrap();
+        assert_eq!(
+            find_realmlist(game_dir),
+            Some(game_dir.join("Data/frFR/realmlist.wtf"))
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Diagnostics through `tracing`: `-v`/`-vv`/`--quiet`, `REALMCTL_LOG` and `--log-file`
+//!
+//! Results (tables, reports, the realmlist that was written) are still printed to stdout.
+//! Progress, warnings and errors go through the `tracing` macros to stderr, so `--quiet`
+//! keeps only problems and `-v` shows every file operation of `create`, `fix` and friends.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Filter directives for stderr, e.g. `realmctl::workspace=trace,info`; replaces -v/--quiet
+pub const LOG_ENV: &str = "REALMCTL_LOG";
+/// Directory under [`crate::state::state_dir`] holding `--log-file` logs
+pub const LOGS_DIR: &str = "logs";
+/// Log files kept, older ones are removed when a new one is started
+const KEEP_LOGS: usize = 20;
+
+static LOG_FILE: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Install the subscriber for the rest of the process; later calls keep the first setup
+pub fn init(verbose: u8, quiet: bool, to_file: bool) {
+    LOG_FILE.get_or_init(|| {
+        let stderr_filter = std::env::var(LOG_ENV)
+            .ok()
+            .and_then(|directives| EnvFilter::try_new(directives).ok())
+            .unwrap_or_else(|| EnvFilter::new(stderr_level(verbose, quiet)));
+        let stderr = tracing_subscriber::fmt::layer()
+            .event_format(Plain)
+            .with_writer(std::io::stderr)
+            .with_filter(stderr_filter);
+
+        let file = to_file
+            .then(|| open_log(&crate::state::state_dir().join(LOGS_DIR)))
+            .and_then(|opened| {
+                opened
+                    .inspect_err(|e| tracing::warn!("⚠ Could not create a log file: {e}"))
+                    .ok()
+            });
+        let (file_layer, path) = match file {
+            Some((path, file)) => {
+                let level = if verbose >= 2 {
+                    Level::TRACE
+                } else {
+                    Level::DEBUG
+                };
+                let layer = tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file))
+                    .with_filter(tracing_subscriber::filter::LevelFilter::from_level(level));
+                (Some(layer), Some(path))
+            }
+            None => (None, None),
+        };
+
+        let _ = tracing_subscriber::registry()
+            .with(stderr)
+            .with(file_layer)
+            .try_init();
+        if let Some(path) = &path {
+            tracing::info!("Writing a debug log to {}", path.display());
+            let args: Vec<String> = std::env::args().collect();
+            tracing::debug!(
+                "{} (realmctl {})",
+                args.join(" "),
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+        path
+    });
+}
+
+/// The `--log-file` log of this process, if one is written
+pub fn log_file() -> Option<&'static Path> {
+    LOG_FILE.get()?.as_deref()
+}
+
+/// Default stderr directive for the verbosity flags
+fn stderr_level(verbose: u8, quiet: bool) -> &'static str {
+    match (quiet, verbose) {
+        (true, _) => "warn",
+        (false, 0) => "info",
+        (false, 1) => "debug",
+        _ => "trace",
+    }
+}
+
+/// Create `realmctl-<unix time>-<pid>.log` in `dir`, removing all but the newest logs
+fn open_log(dir: &Path) -> std::io::Result<(PathBuf, std::fs::File)> {
+    std::fs::create_dir_all(dir)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("realmctl-{now}-{}.log", std::process::id()));
+    let file = std::fs::File::create(&path)?;
+    prune_logs(dir, KEEP_LOGS);
+    Ok((path, file))
+}
+
+/// Remove the oldest `realmctl-*.log` files in `dir` so at most `keep` remain
+fn prune_logs(dir: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut logs: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.starts_with("realmctl-") && name.ends_with(".log")
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    logs.sort();
+    let excess = logs.len().saturating_sub(keep);
+    for (_, path) in logs.into_iter().take(excess) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Messages as the commands always printed them; debug and trace lines name their module
+struct Plain;
+
+impl<S, N> FormatEvent<S, N> for Plain
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        if *metadata.level() > Level::INFO {
+            write!(writer, "[{} {}] ", metadata.level(), metadata.target())?;
+        }
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_prune_logs_keeps_newest() {
+        let dir = TempDir::new().unwrap();
+        let base = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        for i in 0..5u64 {
+            let path = dir.path().join(format!("realmctl-{i}-1.log"));
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_modified(base + std::time::Duration::from_secs(i * 60))
+                .unwrap();
+        }
+        std::fs::write(dir.path().join("notes.txt"), "kept").unwrap();
+
+        prune_logs(dir.path(), 2);
+
+        let mut left: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["notes.txt", "realmctl-3-1.log", "realmctl-4-1.log"]);
+    }
+
+    #[test]
+    fn test_stderr_level() {
+        assert_eq!(stderr_level(0, false), "info");
+        assert_eq!(stderr_level(1, false), "debug");
+        assert_eq!(stderr_level(3, false), "trace");
+        assert_eq!(stderr_level(2, true), "warn");
+    }
+}
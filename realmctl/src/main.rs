@@ -1,7 +1,22 @@
 use clap::Parser;
-use realmctl::cli::Cli;
+use realmctl::cli::{Cli, ExitError};
+use std::process::ExitCode;
 
-fn main() -> anyhow::Result<()> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
-    cli.run()
+    match cli.run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!("Error: {e:?}");
+            let code = e.downcast_ref::<ExitError>().map_or(1, |exit| exit.code);
+            // Scripts get an error document unless the command already reported its result
+            if realmctl::output::is_json() && !realmctl::output::emitted() {
+                let _ = realmctl::output::emit(&serde_json::json!({
+                    "error": format!("{e:#}"),
+                    "exit_code": code,
+                }));
+            }
+            ExitCode::from(code)
+        }
+    }
 }
@@ -0,0 +1,231 @@
+/// Default port of the WoW authentication (logon) server
+pub const AUTH_PORT: u16 = 3724;
+
+/// Allowed values of one cron field
+#[derive(Debug, Clone, PartialEq)]
+struct CronField(Vec<bool>);
+
+impl CronField {
+    /// Parse `*`, `5`, `1,3`, `1-5`, `*/15` and `1-5/2` style fields
+    fn parse(field: &str, min: u32, max: u32) -> Result<Self, String> {
+        let mut allowed = vec![false; max as usize + 1];
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (
+                    range,
+                    step.parse::<u32>()
+                        .ok()
+                        .filter(|s| *s > 0)
+                        .ok_or_else(|| format!("invalid step in '{part}'"))?,
+                ),
+                None => (part, 1),
+            };
+            let number = |s: &str| {
+                s.parse::<u32>()
+                    .ok()
+                    .filter(|n| (min..=max).contains(n))
+                    .ok_or_else(|| format!("'{s}' is not between {min} and {max}"))
+            };
+            let (start, end) = match range {
+                "*" => (min, max),
+                range => match range.split_once('-') {
+                    Some((start, end)) => (number(start)?, number(end)?),
+                    None => (number(range)?, number(range)?),
+                },
+            };
+            for value in (start..=end).step_by(step as usize) {
+                allowed[value as usize] = true;
+            }
+        }
+        Ok(CronField(allowed))
+    }
+
+    fn matches(&self, value: u32) -> bool {
+        self.0.get(value as usize).copied().unwrap_or(false)
+    }
+}
+
+/// A recurring server maintenance window: a cron schedule (UTC) for its start and a duration
+///
+/// Written as `"<minute> <hour> <day of month> <month> <day of week> <duration>"`, e.g.
+/// `"0 6 * * 2 90m"` for Tuesdays 06:00–07:30 UTC. Durations take `m` or `h` suffixes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintenanceWindow {
+    pub spec: String,
+    minute: CronField,
+    hour: CronField,
+    day: CronField,
+    month: CronField,
+    weekday: CronField,
+    /// Length in minutes
+    pub duration: u64,
+}
+
+impl MaintenanceWindow {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        let [minute, hour, day, month, weekday, duration] = fields[..] else {
+            return Err(format!(
+                "'{spec}' needs 6 fields: minute hour day-of-month month day-of-week duration"
+            ));
+        };
+        let mut weekday = CronField::parse(weekday, 0, 7)?;
+        // 7 is Sunday as well
+        if weekday.matches(7) {
+            weekday.0[0] = true;
+        }
+        Ok(MaintenanceWindow {
+            spec: spec.to_string(),
+            minute: CronField::parse(minute, 0, 59)?,
+            hour: CronField::parse(hour, 0, 23)?,
+            day: CronField::parse(day, 1, 31)?,
+            month: CronField::parse(month, 1, 12)?,
+            weekday,
+            duration: parse_duration(duration)?,
+        })
+    }
+
+    /// Whether a window starts at the minute of `timestamp` (seconds since the Unix epoch)
+    fn starts_at(&self, timestamp: u64) -> bool {
+        let (_, month, day, hour, minute, weekday) = civil_time(timestamp);
+        self.minute.matches(minute)
+            && self.hour.matches(hour)
+            && self.day.matches(day)
+            && self.month.matches(month)
+            && self.weekday.matches(weekday)
+    }
+
+    /// End of the window running at `timestamp`, if one is
+    pub fn active_until(&self, timestamp: u64) -> Option<u64> {
+        let now_minute = timestamp - timestamp % 60;
+        (0..self.duration)
+            .map(|ago| now_minute.saturating_sub(ago * 60))
+            .find(|start| self.starts_at(*start))
+            .map(|start| start + self.duration * 60)
+    }
+}
+
+fn parse_duration(duration: &str) -> Result<u64, String> {
+    let (number, factor) = match duration.strip_suffix('h') {
+        Some(hours) => (hours, 60),
+        None => (duration.strip_suffix('m').unwrap_or(duration), 1),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .filter(|n| *n > 0)
+        .map(|n| n * factor)
+        .ok_or_else(|| format!("invalid duration '{duration}' (e.g. 90m or 2h)"))
+}
+
+/// UTC (year, month, day, hour, minute, weekday with 0 = Sunday) of a Unix timestamp
+pub fn civil_time(timestamp: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (timestamp / 86400) as i64;
+    let secs = timestamp % 86400;
+    // Howard Hinnant's civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    // 1970-01-01 was a Thursday
+    let weekday = (days + 4).rem_euclid(7) as u32;
+    (
+        year,
+        month,
+        day,
+        (secs / 3600) as u32,
+        (secs % 3600 / 60) as u32,
+        weekday,
+    )
+}
+
+/// First window of `specs` running at `timestamp`, with its end; invalid specs are skipped
+pub fn active_window(specs: &[String], timestamp: u64) -> Option<(MaintenanceWindow, u64)> {
+    specs
+        .iter()
+        .filter_map(|spec| MaintenanceWindow::parse(spec).ok())
+        .find_map(|window| {
+            let end = window.active_until(timestamp)?;
+            Some((window, end))
+        })
+}
+
+/// `HH:MM UTC` of a Unix timestamp
+pub fn format_utc(timestamp: u64) -> String {
+    let (_, _, _, hour, minute, _) = civil_time(timestamp);
+    format!("{hour:02}:{minute:02} UTC")
+}
+
+/// Host and port of a realmlist value (`host` or `host:port`)
+pub fn realm_address(realmlist: &str) -> (String, u16) {
+    match realmlist.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => match port.parse() {
+            Ok(port) => (host.to_string(), port),
+            Err(_) => (realmlist.to_string(), AUTH_PORT),
+        },
+        _ => (realmlist.to_string(), AUTH_PORT),
+    }
+}
+
+/// Whether the logon server of a realmlist accepts connections
+pub fn realm_reachable(realmlist: &str, timeout: std::time::Duration) -> std::io::Result<()> {
+    use std::net::{TcpStream, ToSocketAddrs};
+
+    let (host, port) = realm_address(realmlist);
+    let mut last_error = std::io::Error::other(format!("{host} did not resolve"));
+    for addr in (host.as_str(), port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_time() {
+        // 2026-10-13 06:30 UTC, a Tuesday
+        assert_eq!(civil_time(1_791_873_000), (2026, 10, 13, 6, 30, 2));
+        assert_eq!(civil_time(0), (1970, 1, 1, 0, 0, 4));
+    }
+
+    #[test]
+    fn test_maintenance_window() {
+        let window = MaintenanceWindow::parse("0 6 * * 2 90m").unwrap();
+        // Tuesday 06:30 is inside, the window ends at 07:30
+        assert_eq!(
+            window.active_until(1_791_873_000),
+            Some(1_791_873_000 + 60 * 60)
+        );
+        // Tuesday 08:00 and Wednesday 06:30 are not
+        assert_eq!(window.active_until(1_791_878_400), None);
+        assert_eq!(window.active_until(1_791_873_000 + 86400), None);
+
+        let weekdays = MaintenanceWindow::parse("*/30 0-2 * * 1-5,7 1h").unwrap();
+        assert!(weekdays.active_until(1_791_873_000).is_none());
+        assert!(MaintenanceWindow::parse("0 25 * * * 1h").is_err());
+        assert!(MaintenanceWindow::parse("0 6 * * 2").is_err());
+        assert!(MaintenanceWindow::parse("0 6 * * 2 soon").is_err());
+    }
+
+    #[test]
+    fn test_realm_address() {
+        assert_eq!(
+            realm_address("logon.chromiecraft.com"),
+            ("logon.chromiecraft.com".to_string(), 3724)
+        );
+        assert_eq!(
+            realm_address("127.0.0.1:3725"),
+            ("127.0.0.1".to_string(), 3725)
+        );
+    }
+}
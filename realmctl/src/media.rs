@@ -0,0 +1,185 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Extensions of screenshots, exports and recordings considered by `media dedup`
+pub const MEDIA_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "tga", "bmp", "gif", "mp4", "avi"];
+
+/// Directory of a game or shared directory holding its media
+pub const MEDIA_DIR: &str = "Screenshots";
+
+/// Media directories of the games in `game_dirs` and of every shared directory below
+/// `<workspace root>/.shared`, each once even when linked into several games
+pub fn media_roots(workspace_root: &Path, game_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let shared_dirs = std::fs::read_dir(workspace_root.join(".shared"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path());
+    let mut roots: Vec<PathBuf> = game_dirs
+        .iter()
+        .cloned()
+        .chain(shared_dirs)
+        .filter_map(|dir| dir.join(MEDIA_DIR).canonicalize().ok())
+        .filter(|root| root.is_dir())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+/// How duplicates are replaced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DedupMode {
+    /// Hard links: no extra space, but all copies are one file
+    Hardlink,
+    /// Copy-on-write clones: no extra space until one is edited (btrfs, XFS, APFS)
+    Reflink,
+}
+
+/// Files with identical contents
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    /// Oldest copy, the one the others are replaced by
+    pub keep: PathBuf,
+    pub duplicates: Vec<PathBuf>,
+    /// Size of each copy
+    pub size: u64,
+}
+
+/// Media files below `root`, not following symlinks so shared directories are seen once
+fn media_files(root: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            media_files(&path, out);
+        } else if file_type.is_file()
+            && path.extension().is_some_and(|ext| {
+                MEDIA_EXTENSIONS
+                    .iter()
+                    .any(|media| ext.eq_ignore_ascii_case(media))
+            })
+        {
+            out.push(path);
+        }
+    }
+}
+
+fn content_hash(path: &Path) -> Result<String> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = crate::base::hasher("sha1").expect("builtin hash algorithm");
+    let mut buffer = [0u8; 65536];
+    loop {
+        crate::cancel::check()?;
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+/// Find media files with identical contents below `roots`
+///
+/// Only files of equal size are hashed. Files that already are one file (hard links of
+/// each other) count once.
+pub fn find_duplicates(roots: &[PathBuf]) -> Result<Vec<DuplicateGroup>> {
+    let mut files = Vec::new();
+    for root in roots {
+        media_files(root, &mut files);
+    }
+    files.sort();
+    files.dedup();
+    let mut by_size: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
+    for path in files {
+        if let Ok(meta) = std::fs::metadata(&path)
+            && meta.len() > 0
+        {
+            by_size.entry(meta.len()).or_default().push(path);
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (size, paths) in by_size.into_iter().filter(|(_, paths)| paths.len() > 1) {
+        let mut by_hash: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for path in paths {
+            let same_as_seen = by_hash
+                .values()
+                .flatten()
+                .any(|seen| crate::workspace::same_file(seen, &path));
+            if !same_as_seen {
+                by_hash.entry(content_hash(&path)?).or_default().push(path);
+            }
+        }
+        for mut paths in by_hash.into_values().filter(|paths| paths.len() > 1) {
+            paths.sort_by_key(|path| {
+                let modified = std::fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .ok();
+                (modified, path.clone())
+            });
+            let keep = paths.remove(0);
+            groups.push(DuplicateGroup {
+                keep,
+                duplicates: paths,
+                size,
+            });
+        }
+    }
+    Ok(groups)
+}
+
+/// Replace one duplicate by a link to (or clone of) `keep`, atomically
+pub fn replace_duplicate(keep: &Path, duplicate: &Path, mode: DedupMode) -> std::io::Result<()> {
+    let mut temp_name = duplicate.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".realmctl-dedup");
+    let temp_path = duplicate.with_file_name(temp_name);
+    match mode {
+        DedupMode::Hardlink => std::fs::hard_link(keep, &temp_path)?,
+        DedupMode::Reflink => crate::reflink::reflink(keep, &temp_path)?,
+    }
+    std::fs::rename(&temp_path, duplicate).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_replace_duplicates() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let shared = temp.path().join(".shared/global/Screenshots");
+        let local = temp.path().join("ws1/Screenshots");
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::create_dir_all(&local).unwrap();
+        std::fs::write(shared.join("a.jpg"), "same").unwrap();
+        std::fs::write(local.join("b.JPG"), "same").unwrap();
+        std::fs::write(local.join("c.jpg"), "diff").unwrap();
+        std::fs::write(local.join("notes.txt"), "same").unwrap();
+
+        let roots = media_roots(temp.path(), &[temp.path().join("ws1")]);
+        assert_eq!(roots.len(), 2);
+        let groups = find_duplicates(&roots).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].duplicates.len(), 1);
+        assert_eq!(groups[0].size, 4);
+
+        let group = &groups[0];
+        replace_duplicate(&group.keep, &group.duplicates[0], DedupMode::Hardlink).unwrap();
+        assert!(crate::workspace::same_file(
+            &shared.join("a.jpg"),
+            &local.join("b.JPG")
+        ));
+        assert!(find_duplicates(&roots).unwrap().is_empty());
+    }
+}
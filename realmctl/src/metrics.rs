@@ -0,0 +1,335 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::base::FileRole;
+use crate::workspace::{WorkspaceConfig, list_workspaces};
+
+/// How long a scraper may take to send its request or read the response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Snapshot of the values exported on the metrics endpoint
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub workspaces: usize,
+    pub bases: usize,
+    /// Bytes of base files reused by workspaces instead of being copied
+    pub disk_savings_bytes: u64,
+    /// Workspaces whose base manifest could not be read
+    pub broken_workspaces: usize,
+    /// Clients started by `launch` that are still running
+    pub sessions: Vec<Session>,
+    /// Outcome of the last `verify-base` of each base, for bases verified at least once
+    pub verifications: Vec<Verification>,
+}
+
+/// A running client, from the launch state
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub game: String,
+    pub directory: PathBuf,
+    pub duration_secs: u64,
+}
+
+/// Last `verify-base` outcome of a base, from the verify state
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    pub base: PathBuf,
+    pub ok: bool,
+    /// When the base was last found intact, seconds since the Unix epoch
+    pub last_verified: Option<u64>,
+}
+
+/// Collect metrics for every workspace under the workspace root and every game directory
+pub fn collect_metrics(workspace_root: &Path, game_dirs: &[PathBuf]) -> Result<Metrics> {
+    let workspaces = list_workspaces(workspace_root)?;
+    let mut metrics = Metrics {
+        workspaces: workspaces.len(),
+        ..Default::default()
+    };
+
+    let mut bases = HashSet::new();
+    for workspace in &workspaces {
+        bases.insert(workspace.base_path.clone());
+        match linked_bytes(workspace) {
+            Ok(bytes) => metrics.disk_savings_bytes += bytes,
+            Err(_) => metrics.broken_workspaces += 1,
+        }
+    }
+    metrics.bases = bases.len();
+
+    let state = crate::state::load_state();
+    let now = crate::state::now();
+    for dir in game_dirs {
+        let Some(running) = state.directory(dir).and_then(|d| d.running.as_ref()) else {
+            continue;
+        };
+        if crate::process::client_processes(dir, &[running.pid]).is_empty() {
+            continue;
+        }
+        metrics.sessions.push(Session {
+            game: running.game.clone(),
+            directory: dir.clone(),
+            duration_secs: now.saturating_sub(running.started_at),
+        });
+    }
+
+    let mut bases: Vec<PathBuf> = bases
+        .into_iter()
+        .map(|base| std::fs::canonicalize(&base).unwrap_or(base))
+        .collect();
+    bases.sort();
+    bases.dedup();
+    for base in bases {
+        let Some(directory) = state.directory(&base) else {
+            continue;
+        };
+        if let Some(ok) = directory.last_verify_ok {
+            metrics.verifications.push(Verification {
+                base,
+                ok,
+                last_verified: directory.last_verified,
+            });
+        }
+    }
+
+    Ok(metrics)
+}
+
+/// Sum the size of base files that are hard-linked or symlinked into the workspace
+fn linked_bytes(workspace: &WorkspaceConfig) -> Result<u64> {
+    let manifest = crate::base::load_manifest(&workspace.base_path)?;
+    let mut total = 0;
+
+    for (rel_path, role) in &manifest.file_roles {
+        if !matches!(role, FileRole::BaseData | FileRole::Executable) {
+            continue;
+        }
+        let base_file = workspace.base_path.join(rel_path);
+        let ws_file = workspace.workspace_path.join(rel_path);
+        if let (Ok(base_meta), Ok(ws_meta)) = (
+            std::fs::metadata(&base_file),
+            std::fs::symlink_metadata(&ws_file),
+        ) && is_linked(&base_meta, &ws_meta)
+        {
+            total += base_meta.len();
+        }
+    }
+
+    Ok(total)
+}
+
+#[cfg(unix)]
+fn is_linked(base_meta: &std::fs::Metadata, ws_meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    ws_meta.file_type().is_symlink()
+        || (base_meta.dev() == ws_meta.dev() && base_meta.ino() == ws_meta.ino())
+}
+
+#[cfg(not(unix))]
+fn is_linked(_base_meta: &std::fs::Metadata, ws_meta: &std::fs::Metadata) -> bool {
+    ws_meta.file_type().is_symlink()
+}
+
+/// Render metrics in the Prometheus text exposition format
+pub fn render_metrics(metrics: &Metrics) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: &[(String, u64)]| {
+        out.push_str(&format!("# HELP {name} {help}\n"));
+        out.push_str(&format!("# TYPE {name} gauge\n"));
+        for (labels, value) in samples {
+            out.push_str(&format!("{name}{labels} {value}\n"));
+        }
+    };
+    let single = |value: u64| [(String::new(), value)];
+    gauge(
+        "realmctl_workspaces",
+        "Number of workspaces under the workspace root",
+        &single(metrics.workspaces as u64),
+    );
+    gauge(
+        "realmctl_bases",
+        "Number of distinct bases referenced by workspaces",
+        &single(metrics.bases as u64),
+    );
+    gauge(
+        "realmctl_disk_savings_bytes",
+        "Bytes of base data linked into workspaces instead of copied",
+        &single(metrics.disk_savings_bytes),
+    );
+    gauge(
+        "realmctl_broken_workspaces",
+        "Workspaces whose base manifest could not be loaded",
+        &single(metrics.broken_workspaces as u64),
+    );
+    gauge(
+        "realmctl_running_clients",
+        "Clients started by launch that are still running",
+        &single(metrics.sessions.len() as u64),
+    );
+    let sessions: Vec<(String, u64)> = metrics
+        .sessions
+        .iter()
+        .map(|session| {
+            let labels = labels(&[
+                ("game", &session.game),
+                ("directory", &session.directory.to_string_lossy()),
+            ]);
+            (labels, session.duration_secs)
+        })
+        .collect();
+    gauge(
+        "realmctl_session_duration_seconds",
+        "Seconds since each running client was launched",
+        &sessions,
+    );
+    let verify_ok: Vec<(String, u64)> = metrics
+        .verifications
+        .iter()
+        .map(|v| (labels(&[("base", &v.base.to_string_lossy())]), v.ok as u64))
+        .collect();
+    gauge(
+        "realmctl_base_verify_ok",
+        "Whether the last verify-base found the base intact (1) or not (0)",
+        &verify_ok,
+    );
+    let verified_at: Vec<(String, u64)> = metrics
+        .verifications
+        .iter()
+        .filter_map(|v| {
+            Some((
+                labels(&[("base", &v.base.to_string_lossy())]),
+                v.last_verified?,
+            ))
+        })
+        .collect();
+    gauge(
+        "realmctl_base_last_verified_timestamp_seconds",
+        "When verify-base last found the base intact, seconds since the Unix epoch",
+        &verified_at,
+    );
+    out
+}
+
+/// Format a label set, escaping values as the exposition format requires
+fn labels(pairs: &[(&str, &str)]) -> String {
+    let pairs: Vec<String> = pairs
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{name}=\"{value}\"")
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Serve metrics over HTTP until the process is stopped
+///
+/// Only loopback addresses are accepted, the endpoint is meant for a local Prometheus scraper.
+/// Connections are handled one at a time, so each one gets a short timeout; an idle client
+/// would otherwise block every scrape after it.
+pub fn serve_metrics(
+    addr: SocketAddr,
+    workspace_root: &Path,
+    game_dirs: impl Fn() -> Vec<PathBuf>,
+) -> Result<()> {
+    if !addr.ip().is_loopback() {
+        anyhow::bail!("Metrics endpoint must bind to a loopback address, got {addr}");
+    }
+
+    let listener = TcpListener::bind(addr)
+        .with_context(|| format!("Failed to bind metrics endpoint {addr}"))?;
+    tracing::info!("Serving metrics on http://{addr}/metrics");
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_request(stream, workspace_root, &game_dirs) {
+                    tracing::error!("✗ Metrics request failed: {e}");
+                }
+            }
+            Err(e) => tracing::error!("✗ Failed to accept connection: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_request(
+    mut stream: TcpStream,
+    workspace_root: &Path,
+    game_dirs: &impl Fn() -> Vec<PathBuf>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, body) = if path == "/metrics" {
+        match collect_metrics(workspace_root, &game_dirs()) {
+            Ok(metrics) => ("200 OK", render_metrics(&metrics)),
+            Err(e) => ("500 Internal Server Error", format!("{e}\n")),
+        }
+    } else {
+        ("404 Not Found", "Not found\n".to_string())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics_sessions_and_verifications() {
+        let metrics = Metrics {
+            workspaces: 2,
+            bases: 1,
+            sessions: vec![Session {
+                game: "wotlk".to_string(),
+                directory: PathBuf::from("/games/\"wotlk\""),
+                duration_secs: 90,
+            }],
+            verifications: vec![
+                Verification {
+                    base: PathBuf::from("/bases/a"),
+                    ok: true,
+                    last_verified: Some(1_700_000_000),
+                },
+                Verification {
+                    base: PathBuf::from("/bases/b"),
+                    ok: false,
+                    last_verified: None,
+                },
+            ],
+            ..Default::default()
+        };
+        let out = render_metrics(&metrics);
+
+        assert!(out.contains("realmctl_workspaces 2\n"));
+        assert!(out.contains("realmctl_running_clients 1\n"));
+        assert!(out.contains(
+            "realmctl_session_duration_seconds{game=\"wotlk\",directory=\"/games/\\\"wotlk\\\"\"} 90\n"
+        ));
+        assert!(out.contains("realmctl_base_verify_ok{base=\"/bases/a\"} 1\n"));
+        assert!(out.contains("realmctl_base_verify_ok{base=\"/bases/b\"} 0\n"));
+        assert!(out.contains(
+            "realmctl_base_last_verified_timestamp_seconds{base=\"/bases/a\"} 1700000000\n"
+        ));
+        assert!(!out.contains("realmctl_base_last_verified_timestamp_seconds{base=\"/bases/b\"}"));
+        assert_eq!(out.matches("# TYPE ").count(), 8);
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::paths::{files_under, long_path};
+use crate::workspace::{
+    CreateOptions, WorkspaceConfig, create_workspace_with, load_workspace_config,
+};
+
+/// What was carried over to the new workspace and what was left behind
+#[derive(Debug)]
+pub struct MigrationReport {
+    /// The new workspace
+    pub workspace: WorkspaceConfig,
+    /// Relative paths copied into the new workspace
+    pub migrated: Vec<String>,
+    /// Relative paths left behind, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Create a workspace from another base and copy over the user data that survives a
+/// client version change
+///
+/// Macros and the SavedVariables of addons installed in the new workspace are copied,
+/// screenshots too unless both workspaces already share them. Client settings, key
+/// bindings and addons themselves are version specific and only reported.
+pub fn migrate_workspace(
+    source_path: &Path,
+    name: &str,
+    to_base: &Path,
+) -> Result<MigrationReport> {
+    let source = load_workspace_config(source_path)?;
+    let workspace_root = source_path
+        .parent()
+        .context("Failed to determine workspace root (parent directory missing)")?;
+
+    // The realm and account a workspace is meant for don't depend on the client version
+    let options = CreateOptions {
+        realmlist: source.realmlist.clone(),
+        account: source.account.clone(),
+        ..Default::default()
+    };
+    let target = create_workspace_with(
+        name,
+        to_base,
+        workspace_root,
+        source.sharing_rules.clone(),
+        &options,
+    )?;
+    let target_path = target.workspace_path.clone();
+    let base_name = target.base_name.clone();
+    let mut report = MigrationReport {
+        workspace: target,
+        migrated: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    migrate_wtf(source_path, &target_path, &mut report)?;
+    migrate_screenshots(source_path, &target_path, &mut report)?;
+
+    let addons = list_dir(&source_path.join("Interface/AddOns"));
+    if !addons.is_empty() {
+        report.skipped.push((
+            "Interface/AddOns".to_string(),
+            format!(
+                "{} addon(s) are built for one client version, install versions for {}",
+                addons.len(),
+                base_name
+            ),
+        ));
+    }
+
+    Ok(report)
+}
+
+fn migrate_wtf(source: &Path, target: &Path, report: &mut MigrationReport) -> Result<()> {
+    let (source, target) = (&long_path(source), &long_path(target));
+    let installed_addons = list_dir(&target.join("Interface/AddOns"));
+    for file in files_under(&source.join("WTF")) {
+        crate::cancel::check()?;
+        let rel_path = file
+            .strip_prefix(source)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let in_saved_variables = file
+            .parent()
+            .and_then(|p| p.file_name())
+            .is_some_and(|n| n.eq_ignore_ascii_case("SavedVariables"));
+
+        let skip_reason = if file_name.eq_ignore_ascii_case("macros-cache.txt") {
+            None
+        } else if in_saved_variables {
+            let addon = file_name.strip_suffix(".lua").unwrap_or(&file_name);
+            if file_name.ends_with(".bak") {
+                Some("backup copy".to_string())
+            } else if addon.starts_with("Blizzard_") {
+                Some("client UI settings differ between versions".to_string())
+            } else if !installed_addons
+                .iter()
+                .any(|installed| installed.eq_ignore_ascii_case(addon))
+            {
+                Some(format!("{addon} is not installed in the new workspace"))
+            } else {
+                None
+            }
+        } else {
+            Some("client settings differ between versions".to_string())
+        };
+
+        if let Some(reason) = skip_reason {
+            report.skipped.push((rel_path, reason));
+            continue;
+        }
+        copy_new(&file, &target.join(&rel_path), rel_path, report)?;
+    }
+    Ok(())
+}
+
+fn migrate_screenshots(source: &Path, target: &Path, report: &mut MigrationReport) -> Result<()> {
+    let (source, target) = (&long_path(source), &long_path(target));
+    let source_dir = source.join("Screenshots");
+    let target_dir = target.join("Screenshots");
+    // Shared screenshots are already visible from the new workspace
+    if let (Ok(a), Ok(b)) = (source_dir.canonicalize(), target_dir.canonicalize())
+        && a == b
+    {
+        return Ok(());
+    }
+
+    for file in files_under(&source_dir) {
+        crate::cancel::check()?;
+        let rel_path = file
+            .strip_prefix(source)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        copy_new(&file, &target.join(&rel_path), rel_path, report)?;
+    }
+    Ok(())
+}
+
+/// Copy a file unless the target already has one at that path
+fn copy_new(from: &Path, to: &Path, rel_path: String, report: &mut MigrationReport) -> Result<()> {
+    if to.exists() {
+        report
+            .skipped
+            .push((rel_path, "already exists in the new workspace".to_string()));
+        return Ok(());
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(from, to).with_context(|| format!("Failed to copy {rel_path}"))?;
+    report.migrated.push(rel_path);
+    Ok(())
+}
+
+fn list_dir(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Profile, scan_and_build_manifest, write_manifest};
+    use crate::workspace::{create_workspace, default_sharing_rules};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn mock_base(dir: &Path, profile: &Profile) -> Result<()> {
+        for rel_dir in &profile.required_dirs {
+            fs::create_dir_all(dir.join(rel_dir))?;
+        }
+        fs::create_dir_all(dir.join("Screenshots"))?;
+        fs::create_dir_all(dir.join("Interface/AddOns"))?;
+        for rel_path in &profile.required_files {
+            fs::create_dir_all(dir.join(rel_path).parent().unwrap())?;
+            fs::write(dir.join(rel_path), "mock")?;
+        }
+        write_manifest(&scan_and_build_manifest(dir, profile)?, dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_workspace() -> Result<()> {
+        let temp = TempDir::new()?;
+        let old_base = temp.path().join("base112");
+        let new_base = temp.path().join("base335");
+        let root = temp.path().join("workspaces");
+        mock_base(&old_base, &Profile::vanilla_112())?;
+        mock_base(&new_base, &Profile::chromie_335a())?;
+
+        let old = create_workspace("old", &old_base, &root, default_sharing_rules())?;
+        let account = old.workspace_path.join("WTF/Account/ME");
+        fs::create_dir_all(account.join("SavedVariables"))?;
+        fs::write(account.join("macros-cache.txt"), "MACRO 1")?;
+        fs::write(account.join("bindings-cache.wtf"), "bind")?;
+        fs::write(account.join("SavedVariables/Questie.lua"), "QuestieDB = {}")?;
+        fs::write(account.join("SavedVariables/pfUI.lua"), "pfUI = {}")?;
+
+        // Questie is already installed for the other workspaces of the new base
+        fs::create_dir_all(root.join(".shared/chromie-3.3.5a/Interface/AddOns/Questie"))?;
+
+        let report = migrate_workspace(&old.workspace_path, "new", &new_base)?;
+        let new = &report.workspace;
+        assert_eq!(new.base_name, "chromie-3.3.5a");
+        let migrated = new.workspace_path.join("WTF/Account/ME");
+        assert!(migrated.join("macros-cache.txt").is_file());
+        assert!(migrated.join("SavedVariables/Questie.lua").is_file());
+        assert!(!migrated.join("SavedVariables/pfUI.lua").exists());
+        assert!(!migrated.join("bindings-cache.wtf").exists());
+        assert!(report.skipped.iter().any(|(p, _)| p.ends_with("pfUI.lua")));
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long fetched news are shown without fetching them again
+pub const CACHE_TTL: Duration = Duration::from_secs(30 * 60);
+/// Give up on slow servers, news must never hold up a launch for long
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Headlines shown from a feed
+const MAX_ITEMS: usize = 3;
+/// Lines shown from a plain text or HTML page
+const MAX_LINES: usize = 5;
+
+/// News ready to print
+#[derive(Debug, Clone, PartialEq)]
+pub struct News {
+    pub lines: Vec<String>,
+    /// Shown from the cache because fetching failed
+    pub stale: bool,
+}
+
+fn cache_path(url: &str) -> PathBuf {
+    crate::state::state_dir()
+        .join("news")
+        .join(format!("{:08x}.txt", crc32fast::hash(url.as_bytes())))
+}
+
+/// Headlines of an RSS or Atom feed, else the first lines of the page's text
+pub fn summarize(body: &str) -> Vec<String> {
+    let items = regex::Regex::new(r"(?s)<(item|entry)[\s>].*?</(?:item|entry)>").unwrap();
+    let title = regex::Regex::new(r"(?s)<title[^>]*>(.*?)</title>").unwrap();
+    let date = regex::Regex::new(
+        r"(?s)<(?:pubDate|updated|published)[^>]*>(.*?)</(?:pubDate|updated|published)>",
+    )
+    .unwrap();
+
+    let headlines: Vec<String> = items
+        .find_iter(body)
+        .filter_map(|item| {
+            let item = item.as_str();
+            let title = text(&title.captures(item)?[1]);
+            Some(match date.captures(item) {
+                Some(date) => format!("{} ({})", title, text(&date[1])),
+                None => title,
+            })
+        })
+        .take(MAX_ITEMS)
+        .collect();
+    if !headlines.is_empty() {
+        return headlines;
+    }
+
+    text(body)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(MAX_LINES)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Plain text of an XML/HTML fragment
+fn text(fragment: &str) -> String {
+    let fragment = fragment
+        .trim()
+        .trim_start_matches("<![CDATA[")
+        .trim_end_matches("]]>");
+    let scripts = regex::Regex::new(r"(?is)<(script|style)[^>]*>.*?</(?:script|style)>").unwrap();
+    let breaks = regex::Regex::new(r"(?i)<(br|/p|/div|/h\d|/li)[^>]*>").unwrap();
+    let tags = regex::Regex::new(r"(?s)<[^>]*>").unwrap();
+    let without_scripts = scripts.replace_all(fragment, "");
+    let with_breaks = breaks.replace_all(&without_scripts, "\n");
+    tags.replace_all(&with_breaks, "")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .trim()
+        .to_string()
+}
+
+/// News of a server, from the cache when fetched within [`CACHE_TTL`]
+///
+/// When fetching fails the last cached news are returned marked stale; without any
+/// cache the fetch error is returned.
+pub fn news(url: &str) -> Result<News> {
+    let path = cache_path(url);
+    let cache_age = std::fs::metadata(&path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    if cache_age.is_some_and(|age| age < CACHE_TTL)
+        && let Ok(body) = std::fs::read_to_string(&path)
+    {
+        return Ok(News {
+            lines: summarize(&body),
+            stale: false,
+        });
+    }
+
+    match crate::fetch::curl(url, &[], FETCH_TIMEOUT) {
+        Ok(body) => {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            if let Err(e) = std::fs::write(&path, &body) {
+                tracing::warn!("⚠ Failed to cache news in {}: {e}", path.display());
+            }
+            Ok(News {
+                lines: summarize(&body),
+                stale: false,
+            })
+        }
+        Err(e) => match std::fs::read_to_string(&path) {
+            Ok(body) => Ok(News {
+                lines: summarize(&body),
+                stale: true,
+            }),
+            Err(_) => Err(e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_feed() {
+        let rss = r#"<?xml version="1.0"?><rss><channel><title>ChromieCraft</title>
+            <item><title><![CDATA[Maintenance on Tuesday]]></title><pubDate>Tue, 13 Oct 2026</pubDate></item>
+            <item><title>Patch 1.2 &amp; fixes</title></item>
+            </channel></rss>"#;
+        assert_eq!(
+            summarize(rss),
+            vec![
+                "Maintenance on Tuesday (Tue, 13 Oct 2026)".to_string(),
+                "Patch 1.2 & fixes".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summarize_html() {
+        let html = "<html><head><style>p{}</style></head><body><h1>News</h1><p>Realm down at 10:00</p></body></html>";
+        assert_eq!(summarize(html), vec!["News", "Realm down at 10:00"]);
+    }
+}
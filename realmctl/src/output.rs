@@ -0,0 +1,79 @@
+//! `--json`: one JSON document per result on stdout, everything else on stderr
+//!
+//! Commands print their progress and tables with [`outln!`](crate::outln) and
+//! [`out!`](crate::out), which write to stdout as usual and to stderr in JSON mode. Hooks and
+//! clients get stderr as their stdout through [`child_stdout`], so nothing mixes with the
+//! documents written by [`emit`].
+
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static EMITTED: AtomicBool = AtomicBool::new(false);
+/// Clients launched side by side report from their own threads
+static WRITE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Switch to JSON mode for the rest of the process
+pub fn enable_json() {
+    JSON.store(true, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Whether a command already wrote its result with [`emit`]
+pub fn emitted() -> bool {
+    EMITTED.load(Ordering::Relaxed)
+}
+
+/// Write `value` as pretty-printed JSON to stdout
+pub fn emit<T: Serialize>(value: &T) -> Result<()> {
+    let mut text = serde_json::to_string_pretty(value)?;
+    text.push('\n');
+    EMITTED.store(true, Ordering::Relaxed);
+    let _guard = WRITE.lock();
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Write human output, see [`outln!`](crate::outln)
+pub fn human(args: std::fmt::Arguments) {
+    let _guard = WRITE.lock();
+    // Like print!, but a closed pipe ends the output instead of the process
+    let _ = match is_json() {
+        true => std::io::stderr().lock().write_fmt(args),
+        false => std::io::stdout().lock().write_fmt(args),
+    };
+}
+
+/// Where a child process's stdout goes: inherited, or stderr in JSON mode
+pub fn child_stdout() -> std::process::Stdio {
+    match is_json() {
+        true => std::io::stderr().into(),
+        false => std::process::Stdio::inherit(),
+    }
+}
+
+/// `println!` for progress, tables and prompts: stdout, or stderr in JSON mode
+#[macro_export]
+macro_rules! outln {
+    () => {
+        $crate::output::human(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::human(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// `print!` counterpart of [`outln!`](crate::outln)
+#[macro_export]
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::output::human(format_args!($($arg)*))
+    };
+}
@@ -0,0 +1,299 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Classic Windows path length limit, without long path support
+pub const MAX_PATH: usize = 260;
+
+/// Extended-length form of a path (`\\?\C:\...`) so file operations aren't limited by MAX_PATH
+///
+/// Only changes anything on Windows, other platforms return the path unchanged.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    if path.to_string_lossy().starts_with(r"\\?\") {
+        return path.to_path_buf();
+    }
+    // `\\?\` paths are not normalized by Windows, resolve `.` and `..` first
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let absolute = absolute.to_string_lossy().replace('/', "\\");
+    match absolute.strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{unc}")),
+        None => PathBuf::from(format!(r"\\?\{absolute}")),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// Whether Windows long path support (`LongPathsEnabled`) is switched on
+///
+/// The game and other tools without `\\?\` paths still hit MAX_PATH when it is off.
+#[cfg(windows)]
+pub fn long_paths_enabled() -> bool {
+    std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SYSTEM\CurrentControlSet\Control\FileSystem",
+            "/v",
+            "LongPathsEnabled",
+        ])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("0x1"))
+}
+
+#[cfg(not(windows))]
+pub fn long_paths_enabled() -> bool {
+    true
+}
+
+/// Maximum path length other programs can use, if the platform limits it
+pub fn path_limit() -> Option<usize> {
+    (cfg!(windows) && !long_paths_enabled()).then_some(MAX_PATH)
+}
+
+/// Files below `dir` in sorted order
+///
+/// Symlinks below `dir` are skipped, not followed, so a tree unpacked from elsewhere
+/// can't lead outside itself or into a loop. Unreadable directories are skipped.
+pub fn files_under(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files);
+    files
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<(PathBuf, std::fs::FileType)> = entries
+        .flatten()
+        .filter_map(|entry| Some((entry.path(), entry.file_type().ok()?)))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    for (path, file_type) in entries {
+        if file_type.is_dir() {
+            collect_files(&path, out);
+        } else if file_type.is_file() {
+            out.push(path);
+        }
+    }
+}
+
+/// Bytes available on the filesystem holding `path`
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(existing)
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let available: u64 = stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+    Some(available * 1024)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Free inodes on the filesystem holding `path`, if the filesystem has a fixed inode count
+#[cfg(unix)]
+pub fn free_inodes(path: &Path) -> Option<u64> {
+    // df needs an existing path, use the nearest existing ancestor
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let output = std::process::Command::new("df")
+        .arg("-Pi")
+        .arg(existing)
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let columns: Vec<&str> = stdout.lines().nth(1)?.split_whitespace().collect();
+    let total: u64 = columns.get(1)?.parse().ok()?;
+    // Filesystems allocating inodes dynamically (btrfs, zfs) report no total
+    if total == 0 {
+        return None;
+    }
+    columns.get(3)?.parse().ok()
+}
+
+#[cfg(not(unix))]
+pub fn free_inodes(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// File next to the realmctl executable that turns on portable mode without `--portable`
+pub const PORTABLE_MARKER: &str = "realmctl-portable";
+
+/// Default locations and where they live under the portable root
+const PORTABLE_DEFAULTS: &[(&str, &str)] = &[
+    ("~/.config/realmctl/config.toml", "config.toml"),
+    ("~/.local/share/wow_workspaces", "workspaces"),
+    ("~/Games/WoW", "bases"),
+];
+
+static PORTABLE_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keep config, state, bases and workspaces under `root` (`--portable`)
+pub fn set_portable_root(root: PathBuf) {
+    let _ = PORTABLE_ROOT.set(root);
+}
+
+pub fn portable_root() -> Option<&'static Path> {
+    PORTABLE_ROOT.get().map(PathBuf::as_path)
+}
+
+/// Directory of the realmctl executable, the root of a portable setup
+pub fn executable_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let exe = exe.canonicalize().unwrap_or(exe);
+    exe.parent().map(Path::to_path_buf)
+}
+
+/// Expand `~` in a path given on the command line
+///
+/// In portable mode the default locations of config.toml, workspaces and bases are
+/// replaced by their place under the portable root.
+pub fn expand(path: &str) -> PathBuf {
+    if let Some(root) = portable_root()
+        && let Some(portable) = portable_default(path, root)
+    {
+        return portable;
+    }
+    PathBuf::from(shellexpand::tilde(path).to_string())
+}
+
+fn portable_default(path: &str, root: &Path) -> Option<PathBuf> {
+    PORTABLE_DEFAULTS
+        .iter()
+        .find(|(default, _)| *default == path)
+        .map(|(_, portable)| root.join(portable))
+}
+
+/// Resolve a path from config.toml, relative ones against the portable root
+pub fn resolve_in_root(path: PathBuf) -> PathBuf {
+    match portable_root() {
+        Some(root) if path.is_relative() => normalize(&root.join(path)),
+        _ => path,
+    }
+}
+
+/// Remove `.` and `..` components without touching the filesystem
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// `path` relative to the directory `from`, e.g. `../../bases/wotlk`
+///
+/// `None` when the two share no prefix, like paths on different Windows drives.
+pub fn relative_path(path: &Path, from: &Path) -> Option<PathBuf> {
+    let (path, from) = (normalize(path), normalize(from));
+    let path: Vec<Component> = path.components().collect();
+    let from: Vec<Component> = from.components().collect();
+    let common = path.iter().zip(&from).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+    let mut relative: PathBuf = from[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    relative.extend(&path[common..]);
+    Some(relative)
+}
+
+/// Inside the portable root, `path` relative to `from`, otherwise unchanged
+///
+/// Links and workspace.toml use this so the setup still works once the drive is
+/// mounted elsewhere.
+pub fn portable_relative(path: &Path, from: &Path) -> PathBuf {
+    let Some(root) = portable_root() else {
+        return path.to_path_buf();
+    };
+    let absolute = |path: &Path| normalize(&std::path::absolute(path).unwrap_or_default());
+    let (absolute_path, absolute_from) = (absolute(path), absolute(from));
+    if absolute_path.starts_with(root) && absolute_from.starts_with(root) {
+        relative_path(&absolute_path, &absolute_from).unwrap_or(absolute_path)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// How config.toml refers to a directory: relative to the portable root when inside it
+pub fn config_value(dir: &Path) -> PathBuf {
+    match portable_root() {
+        Some(root) => portable_relative(dir, root),
+        None => dir.to_path_buf(),
+    }
+}
+
+/// What a symlink at `link` stores to point at `target`, see [`portable_relative`]
+pub fn link_target(target: &Path, link: &Path) -> PathBuf {
+    match link.parent() {
+        Some(parent) => portable_relative(target, parent),
+        None => target.to_path_buf(),
+    }
+}
+
+/// Key of a directory in the state file, relative to the portable root when inside it
+pub fn state_key(dir: &Path) -> String {
+    match portable_root().and_then(|root| dir.strip_prefix(root).ok()) {
+        Some(relative) => relative.to_string_lossy().to_string(),
+        None => dir.to_string_lossy().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_long_path_is_noop_off_windows() {
+        let path = Path::new("/games/wow335/Interface/AddOns");
+        assert_eq!(long_path(path), path.to_path_buf());
+        assert_eq!(path_limit(), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_portable_paths() {
+        let root = Path::new("/media/usb/realmctl");
+        assert_eq!(
+            portable_default("~/.local/share/wow_workspaces", root),
+            Some(root.join("workspaces"))
+        );
+        assert_eq!(portable_default("~/elsewhere", root), None);
+        assert_eq!(
+            relative_path(
+                &root.join("bases/wotlk"),
+                &root.join("workspaces/main/Interface")
+            ),
+            Some(PathBuf::from("../../../bases/wotlk"))
+        );
+        assert_eq!(
+            normalize(Path::new("/a/workspaces/main/../../bases/./wotlk")),
+            PathBuf::from("/a/bases/wotlk")
+        );
+    }
+}
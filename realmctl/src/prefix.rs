@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long a launch keeps a new prefix locked while wine sets it up
+pub const INIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Lock file of a prefix, next to it so the prefix itself is left to wine
+pub fn lock_path(prefix: &Path) -> PathBuf {
+    let mut name = prefix.file_name().unwrap_or_default().to_os_string();
+    name.push(".realmctl-lock");
+    prefix.with_file_name(name)
+}
+
+/// Advisory lock on a wine prefix, released when dropped
+///
+/// Several games can share one prefix (e.g. one `compat_data_path` for all of them);
+/// launches and `realmctl prefix` commands take this lock so only one of them changes
+/// the prefix at a time.
+#[derive(Debug)]
+pub struct PrefixLock {
+    _file: File,
+}
+
+/// Lock a prefix, waiting for another realmctl holding it
+pub fn lock_prefix(prefix: &Path) -> std::io::Result<PrefixLock> {
+    let path = lock_path(prefix);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+    if file.try_lock().is_err() {
+        tracing::info!(
+            "Waiting for another realmctl using the prefix:\n\t{}",
+            prefix.display()
+        );
+        file.lock()?;
+    }
+    Ok(PrefixLock { _file: file })
+}
+
+/// Whether wine has set up the prefix, which it does on first use
+pub fn is_initialized(prefix: &Path) -> bool {
+    prefix.join("system.reg").is_file()
+}
+
+/// Wait until wine has set up a new prefix, up to `timeout`; returns whether it did
+pub fn wait_for_initialization(prefix: &Path, timeout: Duration) -> bool {
+    let start = Instant::now();
+    while !is_initialized(prefix) {
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+    true
+}
+
+/// Whether a wineserver runs for the prefix, i.e. something is using it right now
+///
+/// wineserver keeps its socket in `/tmp/.wine-<uid>/server-<dev>-<inode>` of the prefix
+/// directory while it runs.
+#[cfg(target_os = "linux")]
+pub fn wineserver_running(prefix: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let (Ok(prefix), Ok(me)) = (std::fs::metadata(prefix), std::fs::metadata("/proc/self")) else {
+        return false;
+    };
+    Path::new(&format!("/tmp/.wine-{}", me.uid()))
+        .join(format!("server-{:x}-{:x}", prefix.dev(), prefix.ino()))
+        .join("socket")
+        .exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn wineserver_running(_prefix: &Path) -> bool {
+    false
+}
+
+/// Stop every process of the prefix, including wine services no client command line names
+pub fn kill_wineserver(
+    prefix: &Path,
+    env: &indexmap::IndexMap<String, String>,
+) -> std::io::Result<()> {
+    let status = std::process::Command::new("wineserver")
+        .arg("-k")
+        .envs(env)
+        .env("WINEPREFIX", prefix)
+        .stdout(crate::output::child_stdout())
+        .status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!(
+            "wineserver -k exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_lock_is_exclusive() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let prefix = temp.path().join("shared").join("pfx");
+        assert_eq!(
+            lock_path(&prefix),
+            temp.path().join("shared/pfx.realmctl-lock")
+        );
+        assert!(!is_initialized(&prefix));
+        assert!(!wineserver_running(&prefix));
+
+        let lock = lock_prefix(&prefix).unwrap();
+        let other = File::open(lock_path(&prefix)).unwrap();
+        assert!(other.try_lock().is_err());
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+    }
+}
@@ -0,0 +1,267 @@
+use std::path::{Path, PathBuf};
+
+/// A running process as far as realmctl cares about it
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// Full command line, arguments joined by spaces
+    pub command: String,
+    /// Working directory, where the platform exposes it
+    pub cwd: Option<PathBuf>,
+    /// Parent process, where the platform exposes it
+    pub parent: Option<u32>,
+}
+
+/// All processes of the system that are visible to the current user
+#[cfg(target_os = "linux")]
+pub fn processes() -> Vec<ProcessInfo> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let cmdline = std::fs::read(entry.path().join("cmdline")).ok()?;
+            if cmdline.is_empty() {
+                return None;
+            }
+            let command = String::from_utf8_lossy(&cmdline)
+                .split('\0')
+                .filter(|arg| !arg.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let cwd = std::fs::read_link(entry.path().join("cwd")).ok();
+            // The parent follows the state, after the command name in parentheses
+            let parent = std::fs::read_to_string(entry.path().join("stat"))
+                .ok()
+                .and_then(|stat| {
+                    stat.rsplit_once(')')?
+                        .1
+                        .split_whitespace()
+                        .nth(1)?
+                        .parse()
+                        .ok()
+                });
+            Some(ProcessInfo {
+                pid,
+                command,
+                cwd,
+                parent,
+            })
+        })
+        .collect()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn processes() -> Vec<ProcessInfo> {
+    let Ok(output) = std::process::Command::new("ps")
+        .args(["-axo", "pid=,ppid=,command="])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, rest) = line.trim().split_once(' ')?;
+            let (parent, command) = rest.trim().split_once(' ')?;
+            Some(ProcessInfo {
+                pid: pid.parse().ok()?,
+                command: command.trim().to_string(),
+                cwd: None,
+                parent: parent.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+pub fn processes() -> Vec<ProcessInfo> {
+    let Ok(output) = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-Process | Where-Object Path | ForEach-Object { \"$($_.Id) $($_.Path)\" }",
+        ])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim().split_once(' ')?;
+            Some(ProcessInfo {
+                pid: pid.parse().ok()?,
+                command: command.trim().to_string(),
+                cwd: None,
+                parent: None,
+            })
+        })
+        .collect()
+}
+
+/// CPU time a process has used so far, in clock ticks (1/100 s)
+///
+/// Only available on Linux.
+#[cfg(target_os = "linux")]
+pub fn cpu_ticks(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The command name may contain spaces and parentheses, the other fields follow the last ')'
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    // utime and stime, fields 14 and 15 of proc(5), counting from the state (field 3)
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn cpu_ticks(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// Whether a process with this PID exists and has not exited
+#[cfg(target_os = "linux")]
+pub fn is_running(pid: u32) -> bool {
+    let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else {
+        return false;
+    };
+    // A zombie has exited and only waits for its parent to collect it
+    stat.rsplit_once(')')
+        .is_some_and(|(_, fields)| !fields.trim_start().starts_with('Z'))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_running(pid: u32) -> bool {
+    processes().iter().any(|process| process.pid == pid)
+}
+
+/// Ask a process to exit: SIGTERM, or a close request on Windows
+pub fn terminate(pid: u32) -> std::io::Result<()> {
+    let status = if cfg!(windows) {
+        std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string()])
+            .status()?
+    } else {
+        std::process::Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .status()?
+    };
+    if !status.success() {
+        return Err(std::io::Error::other(format!(
+            "stopping process {pid} failed ({status})"
+        )));
+    }
+    Ok(())
+}
+
+/// A command line and the path prefix of `dir` in one notation, `None` for an empty `dir`
+///
+/// Understands wine's `Z:\home\...` paths as well as native ones.
+fn comparable(command: &str, dir: &Path) -> Option<(String, String)> {
+    let dir = dir.to_string_lossy().replace('\\', "/");
+    let dir = dir.trim_end_matches('/');
+    if dir.is_empty() {
+        return None;
+    }
+    let command = command.replace('\\', "/");
+    let command = command.replace("Z:/", "/").replace("z:/", "/");
+    let prefix = format!("{dir}/");
+    Some(if cfg!(windows) {
+        (command.to_lowercase(), prefix.to_lowercase())
+    } else {
+        (command, prefix)
+    })
+}
+
+/// Whether a command line refers to a file inside `dir`
+pub fn mentions_dir(command: &str, dir: &Path) -> bool {
+    comparable(command, dir).is_some_and(|(command, prefix)| command.contains(&prefix))
+}
+
+/// Whether a command line runs a program inside `dir`, rather than merely naming a file
+/// there like an editor or a shell would
+pub fn runs_from(command: &str, dir: &Path) -> bool {
+    comparable(command, dir).is_some_and(|(command, prefix)| command.starts_with(&prefix))
+}
+
+/// Processes running a program from `dir` (e.g. a WoW client started by realmctl)
+pub fn processes_in(dir: &Path) -> Vec<ProcessInfo> {
+    processes()
+        .into_iter()
+        .filter(|process| process.pid != std::process::id())
+        .filter(|process| {
+            mentions_dir(&process.command, dir) || process.cwd.as_deref() == Some(dir)
+        })
+        .collect()
+}
+
+/// Processes of a client: the `roots` realmctl started with their descendants, and every
+/// program running from `dir` (wine shows the client as `Z:\...\Wow.exe`)
+pub fn client_processes(dir: &Path, roots: &[u32]) -> Vec<ProcessInfo> {
+    let all = processes();
+    let mut tree: Vec<u32> = roots.to_vec();
+    loop {
+        let children: Vec<u32> = all
+            .iter()
+            .filter(|process| {
+                !tree.contains(&process.pid)
+                    && process.parent.is_some_and(|parent| tree.contains(&parent))
+            })
+            .map(|process| process.pid)
+            .collect();
+        if children.is_empty() {
+            break;
+        }
+        tree.extend(children);
+    }
+    all.into_iter()
+        .filter(|process| process.pid != std::process::id())
+        .filter(|process| tree.contains(&process.pid) || runs_from(&process.command, dir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentions_dir() {
+        let dir = Path::new("/home/me/Games/wow335");
+        assert!(mentions_dir("wine /home/me/Games/wow335/Wow.exe", dir));
+        assert!(mentions_dir(
+            r"Z:\home\me\Games\wow335\Wow.exe -console",
+            dir
+        ));
+        assert!(!mentions_dir("/home/me/Games/wow335-ptr/Wow.exe", dir));
+        assert!(!mentions_dir("vim notes.txt", dir));
+        assert!(runs_from(r"Z:\home\me\Games\wow335\Wow.exe -console", dir));
+        assert!(!runs_from("vim /home/me/Games/wow335/WTF/Config.wtf", dir));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_cpu_ticks_of_own_process() {
+        assert!(cpu_ticks(std::process::id()).is_some());
+        assert_eq!(cpu_ticks(u32::MAX), None);
+        assert!(is_running(std::process::id()));
+        assert!(!is_running(u32::MAX));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_client_processes_include_descendants() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+        let clients = client_processes(temp.path(), &[std::process::id()]);
+        let found = clients.iter().any(|process| process.pid == child.id());
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(found);
+        assert!(client_processes(temp.path(), &[]).is_empty());
+    }
+}
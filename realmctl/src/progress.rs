@@ -0,0 +1,79 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressFinish, ProgressStyle};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Human readable byte count (e.g. `1.4 GiB`)
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Progress of an operation over a known set of files, drawn as an indicatif bar on stderr
+///
+/// Shared between worker threads: each reports finished files with [`Progress::add`].
+/// indicatif draws nothing when stderr is not a terminal, so logs and pipes stay clean.
+/// The bar is left in place when the progress is dropped, also when the operation fails
+/// halfway.
+pub struct Progress {
+    bar: ProgressBar,
+    total_files: usize,
+    files: AtomicUsize,
+    /// The bar moves by bytes, unless there are none to count
+    by_bytes: bool,
+}
+
+impl Progress {
+    pub fn new(label: &str, total_files: usize, total_bytes: u64) -> Self {
+        // Bytes tell the remaining time better than files
+        let by_bytes = total_bytes > 0;
+        let (length, template) = match by_bytes {
+            true => (
+                total_bytes,
+                "{prefix} [{bar:24}] {msg} files, {bytes}/{total_bytes}",
+            ),
+            false => (total_files as u64, "{prefix} [{bar:24}] {msg} files"),
+        };
+        let style = ProgressStyle::with_template(template)
+            .expect("valid progress template")
+            .progress_chars("#-");
+        let bar = ProgressBar::with_draw_target(Some(length), ProgressDrawTarget::stderr())
+            .with_style(style)
+            .with_prefix(label.to_string())
+            .with_message(format!("0/{total_files}"))
+            .with_finish(ProgressFinish::AndLeave);
+        Progress {
+            bar,
+            total_files,
+            files: AtomicUsize::new(0),
+            by_bytes,
+        }
+    }
+
+    /// Count `files` finished files and `bytes` processed bytes
+    ///
+    /// Large files can report their bytes as they go and count as a file once done.
+    pub fn add(&self, files: usize, bytes: u64) {
+        if files > 0 {
+            let done = self.files.fetch_add(files, Ordering::Relaxed) + files;
+            self.bar.set_message(format!("{done}/{}", self.total_files));
+        }
+        let step = if self.by_bytes { bytes } else { files as u64 };
+        self.bar.inc(step);
+    }
+
+    /// Draw the final state and end the line
+    pub fn finish(&self) {
+        if !self.bar.is_finished() {
+            self.bar.finish();
+        }
+    }
+}
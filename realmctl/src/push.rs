@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::workspace::{load_workspace_config, plan_deletion};
+
+/// `user@host:/path` destination of a push
+#[derive(Debug, Clone, PartialEq)]
+pub struct Remote {
+    /// Anything ssh accepts as a destination, e.g. `me@desktop` or a Host alias
+    pub host: String,
+    /// Workspace root on the remote machine
+    pub root: String,
+}
+
+impl Remote {
+    /// Parse `host:/path` or `user@host:/path`
+    pub fn parse(target: &str) -> Result<Self> {
+        let (host, root) = target
+            .split_once(':')
+            .filter(|(host, root)| !host.is_empty() && !root.is_empty())
+            .with_context(|| format!("Expected user@host:/path, got {target}"))?;
+        // ssh would read it as an option such as -oProxyCommand=...
+        if host.starts_with('-') {
+            anyhow::bail!("Host must not start with '-', got {host}");
+        }
+        Ok(Remote {
+            host: host.to_string(),
+            root: root.to_string(),
+        })
+    }
+}
+
+/// Quote a value for the remote shell
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Quote a remote path, leaving a leading `~/` for the remote shell to expand
+fn quote_path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", shell_quote(rest)),
+        None => shell_quote(path),
+    }
+}
+
+/// Everything needed to recreate a workspace on another machine
+#[derive(Debug, Clone)]
+pub struct PushPlan {
+    pub remote: Remote,
+    /// Workspace name on the remote machine
+    pub name: String,
+    /// Base the remote workspace is created from, on the remote machine
+    pub remote_base: String,
+    /// realmctl command on the remote machine
+    pub remote_realmctl: String,
+    /// Sharing rules of the local workspace, as `create --share` arguments
+    pub share: Vec<String>,
+    /// Realm and account defaults of the local workspace.toml
+    pub realmlist: Option<String>,
+    pub account: Option<String>,
+    /// Files only the local workspace has, relative to it
+    pub files: Vec<String>,
+    pub bytes: u64,
+}
+
+impl PushPlan {
+    /// Remote workspace directory
+    pub fn remote_dir(&self) -> String {
+        format!("{}/{}", self.remote.root.trim_end_matches('/'), self.name)
+    }
+
+    /// Shell command creating the remote workspace unless it already exists
+    pub fn setup_command(&self) -> String {
+        let mut create = vec![
+            shell_quote(&self.remote_realmctl),
+            "create".to_string(),
+            shell_quote(&self.name),
+            "--base".to_string(),
+            shell_quote(&self.remote_base),
+            "--workspace-root".to_string(),
+            shell_quote(&self.remote.root),
+        ];
+        for rule in &self.share {
+            create.push("--share".to_string());
+            create.push(shell_quote(rule));
+        }
+        for (flag, value) in [("--realm", &self.realmlist), ("--account", &self.account)] {
+            if let Some(value) = value {
+                create.push(flag.to_string());
+                create.push(shell_quote(value));
+            }
+        }
+        format!(
+            "test -f {} || {}",
+            quote_path(&format!("{}/workspace.toml", self.remote_dir())),
+            create.join(" ")
+        )
+    }
+
+    /// Shell command unpacking the transferred files into the remote workspace
+    pub fn unpack_command(&self) -> String {
+        format!("tar -xzf - -C {}", quote_path(&self.remote_dir()))
+    }
+}
+
+/// Work out what pushing a workspace transfers
+///
+/// Without `remote_base`, the base is expected at the same path as on this machine.
+pub fn plan_push(
+    workspace_path: &Path,
+    remote: Remote,
+    name: Option<&str>,
+    remote_base: Option<&str>,
+    remote_realmctl: &str,
+) -> Result<PushPlan> {
+    let config = load_workspace_config(workspace_path)?;
+    let report = plan_deletion(workspace_path)?;
+    let mut share: Vec<String> = config
+        .sharing_rules
+        .iter()
+        .map(|(key, strategy)| format!("{key}={}", strategy.as_str()))
+        .collect();
+    share.sort();
+
+    Ok(PushPlan {
+        remote,
+        name: name.unwrap_or(&config.name).to_string(),
+        remote_base: remote_base
+            .map(str::to_string)
+            .unwrap_or_else(|| config.base_path.to_string_lossy().to_string()),
+        remote_realmctl: remote_realmctl.to_string(),
+        share,
+        realmlist: config.realmlist,
+        account: config.account,
+        bytes: report.unique.iter().map(|(_, size)| size).sum(),
+        files: report.unique.into_iter().map(|(path, _)| path).collect(),
+    })
+}
+
+/// Create the workspace on the remote machine, then stream its unique files over ssh
+pub fn push_workspace(workspace_path: &Path, plan: &PushPlan) -> Result<()> {
+    let status = Command::new("ssh")
+        .arg("--")
+        .arg(&plan.remote.host)
+        .arg(plan.setup_command())
+        .stdout(crate::output::child_stdout())
+        .status()
+        .context("Failed to run ssh")?;
+    if !status.success() {
+        anyhow::bail!(
+            "Creating the workspace on {} failed ({status}); is realmctl installed there and the base at {}?",
+            plan.remote.host,
+            plan.remote_base
+        );
+    }
+    if plan.files.is_empty() {
+        return Ok(());
+    }
+
+    let mut tar = Command::new("tar")
+        .arg("-czf")
+        .arg("-")
+        .arg("-C")
+        .arg(workspace_path)
+        .args(["--null", "-T", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run tar")?;
+    let archive = tar.stdout.take().context("tar has no output")?;
+    let mut ssh = Command::new("ssh")
+        .arg("--")
+        .arg(&plan.remote.host)
+        .arg(plan.unpack_command())
+        .stdin(archive)
+        .stdout(crate::output::child_stdout())
+        .spawn()
+        .context("Failed to run ssh")?;
+    if let Some(mut list) = tar.stdin.take() {
+        // NUL-separated, so names with newlines stay whole and none is read as an option
+        for file in &plan.files {
+            write!(list, "{file}\0")?;
+        }
+    }
+    let packed = tar.wait()?;
+    let unpacked = ssh.wait()?;
+    if !packed.success() || !unpacked.success() {
+        anyhow::bail!(
+            "Transferring files to {}:{} failed (tar {packed}, ssh {unpacked})",
+            plan.remote.host,
+            plan.remote_dir()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote() {
+        assert_eq!(
+            Remote::parse("me@desktop:~/wow_workspaces").unwrap(),
+            Remote {
+                host: "me@desktop".to_string(),
+                root: "~/wow_workspaces".to_string(),
+            }
+        );
+        assert!(Remote::parse("desktop").is_err());
+        assert!(Remote::parse("desktop:").is_err());
+        assert!(Remote::parse("-oProxyCommand=touch pwned:/tmp").is_err());
+    }
+
+    #[test]
+    fn test_setup_command_quotes_paths() {
+        let plan = PushPlan {
+            remote: Remote::parse("desktop:/games/my workspaces/").unwrap(),
+            name: "main".to_string(),
+            remote_base: "/games/it's a base".to_string(),
+            remote_realmctl: "realmctl".to_string(),
+            share: vec!["screenshots=global".to_string()],
+            realmlist: Some("warmane".to_string()),
+            account: None,
+            files: Vec::new(),
+            bytes: 0,
+        };
+        assert_eq!(plan.remote_dir(), "/games/my workspaces/main");
+        assert_eq!(
+            plan.setup_command(),
+            r"test -f '/games/my workspaces/main/workspace.toml' || 'realmctl' create 'main' --base '/games/it'\''s a base' --workspace-root '/games/my workspaces/' --share 'screenshots=global' --realm 'warmane'"
+        );
+        assert_eq!(
+            plan.unpack_command(),
+            "tar -xzf - -C '/games/my workspaces/main'"
+        );
+
+        let home = PushPlan {
+            remote: Remote::parse("desktop:~/wow_workspaces").unwrap(),
+            ..plan
+        };
+        assert_eq!(
+            home.unpack_command(),
+            "tar -xzf - -C ~/'wow_workspaces/main'"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::Path;
+
+/// Extract the realmlist host from realmlist.wtf content (`set realmlist <host>`)
+pub fn parse_realmlist(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let set = parts.next()?;
+        let key = parts.next()?;
+        if !set.eq_ignore_ascii_case("set") || !key.eq_ignore_ascii_case("realmlist") {
+            return None;
+        }
+        let host = parts.collect::<Vec<_>>().join(" ");
+        let host = host.trim_matches('"').trim();
+        (!host.is_empty()).then(|| host.to_string())
+    })
+}
+
+/// Read the realmlist host currently written in a realmlist file
+pub fn read_realmlist(path: &Path) -> std::io::Result<Option<String>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(parse_realmlist(&content))
+}
+
+/// Realmlist history of a game directory, one tab-separated `<timestamp> <from> <to>` line
+/// per switch, `-` standing for no previous realmlist
+pub const HISTORY_FILE: &str = "realmlist.history";
+
+/// A recorded realmlist switch
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealmlistSwitch {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    pub from: Option<String>,
+    pub to: String,
+}
+
+/// Append a switch to the realmlist history of a game directory
+pub fn record_switch(game_dir: &Path, from: Option<&str>, to: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(game_dir.join(HISTORY_FILE))?;
+    writeln!(
+        file,
+        "{}\t{}\t{to}",
+        crate::state::now(),
+        from.unwrap_or("-")
+    )
+}
+
+/// Recorded realmlist switches of a game directory, oldest first
+///
+/// Unreadable lines are skipped, a missing history is empty.
+pub fn load_history(game_dir: &Path) -> std::io::Result<Vec<RealmlistSwitch>> {
+    let content = match std::fs::read_to_string(game_dir.join(HISTORY_FILE)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let at = fields.next()?.parse().ok()?;
+            let from = fields.next()?;
+            let to = fields.next()?;
+            Some(RealmlistSwitch {
+                at,
+                from: (from != "-").then(|| from.to_string()),
+                to: to.to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_realmlist_history() {
+        let temp = TempDir::new().unwrap();
+        assert!(load_history(temp.path()).unwrap().is_empty());
+
+        record_switch(temp.path(), None, "logon.chromiecraft.com").unwrap();
+        record_switch(temp.path(), Some("logon.chromiecraft.com"), "127.0.0.1").unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(temp.path().join(HISTORY_FILE))
+            .and_then(|mut file| std::io::Write::write_all(&mut file, b"garbage\n"))
+            .unwrap();
+
+        let history = load_history(temp.path()).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].from, None);
+        assert_eq!(history[1].from.as_deref(), Some("logon.chromiecraft.com"));
+        assert_eq!(history[1].to, "127.0.0.1");
+    }
+
+    #[test]
+    fn test_parse_realmlist() {
+        assert_eq!(
+            parse_realmlist("set realmlist logon.chromiecraft.com"),
+            Some("logon.chromiecraft.com".to_string())
+        );
+        assert_eq!(
+            parse_realmlist("SET realmList \"127.0.0.1\"\r\nset patchlist localhost\r\n"),
+            Some("127.0.0.1".to_string())
+        );
+        assert_eq!(parse_realmlist("set patchlist localhost"), None);
+        assert_eq!(parse_realmlist(""), None);
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+/// Create `to` as a reflink (copy-on-write clone) of `from`
+///
+/// The clone shares the data blocks of the original until either is written, so it takes
+/// no space up front yet is a file of its own. Needs a filesystem with reflink support
+/// (btrfs, XFS, APFS, ...); elsewhere this fails with [`std::io::ErrorKind::Unsupported`]
+/// or the filesystem's own error and no file is left behind.
+#[cfg(target_os = "linux")]
+pub fn reflink(from: &Path, to: &Path) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+    let source = std::fs::File::open(from)?;
+    let target = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(to)?;
+    // SAFETY: both descriptors stay open for the duration of the call
+    let result = unsafe { libc::ioctl(target.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) };
+    if result != 0 {
+        let error = std::io::Error::last_os_error();
+        drop(target);
+        let _ = std::fs::remove_file(to);
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn reflink(from: &Path, to: &Path) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = |path: &Path| {
+        std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+    };
+    let (from, to) = (c_path(from)?, c_path(to)?);
+    // SAFETY: both paths are valid NUL-terminated strings
+    if unsafe { libc::clonefile(from.as_ptr(), to.as_ptr(), 0) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn reflink(_from: &Path, _to: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "reflinks are only supported on Linux and macOS",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflink_copies_or_leaves_nothing() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let from = temp.path().join("a.MPQ");
+        let to = temp.path().join("b.MPQ");
+        std::fs::write(&from, "data").unwrap();
+        // Whether the temp directory supports reflinks depends on the machine
+        match reflink(&from, &to) {
+            Ok(()) => assert_eq!(std::fs::read(&to).unwrap(), b"data"),
+            Err(_) => assert!(!to.exists()),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Limits on how much of a category is kept; unset limits don't apply
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionPolicy {
+    /// Keep at most this many of the newest entries
+    pub keep: Option<usize>,
+    /// Remove entries older than this
+    pub max_age_days: Option<u64>,
+    /// Remove the oldest entries once the newer ones use this much space
+    pub max_size_mb: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Fill limits this policy leaves unset from `fallback`
+    fn or(&self, fallback: &RetentionPolicy) -> RetentionPolicy {
+        RetentionPolicy {
+            keep: self.keep.or(fallback.keep),
+            max_age_days: self.max_age_days.or(fallback.max_age_days),
+            max_size_mb: self.max_size_mb.or(fallback.max_size_mb),
+        }
+    }
+}
+
+/// Where the entries of a category are found
+pub enum Entries {
+    /// Files and directories in these directories of each game directory
+    Dirs(&'static [&'static str]),
+    /// Lines of this file in each game directory, each starting with a Unix timestamp
+    Lines(&'static str),
+    /// Backups of each workspace, see [`crate::backup`]
+    Backups,
+    /// Archives `delete --archive` and `gc --archive` write to the state directory
+    Archives,
+}
+
+/// Kind of accumulating files `maintain` cleans up
+pub struct RetentionCategory {
+    pub name: &'static str,
+    pub entries: Entries,
+    /// Limits used when config.toml sets none
+    pub default: RetentionPolicy,
+}
+
+/// Categories known to `maintain`, configurable as `[retention.<name>]`
+pub const CATEGORIES: &[RetentionCategory] = &[
+    RetentionCategory {
+        name: "logs",
+        entries: Entries::Dirs(&["Logs"]),
+        default: RetentionPolicy {
+            keep: None,
+            max_age_days: Some(30),
+            max_size_mb: None,
+        },
+    },
+    RetentionCategory {
+        name: "errors",
+        entries: Entries::Dirs(&["Errors"]),
+        default: RetentionPolicy {
+            keep: Some(10),
+            max_age_days: None,
+            max_size_mb: None,
+        },
+    },
+    RetentionCategory {
+        name: "history",
+        entries: Entries::Lines(crate::realm::HISTORY_FILE),
+        default: RetentionPolicy {
+            keep: Some(1000),
+            max_age_days: None,
+            max_size_mb: None,
+        },
+    },
+    RetentionCategory {
+        name: "journal",
+        entries: Entries::Lines(crate::journal::JOURNAL_FILE),
+        default: RetentionPolicy {
+            keep: None,
+            max_age_days: None,
+            max_size_mb: Some(10),
+        },
+    },
+    BACKUPS,
+    RetentionCategory {
+        name: "archives",
+        entries: Entries::Archives,
+        default: RetentionPolicy {
+            keep: Some(20),
+            max_age_days: None,
+            max_size_mb: None,
+        },
+    },
+];
+
+/// Backups of each workspace, also pruned after every new backup
+pub const BACKUPS: RetentionCategory = RetentionCategory {
+    name: "backups",
+    entries: Entries::Backups,
+    default: RetentionPolicy {
+        keep: Some(10),
+        max_age_days: None,
+        max_size_mb: None,
+    },
+};
+
+/// Look up a category by name
+pub fn category(name: &str) -> Option<&'static RetentionCategory> {
+    CATEGORIES.iter().find(|category| category.name == name)
+}
+
+/// The `[retention]` section of config.toml: limits for every category plus
+/// `[retention.<category>]` overrides
+#[derive(Debug, Clone, Default)]
+pub struct RetentionConfig {
+    pub defaults: RetentionPolicy,
+    pub categories: BTreeMap<String, RetentionPolicy>,
+}
+
+impl RetentionConfig {
+    pub fn from_table(table: &toml::Table) -> Result<Self, String> {
+        let mut config = RetentionConfig::default();
+        let mut defaults = toml::Table::new();
+        for (key, value) in table {
+            match value {
+                toml::Value::Table(overrides) => {
+                    if !CATEGORIES.iter().any(|c| c.name == key) {
+                        return Err(format!(
+                            "[retention.{key}] unknown category (expected one of {})",
+                            category_names().join(", ")
+                        ));
+                    }
+                    let policy = toml::Value::Table(overrides.clone())
+                        .try_into()
+                        .map_err(|e| format!("[retention.{key}] {e}"))?;
+                    config.categories.insert(key.clone(), policy);
+                }
+                value => {
+                    defaults.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        config.defaults = toml::Value::Table(defaults)
+            .try_into()
+            .map_err(|e| format!("[retention] {e}"))?;
+        Ok(config)
+    }
+
+    /// Effective limits of a category: its override, then `[retention]`, then built-in defaults
+    pub fn policy(&self, category: &RetentionCategory) -> RetentionPolicy {
+        self.categories
+            .get(category.name)
+            .cloned()
+            .unwrap_or_default()
+            .or(&self.defaults)
+            .or(&category.default)
+    }
+}
+
+pub fn category_names() -> Vec<&'static str> {
+    CATEGORIES.iter().map(|c| c.name).collect()
+}
+
+/// Read the `[retention]` section of a config file
+///
+/// A missing file or section means built-in defaults; an invalid section is reported and
+/// ignored.
+pub fn load_retention(config_path: &str) -> RetentionConfig {
+    let path = crate::paths::expand(config_path);
+    let Ok(source) = std::fs::read_to_string(path) else {
+        return RetentionConfig::default();
+    };
+    let Some(toml::Value::Table(section)) = toml::from_str::<toml::Table>(&source)
+        .ok()
+        .and_then(|mut table| table.remove("retention"))
+    else {
+        return RetentionConfig::default();
+    };
+    RetentionConfig::from_table(&section).unwrap_or_else(|e| {
+        tracing::warn!("⚠ Ignoring invalid [retention] section: {e}");
+        RetentionConfig::default()
+    })
+}
+
+/// A file or directory subject to retention
+#[derive(Debug, Clone)]
+pub struct RetainedItem {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Files and directories in a directory, none when it is missing
+pub fn dir_items(dir: &Path) -> Vec<RetainedItem> {
+    let mut items = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return items;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = path.symlink_metadata() else {
+            continue;
+        };
+        let size = if meta.is_dir() {
+            let (own, linked) = crate::status::disk_usage(&path);
+            own + linked
+        } else {
+            meta.len()
+        };
+        items.push(RetainedItem {
+            path,
+            size,
+            modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+    }
+    items
+}
+
+/// Lines [`trim_lines`] removed, or would remove on a dry run
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Trimmed {
+    pub lines: usize,
+    pub bytes: u64,
+}
+
+/// Drop the lines of a timestamped log a policy expires, keeping the rest in order
+///
+/// Lines that don't start with a timestamp are kept. A missing file has nothing to trim.
+pub fn trim_lines(
+    path: &Path,
+    policy: &RetentionPolicy,
+    now: SystemTime,
+    dry_run: bool,
+) -> std::io::Result<Trimmed> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Trimmed::default()),
+        Err(e) => return Err(e),
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    // Line number, size with its newline and timestamp of each entry
+    let entries = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let at: u64 = line.split('\t').next()?.parse().ok()?;
+            Some((
+                i,
+                line.len() as u64 + 1,
+                UNIX_EPOCH + Duration::from_secs(at),
+            ))
+        })
+        .collect();
+    let removed: Vec<usize> = expired_by(entries, |&(_, size, at)| (size, at), policy, now)
+        .into_iter()
+        .map(|(i, _, _)| i)
+        .collect();
+    let trimmed = Trimmed {
+        lines: removed.len(),
+        bytes: removed.iter().map(|&i| lines[i].len() as u64 + 1).sum(),
+    };
+    if dry_run || removed.is_empty() {
+        return Ok(trimmed);
+    }
+
+    let mut kept = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if !removed.contains(&i) {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    std::fs::write(&temp_path, kept)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(trimmed)
+}
+
+/// Directory of the archives `delete --archive` and `gc --archive` write without a path
+pub fn archives_dir() -> PathBuf {
+    crate::state::state_dir().join("archives")
+}
+
+/// A new archive in [`archives_dir`] named after what it holds
+pub fn default_archive(name: &str) -> PathBuf {
+    archives_dir().join(format!("{name}-{}.tar.gz", crate::state::now()))
+}
+
+/// Items a policy removes, newest entries are kept first
+pub fn expired(
+    items: Vec<RetainedItem>,
+    policy: &RetentionPolicy,
+    now: SystemTime,
+) -> Vec<RetainedItem> {
+    expired_by(items, |item| (item.size, item.modified), policy, now)
+}
+
+/// [`expired`] for entries that aren't files, `entry` gives their size and age
+fn expired_by<T>(
+    mut items: Vec<T>,
+    entry: impl Fn(&T) -> (u64, SystemTime),
+    policy: &RetentionPolicy,
+    now: SystemTime,
+) -> Vec<T> {
+    items.sort_by_key(|item| std::cmp::Reverse(entry(item).1));
+    let max_age = policy
+        .max_age_days
+        .map(|days| Duration::from_secs(days * 24 * 60 * 60));
+    let max_size = policy.max_size_mb.map(|mb| mb * 1024 * 1024);
+
+    let mut kept_size = 0;
+    items
+        .into_iter()
+        .enumerate()
+        .filter(|(i, item)| {
+            let (size, modified) = entry(item);
+            let too_many = policy.keep.is_some_and(|keep| *i >= keep);
+            let too_old = max_age
+                .is_some_and(|max_age| now.duration_since(modified).unwrap_or_default() > max_age);
+            let over_budget = max_size.is_some_and(|max_size| kept_size + size > max_size);
+            let remove = too_many || too_old || over_budget;
+            if !remove {
+                kept_size += size;
+            }
+            remove
+        })
+        .map(|(_, item)| item)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, size: u64, age_days: u64, now: SystemTime) -> RetainedItem {
+        RetainedItem {
+            path: PathBuf::from(name),
+            size,
+            modified: now - Duration::from_secs(age_days * 24 * 60 * 60),
+        }
+    }
+
+    fn names(items: Vec<RetainedItem>) -> Vec<String> {
+        items
+            .into_iter()
+            .map(|item| item.path.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_expired() {
+        let now = SystemTime::now();
+        let items = vec![
+            item("old", 10, 40, now),
+            item("new", 10, 0, now),
+            item("mid", 10, 5, now),
+        ];
+
+        let keep = RetentionPolicy {
+            keep: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(names(expired(items.clone(), &keep, now)), vec!["old"]);
+
+        let age = RetentionPolicy {
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(names(expired(items.clone(), &age, now)), vec!["old"]);
+
+        let size = RetentionPolicy {
+            max_size_mb: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(names(expired(items, &size, now)), vec!["new", "mid", "old"]);
+    }
+
+    #[test]
+    fn test_retention_config() {
+        let table: toml::Table =
+            toml::from_str("keep = 5\nmax_size_mb = 100\n[errors]\nkeep = 2\n").unwrap();
+        let config = RetentionConfig::from_table(&table).unwrap();
+        let errors = category("errors").unwrap();
+        let logs = category("logs").unwrap();
+        assert_eq!(config.policy(errors).keep, Some(2));
+        assert_eq!(config.policy(errors).max_size_mb, Some(100));
+        assert_eq!(config.policy(logs).keep, Some(5));
+        assert_eq!(config.policy(logs).max_age_days, Some(30));
+
+        let table: toml::Table = toml::from_str("[snapshot]\nkeep = 1\n").unwrap();
+        assert!(RetentionConfig::from_table(&table).is_err());
+    }
+
+    #[test]
+    fn test_trim_lines() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join(crate::realm::HISTORY_FILE);
+        let now = SystemTime::now();
+        let day = 24 * 60 * 60;
+        let at = |age_days: u64| {
+            now.duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                - age_days * day
+        };
+        let content = format!(
+            "{}\t-\told.example\nnot a timestamp\n{}\told.example\tmid.example\n{}\tmid.example\tnew.example\n",
+            at(40),
+            at(5),
+            at(0)
+        );
+        std::fs::write(&path, &content).unwrap();
+
+        let age = RetentionPolicy {
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+        let planned = trim_lines(&path, &age, now, true).unwrap();
+        assert_eq!(planned.lines, 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+
+        assert_eq!(trim_lines(&path, &age, now, false).unwrap(), planned);
+        let history = crate::realm::load_history(temp.path()).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].to, "mid.example");
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("not a timestamp\n")
+        );
+
+        let keep = RetentionPolicy {
+            keep: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(trim_lines(&path, &keep, now, false).unwrap().lines, 1);
+        assert_eq!(crate::realm::load_history(temp.path()).unwrap().len(), 1);
+        assert_eq!(
+            trim_lines(&temp.path().join("missing"), &keep, now, false).unwrap(),
+            Trimmed::default()
+        );
+    }
+}
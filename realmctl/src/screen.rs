@@ -0,0 +1,201 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::base::FileRole;
+
+/// Executables and DLLs of stock clients, lowercase, relative to the client (or CASC product)
+/// directory
+const KNOWN_GOOD: &[&str] = &[
+    "wow.exe",
+    "wow-64.exe",
+    "wowerror.exe",
+    "blizzarderror.exe",
+    "launcher.exe",
+    "repair.exe",
+    "backgrounddownloader.exe",
+    "blizzard updater.exe",
+    "blizzard launcher.exe",
+    "world of warcraft launcher.exe",
+    "utils/wowvoiceproxy.exe",
+    "battle.net.dll",
+    "battle.net-64.dll",
+    "dbghelp.dll",
+    "divxdecoder.dll",
+    "fmod.dll",
+    "ijl15.dll",
+    "scan.dll",
+    "unicows.dll",
+    "msvcr80.dll",
+    "microsoft.vc80.crt/msvcr80.dll",
+    "microsoft.vc80.crt/msvcp80.dll",
+    "microsoft.vc80.crt/msvcm80.dll",
+];
+
+/// Extensions of Windows PE images
+const BINARY_EXTENSIONS: &[&str] = &["exe", "dll", "com", "scr", "cpl", "ocx", "sys"];
+
+/// Extensions Windows runs with a script host or shell, never part of a client
+const SCRIPT_EXTENSIONS: &[&str] = &[
+    "bat", "cmd", "ps1", "vbs", "vbe", "js", "jse", "wsf", "hta", "lnk", "reg",
+];
+
+/// System DLLs a client loads from its own directory first, the usual way to inject code
+const PROXY_DLLS: &[&str] = &[
+    "d3d8.dll",
+    "d3d9.dll",
+    "d3d11.dll",
+    "dxgi.dll",
+    "dinput8.dll",
+    "dsound.dll",
+    "version.dll",
+    "winmm.dll",
+    "wininet.dll",
+    "winhttp.dll",
+];
+
+/// Publisher name in the Authenticode certificates of Blizzard's binaries
+const BLIZZARD_SIGNER: &[u8] = b"Blizzard Entertainment";
+
+/// Why a file stands out in a client directory, if it does
+///
+/// `path` is only read for PE images the client is expected to ship, to check who signed them.
+pub fn red_flag(path: &Path, rel_path: &str, role: &FileRole) -> Option<String> {
+    let lower = rel_path.to_lowercase();
+    let name = lower.rsplit('/').next()?;
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+
+    if name == "autorun.inf" {
+        return Some("autorun file, can start programs when the folder is opened".to_string());
+    }
+    if SCRIPT_EXTENSIONS.contains(&extension) {
+        return Some("script Windows runs directly".to_string());
+    }
+    if PROXY_DLLS.contains(&name) {
+        return Some("replaces a system DLL when the client starts".to_string());
+    }
+    if !BINARY_EXTENSIONS.contains(&extension) {
+        return None;
+    }
+
+    if *role != FileRole::Executable && !is_known_good(&lower) {
+        let kind = if extension == "dll" {
+            "DLL"
+        } else {
+            "executable"
+        };
+        return Some(format!("{kind} that is not part of a stock client"));
+    }
+    match signed_by_blizzard(path) {
+        Some(false) => Some("signed, but not by Blizzard Entertainment".to_string()),
+        _ => None,
+    }
+}
+
+/// Whether a lowercase relative path is a stock client file, in the root or a CASC product dir
+fn is_known_good(lower: &str) -> bool {
+    let in_product = lower
+        .split_once('/')
+        .filter(|(dir, _)| dir.len() > 2 && dir.starts_with('_') && dir.ends_with('_'))
+        .map(|(_, rest)| rest);
+    KNOWN_GOOD.contains(&lower) || in_product.is_some_and(|rest| KNOWN_GOOD.contains(&rest))
+}
+
+/// Whether the Authenticode certificate of a PE image names Blizzard, `None` if it has none
+///
+/// Only the signer's name is compared; whether the signature still matches the contents
+/// is not verified.
+pub fn signed_by_blizzard(path: &Path) -> Option<bool> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let read_u32 = |file: &mut std::fs::File, offset: u64| -> Option<u32> {
+        let mut buf = [0u8; 4];
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        file.read_exact(&mut buf).ok()?;
+        Some(u32::from_le_bytes(buf))
+    };
+
+    let pe_offset = u64::from(read_u32(&mut file, 0x3c)?);
+    if read_u32(&mut file, pe_offset)? != u32::from_le_bytes(*b"PE\0\0") {
+        return None;
+    }
+    // Optional header follows the 4 byte signature and the 20 byte COFF header
+    let optional = pe_offset + 24;
+    let magic = read_u32(&mut file, optional)? & 0xffff;
+    let directories = match magic {
+        0x10b => optional + 96,
+        0x20b => optional + 112,
+        _ => return None,
+    };
+    // Entry 4 is the certificate table, addressed by file offset
+    let offset = read_u32(&mut file, directories + 4 * 8)?;
+    let size = read_u32(&mut file, directories + 4 * 8 + 4)?;
+    if offset == 0 || size == 0 {
+        return None;
+    }
+
+    let mut certificates = Vec::new();
+    file.seek(SeekFrom::Start(u64::from(offset))).ok()?;
+    file.take(u64::from(size.min(1 << 20)))
+        .read_to_end(&mut certificates)
+        .ok()?;
+    Some(
+        certificates
+            .windows(BLIZZARD_SIGNER.len())
+            .any(|window| window == BLIZZARD_SIGNER),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Minimal PE32 image with the given certificate table contents
+    fn pe_image(certificate: &[u8]) -> Vec<u8> {
+        let mut image = vec![0u8; 0x200];
+        image[0..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        image[0x80..0x84].copy_from_slice(b"PE\0\0");
+        image[0x98..0x9a].copy_from_slice(&0x10bu16.to_le_bytes());
+        if !certificate.is_empty() {
+            let table = 0x98 + 96 + 4 * 8;
+            image[table..table + 4].copy_from_slice(&0x200u32.to_le_bytes());
+            image[table + 4..table + 8].copy_from_slice(&(certificate.len() as u32).to_le_bytes());
+            image.extend_from_slice(certificate);
+        }
+        image
+    }
+
+    #[test]
+    fn test_signed_by_blizzard() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("Wow.exe");
+
+        std::fs::write(&path, pe_image(b"..Blizzard Entertainment, Inc...")).unwrap();
+        assert_eq!(signed_by_blizzard(&path), Some(true));
+        assert_eq!(red_flag(&path, "Wow.exe", &FileRole::Executable), None);
+
+        std::fs::write(&path, pe_image(b"..Totally Legit Software..")).unwrap();
+        assert_eq!(signed_by_blizzard(&path), Some(false));
+        assert!(red_flag(&path, "Wow.exe", &FileRole::Executable).is_some());
+
+        std::fs::write(&path, pe_image(b"")).unwrap();
+        assert_eq!(signed_by_blizzard(&path), None);
+        std::fs::write(&path, b"not a PE image").unwrap();
+        assert_eq!(signed_by_blizzard(&path), None);
+    }
+
+    #[test]
+    fn test_red_flags() {
+        let missing = Path::new("/nonexistent");
+        let flagged = |rel_path: &str| red_flag(missing, rel_path, &FileRole::Other).is_some();
+        assert!(!flagged("Repair.exe"));
+        assert!(!flagged("Microsoft.VC80.CRT/msvcp80.dll"));
+        assert!(!flagged("_retail_/Utils/WowVoiceProxy.exe"));
+        assert!(!flagged("Interface/AddOns/Helper/Helper.lua"));
+        assert!(flagged("autorun.inf"));
+        assert!(flagged("Interface/AddOns/Helper/setup.js"));
+        assert!(flagged("dxgi.dll"));
+        assert!(flagged("Data/helper.dll"));
+        assert!(flagged("WowHelper.exe"));
+    }
+}
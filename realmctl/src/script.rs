@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::base::FileRole;
+
+/// Upper bound on script operations per call, so a runaway loop can't hang a scan
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Compiled scripts by source, so a scan compiles each profile script once
+static COMPILED: OnceLock<Mutex<HashMap<String, Arc<ProfileScript>>>> = OnceLock::new();
+
+/// Compile a script, reusing an earlier compilation of the same source
+pub fn compiled(source: &str) -> Result<Arc<ProfileScript>> {
+    let cache = COMPILED.get_or_init(Default::default);
+    if let Some(script) = cache.lock().unwrap().get(source) {
+        return Ok(script.clone());
+    }
+    let script = Arc::new(ProfileScript::compile(source)?);
+    cache
+        .lock()
+        .unwrap()
+        .insert(source.to_string(), script.clone());
+    Ok(script)
+}
+
+/// A compiled Rhai profile script
+///
+/// Scripts may define `classify(path)` returning a role name such as `"MutableData"`,
+/// and `warn(path)` returning a warning message. Returning nothing (`()`) falls back
+/// to the profile's regular rules.
+pub struct ProfileScript {
+    engine: rhai::Engine,
+    ast: rhai::AST,
+}
+
+impl fmt::Debug for ProfileScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProfileScript").finish_non_exhaustive()
+    }
+}
+
+impl ProfileScript {
+    pub fn compile(source: &str) -> Result<Self> {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| anyhow::anyhow!("{e}"))
+            .context("Failed to compile profile script")?;
+        Ok(ProfileScript { engine, ast })
+    }
+
+    fn defines(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+
+    fn call(&self, name: &str, rel_path: &str) -> Option<String> {
+        if !self.defines(name) {
+            return None;
+        }
+        let result = self.engine.call_fn::<rhai::Dynamic>(
+            &mut rhai::Scope::new(),
+            &self.ast,
+            name,
+            (rel_path.to_string(),),
+        );
+        match result {
+            Ok(value) if value.is_unit() => None,
+            Ok(value) => match value.into_string() {
+                Ok(value) => Some(value),
+                Err(kind) => {
+                    tracing::warn!(
+                        "⚠ Profile script {name}({rel_path}) returned {kind}, expected a string"
+                    );
+                    None
+                }
+            },
+            Err(e) => {
+                tracing::warn!("⚠ Profile script {name}({rel_path}) failed: {e}");
+                None
+            }
+        }
+    }
+
+    /// Role the script assigns to a path, if any
+    pub fn classify(&self, rel_path: &str) -> Option<FileRole> {
+        let name = self.call("classify", rel_path)?;
+        let role = FileRole::from_name(&name);
+        if role.is_none() {
+            tracing::warn!("⚠ Profile script returned unknown role '{name}' for {rel_path}");
+        }
+        role
+    }
+
+    /// Warning the script raises for a path, if any
+    pub fn warn(&self, rel_path: &str) -> Option<String> {
+        self.call("warn", rel_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_classifies_late_patches() {
+        let script = ProfileScript::compile(
+            r#"
+fn classify(path) {
+    if path.starts_with("Data/patch-") && path.ends_with(".MPQ") {
+        if path.sub_string(11, 1) > "J" {
+            return "MutableData";
+        }
+        return "BaseData";
+    }
+}
+
+fn warn(path) {
+    if path.ends_with(".exe") && path != "Wow.exe" {
+        return `unexpected executable ${path}`;
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            script.classify("Data/patch-K.MPQ"),
+            Some(FileRole::MutableData)
+        );
+        assert_eq!(
+            script.classify("Data/patch-A.MPQ"),
+            Some(FileRole::BaseData)
+        );
+        assert_eq!(script.classify("WTF"), None);
+        assert_eq!(
+            script.warn("Launcher.exe").as_deref(),
+            Some("unexpected executable Launcher.exe")
+        );
+        assert_eq!(script.warn("Wow.exe"), None);
+    }
+
+    #[test]
+    fn test_script_runaway_loop_is_stopped() {
+        let script = ProfileScript::compile("fn classify(path) { loop {} }").unwrap();
+        assert_eq!(script.classify("Wow.exe"), None);
+    }
+}
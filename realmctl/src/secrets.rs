@@ -0,0 +1,582 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::outln;
+
+/// Name of the encrypted companion file stored next to config.toml
+pub const SECRETS_FILE: &str = "accounts.enc";
+
+/// Keys a secrets table is allowed to set on a game entry
+pub const SECRET_KEYS: &[&str] = &["account", "password", "accounts"];
+
+/// Service and account name the age identity is stored under in the OS keyring
+const KEYRING_SERVICE: &str = "realmctl";
+const KEYRING_ACCOUNT: &str = "age-identity";
+/// Service passwords typed at the launch prompt are cached under, see [`cache_password`]
+const PASSWORD_SERVICE: &str = "realmctl-password";
+
+/// Seconds a cached password is used when `password_cache_secs` is unset
+pub const DEFAULT_PASSWORD_CACHE_SECS: u64 = 12 * 60 * 60;
+
+const TEMPLATE: &str = "# Decrypted realmctl secrets, merged into config.toml at launch.
+# Only account, password and accounts are read from this file.
+#
+# [Chromie]
+# accounts.main = \"password1\"
+";
+
+/// How accounts.enc is encrypted
+#[derive(Debug, Clone, PartialEq)]
+pub enum Protection {
+    /// An age identity file (age.key or `REALMCTL_AGE_IDENTITY`)
+    Identity(PathBuf),
+    /// An age identity kept in the OS keyring (Secret Service, Keychain, Credential Manager)
+    Keyring,
+    /// A master passphrase age asks for on the terminal
+    Passphrase,
+}
+
+impl std::fmt::Display for Protection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protection::Identity(path) => write!(f, "identity file {}", path.display()),
+            Protection::Keyring => write!(f, "OS keyring"),
+            Protection::Passphrase => write!(f, "master passphrase"),
+        }
+    }
+}
+
+/// Path of the secrets file belonging to a config file
+pub fn secrets_path(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(SECRETS_FILE)
+}
+
+/// age (or rage) binary to run, `REALMCTL_AGE` takes precedence
+fn age_binary() -> String {
+    std::env::var("REALMCTL_AGE").unwrap_or_else(|_| "age".to_string())
+}
+
+/// Identity file used to decrypt and re-encrypt secrets
+///
+/// `REALMCTL_AGE_IDENTITY` takes precedence over `age.key` next to config.toml.
+pub fn identity_path(config_path: &Path) -> PathBuf {
+    match std::env::var("REALMCTL_AGE_IDENTITY") {
+        Ok(path) => PathBuf::from(shellexpand::tilde(&path).to_string()),
+        Err(_) => config_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("age.key"),
+    }
+}
+
+/// Whether an age file was encrypted with a passphrase (its header has an scrypt stanza)
+fn is_passphrase_encrypted(path: &Path) -> bool {
+    let Ok(content) = std::fs::read(path) else {
+        return false;
+    };
+    content
+        .split(|b| *b == b'\n')
+        .take_while(|line| !line.starts_with(b"---"))
+        .any(|line| line.starts_with(b"-> scrypt"))
+}
+
+/// Protection of the existing secrets file: a passphrase if it was encrypted with one,
+/// else the identity file if it exists, else the OS keyring
+pub fn current_protection(config_path: &Path) -> Protection {
+    if is_passphrase_encrypted(&secrets_path(config_path)) {
+        return Protection::Passphrase;
+    }
+    let identity = identity_path(config_path);
+    if identity.exists() {
+        Protection::Identity(identity)
+    } else {
+        Protection::Keyring
+    }
+}
+
+/// Decrypt the secrets file next to the config, if there is one
+pub fn load_secrets(config_path: &Path) -> Result<Option<toml::Table>> {
+    let path = secrets_path(config_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let plaintext = decrypt(&path, config_path)?;
+    let table: toml::Table = toml::from_str(&plaintext)
+        .with_context(|| format!("Failed to parse decrypted {}", path.display()))?;
+    Ok(Some(table))
+}
+
+/// Merge the secret keys of the matching game table into a game entry
+pub fn apply_secrets(game_value: &mut toml::Value, secrets: &toml::Table, game: &str) {
+    let Some(secret) = secrets
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(game))
+        .and_then(|(_, value)| value.as_table())
+    else {
+        return;
+    };
+    let Some(game_table) = game_value.as_table_mut() else {
+        return;
+    };
+
+    for key in SECRET_KEYS {
+        match (secret.get(*key), game_table.get_mut(*key)) {
+            (Some(toml::Value::Table(extra)), Some(toml::Value::Table(existing))) => {
+                for (name, value) in extra {
+                    existing.insert(name.clone(), value.clone());
+                }
+            }
+            (Some(value), _) => {
+                game_table.insert(key.to_string(), value.clone());
+            }
+            (None, _) => {}
+        }
+    }
+}
+
+/// Split `account`, `password` or `accounts.<name>` into the game key and account name
+fn parse_secret_key(key: &str) -> Result<(&str, Option<&str>)> {
+    match key.split_once('.') {
+        Some(("accounts", name)) if !name.is_empty() => Ok(("accounts", Some(name))),
+        None if key == "account" || key == "password" => Ok((key, None)),
+        _ => {
+            anyhow::bail!("Unknown secret '{key}' (expected account, password or accounts.<name>)")
+        }
+    }
+}
+
+/// Set a secret of a game, creating its table as needed
+pub fn set_secret(secrets: &mut toml::Table, game: &str, key: &str, value: &str) -> Result<()> {
+    let (key, account) = parse_secret_key(key)?;
+    let game_table = secrets
+        .entry(game.to_string())
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .with_context(|| format!("[{game}] in the secrets is not a table"))?;
+    let value = toml::Value::String(value.to_string());
+    match account {
+        Some(account) => {
+            game_table
+                .entry("accounts")
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .with_context(|| format!("{game}.accounts in the secrets is not a table"))?
+                .insert(account.to_string(), value);
+        }
+        None => {
+            game_table.insert(key.to_string(), value);
+        }
+    }
+    Ok(())
+}
+
+/// Value of a secret of a game; game names match case-insensitively
+pub fn get_secret(secrets: &toml::Table, game: &str, key: &str) -> Result<Option<String>> {
+    let (key, account) = parse_secret_key(key)?;
+    let value = secrets
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(game))
+        .and_then(|(_, table)| table.get(key));
+    let value = match account {
+        Some(account) => value.and_then(|accounts| accounts.get(account)),
+        None => value,
+    };
+    Ok(value.and_then(|v| v.as_str()).map(str::to_string))
+}
+
+/// Remove a secret of a game, dropping tables left empty; returns whether it existed
+pub fn remove_secret(secrets: &mut toml::Table, game: &str, key: &str) -> Result<bool> {
+    let (key, account) = parse_secret_key(key)?;
+    let Some(game_name) = secrets
+        .keys()
+        .find(|name| name.eq_ignore_ascii_case(game))
+        .cloned()
+    else {
+        return Ok(false);
+    };
+    let Some(game_table) = secrets[&game_name].as_table_mut() else {
+        return Ok(false);
+    };
+    let removed = match account {
+        Some(account) => {
+            let removed = game_table
+                .get_mut(key)
+                .and_then(|accounts| accounts.as_table_mut())
+                .is_some_and(|accounts| accounts.remove(account).is_some());
+            if game_table
+                .get(key)
+                .and_then(|accounts| accounts.as_table())
+                .is_some_and(|accounts| accounts.is_empty())
+            {
+                game_table.remove(key);
+            }
+            removed
+        }
+        None => game_table.remove(key).is_some(),
+    };
+    if game_table.is_empty() {
+        secrets.remove(&game_name);
+    }
+    Ok(removed)
+}
+
+fn decrypt(path: &Path, config_path: &Path) -> Result<String> {
+    let output = match current_protection(config_path) {
+        // age asks for the passphrase on the terminal itself
+        Protection::Passphrase => Command::new(age_binary())
+            .arg("--decrypt")
+            .arg(path)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .output(),
+        Protection::Identity(identity) => Command::new(age_binary())
+            .arg("--decrypt")
+            .arg("--identity")
+            .arg(identity)
+            .arg(path)
+            .output(),
+        // The identity goes through a pipe, it is never written to disk
+        Protection::Keyring => run_with_input(
+            Command::new(age_binary())
+                .args(["--decrypt", "--identity", "-"])
+                .arg(path),
+            &keyring_identity(config_path)?,
+        ),
+    }
+    .with_context(|| format!("Failed to run {} (is age installed?)", age_binary()))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to decrypt {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).context("Decrypted secrets are not valid UTF-8")
+}
+
+fn encrypt(
+    plaintext_path: &Path,
+    output_path: &Path,
+    config_path: &Path,
+    protection: &Protection,
+) -> Result<()> {
+    let mut command = Command::new(age_binary());
+    command.arg("--encrypt");
+    match protection {
+        Protection::Identity(identity) => command.arg("--identity").arg(identity),
+        Protection::Keyring => command.args(["--identity", "-"]),
+        Protection::Passphrase => command.arg("--passphrase"),
+    };
+    command.arg("--output").arg(output_path).arg(plaintext_path);
+    let result = match protection {
+        Protection::Keyring => {
+            run_with_input(&mut command, &keyring_identity(config_path)?).map(|output| {
+                (
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).to_string(),
+                )
+            })
+        }
+        _ => command.status().map(|status| (status, String::new())),
+    };
+    let (status, stderr) =
+        result.with_context(|| format!("Failed to run {} (is age installed?)", age_binary()))?;
+
+    if !status.success() {
+        anyhow::bail!(
+            "Failed to encrypt {}: {}",
+            output_path.display(),
+            stderr.trim()
+        );
+    }
+    Ok(())
+}
+
+/// The age identity in the OS keyring, an error naming the alternatives if there is none
+fn keyring_identity(config_path: &Path) -> Result<String> {
+    keyring_load()?.with_context(|| {
+        format!(
+            "No age identity found in {} or the OS keyring (store one with `realmctl secrets set --keyring`)",
+            identity_path(config_path).display()
+        )
+    })
+}
+
+/// Run a command with `input` on stdin and return its output
+fn run_with_input(command: &mut Command, input: &str) -> std::io::Result<std::process::Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    child.wait_with_output()
+}
+
+fn keyring_entry() -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_ACCOUNT).context("Failed to open the OS keyring")
+}
+
+/// Age identity stored in the OS keyring, if any
+fn keyring_load() -> Result<Option<String>> {
+    match keyring_entry()?.get_password() {
+        Ok(identity) => Ok(Some(identity)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e).context("Failed to read the age identity from the OS keyring"),
+    }
+}
+
+fn keyring_store(identity: &str) -> Result<()> {
+    keyring_entry()?
+        .set_password(identity)
+        .context("Failed to store the age identity in the OS keyring")
+}
+
+/// Keyring entry of a cached password; accounts are per server, so per game directory
+fn password_entry(game_dir: &Path, account: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(
+        PASSWORD_SERVICE,
+        &format!("{account}@{}", game_dir.display()),
+    )
+    .context("Failed to open the OS keyring")
+}
+
+/// Store a password typed at the launch prompt in the OS keyring for `ttl`
+pub fn cache_password(game_dir: &Path, account: &str, password: &str, ttl: Duration) -> Result<()> {
+    let expires = (SystemTime::now() + ttl)
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    password_entry(game_dir, account)?
+        .set_password(&format!("{expires}\n{password}"))
+        .context("Failed to store the password in the OS keyring")
+}
+
+/// Password cached by [`cache_password`]; an expired one is removed from the keyring
+pub fn cached_password(game_dir: &Path, account: &str) -> Option<String> {
+    let entry = password_entry(game_dir, account).ok()?;
+    let value = entry.get_password().ok()?;
+    let (expires, password) = value.split_once('\n')?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if expires.parse::<u64>().is_ok_and(|expires| expires > now) {
+        return Some(password.to_string());
+    }
+    let _ = entry.delete_credential();
+    None
+}
+
+/// Generate an age identity and store it in the OS keyring, unless one is there already
+fn ensure_keyring_identity() -> Result<()> {
+    if keyring_load()?.is_some() {
+        return Ok(());
+    }
+    let keygen = format!("{}-keygen", age_binary());
+    let output = Command::new(&keygen)
+        .output()
+        .with_context(|| format!("Failed to run {keygen}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{keygen} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    keyring_store(&String::from_utf8_lossy(&output.stdout))?;
+    outln!("✓ Generated an age identity and stored it in the OS keyring");
+    Ok(())
+}
+
+/// Decrypted secrets, empty if there is no secrets file yet
+pub fn read_secrets(config_path: &Path) -> Result<toml::Table> {
+    Ok(load_secrets(config_path)?.unwrap_or_default())
+}
+
+/// Encrypt a secrets table to accounts.enc
+///
+/// `protection` switches how the file is encrypted; `None` keeps the current protection.
+pub fn write_secrets(
+    config_path: &Path,
+    secrets: &toml::Table,
+    protection: Option<Protection>,
+) -> Result<()> {
+    let path = secrets_path(config_path);
+    let protection = protection.unwrap_or_else(|| {
+        if path.exists() {
+            current_protection(config_path)
+        } else {
+            Protection::Identity(identity_path(config_path))
+        }
+    });
+    match &protection {
+        Protection::Identity(identity) if !identity.exists() => anyhow::bail!(
+            "age identity not found: {} (create one with `age-keygen -o {}`, or use --keyring or --passphrase)",
+            identity.display(),
+            identity.display()
+        ),
+        Protection::Keyring => ensure_keyring_identity()?,
+        _ => {}
+    }
+
+    let plaintext = toml::to_string_pretty(secrets)?;
+    let temp = write_private(config_path, &format!("{TEMPLATE}\n{plaintext}"))?;
+    // Encrypt next to the target so a failed run never truncates the existing file
+    let encrypted_path = path.with_extension("enc.tmp");
+    let result = encrypt(temp.path(), &encrypted_path, config_path, &protection)
+        .and_then(|_| Ok(std::fs::rename(&encrypted_path, &path)?));
+    let _ = std::fs::remove_file(&encrypted_path);
+    result
+}
+
+/// Decrypt the secrets to a private temp file, open it in $EDITOR and re-encrypt it
+pub fn edit_secrets(config_path: &Path) -> Result<()> {
+    let path = secrets_path(config_path);
+    let protection = current_protection(config_path);
+    if let Protection::Identity(identity) = &protection
+        && !identity.exists()
+    {
+        anyhow::bail!(
+            "age identity not found: {} (create one with `age-keygen -o {}`)",
+            identity.display(),
+            identity.display()
+        );
+    }
+
+    let plaintext = if path.exists() {
+        decrypt(&path, config_path)?
+    } else {
+        TEMPLATE.to_string()
+    };
+
+    // Removed when dropped, decrypted secrets are never left behind, even if editing failed
+    let temp = write_private(config_path, &plaintext)?;
+    edit_and_encrypt(temp.path(), &path, config_path, &protection)
+}
+
+fn edit_and_encrypt(
+    temp_path: &Path,
+    path: &Path,
+    config_path: &Path,
+    protection: &Protection,
+) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+
+    let status = Command::new(&editor)
+        .arg(temp_path)
+        .status()
+        .with_context(|| format!("Failed to start editor: {editor}"))?;
+    if !status.success() {
+        anyhow::bail!("Editor exited with {status}, secrets left unchanged");
+    }
+
+    let edited = std::fs::read_to_string(temp_path)?;
+    toml::from_str::<toml::Table>(&edited)
+        .context("Edited secrets are not valid TOML, secrets left unchanged")?;
+
+    encrypt(temp_path, path, config_path, protection)?;
+    outln!("✓ Secrets encrypted to {}", path.display());
+    Ok(())
+}
+
+/// Write decrypted secrets to a file only the current user can read, next to the config
+/// under a random name; the file is removed when the handle is dropped
+fn write_private(config_path: &Path, content: &str) -> Result<tempfile::NamedTempFile> {
+    let dir = config_path.parent().unwrap_or_else(|| Path::new("."));
+    // Created with mode 0600 on Unix
+    let mut file = tempfile::Builder::new()
+        .prefix(".realmctl-secrets-")
+        .suffix(".toml")
+        .tempfile_in(dir)
+        .with_context(|| format!("Failed to create a private file in {}", dir.display()))?;
+    file.write_all(content.as_bytes())?;
+    file.flush()?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_secrets_merges_only_credentials() {
+        let mut game: toml::Value = toml::from_str(
+            r#"
+directory = "/games/wow335"
+accounts.main = "plain"
+"#,
+        )
+        .unwrap();
+        let secrets: toml::Table = toml::from_str(
+            r#"
+[chromie]
+password = "hunter2"
+directory = "/ignored"
+accounts.alt = "secret"
+"#,
+        )
+        .unwrap();
+
+        apply_secrets(&mut game, &secrets, "Chromie");
+
+        assert_eq!(game["password"].as_str(), Some("hunter2"));
+        assert_eq!(game["directory"].as_str(), Some("/games/wow335"));
+        assert_eq!(game["accounts"]["main"].as_str(), Some("plain"));
+        assert_eq!(game["accounts"]["alt"].as_str(), Some("secret"));
+    }
+
+    #[test]
+    fn test_set_get_remove_secret() {
+        let mut secrets = toml::Table::new();
+        set_secret(&mut secrets, "Chromie", "password", "hunter2").unwrap();
+        set_secret(&mut secrets, "Chromie", "accounts.alt", "secret").unwrap();
+        assert_eq!(
+            get_secret(&secrets, "chromie", "accounts.alt").unwrap(),
+            Some("secret".to_string())
+        );
+        assert!(set_secret(&mut secrets, "Chromie", "directory", "/x").is_err());
+
+        assert!(remove_secret(&mut secrets, "chromie", "accounts.alt").unwrap());
+        assert!(secrets["Chromie"].get("accounts").is_none());
+        assert!(!remove_secret(&mut secrets, "Chromie", "accounts.alt").unwrap());
+        assert!(remove_secret(&mut secrets, "Chromie", "password").unwrap());
+        assert!(secrets.is_empty());
+    }
+
+    #[test]
+    fn test_passphrase_header_detection() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join(SECRETS_FILE);
+        std::fs::write(
+            &path,
+            "age-encryption.org/v1\n-> scrypt c2FsdA 18\nYm9keQ\n--- aGVhZGVy\n\u{1}-> scrypt",
+        )
+        .unwrap();
+        assert!(is_passphrase_encrypted(&path));
+        std::fs::write(
+            &path,
+            "age-encryption.org/v1\n-> X25519 a2V5\nYm9keQ\n--- aGVhZGVy\n-> scrypt",
+        )
+        .unwrap();
+        assert!(!is_passphrase_encrypted(&path));
+    }
+}
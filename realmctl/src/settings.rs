@@ -0,0 +1,381 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Describes the archive, next to its WTF directory
+pub const SETTINGS_MANIFEST: &str = "realmctl-settings.toml";
+
+/// Account folders of a stripped archive are named `ACCOUNT1`, `ACCOUNT2`, ...
+pub const ACCOUNT_PLACEHOLDER: &str = "ACCOUNT";
+
+/// Config.wtf settings naming the accounts of the player
+const ACCOUNT_SETTINGS: &[&str] = &["accountName", "accountList", "lastAccountName"];
+
+const STAGING_PREFIX: &str = ".realmctl-settings-";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SettingsManifest {
+    /// Client version the settings were exported from (e.g., "3.3.5a")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    /// Number of account folders renamed to placeholders, 0 when accounts were kept
+    #[serde(default)]
+    pub stripped_accounts: usize,
+}
+
+/// WTF directory of a client, next to the executable
+pub fn wtf_dir(game_dir: &Path, executable: &str) -> PathBuf {
+    crate::wtf::config_wtf_path(game_dir, executable)
+        .parent()
+        .map_or_else(|| game_dir.join("WTF"), Path::to_path_buf)
+}
+
+/// Staging directory next to `wtf_dir`, removed and created again
+pub fn staging_dir(wtf_dir: &Path) -> Result<PathBuf> {
+    let staging = wtf_dir.with_file_name(format!("{STAGING_PREFIX}{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::create_dir_all(&staging)
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+    Ok(staging)
+}
+
+/// Backup copies and client leftovers not worth transferring
+fn is_leftover(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        ["bak", "old"]
+            .iter()
+            .any(|leftover| ext.eq_ignore_ascii_case(leftover))
+    })
+}
+
+/// Files below `dir` worth transferring, see [`is_leftover`]
+fn collect_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = crate::paths::files_under(dir);
+    files.retain(|path| !is_leftover(path));
+    files
+}
+
+/// Copy a WTF directory to `<staging>/WTF` and write the manifest next to it
+///
+/// With `strip_accounts` the account folders below `Account` are renamed to
+/// [`ACCOUNT_PLACEHOLDER`]s and the account settings are removed from Config.wtf.
+/// Names inside SavedVariables, written by addons, are left as they are.
+pub fn stage_export(
+    wtf_dir: &Path,
+    staging: &Path,
+    client: Option<String>,
+    strip_accounts: bool,
+) -> Result<SettingsManifest> {
+    if !wtf_dir.is_dir() {
+        bail!(
+            "{} does not exist, start the client once",
+            wtf_dir.display()
+        );
+    }
+    let mut accounts: Vec<String> = std::fs::read_dir(wtf_dir.join("Account"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    accounts.sort();
+    let placeholder = |account: &str| -> Option<String> {
+        let index = accounts.iter().position(|a| a == account)?;
+        Some(format!("{ACCOUNT_PLACEHOLDER}{}", index + 1))
+    };
+
+    for file in collect_files(wtf_dir) {
+        crate::cancel::check()?;
+        let rel_path = file.strip_prefix(wtf_dir).unwrap_or(&file);
+        let mut components: Vec<String> = rel_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        if strip_accounts
+            && components.len() > 1
+            && components[0].eq_ignore_ascii_case("Account")
+            && let Some(name) = placeholder(&components[1])
+        {
+            components[1] = name;
+        }
+        let target = components
+            .iter()
+            .fold(staging.join("WTF"), |path, c| path.join(c));
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let is_config = components.len() == 1 && components[0].eq_ignore_ascii_case("Config.wtf");
+        if strip_accounts && is_config {
+            let content = String::from_utf8_lossy(&std::fs::read(&file)?).into_owned();
+            std::fs::write(
+                &target,
+                crate::wtf::remove_values(&content, ACCOUNT_SETTINGS),
+            )?;
+        } else {
+            std::fs::copy(&file, &target)
+                .with_context(|| format!("Failed to copy {}", file.display()))?;
+        }
+    }
+
+    let manifest = SettingsManifest {
+        client,
+        stripped_accounts: if strip_accounts { accounts.len() } else { 0 },
+    };
+    std::fs::write(
+        staging.join(SETTINGS_MANIFEST),
+        toml::to_string(&manifest).context("Failed to serialize the manifest")?,
+    )?;
+    Ok(manifest)
+}
+
+/// tar flag compressing for an archive name, zstd unless it names another format
+fn compression_flag(archive: Option<&Path>) -> Option<&'static str> {
+    let name = archive
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some("-z")
+    } else if name.ends_with(".tar") {
+        None
+    } else {
+        Some("--zstd")
+    }
+}
+
+/// Pack a staging directory into `archive`, or to stdout when None
+pub fn pack(staging: &Path, archive: Option<&Path>) -> Result<()> {
+    let mut tar = std::process::Command::new("tar");
+    tar.arg("-c").args(compression_flag(archive));
+    match archive {
+        Some(archive) => tar.arg("-f").arg(archive),
+        None => tar.args(["-f", "-"]),
+    };
+    let status = tar
+        .arg("-C")
+        .arg(staging)
+        .args([SETTINGS_MANIFEST, "WTF"])
+        .status()
+        .context("Failed to run tar")?;
+    if !status.success() {
+        bail!("tar failed to pack the settings ({status})");
+    }
+    Ok(())
+}
+
+/// Unpack a settings archive into `staging` and read its manifest
+///
+/// tar recognises the compression of a file but not of a pipe, so an archive coming
+/// from stdin is saved to the staging directory first.
+pub fn unpack(archive: Option<&Path>, staging: &Path) -> Result<SettingsManifest> {
+    let saved = staging.join("settings.archive");
+    let archive = match archive {
+        Some(archive) => archive.to_path_buf(),
+        None => {
+            let mut file = std::fs::File::create(&saved)?;
+            std::io::copy(&mut std::io::stdin().lock(), &mut file)
+                .context("Failed to read the archive from stdin")?;
+            saved.clone()
+        }
+    };
+    let extracted = staging.join("extracted");
+    std::fs::create_dir_all(&extracted)?;
+    let status = std::process::Command::new("tar")
+        .arg("-xf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&extracted)
+        .status()
+        .context("Failed to run tar")?;
+    let _ = std::fs::remove_file(&saved);
+    if !status.success() {
+        bail!("tar failed to unpack {} ({status})", archive.display());
+    }
+    let manifest = std::fs::read_to_string(extracted.join(SETTINGS_MANIFEST))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok());
+    // A WTF link would point the import at whatever it names
+    let wtf = std::fs::symlink_metadata(extracted.join("WTF"));
+    match manifest {
+        Some(manifest) if wtf.is_ok_and(|meta| meta.is_dir()) => Ok(manifest),
+        _ => bail!("Not a settings archive exported by realmctl"),
+    }
+}
+
+/// Files copied by [`import_settings`]
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Paths relative to WTF
+    pub imported: Vec<String>,
+    /// Files replaced, their previous versions are in [`backup_dir`]
+    pub replaced: usize,
+}
+
+/// Where [`import_settings`] keeps the files it replaced, next to WTF
+pub fn backup_dir(wtf_dir: &Path) -> PathBuf {
+    let mut name = wtf_dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".realmctl-backup");
+    wtf_dir.with_file_name(name)
+}
+
+/// Copy unpacked settings into a WTF directory, keeping files the archive doesn't have
+///
+/// Symlinks in the archive are skipped.
+///
+/// Files that get replaced are copied to [`backup_dir`] first, which replaces the backup
+/// of the previous import. `account` names the folder of a stripped archive's single
+/// account; without it placeholders are imported as they are.
+pub fn import_settings(
+    staging: &Path,
+    manifest: &SettingsManifest,
+    wtf_dir: &Path,
+    account: Option<&str>,
+) -> Result<ImportReport> {
+    let rename = match account {
+        Some(account) if manifest.stripped_accounts == 1 => {
+            Some((format!("{ACCOUNT_PLACEHOLDER}1"), account.to_uppercase()))
+        }
+        Some(_) if manifest.stripped_accounts > 1 => bail!(
+            "The archive holds {} stripped accounts, import it without --account and rename \
+             the WTF/Account/{ACCOUNT_PLACEHOLDER}<n> folders yourself",
+            manifest.stripped_accounts
+        ),
+        _ => None,
+    };
+
+    let source = staging.join("extracted").join("WTF");
+    let backup = backup_dir(wtf_dir);
+    let _ = std::fs::remove_dir_all(&backup);
+    let mut report = ImportReport::default();
+    for file in collect_files(&source) {
+        crate::cancel::check()?;
+        let mut rel_path = file
+            .strip_prefix(&source)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if let Some((placeholder, account)) = &rename
+            && let Some(rest) = rel_path.strip_prefix(&format!("Account/{placeholder}/"))
+        {
+            rel_path = format!("Account/{account}/{rest}");
+        }
+        let target = wtf_dir.join(&rel_path);
+        if target.is_file() {
+            let saved = backup.join(&rel_path);
+            if let Some(parent) = saved.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&target, &saved)
+                .with_context(|| format!("Failed to back up {}", target.display()))?;
+            report.replaced += 1;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&file, &target)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        report.imported.push(rel_path);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_and_import_settings() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let wtf = temp.path().join("source/WTF");
+        std::fs::create_dir_all(wtf.join("Account/PLAYER/SavedVariables")).unwrap();
+        std::fs::write(
+            wtf.join("Config.wtf"),
+            "SET accountName \"PLAYER\"\r\nSET gxWindow \"1\"\r\n",
+        )
+        .unwrap();
+        std::fs::write(wtf.join("Account/PLAYER/SavedVariables/Questie.lua"), "a").unwrap();
+        std::fs::write(
+            wtf.join("Account/PLAYER/SavedVariables/Questie.lua.bak"),
+            "b",
+        )
+        .unwrap();
+
+        let staging = staging_dir(&wtf).unwrap();
+        let manifest = stage_export(&wtf, &staging, Some("3.3.5a".to_string()), true).unwrap();
+        assert_eq!(manifest.stripped_accounts, 1);
+        let exported = staging.join("WTF");
+        assert_eq!(
+            std::fs::read_to_string(exported.join("Config.wtf")).unwrap(),
+            "SET gxWindow \"1\"\r\n"
+        );
+        assert!(
+            exported
+                .join("Account/ACCOUNT1/SavedVariables/Questie.lua")
+                .is_file()
+        );
+        assert!(
+            !exported
+                .join("Account/ACCOUNT1/SavedVariables/Questie.lua.bak")
+                .exists()
+        );
+
+        // Import as unpack leaves it, into a client that already has settings
+        std::fs::rename(&staging, temp.path().join("extracted")).unwrap();
+        let target = temp.path().join("target/WTF");
+        std::fs::create_dir_all(&target).unwrap();
+        std::fs::write(target.join("Config.wtf"), "SET gxWindow \"0\"\r\n").unwrap();
+        std::fs::write(target.join("Keep.txt"), "mine").unwrap();
+        let report = import_settings(temp.path(), &manifest, &target, Some("me")).unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert_eq!(report.replaced, 1);
+        assert!(
+            target
+                .join("Account/ME/SavedVariables/Questie.lua")
+                .is_file()
+        );
+        assert!(target.join("Keep.txt").is_file());
+        assert_eq!(
+            std::fs::read_to_string(backup_dir(&target).join("Config.wtf")).unwrap(),
+            "SET gxWindow \"0\"\r\n"
+        );
+        assert!(
+            import_settings(
+                temp.path(),
+                &SettingsManifest {
+                    stripped_accounts: 2,
+                    ..manifest
+                },
+                &target,
+                Some("me")
+            )
+            .is_err()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_import_skips_symlinks() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let outside = temp.path().join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), "not settings").unwrap();
+        let packed = temp.path().join("packed");
+        std::fs::create_dir_all(packed.join("WTF")).unwrap();
+        std::fs::write(packed.join("WTF/Config.wtf"), "SET gxWindow \"1\"\r\n").unwrap();
+        std::os::unix::fs::symlink(&outside, packed.join("WTF/escape")).unwrap();
+        std::os::unix::fs::symlink(".", packed.join("WTF/loop")).unwrap();
+        let manifest = toml::to_string(&SettingsManifest::default()).unwrap();
+        std::fs::write(packed.join(SETTINGS_MANIFEST), manifest).unwrap();
+        let archive = temp.path().join("settings.tar");
+        pack(&packed, Some(&archive)).unwrap();
+
+        let staging = temp.path().join("staging");
+        let manifest = unpack(Some(&archive), &staging).unwrap();
+        let target = temp.path().join("target/WTF");
+        let report = import_settings(&staging, &manifest, &target, None).unwrap();
+        assert_eq!(report.imported, vec!["Config.wtf".to_string()]);
+        assert!(!target.join("escape").exists());
+        assert!(!target.join("loop").exists());
+    }
+}
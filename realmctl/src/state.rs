@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const STATE_FILE: &str = "state.toml";
+/// Held while the state file is changed, see [`update_state`]
+const LOCK_FILE: &str = "state.lock";
+
+/// Directory holding realmctl's runtime state
+///
+/// `REALMCTL_STATE_DIR` takes precedence, then `state` in the portable root,
+/// `$XDG_STATE_HOME/realmctl`, `%LOCALAPPDATA%\realmctl` on Windows and
+/// `~/.local/state/realmctl` elsewhere.
+pub fn state_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("REALMCTL_STATE_DIR") {
+        return PathBuf::from(shellexpand::tilde(&dir).to_string());
+    }
+    if let Some(root) = crate::paths::portable_root() {
+        return root.join("state");
+    }
+    if let Ok(dir) = std::env::var("XDG_STATE_HOME") {
+        return PathBuf::from(dir).join("realmctl");
+    }
+    if cfg!(windows)
+        && let Ok(dir) = std::env::var("LOCALAPPDATA")
+    {
+        return PathBuf::from(dir).join("realmctl");
+    }
+    PathBuf::from(shellexpand::tilde("~/.local/state/realmctl").to_string())
+}
+
+/// Cached result of a workspace health check
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthSummary {
+    /// Seconds since the Unix epoch
+    pub checked_at: u64,
+    pub warnings: Vec<String>,
+}
+
+/// What realmctl remembers about one game directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirectoryState {
+    /// Seconds since the Unix epoch
+    pub last_launched: Option<u64>,
+    /// Realmlist host the directory was last launched against
+    pub last_realm: Option<String>,
+    pub health: Option<HealthSummary>,
+    /// When `verify-base` last found a base intact, seconds since the Unix epoch
+    #[serde(default)]
+    pub last_verified: Option<u64>,
+    /// Whether the last `verify-base` run found the base intact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_verify_ok: Option<bool>,
+    /// Crashes of attached sessions in the last few minutes, see [`crate::crash`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crashes: Vec<u64>,
+    /// Client last started from the directory, kept until it is seen to have exited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub running: Option<RunningClient>,
+}
+
+/// A client started by `launch`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunningClient {
+    /// Game name as in config.toml
+    pub game: String,
+    /// Process realmctl started; with wine the client itself is one of its children
+    pub pid: u32,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+}
+
+/// Runtime state shared between realmctl invocations, keyed by game directory (see
+/// [`crate::paths::state_key`])
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub directories: BTreeMap<String, DirectoryState>,
+}
+
+impl State {
+    pub fn directory(&self, dir: &Path) -> Option<&DirectoryState> {
+        self.directories.get(&crate::paths::state_key(dir))
+    }
+
+    pub fn directory_mut(&mut self, dir: &Path) -> &mut DirectoryState {
+        self.directories
+            .entry(crate::paths::state_key(dir))
+            .or_default()
+    }
+}
+
+/// Current time in seconds since the Unix epoch
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// How long ago a Unix timestamp was (e.g. `3h ago`)
+pub fn format_age(timestamp: u64) -> String {
+    let secs = now().saturating_sub(timestamp);
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// Load the state file, starting fresh if it is missing or unreadable
+pub fn load_state() -> State {
+    std::fs::read_to_string(state_dir().join(STATE_FILE))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Write the state file
+pub fn save_state(state: &State) -> std::io::Result<()> {
+    let dir = state_dir();
+    std::fs::create_dir_all(&dir)?;
+    let content = toml::to_string(state).map_err(std::io::Error::other)?;
+    // Write then rename, concurrent realmctl runs never see a half-written file
+    let temp_path = dir.join(format!("{STATE_FILE}.{}", std::process::id()));
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, dir.join(STATE_FILE))
+}
+
+/// Load, change and save the state, reporting (not failing on) write errors
+///
+/// Concurrent updates, e.g. of clients launched side by side, wait for each other so none
+/// of them is lost.
+pub fn update_state(change: impl FnOnce(&mut State)) {
+    let dir = state_dir();
+    let _lock = std::fs::create_dir_all(&dir)
+        .and_then(|_| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(dir.join(LOCK_FILE))
+        })
+        .and_then(|file| file.lock().map(|_| file));
+    let mut state = load_state();
+    change(&mut state);
+    if let Err(e) = save_state(&state) {
+        tracing::warn!("⚠ Failed to save state to {}: {e}", state_dir().display());
+    }
+}
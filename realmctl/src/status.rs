@@ -0,0 +1,1058 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub use crate::progress::format_bytes;
+use crate::state::{HealthSummary, load_state, now, update_state};
+use crate::workspace::{WorkspaceConfig, check_health, list_workspaces, load_workspace_config};
+
+/// Overview of every known base and workspace
+#[derive(Debug, Default, Serialize)]
+pub struct FleetStatus {
+    pub bases: Vec<BaseStatus>,
+    pub workspaces: Vec<WorkspaceStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BaseStatus {
+    pub path: PathBuf,
+    /// Profile the base was scanned with, `None` when manifest.toml can't be read
+    pub profile: Option<String>,
+    pub version: Option<String>,
+    pub locale: Option<String>,
+    pub size_bytes: u64,
+    /// Workspaces created from this base
+    pub workspaces: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceStatus {
+    pub name: String,
+    pub path: PathBuf,
+    /// Game keys in config.toml pointing at this workspace
+    pub games: Vec<String>,
+    pub base_path: PathBuf,
+    pub healthy: bool,
+    pub warnings: Vec<String>,
+    /// Bytes only this workspace uses
+    pub own_bytes: u64,
+    /// Bytes hard-linked from the base (or shared with other workspaces)
+    pub linked_bytes: u64,
+    pub running: bool,
+    /// Seconds since the Unix epoch
+    pub last_launched: Option<u64>,
+    /// Detailed findings, only collected for a single workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagnosis: Option<Diagnosis>,
+}
+
+/// Read-only findings about a workspace's links and its base, see [`diagnose`]
+#[derive(Debug, Default, Serialize)]
+pub struct Diagnosis {
+    /// Symlinks whose target is gone
+    pub broken_links: Vec<String>,
+    /// Base files missing from the workspace
+    pub missing: Vec<String>,
+    /// Base files that are a separate copy with the same size instead of a link
+    pub unlinked: Vec<String>,
+    /// Base files whose workspace copy differs in size from the base
+    pub drifted: Vec<String>,
+    /// Directories under the workspace root's `.shared` that no workspace links to
+    pub orphaned_shared: Vec<PathBuf>,
+    /// Bytes in caches, logs and other ephemeral files
+    pub cache_bytes: u64,
+    /// How the base manifest changed since the workspace was created or upgraded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_change: Option<String>,
+    /// Paths too long for the game on Windows without long path support
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_limit: Option<String>,
+}
+
+impl Diagnosis {
+    pub fn is_clean(&self) -> bool {
+        self.broken_links.is_empty()
+            && self.missing.is_empty()
+            && self.unlinked.is_empty()
+            && self.drifted.is_empty()
+            && self.orphaned_shared.is_empty()
+            && self.base_change.is_none()
+            && self.path_limit.is_none()
+    }
+}
+
+/// Disk usage of a directory tree, without following symlinks
+///
+/// Returns (own bytes, bytes of files with more than one hard link).
+pub fn disk_usage(dir: &Path) -> (u64, u64) {
+    let mut own = 0;
+    let mut linked = 0;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.path().symlink_metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                pending.push(entry.path());
+            } else if meta.is_file() {
+                if is_hardlinked(&meta) {
+                    linked += meta.len();
+                } else {
+                    own += meta.len();
+                }
+            }
+        }
+    }
+    (own, linked)
+}
+
+#[cfg(unix)]
+fn is_hardlinked(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn is_hardlinked(_meta: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Size of a directory tree, the way `du -sb` and `du -sb --apparent-size` report it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DirectorySize {
+    /// Bytes stored, each hard-linked file counted once
+    pub on_disk: u64,
+    /// Bytes a full copy would take, hard-linked files counted at every path
+    pub apparent: u64,
+}
+
+/// Measure a directory tree without following symlinks
+pub fn directory_size(dir: &Path) -> DirectorySize {
+    let mut size = DirectorySize::default();
+    let mut seen = std::collections::HashSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.path().symlink_metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                pending.push(entry.path());
+            } else if meta.is_file() {
+                size.apparent += meta.len();
+                if file_id(&meta).is_none_or(|id| seen.insert(id)) {
+                    size.on_disk += meta.len();
+                }
+            }
+        }
+    }
+    size
+}
+
+/// Identity of a file shared by all its hard links
+#[cfg(unix)]
+fn file_id(meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (meta.nlink() > 1).then(|| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(unix)]
+fn link_count(meta: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink()
+}
+
+#[cfg(not(unix))]
+fn link_count(_meta: &std::fs::Metadata) -> u64 {
+    1
+}
+
+/// A base, workspace or shared directory measured by [`collect_usage`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TreeUsage {
+    /// `base`, `workspace` or `shared`
+    pub kind: &'static str,
+    pub name: String,
+    pub path: PathBuf,
+    /// Directory name of the base a workspace was created from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Bytes at every path, what a full copy would take
+    pub apparent_bytes: u64,
+    /// Bytes of files nothing outside the tree links to, freed by deleting it
+    pub unique_bytes: u64,
+    /// Workspaces and shared directories: apparent bytes they don't store themselves.
+    /// Bases: the sum of that over their workspaces.
+    pub saved_bytes: u64,
+}
+
+/// `realmctl du`: every tree with its deduplicated usage, and the totals
+#[derive(Debug, Default, Serialize)]
+pub struct UsageReport {
+    pub trees: Vec<TreeUsage>,
+    /// Bytes of all trees with every path counted
+    pub apparent_bytes: u64,
+    /// Bytes actually stored, each hard-linked file counted once
+    pub on_disk_bytes: u64,
+}
+
+/// A hard-linked file met while measuring, with the trees its links were found in
+struct LinkedFile {
+    size: u64,
+    links: u64,
+    found: BTreeMap<usize, u64>,
+}
+
+/// Measure trees together, attributing hard-linked files by inode
+///
+/// A file linked from several trees counts towards the apparent size of each but is
+/// unique to none of them; it is stored once in the total. Symlinks are not followed.
+pub fn measure_trees(mut trees: Vec<TreeUsage>) -> UsageReport {
+    let mut linked: std::collections::HashMap<(u64, u64), LinkedFile> = Default::default();
+    let mut report = UsageReport::default();
+    for (i, tree) in trees.iter_mut().enumerate() {
+        let mut pending = vec![tree.path.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(meta) = entry.path().symlink_metadata() else {
+                    continue;
+                };
+                if meta.is_dir() {
+                    pending.push(entry.path());
+                    continue;
+                } else if !meta.is_file() {
+                    continue;
+                }
+                tree.apparent_bytes += meta.len();
+                match file_id(&meta) {
+                    Some(id) => {
+                        let file = linked.entry(id).or_insert_with(|| LinkedFile {
+                            size: meta.len(),
+                            links: link_count(&meta),
+                            found: BTreeMap::new(),
+                        });
+                        *file.found.entry(i).or_default() += 1;
+                    }
+                    None => {
+                        tree.unique_bytes += meta.len();
+                        report.on_disk_bytes += meta.len();
+                    }
+                }
+            }
+        }
+    }
+    for file in linked.values() {
+        report.on_disk_bytes += file.size;
+        // Links outside the measured trees (e.g. a second copy of a base) keep it alive too
+        let mut found = file.found.iter();
+        if let (Some((&i, &count)), None) = (found.next(), found.next())
+            && count >= file.links
+        {
+            trees[i].unique_bytes += file.size;
+        }
+    }
+    for tree in &mut trees {
+        report.apparent_bytes += tree.apparent_bytes;
+        if tree.kind != "base" {
+            tree.saved_bytes = tree.apparent_bytes - tree.unique_bytes;
+        }
+    }
+    let saved_by_base: Vec<(String, u64)> = trees
+        .iter()
+        .filter_map(|tree| Some((tree.base.clone()?, tree.saved_bytes)))
+        .collect();
+    for tree in trees.iter_mut().filter(|tree| tree.kind == "base") {
+        tree.saved_bytes = saved_by_base
+            .iter()
+            .filter(|(base, _)| *base == tree.name)
+            .map(|(_, saved)| saved)
+            .sum();
+    }
+    report.trees = trees;
+    report
+}
+
+/// [`measure_trees`] over the bases under `base_roots`, the workspaces under `workspace_root`,
+/// the bases those were created from and the shared directories
+pub fn collect_usage(workspace_root: &Path, base_roots: &[PathBuf]) -> Result<UsageReport> {
+    let workspaces = list_workspaces(workspace_root)?;
+    let canonical =
+        |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut base_paths: Vec<PathBuf> = base_roots
+        .iter()
+        .flat_map(|root| find_bases(root))
+        .chain(workspaces.iter().map(|ws| ws.base_path.clone()))
+        .map(|path| canonical(&path))
+        .collect();
+    base_paths.sort();
+    base_paths.dedup();
+
+    let dir_name = |path: &Path| {
+        path.file_name().map_or_else(
+            || path.display().to_string(),
+            |n| n.to_string_lossy().to_string(),
+        )
+    };
+    let tree = |kind, name, path: PathBuf, base| TreeUsage {
+        kind,
+        name,
+        path,
+        base,
+        apparent_bytes: 0,
+        unique_bytes: 0,
+        saved_bytes: 0,
+    };
+    let mut trees: Vec<TreeUsage> = base_paths
+        .iter()
+        .map(|path| tree("base", dir_name(path), path.clone(), None))
+        .collect();
+    for workspace in workspaces {
+        let base = dir_name(&canonical(&workspace.base_path));
+        trees.push(tree(
+            "workspace",
+            workspace.name,
+            workspace.workspace_path,
+            Some(base),
+        ));
+    }
+    let shared_root = workspace_root.join(".shared");
+    if let Ok(entries) = std::fs::read_dir(&shared_root) {
+        let mut shared: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        shared.sort();
+        for path in shared {
+            trees.push(tree(
+                "shared",
+                format!(".shared/{}", dir_name(&path)),
+                path,
+                None,
+            ));
+        }
+    }
+    Ok(measure_trees(trees))
+}
+
+/// Render a directory tree like `tree -a -L <max_depth>`, symlinks shown with their target
+pub fn render_tree(dir: &Path, max_depth: usize) -> String {
+    let mut out = format!("{}\n", dir.display());
+    render_entries(dir, "", max_depth, &mut out);
+    out
+}
+
+fn render_entries(dir: &Path, prefix: &str, depth: usize, out: &mut String) {
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for (i, path) in paths.iter().enumerate() {
+        let last = i + 1 == paths.len();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let branch = if last { "└── " } else { "├── " };
+        match std::fs::read_link(path) {
+            Ok(target) => {
+                out.push_str(&format!("{prefix}{branch}{name} -> {}\n", target.display()))
+            }
+            Err(_) => out.push_str(&format!("{prefix}{branch}{name}\n")),
+        }
+        if path.symlink_metadata().is_ok_and(|meta| meta.is_dir()) {
+            let child_prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            render_entries(path, &child_prefix, depth - 1, out);
+        }
+    }
+}
+
+/// Collect the status of every workspace under `workspace_root` and every game
+/// directory in `games` that is a workspace or a base
+///
+/// Health checks run in full and refresh the cached results used by `launch`.
+pub fn collect_status(
+    workspace_root: Option<&Path>,
+    games: &[(String, PathBuf)],
+) -> Result<FleetStatus> {
+    let mut workspaces: Vec<WorkspaceConfig> = match workspace_root {
+        Some(root) => list_workspaces(root)?,
+        None => Vec::new(),
+    };
+    let mut base_paths: Vec<PathBuf> = Vec::new();
+    for (_, dir) in games {
+        if workspaces.iter().any(|ws| &ws.workspace_path == dir) {
+            continue;
+        }
+        if dir.join("workspace.toml").is_file() {
+            match load_workspace_config(dir) {
+                Ok(config) => workspaces.push(config),
+                Err(e) => tracing::warn!("⚠ Skipping {}: {}", dir.display(), e),
+            }
+        } else if dir.join("manifest.toml").is_file() {
+            base_paths.push(dir.clone());
+        }
+    }
+    for workspace in &workspaces {
+        base_paths.push(workspace.base_path.clone());
+    }
+    base_paths.sort();
+    base_paths.dedup();
+
+    let state = load_state();
+    let mut status = FleetStatus::default();
+    let mut checked: BTreeMap<PathBuf, HealthSummary> = BTreeMap::new();
+
+    for workspace in &workspaces {
+        let dir = &workspace.workspace_path;
+        let warnings = match check_health(dir, None) {
+            Ok(report) => {
+                checked.insert(
+                    dir.clone(),
+                    HealthSummary {
+                        checked_at: now(),
+                        warnings: report.warnings.clone(),
+                    },
+                );
+                report.warnings
+            }
+            Err(e) => vec![e.to_string()],
+        };
+        let (own_bytes, linked_bytes) = disk_usage(dir);
+        status.workspaces.push(WorkspaceStatus {
+            name: workspace.name.clone(),
+            path: dir.clone(),
+            games: games
+                .iter()
+                .filter(|(_, game_dir)| game_dir == dir)
+                .map(|(key, _)| key.clone())
+                .collect(),
+            base_path: workspace.base_path.clone(),
+            healthy: warnings.is_empty(),
+            warnings,
+            own_bytes,
+            linked_bytes,
+            running: !crate::process::processes_in(dir).is_empty(),
+            last_launched: state.directory(dir).and_then(|d| d.last_launched),
+            diagnosis: None,
+        });
+    }
+
+    for path in base_paths {
+        let manifest = crate::base::load_manifest(&path).ok();
+        let (own, linked) = disk_usage(&path);
+        status.bases.push(BaseStatus {
+            workspaces: workspaces.iter().filter(|ws| ws.base_path == path).count(),
+            profile: manifest.as_ref().map(|m| m.profile.clone()),
+            version: manifest.as_ref().and_then(|m| m.version.clone()),
+            locale: manifest.and_then(|m| m.locale),
+            size_bytes: own + linked,
+            path,
+        });
+    }
+
+    if !checked.is_empty() {
+        update_state(|state| {
+            for (dir, health) in checked {
+                state.directory_mut(&dir).health = Some(health);
+            }
+        });
+    }
+    Ok(status)
+}
+
+/// Symlinks under a directory, without following them
+fn symlinks(dir: &Path) -> Vec<PathBuf> {
+    let mut links = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_symlink() => links.push(entry.path()),
+                Ok(file_type) if file_type.is_dir() => pending.push(entry.path()),
+                _ => {}
+            }
+        }
+    }
+    links
+}
+
+/// Topmost directories under `dir` that contain none of `targets` and lie inside none of them
+fn orphaned_dirs(dir: &Path, targets: &[PathBuf], out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    for dir in dirs {
+        if targets.iter().any(|target| dir.starts_with(target)) {
+            continue;
+        }
+        if targets.iter().any(|target| target.starts_with(&dir)) {
+            orphaned_dirs(&dir, targets, out);
+        } else {
+            out.push(dir);
+        }
+    }
+}
+
+/// Shared directories nothing links to, found by [`orphaned_shared`]
+#[derive(Debug, Default, PartialEq)]
+pub struct SharedOrphans {
+    pub orphans: Vec<PathBuf>,
+    /// Directories under the workspace root whose workspace.toml is missing or unreadable.
+    /// Their links still count, but removing orphans is unsafe until they are sorted out.
+    pub unloaded: Vec<PathBuf>,
+}
+
+/// Directories under the workspace root's `.shared` that no workspace links to any more
+///
+/// Links are collected from every directory under the root, also those whose
+/// workspace.toml does not load, so their shared data never counts as orphaned.
+pub fn orphaned_shared(workspace_root: &Path) -> Result<SharedOrphans> {
+    let shared = workspace_root.join(".shared");
+    let canonical = |path: PathBuf| std::fs::canonicalize(&path).unwrap_or(path);
+    let mut result = SharedOrphans::default();
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(workspace_root).into_iter().flatten() {
+        let entry = entry?;
+        if entry.file_name() == ".shared" || !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let dir = entry.path();
+        if load_workspace_config(&dir).is_err() {
+            result.unloaded.push(dir.clone());
+        }
+        dirs.push(dir);
+    }
+    result.unloaded.sort();
+    let mut targets: Vec<PathBuf> = dirs
+        .iter()
+        .flat_map(|dir| symlinks(dir))
+        .filter_map(|link| {
+            let target = std::fs::read_link(&link).ok()?;
+            Some(canonical(link.parent()?.join(target)))
+        })
+        .collect();
+    targets.sort();
+    targets.dedup();
+    // `.shared/global` and `.shared/<base>` are layout, look for orphans inside them
+    for scope in std::fs::read_dir(&shared).into_iter().flatten().flatten() {
+        if scope.file_type().is_ok_and(|t| t.is_dir()) {
+            orphaned_dirs(&canonical(scope.path()), &targets, &mut result.orphans);
+        }
+    }
+    result.orphans.sort();
+    Ok(result)
+}
+
+/// Diagnose a workspace without changing anything: broken links, base files that are missing,
+/// no longer linked or changed, shared directories nothing links to, and cache size
+pub fn diagnose(workspace_path: &Path) -> Result<Diagnosis> {
+    use crate::base::{FileRole, Profile};
+    use crate::workspace::same_file;
+
+    let config = load_workspace_config(workspace_path)?;
+    let mut diagnosis = Diagnosis::default();
+    if config.standalone {
+        return Ok(diagnosis);
+    }
+    let manifest = crate::base::load_manifest(&config.base_path)?;
+    diagnosis.base_change = config.base_change(&manifest);
+    diagnosis.path_limit = crate::workspace::path_limit_warning(workspace_path, &manifest);
+
+    let rel = |path: &Path| {
+        path.strip_prefix(workspace_path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    };
+    for link in symlinks(workspace_path) {
+        if !link.exists() {
+            diagnosis.broken_links.push(rel(&link));
+        }
+    }
+    diagnosis.broken_links.sort();
+
+    for (rel_path, role) in &manifest.file_roles {
+        if !matches!(role, FileRole::BaseData | FileRole::Executable) {
+            continue;
+        }
+        let ws_file = workspace_path.join(rel_path);
+        let base_file = config.base_path.join(rel_path);
+        let Ok(meta) = std::fs::symlink_metadata(&ws_file) else {
+            diagnosis.missing.push(rel_path.clone());
+            continue;
+        };
+        // Symlinked base files (bases owned by another user) are fine while the target exists
+        if meta.file_type().is_symlink() || same_file(&ws_file, &base_file) {
+            continue;
+        }
+        match std::fs::metadata(&base_file) {
+            Ok(base) if base.len() == meta.len() => diagnosis.unlinked.push(rel_path.clone()),
+            _ => diagnosis.drifted.push(rel_path.clone()),
+        }
+    }
+
+    if let Some(root) = workspace_path.parent() {
+        diagnosis.orphaned_shared = orphaned_shared(root)?.orphans;
+    }
+
+    let profile =
+        Profile::resolve(&manifest.profile).unwrap_or_else(|_| Profile::detect(workspace_path));
+    for path in profile.ephemeral_paths(workspace_path, true) {
+        let (own, linked) = if path.is_dir() {
+            disk_usage(&path)
+        } else {
+            (path.metadata().map_or(0, |meta| meta.len()), 0)
+        };
+        diagnosis.cache_bytes += own + linked;
+    }
+    Ok(diagnosis)
+}
+
+/// Bases and workspaces found on disk, without health checks
+#[derive(Debug, Default, Serialize)]
+pub struct Listing {
+    pub bases: Vec<ListedBase>,
+    pub workspaces: Vec<ListedWorkspace>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedBase {
+    /// Directory name of the base
+    pub name: String,
+    pub path: PathBuf,
+    pub profile: Option<String>,
+    pub size_bytes: u64,
+    pub workspaces: usize,
+    pub last_launched: Option<u64>,
+    /// Last time `verify-base` found it intact
+    pub last_verified: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedWorkspace {
+    pub name: String,
+    pub path: PathBuf,
+    /// Directory name of the base it was created from
+    pub base: String,
+    /// Profile of the base, `None` when its manifest.toml can't be read
+    pub profile: Option<String>,
+    /// Bytes only this workspace uses
+    pub own_bytes: u64,
+    /// Bytes hard-linked from the base
+    pub linked_bytes: u64,
+    pub last_launched: Option<u64>,
+}
+
+/// Directories holding a manifest.toml: `root` itself or its direct children
+pub fn find_bases(root: &Path) -> Vec<PathBuf> {
+    if root.join("manifest.toml").is_file() {
+        return vec![root.to_path_buf()];
+    }
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut bases: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("manifest.toml").is_file())
+        .collect();
+    bases.sort();
+    bases
+}
+
+/// List the workspaces under `workspace_root`, the bases under `base_roots` and the bases
+/// those workspaces were created from
+pub fn collect_listing(workspace_root: &Path, base_roots: &[PathBuf]) -> Result<Listing> {
+    let workspaces = list_workspaces(workspace_root)?;
+    // Workspaces may store a relative base path, compare canonical ones
+    let canonical =
+        |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut base_paths: Vec<PathBuf> = base_roots
+        .iter()
+        .flat_map(|root| find_bases(root))
+        .chain(workspaces.iter().map(|ws| ws.base_path.clone()))
+        .map(|path| canonical(&path))
+        .collect();
+    base_paths.sort();
+    base_paths.dedup();
+
+    let state = load_state();
+    let profiles: BTreeMap<PathBuf, Option<String>> = base_paths
+        .iter()
+        .map(|path| {
+            let profile = crate::base::load_manifest(path).ok().map(|m| m.profile);
+            (path.clone(), profile)
+        })
+        .collect();
+
+    let mut listing = Listing::default();
+    for path in &base_paths {
+        let (own, linked) = disk_usage(path);
+        listing.bases.push(ListedBase {
+            name: path.file_name().map_or_else(
+                || path.display().to_string(),
+                |n| n.to_string_lossy().to_string(),
+            ),
+            profile: profiles[path].clone(),
+            size_bytes: own + linked,
+            workspaces: workspaces
+                .iter()
+                .filter(|ws| &canonical(&ws.base_path) == path)
+                .count(),
+            last_launched: state.directory(path).and_then(|d| d.last_launched),
+            last_verified: state.directory(path).and_then(|d| d.last_verified),
+            path: path.clone(),
+        });
+    }
+    for workspace in workspaces {
+        let dir = &workspace.workspace_path;
+        let base_path = canonical(&workspace.base_path);
+        let (own_bytes, linked_bytes) = disk_usage(dir);
+        listing.workspaces.push(ListedWorkspace {
+            base: base_path.file_name().map_or_else(
+                || base_path.display().to_string(),
+                |n| n.to_string_lossy().to_string(),
+            ),
+            profile: profiles.get(&base_path).cloned().flatten(),
+            own_bytes,
+            linked_bytes,
+            last_launched: state.directory(dir).and_then(|d| d.last_launched),
+            name: workspace.name,
+            path: workspace.workspace_path,
+        });
+    }
+    Ok(listing)
+}
+
+/// Days after which a base is due for another `verify-base`
+pub const VERIFY_AFTER_DAYS: u64 = 30;
+/// Days without a launch after which a workspace is suggested for removal
+pub const IDLE_AFTER_DAYS: u64 = 90;
+
+/// Local totals over all bases and workspaces, with what deserves attention
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub bases: usize,
+    pub workspaces: usize,
+    /// Bytes of the bases, the workspaces' own files and the shared directories
+    pub managed_bytes: u64,
+    /// Bytes the same setup would take with every workspace a full copy of its base
+    pub naive_bytes: u64,
+    /// Base verified longest ago, if one is due
+    pub oldest_unverified: Option<PathBuf>,
+    /// When that base was last verified, `None` if never
+    pub oldest_verified_at: Option<u64>,
+    /// Workspaces not launched for [`IDLE_AFTER_DAYS`], with their own bytes
+    pub idle_workspaces: Vec<(String, u64)>,
+}
+
+/// Sum up a listing; `shared_bytes` is the size of the workspace root's `.shared` directory
+pub fn summarize(listing: &Listing, shared_bytes: u64) -> Summary {
+    let base_bytes: u64 = listing.bases.iter().map(|base| base.size_bytes).sum();
+    let own_bytes: u64 = listing.workspaces.iter().map(|ws| ws.own_bytes).sum();
+    let linked_bytes: u64 = listing.workspaces.iter().map(|ws| ws.linked_bytes).sum();
+
+    let due = now().saturating_sub(VERIFY_AFTER_DAYS * 24 * 60 * 60);
+    let oldest = listing
+        .bases
+        .iter()
+        .filter(|base| base.last_verified.is_none_or(|verified| verified < due))
+        .min_by_key(|base| base.last_verified);
+    let idle_since = now().saturating_sub(IDLE_AFTER_DAYS * 24 * 60 * 60);
+
+    Summary {
+        bases: listing.bases.len(),
+        workspaces: listing.workspaces.len(),
+        managed_bytes: base_bytes + own_bytes + shared_bytes,
+        naive_bytes: base_bytes + own_bytes + linked_bytes + shared_bytes,
+        oldest_unverified: oldest.map(|base| base.path.clone()),
+        oldest_verified_at: oldest.and_then(|base| base.last_verified),
+        idle_workspaces: listing
+            .workspaces
+            .iter()
+            .filter(|ws| {
+                ws.last_launched
+                    .is_some_and(|launched| launched < idle_since)
+            })
+            .map(|ws| (ws.name.clone(), ws.own_bytes))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_directory_size_and_tree() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("Data")).unwrap();
+        std::fs::write(dir.join("Data/common.MPQ"), vec![0u8; 1000]).unwrap();
+        std::fs::write(dir.join("Wow.exe"), vec![0u8; 24]).unwrap();
+        std::fs::hard_link(dir.join("Data/common.MPQ"), dir.join("Data/copy.MPQ")).unwrap();
+
+        let size = directory_size(dir);
+        assert_eq!(size.apparent, 2024);
+        if cfg!(unix) {
+            assert_eq!(size.on_disk, 1024);
+        }
+
+        let tree = render_tree(dir, 4);
+        let lines: Vec<&str> = tree.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "├── Data",
+                "│   ├── common.MPQ",
+                "│   └── copy.MPQ",
+                "└── Wow.exe"
+            ]
+        );
+        assert_eq!(render_tree(dir, 1).lines().count(), 3);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(17 * 1024 * 1024 * 1024), "17.0 GiB");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_disk_usage_splits_hardlinks() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path().join("base");
+        let ws = temp.path().join("ws");
+        std::fs::create_dir_all(base.join("Data")).unwrap();
+        std::fs::create_dir_all(ws.join("Data")).unwrap();
+        std::fs::write(base.join("Data/common.MPQ"), vec![0u8; 100]).unwrap();
+        std::fs::hard_link(base.join("Data/common.MPQ"), ws.join("Data/common.MPQ")).unwrap();
+        std::fs::write(ws.join("Wow.log"), vec![0u8; 10]).unwrap();
+
+        let (own, linked) = disk_usage(&ws);
+        assert_eq!((own, linked), (10, 100));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_measure_trees_attributes_hardlinks() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path().join("base");
+        let ws = temp.path().join("ws");
+        std::fs::create_dir_all(base.join("Data")).unwrap();
+        std::fs::create_dir_all(&ws).unwrap();
+        std::fs::write(base.join("Data/common.MPQ"), vec![0u8; 100]).unwrap();
+        std::fs::hard_link(base.join("Data/common.MPQ"), ws.join("common.MPQ")).unwrap();
+        std::fs::write(base.join("Wow.exe"), vec![0u8; 20]).unwrap();
+        std::fs::write(ws.join("Wow.log"), vec![0u8; 10]).unwrap();
+        // Linked twice inside the workspace itself: still only the workspace's
+        std::fs::write(ws.join("own.dat"), vec![0u8; 5]).unwrap();
+        std::fs::hard_link(ws.join("own.dat"), ws.join("own2.dat")).unwrap();
+
+        let tree = |kind, path: &Path, base: Option<&str>| TreeUsage {
+            kind,
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            path: path.to_path_buf(),
+            base: base.map(String::from),
+            ..Default::default()
+        };
+        let report = measure_trees(vec![
+            tree("base", &base, None),
+            tree("workspace", &ws, Some("base")),
+        ]);
+        let (base_usage, ws_usage) = (&report.trees[0], &report.trees[1]);
+        assert_eq!(
+            (
+                base_usage.apparent_bytes,
+                base_usage.unique_bytes,
+                base_usage.saved_bytes
+            ),
+            (120, 20, 105)
+        );
+        assert_eq!(
+            (
+                ws_usage.apparent_bytes,
+                ws_usage.unique_bytes,
+                ws_usage.saved_bytes
+            ),
+            (120, 15, 105)
+        );
+        assert_eq!((report.apparent_bytes, report.on_disk_bytes), (240, 135));
+    }
+
+    #[test]
+    fn test_find_bases() {
+        let temp = TempDir::new().unwrap();
+        for name in ["Base335a", "Base112", "Downloads"] {
+            std::fs::create_dir_all(temp.path().join(name)).unwrap();
+        }
+        std::fs::write(temp.path().join("Base335a/manifest.toml"), "").unwrap();
+        std::fs::write(temp.path().join("Base112/manifest.toml"), "").unwrap();
+
+        let names: Vec<String> = find_bases(temp.path())
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["Base112", "Base335a"]);
+        // A root that is itself a base
+        assert_eq!(find_bases(&temp.path().join("Base112")).len(), 1);
+        assert!(find_bases(&temp.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_diagnose_workspace() {
+        use crate::base::{Profile, scan_and_build_manifest, write_manifest};
+        use crate::workspace::{create_workspace, default_sharing_rules};
+
+        let temp = TempDir::new().unwrap();
+        let base = temp.path().join("base");
+        let root = temp.path().join("workspaces");
+        std::fs::create_dir_all(base.join("Data")).unwrap();
+        std::fs::create_dir_all(base.join("Screenshots")).unwrap();
+        std::fs::write(base.join("Wow.exe"), "exe").unwrap();
+        for name in ["common", "patch", "lichking"] {
+            std::fs::write(base.join(format!("Data/{name}.MPQ")), name).unwrap();
+        }
+        let manifest = scan_and_build_manifest(&base, &Profile::chromie_335a()).unwrap();
+        write_manifest(&manifest, &base).unwrap();
+        let ws = create_workspace("main", &base, &root, default_sharing_rules()).unwrap();
+        let dir = &ws.workspace_path;
+        assert!(diagnose(dir).unwrap().is_clean());
+
+        std::fs::remove_file(dir.join("Data/common.MPQ")).unwrap();
+        std::fs::write(dir.join("Data/common.MPQ"), "changed").unwrap();
+        std::fs::remove_file(dir.join("Data/lichking.MPQ")).unwrap();
+        std::fs::remove_dir_all(root.join(".shared/global/Screenshots")).unwrap();
+        std::fs::create_dir_all(root.join(".shared/global/Videos")).unwrap();
+        std::fs::create_dir_all(dir.join("Cache")).unwrap();
+        std::fs::write(dir.join("Cache/creaturecache.wdb"), "cache").unwrap();
+
+        let diagnosis = diagnose(dir).unwrap();
+        assert_eq!(diagnosis.broken_links, vec!["Screenshots"]);
+        assert_eq!(diagnosis.drifted, vec!["Data/common.MPQ"]);
+        assert_eq!(diagnosis.missing, vec!["Data/lichking.MPQ"]);
+        assert_eq!(diagnosis.orphaned_shared.len(), 1);
+        assert!(diagnosis.orphaned_shared[0].ends_with(".shared/global/Videos"));
+        assert_eq!(diagnosis.cache_bytes, 5);
+
+        // Deleting the only workspace orphans everything it shared
+        std::fs::create_dir_all(root.join(".shared/global/Screenshots")).unwrap();
+        assert_eq!(
+            orphaned_shared(&root).unwrap().orphans,
+            diagnosis.orphaned_shared
+        );
+        crate::workspace::delete_workspace(dir).unwrap();
+        let orphans = orphaned_shared(&root).unwrap().orphans;
+        assert_eq!(orphans.len(), 2);
+        assert!(orphans[0].ends_with(".shared/global/Screenshots"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_orphaned_shared_keeps_links_of_broken_workspaces() {
+        use crate::base::{Profile, scan_and_build_manifest, write_manifest};
+        use crate::workspace::{create_workspace, default_sharing_rules};
+
+        let temp = TempDir::new().unwrap();
+        let base = temp.path().join("base");
+        let root = temp.path().join("workspaces");
+        std::fs::create_dir_all(base.join("Data")).unwrap();
+        std::fs::create_dir_all(base.join("Screenshots")).unwrap();
+        std::fs::write(base.join("Wow.exe"), "exe").unwrap();
+        for name in ["common", "patch", "lichking"] {
+            std::fs::write(base.join(format!("Data/{name}.MPQ")), name).unwrap();
+        }
+        let manifest = scan_and_build_manifest(&base, &Profile::chromie_335a()).unwrap();
+        write_manifest(&manifest, &base).unwrap();
+        let ws = create_workspace("main", &base, &root, default_sharing_rules()).unwrap();
+        std::fs::create_dir_all(root.join(".shared/global/Videos")).unwrap();
+        assert!(orphaned_shared(&root).unwrap().unloaded.is_empty());
+
+        std::fs::write(ws.workspace_path.join("workspace.toml"), "not = [valid").unwrap();
+        let result = orphaned_shared(&root).unwrap();
+        assert_eq!(result.unloaded, vec![ws.workspace_path.clone()]);
+        assert_eq!(result.orphans.len(), 1);
+        assert!(result.orphans[0].ends_with(".shared/global/Videos"));
+
+        std::fs::remove_file(ws.workspace_path.join("workspace.toml")).unwrap();
+        let result = orphaned_shared(&root).unwrap();
+        assert_eq!(result.unloaded, vec![ws.workspace_path.clone()]);
+        assert_eq!(result.orphans.len(), 1);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let day = 24 * 60 * 60;
+        let base = |name: &str, last_verified: Option<u64>| ListedBase {
+            name: name.to_string(),
+            path: PathBuf::from(format!("/bases/{name}")),
+            profile: None,
+            size_bytes: 1000,
+            workspaces: 1,
+            last_launched: None,
+            last_verified,
+        };
+        let workspace = |name: &str, last_launched: Option<u64>| ListedWorkspace {
+            name: name.to_string(),
+            path: PathBuf::from(format!("/workspaces/{name}")),
+            base: "b".to_string(),
+            profile: None,
+            own_bytes: 10,
+            linked_bytes: 990,
+            last_launched,
+        };
+        let listing = Listing {
+            bases: vec![
+                base("fresh", Some(now() - day)),
+                base("stale", Some(now() - 60 * day)),
+                base("never", None),
+            ],
+            workspaces: vec![
+                workspace("main", Some(now())),
+                workspace("old", Some(now() - 100 * day)),
+                workspace("new", None),
+            ],
+        };
+
+        let summary = summarize(&listing, 5);
+        assert_eq!(summary.managed_bytes, 3000 + 30 + 5);
+        assert_eq!(summary.naive_bytes, 3000 + 3000 + 5);
+        assert_eq!(
+            summary.oldest_unverified,
+            Some(PathBuf::from("/bases/never"))
+        );
+        assert_eq!(summary.oldest_verified_at, None);
+        assert_eq!(summary.idle_workspaces, vec![("old".to_string(), 10)]);
+    }
+}
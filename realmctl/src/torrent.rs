@@ -0,0 +1,446 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// A decoded bencode value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bencode {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Bencode>),
+    Dict(BTreeMap<Vec<u8>, Bencode>),
+}
+
+impl Bencode {
+    fn get(&self, key: &str) -> Option<&Bencode> {
+        match self {
+            Bencode::Dict(dict) => dict.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Bencode::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Bencode::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<String> {
+        self.as_bytes()
+            .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+    }
+
+    fn as_list(&self) -> Option<&[Bencode]> {
+        match self {
+            Bencode::List(list) => Some(list),
+            _ => None,
+        }
+    }
+}
+
+/// Decode one bencode value, returning it with the bytes that follow
+fn decode(data: &[u8]) -> Result<(Bencode, &[u8]), String> {
+    match data.first() {
+        Some(b'i') => {
+            let end = data
+                .iter()
+                .position(|b| *b == b'e')
+                .ok_or("unterminated integer")?;
+            let number = std::str::from_utf8(&data[1..end])
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or("invalid integer")?;
+            Ok((Bencode::Int(number), &data[end + 1..]))
+        }
+        Some(b'l') => {
+            let mut rest = &data[1..];
+            let mut list = Vec::new();
+            while rest.first() != Some(&b'e') {
+                let (value, next) = decode(rest)?;
+                list.push(value);
+                rest = next;
+            }
+            Ok((Bencode::List(list), &rest[1..]))
+        }
+        Some(b'd') => {
+            let mut rest = &data[1..];
+            let mut dict = BTreeMap::new();
+            while rest.first() != Some(&b'e') {
+                let (Bencode::Bytes(key), next) = decode(rest)? else {
+                    return Err("dictionary key is not a string".to_string());
+                };
+                let (value, next) = decode(next)?;
+                dict.insert(key, value);
+                rest = next;
+            }
+            Ok((Bencode::Dict(dict), &rest[1..]))
+        }
+        Some(b'0'..=b'9') => {
+            let colon = data
+                .iter()
+                .position(|b| *b == b':')
+                .ok_or("unterminated string length")?;
+            let len: usize = std::str::from_utf8(&data[..colon])
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or("invalid string length")?;
+            let start = colon + 1;
+            let bytes = data
+                .get(start..start + len)
+                .ok_or("string runs past the end")?;
+            Ok((Bencode::Bytes(bytes.to_vec()), &data[start + len..]))
+        }
+        Some(other) => Err(format!("unexpected byte '{}'", *other as char)),
+        None => Err("unexpected end of data".to_string()),
+    }
+}
+
+/// A file described by a torrent
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorrentFile {
+    /// Path relative to the torrent's root, `/`-separated
+    pub path: String,
+    pub length: u64,
+    /// BEP 47 padding: zeros that exist only in the piece stream
+    pub padding: bool,
+}
+
+/// The file list and piece hashes of a (v1) .torrent
+#[derive(Debug, Clone)]
+pub struct TorrentInfo {
+    /// Name of the root directory (multi-file) or of the single file
+    pub name: String,
+    pub piece_length: u64,
+    /// SHA-1 of every piece, hex encoded
+    pub pieces: Vec<String>,
+    pub files: Vec<TorrentFile>,
+    /// Whether files live in a directory called `name`
+    pub multi_file: bool,
+}
+
+/// Whether a torrent path component stays inside the directory it is joined to
+fn is_plain_component(part: &str) -> bool {
+    part != ".." && !part.contains(['/', '\\'])
+}
+
+fn non_negative_length(length: i64) -> Result<u64> {
+    u64::try_from(length)
+        .map_err(|_| anyhow::anyhow!("Torrent has a negative file length: {length}"))
+}
+
+impl TorrentInfo {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let (root, _) = decode(data).map_err(|e| anyhow::anyhow!("Invalid torrent: {e}"))?;
+        let info = root.get("info").context("Torrent has no info dictionary")?;
+        let name = info
+            .get("name")
+            .and_then(Bencode::as_str)
+            .context("Torrent has no name")?;
+        // The name is the root directory (or the file) the torrent is checked under
+        if !is_plain_component(&name) {
+            anyhow::bail!("Torrent name escapes its root: {name}");
+        }
+        let piece_length = info
+            .get("piece length")
+            .and_then(Bencode::as_int)
+            .context("Torrent has no piece length")?;
+        let piece_length = match u64::try_from(piece_length) {
+            Ok(n) if n > 0 => n,
+            _ => anyhow::bail!("Torrent has an invalid piece length: {piece_length}"),
+        };
+        let pieces = info
+            .get("pieces")
+            .and_then(Bencode::as_bytes)
+            .filter(|p| p.len() % 20 == 0)
+            .context("Torrent has no v1 piece hashes (v2-only torrents are not supported)")?
+            .chunks(20)
+            .map(|hash| hash.iter().map(|b| format!("{b:02x}")).collect())
+            .collect();
+
+        let (files, multi_file) = match info.get("files").and_then(Bencode::as_list) {
+            Some(list) => {
+                let files = list
+                    .iter()
+                    .map(|file| {
+                        let path: Vec<String> = file
+                            .get("path")
+                            .and_then(Bencode::as_list)
+                            .context("Torrent file entry has no path")?
+                            .iter()
+                            .filter_map(Bencode::as_str)
+                            .collect();
+                        if !path.iter().all(|part| is_plain_component(part)) {
+                            anyhow::bail!("Torrent file path escapes its root: {}", path.join("/"));
+                        }
+                        let attr = file.get("attr").and_then(Bencode::as_str);
+                        Ok(TorrentFile {
+                            padding: attr.is_some_and(|a| a.contains('p'))
+                                || path.first().is_some_and(|p| p == ".pad"),
+                            path: path.join("/"),
+                            length: non_negative_length(
+                                file.get("length")
+                                    .and_then(Bencode::as_int)
+                                    .context("Torrent file entry has no length")?,
+                            )?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (files, true)
+            }
+            None => {
+                let length = info
+                    .get("length")
+                    .and_then(Bencode::as_int)
+                    .context("Torrent has neither files nor length")?;
+                (
+                    vec![TorrentFile {
+                        path: name.clone(),
+                        length: non_negative_length(length)?,
+                        padding: false,
+                    }],
+                    false,
+                )
+            }
+        };
+
+        Ok(TorrentInfo {
+            name,
+            piece_length,
+            pieces,
+            files,
+            multi_file,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        TorrentInfo::parse(&data).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Directory the torrent's paths are relative to: `base_dir` itself, or the torrent's
+    /// root directory inside it when `base_dir` is where it was downloaded to
+    pub fn root_in(&self, base_dir: &Path) -> PathBuf {
+        let nested = base_dir.join(&self.name);
+        let first = self.files.iter().find(|f| !f.padding);
+        match first {
+            Some(file) if self.multi_file => {
+                if !base_dir.join(&file.path).exists() && nested.join(&file.path).exists() {
+                    nested
+                } else {
+                    base_dir.to_path_buf()
+                }
+            }
+            _ => base_dir.to_path_buf(),
+        }
+    }
+}
+
+/// Outcome of checking a directory against a torrent's piece hashes
+#[derive(Debug, Default, serde::Serialize)]
+pub struct TorrentReport {
+    pub pieces_ok: usize,
+    pub pieces_bad: usize,
+    /// Files overlapping at least one bad piece
+    pub corrupt: BTreeSet<String>,
+    pub missing: Vec<String>,
+    /// Files whose size differs from the torrent
+    pub wrong_size: Vec<String>,
+}
+
+impl TorrentReport {
+    pub fn is_ok(&self) -> bool {
+        self.pieces_bad == 0 && self.missing.is_empty() && self.wrong_size.is_empty()
+    }
+}
+
+/// Hash `root` piece by piece and compare with the torrent
+///
+/// Files are read in torrent order as one stream, like a client does; a missing file
+/// reads as nothing and fails every piece it is part of.
+pub fn verify_torrent(root: &Path, torrent: &TorrentInfo) -> Result<TorrentReport> {
+    let mut report = TorrentReport::default();
+    let mut piece = 0;
+    let mut piece_len = 0u64;
+    let mut piece_files: Vec<String> = Vec::new();
+    let mut piece_hasher = crate::base::hasher("sha1").expect("builtin hash algorithm");
+    let mut buffer = vec![0u8; 64 * 1024];
+    let bar = crate::progress::Progress::new(
+        "Verifying",
+        torrent.files.iter().filter(|f| !f.padding).count(),
+        torrent.files.iter().map(|f| f.length).sum(),
+    );
+
+    let finish_piece = |hasher: Box<dyn crate::base::ContentHasher>,
+                        files: &mut Vec<String>,
+                        report: &mut TorrentReport,
+                        piece: usize| {
+        if torrent.pieces.get(piece) == Some(&hasher.finish()) {
+            report.pieces_ok += 1;
+        } else {
+            report.pieces_bad += 1;
+            report.corrupt.extend(files.drain(..));
+        }
+        files.clear();
+    };
+
+    for file in &torrent.files {
+        let path = root.join(&file.path);
+        let mut reader: Box<dyn Read> = if file.padding {
+            Box::new(std::io::repeat(0).take(file.length))
+        } else {
+            match std::fs::File::open(&path) {
+                Ok(f) => {
+                    if f.metadata()?.len() != file.length {
+                        report.wrong_size.push(file.path.clone());
+                    }
+                    Box::new(f.take(file.length))
+                }
+                Err(_) => {
+                    report.missing.push(file.path.clone());
+                    Box::new(std::io::empty())
+                }
+            }
+        };
+
+        let mut remaining = file.length;
+        while remaining > 0 {
+            crate::cancel::check()?;
+            let want = (torrent.piece_length - piece_len).min(remaining);
+            let want = want.min(buffer.len() as u64) as usize;
+            let read = reader.read(&mut buffer[..want])?;
+            if read == 0 {
+                // Short or missing file: pad with zeros so piece boundaries stay aligned
+                buffer[..want].fill(0);
+            }
+            let n = if read == 0 { want } else { read };
+            piece_hasher.update(&buffer[..n]);
+            if !file.padding && piece_files.last() != Some(&file.path) {
+                piece_files.push(file.path.clone());
+            }
+            piece_len += n as u64;
+            remaining -= n as u64;
+            bar.add(0, n as u64);
+            if piece_len == torrent.piece_length {
+                let hasher = std::mem::replace(
+                    &mut piece_hasher,
+                    crate::base::hasher("sha1").expect("builtin hash algorithm"),
+                );
+                finish_piece(hasher, &mut piece_files, &mut report, piece);
+                piece += 1;
+                piece_len = 0;
+            }
+        }
+        if !file.padding {
+            bar.add(1, 0);
+        }
+    }
+    bar.finish();
+    if piece_len > 0 {
+        finish_piece(piece_hasher, &mut piece_files, &mut report, piece);
+    }
+    // Missing and short files failed their pieces only because they are missing or short
+    for path in report.missing.iter().chain(&report.wrong_size) {
+        report.corrupt.remove(path);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn bytes(s: &str) -> String {
+        format!("{}:{}", s.len(), s)
+    }
+
+    fn sha1_raw(data: &[u8]) -> Vec<u8> {
+        sha1_smol::Sha1::from(data).digest().bytes().to_vec()
+    }
+
+    /// A multi-file torrent of `files` with 4-byte pieces
+    fn make_torrent(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let stream: Vec<u8> = files.iter().flat_map(|(_, data)| data.to_vec()).collect();
+        let pieces: Vec<u8> = stream.chunks(4).flat_map(sha1_raw).collect();
+        let mut out = b"d4:infod5:filesl".to_vec();
+        for (path, data) in files {
+            out.extend(format!("d6:lengthi{}e4:pathl{}ee", data.len(), bytes(path)).bytes());
+        }
+        out.extend(
+            format!(
+                "e4:name{}12:piece lengthi4e6:pieces{}:",
+                bytes("wow"),
+                pieces.len()
+            )
+            .bytes(),
+        );
+        out.extend(pieces);
+        out.extend(b"ee");
+        out
+    }
+
+    #[test]
+    fn test_verify_torrent() -> Result<()> {
+        let temp = TempDir::new()?;
+        let root = temp.path().join("wow");
+        std::fs::create_dir_all(&root)?;
+        std::fs::write(root.join("a.MPQ"), b"abcdef")?;
+        std::fs::write(root.join("b.MPQ"), b"ghij")?;
+        std::fs::write(root.join("c.MPQ"), b"kl")?;
+        let torrent = TorrentInfo::parse(&make_torrent(&[
+            ("a.MPQ", b"abcdef"),
+            ("b.MPQ", b"ghij"),
+            ("c.MPQ", b"kl"),
+        ]))?;
+        assert_eq!(torrent.pieces.len(), 3);
+        // Pointing at the download directory finds the torrent's root inside it
+        assert_eq!(torrent.root_in(temp.path()), root);
+
+        let report = verify_torrent(&root, &torrent)?;
+        assert!(report.is_ok());
+        assert_eq!(report.pieces_ok, 3);
+
+        // Corrupting b.MPQ fails the pieces it shares with a.MPQ and c.MPQ
+        std::fs::write(root.join("b.MPQ"), b"gxij")?;
+        std::fs::remove_file(root.join("c.MPQ"))?;
+        let report = verify_torrent(&root, &torrent)?;
+        assert!(!report.is_ok());
+        assert!(report.corrupt.contains("b.MPQ"));
+        assert!(report.corrupt.contains("a.MPQ"));
+        assert_eq!(report.missing, vec!["c.MPQ"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_escaping_paths() {
+        let data = b"d4:infod5:filesld6:lengthi1e4:pathl2:..6:passwdee4:name1:x12:piece lengthi4e6:pieces0:ee";
+        assert!(TorrentInfo::parse(data).is_err());
+        let data = b"d4:infod6:lengthi1e4:name2:..12:piece lengthi4e6:pieces0:ee";
+        assert!(TorrentInfo::parse(data).is_err());
+        let data = b"d4:infod6:lengthi1e4:name9:../passwd12:piece lengthi4e6:pieces0:ee";
+        assert!(TorrentInfo::parse(data).is_err());
+    }
+
+    #[test]
+    fn test_rejects_negative_lengths() {
+        let data = b"d4:infod6:lengthi1e4:name1:x12:piece lengthi4e6:pieces0:ee";
+        assert!(TorrentInfo::parse(data).is_ok());
+        let data = b"d4:infod6:lengthi-1e4:name1:x12:piece lengthi4e6:pieces0:ee";
+        assert!(TorrentInfo::parse(data).is_err());
+        let data = b"d4:infod6:lengthi1e4:name1:x12:piece lengthi-4e6:pieces0:ee";
+        assert!(TorrentInfo::parse(data).is_err());
+        let data =
+            b"d4:infod5:filesld6:lengthi-1e4:pathl1:aee4:name1:x12:piece lengthi4e6:pieces0:ee";
+        assert!(TorrentInfo::parse(data).is_err());
+    }
+}
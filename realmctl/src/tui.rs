@@ -0,0 +1,331 @@
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::{execute, terminal};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::state::{format_age, load_state};
+use crate::{ConfigFile, current_hostname};
+
+/// How often running games are looked up again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// Crash reports listed per game
+const MAX_ERRORS: usize = 3;
+
+/// One configured game as shown in the list
+#[derive(Debug)]
+pub struct Entry {
+    pub key: String,
+    /// Config error, if the entry can't be built
+    pub invalid: Option<String>,
+    pub directory: Option<std::path::PathBuf>,
+    /// Realmlist on disk, else the configured one
+    pub realmlist: Option<String>,
+    /// Account names, never passwords
+    pub accounts: Vec<String>,
+    pub running: bool,
+    pub last_launched: Option<u64>,
+    /// Outcome of the last launch from this session
+    pub launch_status: Option<Result<String, String>>,
+    /// Newest files in the game's Errors directory, with their age
+    pub errors: Vec<String>,
+}
+
+impl Entry {
+    fn load(key: String, config: std::io::Result<crate::Config>) -> Self {
+        let config = match config {
+            Ok(config) => config,
+            Err(e) => {
+                return Entry {
+                    key,
+                    invalid: Some(e.to_string()),
+                    directory: None,
+                    realmlist: None,
+                    accounts: Vec::new(),
+                    running: false,
+                    last_launched: None,
+                    launch_status: None,
+                    errors: Vec::new(),
+                };
+            }
+        };
+        let realmlist = config
+            .realmlist_path()
+            .and_then(|path| crate::realm::read_realmlist(&path).ok().flatten())
+            .or(config.realmlist.clone());
+        let accounts = config
+            .account
+            .iter()
+            .cloned()
+            .chain(config.accounts.iter().flat_map(|a| a.keys().cloned()))
+            .collect();
+        let mut entry = Entry {
+            key,
+            invalid: None,
+            realmlist,
+            accounts,
+            running: false,
+            last_launched: None,
+            launch_status: None,
+            errors: recent_errors(&config.directory),
+            directory: Some(config.directory),
+        };
+        entry.refresh();
+        entry
+    }
+
+    /// Look up whether the game runs and when it was last launched
+    fn refresh(&mut self) {
+        let Some(dir) = &self.directory else {
+            return;
+        };
+        self.running = !crate::process::processes_in(dir).is_empty();
+        self.last_launched = load_state().directory(dir).and_then(|d| d.last_launched);
+        self.errors = recent_errors(dir);
+    }
+
+    fn details(&self) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        if let Some(e) = &self.invalid {
+            lines.push(Line::styled(
+                format!("✗ {e}"),
+                Style::default().fg(Color::Red),
+            ));
+            return lines;
+        }
+        if let Some(dir) = &self.directory {
+            lines.push(Line::from(format!("Directory:  {}", dir.display())));
+        }
+        lines.push(Line::from(format!(
+            "Realmlist:  {}",
+            self.realmlist.as_deref().unwrap_or("-")
+        )));
+        lines.push(Line::from(format!(
+            "Accounts:   {}",
+            if self.accounts.is_empty() {
+                "-".to_string()
+            } else {
+                self.accounts.join(", ")
+            }
+        )));
+        lines.push(Line::from(format!(
+            "Launched:   {}{}",
+            self.last_launched.map_or("never".to_string(), format_age),
+            if self.running { " (running)" } else { "" }
+        )));
+        match &self.launch_status {
+            Some(Ok(status)) => lines.push(Line::styled(
+                format!("✓ {status}"),
+                Style::default().fg(Color::Green),
+            )),
+            Some(Err(e)) => lines.push(Line::styled(
+                format!("✗ {e}"),
+                Style::default().fg(Color::Red),
+            )),
+            None => {}
+        }
+        if !self.errors.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from("Recent errors:"));
+            for error in &self.errors {
+                lines.push(Line::styled(
+                    format!("  ⚠ {error}"),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// Newest crash reports a client wrote to its Errors directory
+pub fn recent_errors(game_dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(game_dir.join("Errors")) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<(u64, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            let secs = modified
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_secs();
+            Some((secs, entry.file_name().to_string_lossy().to_string()))
+        })
+        .collect();
+    reports.sort_by(|a, b| b.cmp(a));
+    reports
+        .into_iter()
+        .take(MAX_ERRORS)
+        .map(|(secs, name)| format!("{name} ({})", format_age(secs)))
+        .collect()
+}
+
+/// Load every game of a config file
+pub fn load_entries(config_path: &str) -> Result<Vec<Entry>> {
+    let file = ConfigFile::read(config_path)?;
+    Ok(file
+        .games(&current_hostname())
+        .into_iter()
+        .map(|(key, config)| Entry::load(key, config))
+        .collect())
+}
+
+/// Leave the terminal UI so a launch can print and prompt, then wait for Enter
+fn suspended<T>(f: impl FnOnce() -> T) -> Result<T> {
+    terminal::disable_raw_mode()?;
+    execute!(std::io::stdout(), terminal::LeaveAlternateScreen)?;
+    let result = f();
+    println!("\nPress Enter to return to the game list");
+    let _ = std::io::stdin().read_line(&mut String::new());
+    execute!(std::io::stdout(), terminal::EnterAlternateScreen)?;
+    terminal::enable_raw_mode()?;
+    Ok(result)
+}
+
+/// Pick and launch games interactively until the user quits
+///
+/// `launch` runs with the terminal restored, so it can print and ask for passwords.
+pub fn run(config_path: &str, mut launch: impl FnMut(&str) -> Result<()>) -> Result<()> {
+    let mut entries = load_entries(config_path)?;
+    if entries.is_empty() {
+        anyhow::bail!("No games configured in {config_path}");
+    }
+
+    let mut terminal = ratatui::init();
+    let mut list_state = ListState::default().with_selected(Some(0));
+    let mut last_refresh = Instant::now();
+
+    let result = loop {
+        let drawn = terminal.draw(|frame| {
+            let [main, help] = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)])
+                .areas(frame.area());
+            let [list_area, details_area] = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(main);
+
+            let items: Vec<ListItem> = entries
+                .iter()
+                .map(|entry| {
+                    let mark = match (&entry.invalid, entry.running) {
+                        (Some(_), _) => "✗",
+                        (None, true) => "▶",
+                        (None, false) => " ",
+                    };
+                    ListItem::new(format!("{mark} {}", entry.key))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(" Games "))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list, list_area, &mut list_state);
+
+            let selected = &entries[list_state.selected().unwrap_or(0)];
+            let details = Paragraph::new(selected.details())
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(" {} ", selected.key)),
+                );
+            frame.render_widget(details, details_area);
+            frame.render_widget(
+                Paragraph::new(" ↑/↓ select   Enter launch   r refresh   q quit"),
+                help,
+            );
+        });
+        if let Err(e) = drawn {
+            break Err(e.into());
+        }
+
+        if last_refresh.elapsed() >= REFRESH_INTERVAL {
+            entries.iter_mut().for_each(Entry::refresh);
+            last_refresh = Instant::now();
+        }
+        match event::poll(Duration::from_millis(500)) {
+            Ok(false) => continue,
+            Ok(true) => {}
+            Err(e) => break Err(e.into()),
+        }
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e.into()),
+        };
+        let selected = list_state.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => {
+                list_state.select(Some(selected.checked_sub(1).unwrap_or(entries.len() - 1)));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                list_state.select(Some((selected + 1) % entries.len()));
+            }
+            KeyCode::Char('r') => {
+                entries.iter_mut().for_each(Entry::refresh);
+                last_refresh = Instant::now();
+            }
+            KeyCode::Enter => {
+                let entry = &mut entries[selected];
+                if entry.invalid.is_some() {
+                    continue;
+                }
+                let key = entry.key.clone();
+                let launched = match suspended(|| launch(&key)) {
+                    Ok(launched) => launched,
+                    Err(e) => break Err(e),
+                };
+                entry.launch_status = Some(match launched {
+                    Ok(()) => Ok(format!(
+                        "Launched at {}",
+                        crate::maintenance::format_utc(crate::state::now())
+                    )),
+                    Err(e) => Err(format!("Launch failed: {e:#}")),
+                });
+                entry.refresh();
+                if let Err(e) = terminal.clear() {
+                    break Err(e.into());
+                }
+            }
+            _ => {}
+        }
+    };
+
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_recent_errors_newest_first() {
+        let temp = TempDir::new().unwrap();
+        let errors = temp.path().join("Errors");
+        std::fs::create_dir_all(&errors).unwrap();
+        for (i, name) in ["a.txt", "b.txt", "c.txt", "d.txt"].iter().enumerate() {
+            let file = std::fs::File::create(errors.join(name)).unwrap();
+            let modified =
+                std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + i as u64 * 60);
+            file.set_modified(modified).unwrap();
+        }
+
+        let names: Vec<String> = recent_errors(temp.path())
+            .into_iter()
+            .map(|e| e.split(' ').next().unwrap().to_string())
+            .collect();
+        assert_eq!(names, vec!["d.txt", "c.txt", "b.txt"]);
+        assert!(recent_errors(&temp.path().join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Clock ticks per second of [`crate::process::cpu_ticks`]
+const TICKS_PER_SEC: u64 = 100;
+
+/// How often the watchdog samples the CPU time of running clients
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What the watchdog does with a client idle for too long
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdleAction {
+    /// Print a warning once
+    #[default]
+    Warn,
+    /// Ask the client's processes to exit
+    Close,
+}
+
+/// The `[watchdog]` section of config.toml
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WatchdogConfig {
+    /// Hours of (almost) no CPU activity after which a client counts as idle
+    pub idle_hours: f64,
+    #[serde(default)]
+    pub action: IdleAction,
+    /// CPU usage in percent of one core below which a client counts as doing nothing
+    #[serde(default = "default_cpu_percent")]
+    pub cpu_percent: f64,
+}
+
+fn default_cpu_percent() -> f64 {
+    2.0
+}
+
+impl WatchdogConfig {
+    pub fn from_value(value: toml::Value) -> Result<Self, String> {
+        let config: WatchdogConfig = value.try_into().map_err(|e| format!("[watchdog] {e}"))?;
+        if config.idle_hours <= 0.0 {
+            return Err("[watchdog] idle_hours must be positive".to_string());
+        }
+        Ok(config)
+    }
+}
+
+/// Read the `[watchdog]` section of a config file, `None` when there is none
+///
+/// An invalid section is reported and ignored.
+pub fn load_watchdog(config_path: &str) -> Option<WatchdogConfig> {
+    let path = crate::paths::expand(config_path);
+    let source = std::fs::read_to_string(path).ok()?;
+    let section = toml::from_str::<toml::Table>(&source)
+        .ok()?
+        .remove("watchdog")?;
+    WatchdogConfig::from_value(section)
+        .inspect_err(|e| tracing::warn!("⚠ Ignoring invalid [watchdog] section: {e}"))
+        .ok()
+}
+
+/// Idle time of one client, fed with CPU time samples
+#[derive(Debug, Clone, Default)]
+pub struct IdleTracker {
+    ticks: Option<u64>,
+    idle_secs: u64,
+    reported: bool,
+}
+
+impl IdleTracker {
+    /// Add a sample of the total CPU ticks used, `elapsed` seconds after the previous one
+    ///
+    /// Returns true once, when the client has been idle for longer than configured.
+    pub fn sample(&mut self, ticks: u64, elapsed: u64, config: &WatchdogConfig) -> bool {
+        let previous = self.ticks.replace(ticks);
+        // A process that exited lowers the total, start over from the new one
+        let Some(used) = previous.and_then(|previous| ticks.checked_sub(previous)) else {
+            return false;
+        };
+        let percent = used as f64 * 100.0 / (elapsed.max(1) * TICKS_PER_SEC) as f64;
+        if percent >= config.cpu_percent {
+            self.idle_secs = 0;
+            self.reported = false;
+            return false;
+        }
+        self.idle_secs += elapsed;
+        if self.reported || (self.idle_secs as f64) < config.idle_hours * 3600.0 {
+            return false;
+        }
+        self.reported = true;
+        true
+    }
+
+    pub fn idle_secs(&self) -> u64 {
+        self.idle_secs
+    }
+}
+
+/// Sample the clients running from `dir` and act once they are idle for too long
+///
+/// Returns false when no client runs from `dir`.
+fn check(
+    trackers: &mut HashMap<PathBuf, IdleTracker>,
+    name: &str,
+    dir: &Path,
+    config: &WatchdogConfig,
+) -> bool {
+    let processes = crate::process::processes_in(dir);
+    if processes.is_empty() {
+        trackers.remove(dir);
+        return false;
+    }
+    let ticks = processes
+        .iter()
+        .filter_map(|process| crate::process::cpu_ticks(process.pid))
+        .sum();
+    let tracker = trackers.entry(dir.to_path_buf()).or_default();
+    if !tracker.sample(ticks, CHECK_INTERVAL.as_secs(), config) {
+        return true;
+    }
+    let hours = tracker.idle_secs() as f64 / 3600.0;
+    match config.action {
+        IdleAction::Warn => tracing::warn!("⚠ {name} has been idle for {hours:.1}h"),
+        IdleAction::Close => {
+            tracing::info!("{name} has been idle for {hours:.1}h, closing it");
+            for process in &processes {
+                if let Err(e) = crate::process::terminate(process.pid) {
+                    tracing::error!("✗ {e}");
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Watch a client started in attached mode from a background thread until it exits
+pub fn spawn_watch(name: &str, dir: &Path, config: WatchdogConfig) {
+    let name = name.to_string();
+    let dir = dir.to_path_buf();
+    std::thread::spawn(move || {
+        let mut trackers = HashMap::new();
+        let mut seen = false;
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            let running = check(&mut trackers, &name, &dir, &config);
+            // Wine may take a moment before the client shows up
+            if seen && !running {
+                return;
+            }
+            seen |= running;
+        }
+    });
+}
+
+/// Watch the clients of every game directory `dirs` returns, forever
+pub fn watch(dirs: impl Fn() -> Vec<PathBuf>, config: &WatchdogConfig) {
+    if cfg!(not(target_os = "linux")) {
+        tracing::warn!("⚠ The watchdog needs CPU times from /proc, only available on Linux");
+        return;
+    }
+    let mut trackers = HashMap::new();
+    loop {
+        for dir in dirs() {
+            check(&mut trackers, &dir.display().to_string(), &dir, config);
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_tracker() {
+        let config = WatchdogConfig::from_value(toml::Value::Table(
+            toml::from_str("idle_hours = 0.05\naction = \"close\"").unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(config.action, IdleAction::Close);
+        assert_eq!(config.cpu_percent, 2.0);
+
+        let mut tracker = IdleTracker::default();
+        assert!(!tracker.sample(1000, 60, &config));
+        // 1% of a core for two minutes: idle, but not for long enough yet
+        assert!(!tracker.sample(1060, 60, &config));
+        assert!(!tracker.sample(1120, 60, &config));
+        // Busy again resets the idle time
+        assert!(!tracker.sample(2120, 60, &config));
+        assert_eq!(tracker.idle_secs(), 0);
+        assert!(!tracker.sample(2120, 60, &config));
+        assert!(!tracker.sample(2120, 60, &config));
+        assert!(tracker.sample(2120, 60, &config));
+        // Reported only once per idle stretch
+        assert!(!tracker.sample(2120, 60, &config));
+
+        assert!(
+            WatchdogConfig::from_value(toml::Value::Table(
+                toml::from_str("idle_hours = 0").unwrap()
+            ))
+            .is_err()
+        );
+    }
+}
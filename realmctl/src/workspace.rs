@@ -1,9 +1,13 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 use crate::base::{BaseManifest, FileRole};
+use crate::journal::{Journal, JournalAction};
+use crate::outln;
+use crate::paths::{free_inodes, long_path, path_limit};
+use crate::progress::Progress;
 
 /// Sharing strategy for workspace files
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -17,32 +21,272 @@ pub enum SharingStrategy {
     Workspace,
 }
 
+impl SharingStrategy {
+    /// Name as written in sharing rules
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SharingStrategy::Global => "global",
+            SharingStrategy::Base => "base",
+            SharingStrategy::Workspace => "workspace",
+        }
+    }
+}
+
+/// Current layout version of workspace.toml and the workspace tree
+///
+/// Workspaces without a `schema_version` were created before it was recorded (version 0).
+pub const WORKSPACE_SCHEMA_VERSION: u32 = 1;
+
+/// Lock file in `.shared` guarding shared root setup
+const SHARED_LOCK_FILE: &str = ".lock";
+
+/// How base files are brought into a workspace
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStrategy {
+    /// Hard links where the role allows, falling back per role, see [`LinkMethod`]
+    Hardlink,
+}
+
+/// One way of bringing a base file into a workspace, tried in a role's fallback order
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMethod {
+    /// Shares the base's file, needs the same filesystem and owner
+    Hardlink,
+    /// Copy-on-write clone, needs btrfs, XFS, APFS or the like
+    Reflink,
+    /// Points to the base's file
+    Symlink,
+    /// Private copy
+    Copy,
+}
+
+impl LinkMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkMethod::Hardlink => "hardlink",
+            LinkMethod::Reflink => "reflink",
+            LinkMethod::Symlink => "symlink",
+            LinkMethod::Copy => "copy",
+        }
+    }
+}
+
+impl std::str::FromStr for LinkMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "hardlink" => Ok(LinkMethod::Hardlink),
+            "reflink" => Ok(LinkMethod::Reflink),
+            "symlink" => Ok(LinkMethod::Symlink),
+            "copy" => Ok(LinkMethod::Copy),
+            _ => Err(format!(
+                "expected hardlink, reflink, symlink or copy, got {s}"
+            )),
+        }
+    }
+}
+
+/// Roles whose fallback order can be configured with `create --fallback`
+pub const FALLBACK_ROLES: &[&str] = &["Executable", "BaseData", "MutableData", "Other"];
+
+/// Methods tried in order for a role's files when the workspace configures none
+pub fn default_fallbacks(role: &FileRole) -> Vec<LinkMethod> {
+    match role {
+        // Some Wine setups refuse to start a symlinked executable, and it is small to copy
+        FileRole::Executable => vec![LinkMethod::Hardlink, LinkMethod::Copy],
+        FileRole::BaseData => vec![LinkMethod::Hardlink, LinkMethod::Symlink],
+        _ => vec![LinkMethod::Reflink, LinkMethod::Copy],
+    }
+}
+
+/// Parse a `--fallback` argument, `ROLE=METHOD,METHOD` such as `BaseData=hardlink,copy`
+pub fn parse_fallback(arg: &str) -> std::result::Result<(String, Vec<LinkMethod>), String> {
+    let (role, methods) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected ROLE=METHOD,..., got {arg}"))?;
+    let role = FALLBACK_ROLES
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(role.trim()))
+        .ok_or_else(|| {
+            format!(
+                "unknown role {role}, expected {}",
+                FALLBACK_ROLES.join(", ")
+            )
+        })?;
+    let methods = methods
+        .split(',')
+        .map(|method| method.trim().parse())
+        .collect::<std::result::Result<Vec<LinkMethod>, String>>()?;
+    Ok((role.to_string(), methods))
+}
+
 /// Workspace configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Only `base_path` is required, the name and path are taken from the workspace directory
+/// when missing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WorkspaceConfig {
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub base_name: String,
     pub base_path: PathBuf,
+    #[serde(default)]
     pub workspace_path: PathBuf,
+    #[serde(default)]
     pub created_at: String,
+    #[serde(default)]
     pub sharing_rules: HashMap<String, SharingStrategy>,
+    /// Layout version, see [`WORKSPACE_SCHEMA_VERSION`]
+    #[serde(default)]
+    pub schema_version: u32,
+    /// realmctl version that created the workspace
+    #[serde(default)]
+    pub tool_version: Option<String>,
+    #[serde(default)]
+    pub link_strategy: Option<LinkStrategy>,
+    /// Fallback order of roles created with a non-default one, by role name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub link_fallbacks: BTreeMap<String, Vec<LinkMethod>>,
+    /// Checksum algorithm of the base manifest at creation time
+    #[serde(default)]
+    pub hash_algorithm: Option<String>,
+    /// MutableData files hard-linked until first changed, with their modification time
+    /// (seconds since the Unix epoch) when linked
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lazy_files: BTreeMap<String, u64>,
+    /// Realmlist address or `[realms]` preset used when config.toml sets none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub realmlist: Option<String>,
+    /// Account used when config.toml sets none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    /// Holds its own copies of every file, see [`detach_workspace`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub standalone: bool,
+    /// Base manifest the workspace was created from or last upgraded to, not recorded by
+    /// older releases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_state: Option<BaseState>,
+}
+
+/// Which state of its base manifest a workspace was derived from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BaseState {
+    /// [`BaseManifest::digest`]
+    pub digest: String,
+    /// When the manifest was written, seconds since the Unix epoch
+    #[serde(default)]
+    pub scanned_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Files in the manifest
+    #[serde(default)]
+    pub files: usize,
+}
+
+impl BaseState {
+    pub fn of(manifest: &BaseManifest) -> Self {
+        BaseState {
+            digest: manifest.digest(),
+            scanned_at: manifest.created_at.clone(),
+            version: manifest.version.clone(),
+            files: manifest.file_roles.len(),
+        }
+    }
+}
+
+impl WorkspaceConfig {
+    /// Check the workspace can be handled by this release
+    ///
+    /// Fails for workspaces from a newer release, returns a warning for ones from
+    /// releases that did not record version information.
+    pub fn check_compatibility(&self) -> Result<Option<String>> {
+        if self.schema_version > WORKSPACE_SCHEMA_VERSION {
+            anyhow::bail!(
+                "Workspace {} was created by realmctl {} (schema {}), this release supports schema {}; upgrade realmctl",
+                self.name,
+                self.tool_version.as_deref().unwrap_or("unknown"),
+                self.schema_version,
+                WORKSPACE_SCHEMA_VERSION
+            );
+        }
+        if self.schema_version < WORKSPACE_SCHEMA_VERSION {
+            return Ok(Some(format!(
+                "Workspace {} was created by an older realmctl that did not record its version; recreate it if repairs misbehave",
+                self.name
+            )));
+        }
+        Ok(None)
+    }
+
+    /// Describe how `manifest` differs from the one the workspace was derived from, None when
+    /// it is the same or the workspace did not record it
+    pub fn base_change(&self, manifest: &BaseManifest) -> Option<String> {
+        let recorded = self.base_state.as_ref()?;
+        let current = BaseState::of(manifest);
+        if recorded.digest == current.digest {
+            return None;
+        }
+        let files = match current.files.cmp(&recorded.files) {
+            std::cmp::Ordering::Equal => format!("{} files", current.files),
+            _ => format!("{} files, was {}", current.files, recorded.files),
+        };
+        Some(format!(
+            "Base of {} changed since the workspace was created or last upgraded ({files}), `realmctl upgrade` brings the changes in",
+            self.name
+        ))
+    }
 }
 
 /// Default sharing rules
 pub fn default_sharing_rules() -> HashMap<String, SharingStrategy> {
     let mut rules = HashMap::new();
     rules.insert("screenshots".to_string(), SharingStrategy::Global);
-    rules.insert("interface/addons".to_string(), SharingStrategy::Base);
+    rules.insert("addons".to_string(), SharingStrategy::Base);
     rules.insert("wtf".to_string(), SharingStrategy::Workspace);
     rules
 }
 
+/// Optional behaviour of [`create_workspace_with`]
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// Hard link MutableData files and copy them only once they are changed
+    pub lazy_mutable: bool,
+    /// Default realmlist recorded in workspace.toml
+    pub realmlist: Option<String>,
+    /// Default account recorded in workspace.toml
+    pub account: Option<String>,
+    /// Methods tried for the files of a role instead of [`default_fallbacks`], by role name
+    pub link_fallbacks: BTreeMap<String, Vec<LinkMethod>>,
+}
+
 /// Create a new workspace
 pub fn create_workspace(
     name: &str,
     base_path: &Path,
     workspace_root: &Path,
     sharing_rules: HashMap<String, SharingStrategy>,
+) -> Result<WorkspaceConfig> {
+    create_workspace_with(
+        name,
+        base_path,
+        workspace_root,
+        sharing_rules,
+        &CreateOptions::default(),
+    )
+}
+
+/// Create a new workspace with non-default options
+pub fn create_workspace_with(
+    name: &str,
+    base_path: &Path,
+    workspace_root: &Path,
+    sharing_rules: HashMap<String, SharingStrategy>,
+    options: &CreateOptions,
 ) -> Result<WorkspaceConfig> {
     use std::time::SystemTime;
 
@@ -55,24 +299,58 @@ pub fn create_workspace(
     if workspace_path.exists() {
         anyhow::bail!("Workspace already exists: {}", workspace_path.display());
     }
-    std::fs::create_dir_all(&workspace_path)?;
+    check_base_readable(base_path, &base_manifest)?;
+    for warning in check_workspace_limits(&workspace_path, &base_manifest)? {
+        tracing::warn!("⚠ {warning}");
+    }
+    std::fs::create_dir_all(workspace_root)?;
+    // create_dir, not create_dir_all: of two runs creating the same workspace only one may win
+    match std::fs::create_dir(&workspace_path) {
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            anyhow::bail!("Workspace already exists: {}", workspace_path.display());
+        }
+        result => result?,
+    }
 
-    // Create shared directories based on strategy
-    let global_shared_dir = workspace_root.join(".shared").join("global");
-    let per_base_shared_dir = workspace_root.join(".shared").join(&base_manifest.profile);
+    // Create shared directories based on strategy, using extended-length paths on Windows
+    let long_root = long_path(workspace_root);
+    let global_shared_dir = long_root.join(".shared").join("global");
+    let per_base_shared_dir = long_root.join(".shared").join(&base_manifest.profile);
 
-    std::fs::create_dir_all(&global_shared_dir)?;
-    std::fs::create_dir_all(&per_base_shared_dir)?;
+    {
+        let _lock = lock_shared_root(&long_root)?;
+        std::fs::create_dir_all(&global_shared_dir)?;
+        std::fs::create_dir_all(&per_base_shared_dir)?;
+    }
 
     // Link files according to manifest and sharing rules
-    link_workspace_files(
-        base_path,
-        &workspace_path,
+    let mut journal = Journal::new("create");
+    let linked = link_workspace_files(
+        &long_path(base_path),
+        &long_root.join(name),
         &global_shared_dir,
         &per_base_shared_dir,
         &base_manifest,
         &sharing_rules,
-    )?;
+        options,
+        &mut journal,
+        false,
+    );
+    let lazy_files = match linked {
+        Ok(lazy_files) => lazy_files,
+        Err(e) => {
+            // Roll back so an interrupted or failed create never leaves a half-linked workspace.
+            // remove_dir_all does not follow the shared symlinks, shared data is untouched.
+            tracing::info!(
+                "Removing partially created workspace: {}",
+                workspace_path.display()
+            );
+            if let Err(cleanup) = std::fs::remove_dir_all(long_root.join(name)) {
+                tracing::error!("✗ Failed to remove {}: {cleanup}", workspace_path.display());
+            }
+            return Err(e);
+        }
+    };
 
     let created_at = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -87,16 +365,123 @@ pub fn create_workspace(
         workspace_path: workspace_path.clone(),
         created_at,
         sharing_rules,
+        schema_version: WORKSPACE_SCHEMA_VERSION,
+        tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        link_strategy: Some(LinkStrategy::Hardlink),
+        link_fallbacks: options.link_fallbacks.clone(),
+        hash_algorithm: Some(crate::base::HASH_ALGORITHM.to_string()),
+        lazy_files,
+        realmlist: options.realmlist.clone(),
+        account: options.account.clone(),
+        standalone: false,
+        base_state: Some(BaseState::of(&base_manifest)),
     };
 
-    // Write workspace config
-    let config_path = workspace_path.join("workspace.toml");
-    let toml_string = toml::to_string_pretty(&config)?;
-    std::fs::write(config_path, toml_string)?;
+    save_workspace_config(&workspace_path, &config)?;
+    save_snapshot(
+        &workspace_path,
+        &take_snapshot(base_path, &workspace_path, &base_manifest),
+    )?;
+    journal
+        .append(&workspace_path)
+        .context("Failed to write workspace.log")?;
 
     Ok(config)
 }
 
+/// What [`create_workspace_with`] would do, without touching the disk
+///
+/// The journal holds an entry per directory and file of the new workspace. Files name the
+/// first link method that applies to them; a later one is only used if it fails on creation.
+pub fn plan_workspace(
+    name: &str,
+    base_path: &Path,
+    workspace_root: &Path,
+    sharing_rules: &HashMap<String, SharingStrategy>,
+    options: &CreateOptions,
+) -> Result<Journal> {
+    let base_manifest = crate::base::load_manifest(base_path)
+        .context("Failed to load base manifest - is this a valid base?")?;
+    let workspace_path = workspace_root.join(name);
+    if workspace_path.exists() {
+        anyhow::bail!("Workspace already exists: {}", workspace_path.display());
+    }
+    check_base_readable(base_path, &base_manifest)?;
+    for warning in check_workspace_limits(&workspace_path, &base_manifest)? {
+        tracing::warn!("⚠ {warning}");
+    }
+
+    let long_root = long_path(workspace_root);
+    let mut journal = Journal::new("create");
+    link_workspace_files(
+        &long_path(base_path),
+        &long_root.join(name),
+        &long_root.join(".shared").join("global"),
+        &long_root.join(".shared").join(&base_manifest.profile),
+        &base_manifest,
+        sharing_rules,
+        options,
+        &mut journal,
+        true,
+    )?;
+    Ok(journal)
+}
+
+/// Check that the filesystem can hold a workspace before creating it
+///
+/// Fails when the filesystem is out of inodes; returns warnings for paths that exceed
+/// MAX_PATH on Windows without long path support, see [`path_limit_warning`].
+pub fn check_workspace_limits(
+    workspace_path: &Path,
+    manifest: &BaseManifest,
+) -> Result<Vec<String>> {
+    let mut warnings = Vec::new();
+
+    // Hard-linked files share the base's inode, everything else needs its own
+    let needed_inodes = manifest
+        .file_roles
+        .values()
+        .filter(|role| !matches!(role, FileRole::BaseData | FileRole::Executable))
+        .count() as u64
+        + 16;
+    if let Some(free) = free_inodes(workspace_path)
+        && free < needed_inodes
+    {
+        anyhow::bail!(
+            "Not enough free inodes on the filesystem of {}: {free} free, about {needed_inodes} needed",
+            workspace_path.display()
+        );
+    }
+
+    warnings.extend(path_limit_warning(workspace_path, manifest));
+    Ok(warnings)
+}
+
+/// Warning for manifest paths that exceed MAX_PATH under `workspace_path`, on Windows
+/// without long path support
+pub fn path_limit_warning(workspace_path: &Path, manifest: &BaseManifest) -> Option<String> {
+    let limit = path_limit()?;
+    let prefix_len = workspace_path.to_string_lossy().chars().count() + 1;
+    let too_long = manifest
+        .file_roles
+        .keys()
+        .filter(|rel_path| prefix_len + rel_path.chars().count() >= limit)
+        .count();
+    let longest = manifest
+        .file_roles
+        .keys()
+        .max_by_key(|p| p.chars().count())?;
+    (too_long > 0).then(|| {
+        format!(
+            "{too_long} path(s) exceed {limit} characters under {}, e.g. {longest}. \
+             Enable LongPathsEnabled in Windows or use a shorter workspace root, \
+             otherwise the game may fail to load them",
+            workspace_path.display()
+        )
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 fn link_workspace_files(
     base_path: &Path,
     workspace_path: &Path,
@@ -104,7 +489,10 @@ fn link_workspace_files(
     per_base_shared_dir: &Path,
     manifest: &BaseManifest,
     sharing_rules: &HashMap<String, SharingStrategy>,
-) -> Result<()> {
+    options: &CreateOptions,
+    journal: &mut Journal,
+    dry_run: bool,
+) -> Result<BTreeMap<String, u64>> {
     // First pass: create shared links for directories
     // Process directories from shallowest to deepest to ensure parents are created first
     let mut dir_entries: Vec<_> = manifest
@@ -120,6 +508,7 @@ fn link_workspace_files(
     let mut processed_shared_dirs: Vec<String> = Vec::new();
 
     for (rel_path, role) in dir_entries {
+        crate::cancel::check()?;
         let workspace_file = workspace_path.join(rel_path);
 
         // Skip if a parent directory is already shared
@@ -131,58 +520,49 @@ fn link_workspace_files(
             continue;
         }
 
-        match role {
-            FileRole::UserMedia => {
-                // Check sharing strategy for media directories
-                let strategy = determine_strategy(rel_path, sharing_rules, SharingStrategy::Global);
-                if !matches!(strategy, SharingStrategy::Workspace) {
-                    create_shared_link(
-                        rel_path,
-                        &workspace_file,
-                        global_shared_dir,
-                        per_base_shared_dir,
-                        strategy,
-                    )?;
-                    processed_shared_dirs.push(rel_path.to_string());
-                } else {
-                    create_shared_link(
-                        rel_path,
-                        &workspace_file,
-                        global_shared_dir,
-                        per_base_shared_dir,
-                        strategy,
-                    )?;
-                }
-            }
-            FileRole::UserConfig => {
-                // Check sharing strategy for config directories
-                let strategy =
-                    determine_strategy(rel_path, sharing_rules, SharingStrategy::Workspace);
-                if !matches!(strategy, SharingStrategy::Workspace) {
-                    create_shared_link(
-                        rel_path,
-                        &workspace_file,
-                        global_shared_dir,
-                        per_base_shared_dir,
-                        strategy,
-                    )?;
-                    processed_shared_dirs.push(rel_path.to_string());
-                } else {
-                    create_shared_link(
-                        rel_path,
-                        &workspace_file,
-                        global_shared_dir,
-                        per_base_shared_dir,
-                        strategy,
-                    )?;
+        let default = match role {
+            FileRole::UserMedia => SharingStrategy::Global,
+            _ => SharingStrategy::Workspace,
+        };
+        let strategy = determine_strategy(rel_path, sharing_rules, default);
+        if dry_run {
+            let reason = format!("{role:?} directory, shared per {}", strategy.as_str());
+            let target = match strategy {
+                SharingStrategy::Global => global_shared_dir.join(rel_path),
+                SharingStrategy::Base => per_base_shared_dir.join(rel_path),
+                SharingStrategy::Workspace => {
+                    journal.record(JournalAction::Mkdir, rel_path, reason);
+                    continue;
                 }
+            };
+            let link = crate::paths::link_target(&target, &workspace_file);
+            journal.record_symlink(rel_path, &link, reason);
+            processed_shared_dirs.push(rel_path.to_string());
+            continue;
+        }
+        create_shared_link(
+            rel_path,
+            &workspace_file,
+            global_shared_dir,
+            per_base_shared_dir,
+            strategy.clone(),
+        )?;
+        let reason = format!("{role:?} directory, shared per {}", strategy.as_str());
+        match workspace_file.read_link() {
+            Ok(target) => {
+                journal.record_symlink(rel_path, &target, reason);
+                processed_shared_dirs.push(rel_path.to_string());
             }
-            _ => {}
+            Err(_) => journal.record(JournalAction::Mkdir, rel_path, reason),
         }
     }
 
-    // Second pass: create files and other directories
+    // Second pass: create parent directories and collect what to bring in, in manifest order.
+    // A planned workspace does not exist yet, its root tells who will own it.
+    let owner = workspace_path.ancestors().find_map(file_owner);
+    let mut jobs: Vec<LinkJob> = Vec::new();
     for (rel_path, role) in &manifest.file_roles {
+        crate::cancel::check()?;
         let base_file = base_path.join(rel_path);
         let workspace_file = workspace_path.join(rel_path);
 
@@ -191,91 +571,1104 @@ fn link_workspace_files(
             continue;
         }
 
-        // Ensure parent directory exists in workspace
-        // But don't create it if any ancestor should be a symlink
-        if let Some(parent) = workspace_file.parent()
-            && parent != workspace_path {
-                // Check if any ancestor should be a shared link
-                let mut should_create = true;
-                let mut current = parent;
-                while current != workspace_path {
-                    // Check if this directory exists and is a symlink
-                    if current.read_link().is_ok() {
-                        should_create = false;
-                        break;
-                    }
-                    // Check if this directory should be a shared directory
-                    if let Ok(rel) = current.strip_prefix(workspace_path) {
-                        let rel_str = rel.to_string_lossy().to_string();
-                        // Check if this path matches a sharing rule
-                        for key in sharing_rules.keys() {
-                            if &rel_str == key || rel_str.starts_with(&format!("{}/", key)) {
-                                should_create = false;
-                                break;
-                            }
-                        }
-                    }
-                    if !should_create {
-                        break;
-                    }
-                    if let Some(p) = current.parent() {
-                        current = p;
-                    } else {
-                        break;
-                    }
-                }
-                if should_create && !parent.exists() {
-                    std::fs::create_dir_all(parent)?;
-                }
-            }
-
-        match role {
-            FileRole::BaseData | FileRole::Executable => {
-                // Hard link immutable files from base
-                if base_file.is_file() && !workspace_file.exists() {
-                    std::fs::hard_link(&base_file, &workspace_file)
-                        .or_else(|_| {
-                            // Fallback to symlink if hard link fails
-                            #[cfg(unix)]
-                            std::os::unix::fs::symlink(&base_file, &workspace_file)?;
-                            #[cfg(windows)]
-                            std::os::windows::fs::symlink_file(&base_file, &workspace_file)?;
-                            Ok::<(), std::io::Error>(())
-                        })
-                        .with_context(|| format!("Failed to link {}", rel_path))?;
-                }
-            }
-            FileRole::MutableData => {
-                // Copy mutable data to workspace
-                if base_file.is_file() && !workspace_file.exists() {
-                    std::fs::copy(&base_file, &workspace_file)?;
-                }
+        // Ensure parent directory exists in workspace
+        // But don't create it if any ancestor should be a symlink
+        if let Some(parent) = workspace_file.parent()
+            && parent != workspace_path {
+                // Check if any ancestor should be a shared link
+                let mut should_create = true;
+                let mut current = parent;
+                while current != workspace_path {
+                    // Check if this directory exists and is a symlink
+                    if current.read_link().is_ok() {
+                        should_create = false;
+                        break;
+                    }
+                    // Check if this directory should be a shared directory
+                    if let Ok(rel) = current.strip_prefix(workspace_path) {
+                        let rel_str = rel.to_string_lossy().to_string();
+                        // Check if this path matches a sharing rule
+                        for key in sharing_rules.keys() {
+                            if &rel_str == key || rel_str.starts_with(&format!("{}/", key)) {
+                                should_create = false;
+                                break;
+                            }
+                        }
+                    }
+                    if !should_create {
+                        break;
+                    }
+                    if let Some(p) = current.parent() {
+                        current = p;
+                    } else {
+                        break;
+                    }
+                }
+                if should_create && !dry_run && !parent.exists() {
+                    std::fs::create_dir_all(parent)?;
+                }
+            }
+
+        let kind = match role {
+            FileRole::BaseData | FileRole::Executable | FileRole::Other
+                if base_file.is_file() && !workspace_file.exists() =>
+            {
+                LinkKind::Place
+            }
+            FileRole::MutableData if base_file.is_file() && !workspace_file.exists() => {
+                LinkKind::Mutable
+            }
+            FileRole::Ephemeral if base_file.is_dir() && !workspace_file.exists() => {
+                LinkKind::Mkdir
+            }
+            FileRole::Managed
+                if base_file.is_file()
+                    && !workspace_file.exists()
+                    && !crate::base::TOOL_FILES.contains(&rel_path.as_str()) =>
+            {
+                LinkKind::Managed
+            }
+            _ => continue,
+        };
+        let size = match kind {
+            LinkKind::Mkdir => 0,
+            _ => std::fs::metadata(&base_file).map_or(0, |meta| meta.len()),
+        };
+        jobs.push(LinkJob {
+            rel_path,
+            role,
+            base_file,
+            workspace_file,
+            kind,
+            size,
+        });
+    }
+
+    // Third pass: bring the files in on several threads. Large clients hold tens of
+    // thousands of files, and linking them one by one takes minutes.
+    let fallbacks = |role: &FileRole| {
+        options
+            .link_fallbacks
+            .get(&format!("{role:?}"))
+            .cloned()
+            .unwrap_or_else(|| default_fallbacks(role))
+    };
+    if dry_run {
+        for job in &jobs {
+            let same_owner = file_owner(&job.base_file) == owner;
+            record_planned(journal, job, options, same_owner, &fallbacks(job.role))?;
+        }
+        return Ok(BTreeMap::new());
+    }
+    let progress = Progress::new("Linking", jobs.len(), jobs.iter().map(|job| job.size).sum());
+    let outcomes = run_parallel(&jobs, |job| {
+        let outcome = bring_in(job, options, owner, &fallbacks(job.role))?;
+        progress.add(1, job.size);
+        Ok(outcome)
+    });
+    progress.finish();
+
+    // Journal in manifest order, whatever order the threads finished in
+    let mut lazy_files = BTreeMap::new();
+    for (job, outcome) in jobs.iter().zip(outcomes?) {
+        let (rel_path, role) = (job.rel_path, job.role);
+        match outcome {
+            LinkOutcome::Lazy(modified) => {
+                lazy_files.insert(rel_path.clone(), modified);
+                journal.record(
+                    JournalAction::Lazy,
+                    rel_path,
+                    "MutableData, shared with the base until changed",
+                );
+            }
+            LinkOutcome::Placed(placed) => {
+                let reason = match role {
+                    FileRole::MutableData if options.lazy_mutable => "could not be hard-linked",
+                    FileRole::MutableData => "the game changes it",
+                    FileRole::Other => "no role in the profile",
+                    _ => "own copy",
+                };
+                record_placed(journal, rel_path, &job.workspace_file, role, placed, reason)?;
+            }
+            LinkOutcome::Mkdir => {
+                journal.record(JournalAction::Mkdir, rel_path, "Ephemeral, starts empty")
+            }
+            LinkOutcome::Managed => journal.record(
+                JournalAction::Copy,
+                rel_path,
+                "Managed, rewritten per workspace",
+            ),
+        }
+    }
+
+    Ok(lazy_files)
+}
+
+/// Most threads [`link_workspace_files`] links with; more only contend for the disk
+const LINK_THREADS: usize = 8;
+
+/// How a file of the base is brought into a new workspace
+#[derive(Clone, Copy)]
+enum LinkKind {
+    /// With the role's link methods, see [`place_file`]
+    Place,
+    /// Hard-linked until changed with `lazy_mutable`, else like `Place`
+    Mutable,
+    /// An empty directory
+    Mkdir,
+    /// A copy realmctl rewrites per workspace
+    Managed,
+}
+
+/// A file or directory [`link_workspace_files`] brings into the workspace
+struct LinkJob<'a> {
+    rel_path: &'a String,
+    role: &'a FileRole,
+    base_file: PathBuf,
+    workspace_file: PathBuf,
+    kind: LinkKind,
+    /// Size in the base, for the progress bar
+    size: u64,
+}
+
+/// What became of a [`LinkJob`], journaled once all threads are done
+enum LinkOutcome {
+    Placed(std::result::Result<(LinkMethod, Vec<String>), Vec<String>>),
+    /// Hard-linked lazily, with the modification time it was linked at
+    Lazy(u64),
+    Mkdir,
+    Managed,
+}
+
+/// Bring one file into the workspace; safe to run on several threads at once
+fn bring_in(
+    job: &LinkJob,
+    options: &CreateOptions,
+    owner: Option<u32>,
+    methods: &[LinkMethod],
+) -> Result<LinkOutcome> {
+    let (base_file, workspace_file) = (&job.base_file, &job.workspace_file);
+    // Files of another owner (a system-wide base) are not hard-linked: the kernel may refuse
+    // it, and a hard link would keep the old content when the owner updates the base.
+    let same_owner = file_owner(base_file) == owner;
+    Ok(match job.kind {
+        LinkKind::Place => {
+            LinkOutcome::Placed(place_file(base_file, workspace_file, methods, same_owner))
+        }
+        // Lazily: share with the base until changed, see `check_lazy_files`
+        LinkKind::Mutable
+            if options.lazy_mutable
+                && same_owner
+                && std::fs::hard_link(base_file, workspace_file).is_ok() =>
+        {
+            LinkOutcome::Lazy(modified_secs(workspace_file))
+        }
+        LinkKind::Mutable => {
+            LinkOutcome::Placed(place_file(base_file, workspace_file, methods, same_owner))
+        }
+        LinkKind::Mkdir => {
+            std::fs::create_dir_all(workspace_file)?;
+            LinkOutcome::Mkdir
+        }
+        LinkKind::Managed => {
+            copy_writable(base_file, workspace_file)?;
+            LinkOutcome::Managed
+        }
+    })
+}
+
+/// Run `work` on every job on up to [`LINK_THREADS`] threads, returning the results in the
+/// order of `jobs`
+///
+/// The threads stop at the first error or at Ctrl-C; the error of the earliest job is returned.
+fn run_parallel<J: Sync, T: Send>(
+    jobs: &[J],
+    work: impl Fn(&J) -> Result<T> + Sync,
+) -> Result<Vec<T>> {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    let threads = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(LINK_THREADS)
+        .min(jobs.len())
+        .max(1);
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let finished: Vec<(usize, Result<T>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while !failed.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(job) = jobs.get(i) else { break };
+                        let result = match crate::cancel::check() {
+                            Ok(()) => work(job),
+                            Err(cancelled) => Err(cancelled.into()),
+                        };
+                        if result.is_err() {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                        done.push((i, result));
+                    }
+                    done
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("link thread panicked"))
+            .collect()
+    });
+
+    let mut results: Vec<Option<T>> = (0..jobs.len()).map(|_| None).collect();
+    let mut first_error: Option<(usize, anyhow::Error)> = None;
+    for (i, result) in finished {
+        match result {
+            Ok(value) => results[i] = Some(value),
+            Err(e) if first_error.as_ref().is_none_or(|(first, _)| i < *first) => {
+                first_error = Some((i, e))
+            }
+            Err(_) => {}
+        }
+    }
+    if let Some((_, e)) = first_error {
+        return Err(e);
+    }
+    Ok(results
+        .into_iter()
+        .map(|result| result.expect("every job ran"))
+        .collect())
+}
+
+/// Owning user of a file, `None` where the platform has no such notion
+#[cfg(unix)]
+fn file_owner(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|meta| meta.uid())
+}
+
+#[cfg(not(unix))]
+fn file_owner(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Copy a file and make the copy writable by its new owner
+///
+/// Files of a read-only base (e.g. installed by root with mode 444) would otherwise
+/// stay read-only in the workspace, and the game or its patcher could not update them.
+fn copy_writable(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::copy(from, to)?;
+    make_writable(to)
+}
+
+/// Bring a base file into the workspace with the first of `methods` that works
+///
+/// Returns the method used and why the ones before it failed. A reflink clone shares the
+/// base's blocks until the game writes to it, so patch archives the game rarely touches
+/// take no extra space per workspace; clones and copies are made writable.
+fn place_file(
+    base_file: &Path,
+    workspace_file: &Path,
+    methods: &[LinkMethod],
+    same_owner: bool,
+) -> std::result::Result<(LinkMethod, Vec<String>), Vec<String>> {
+    let mut failures = Vec::new();
+    for &method in methods {
+        let placed = match method {
+            LinkMethod::Hardlink if !same_owner => {
+                Err("base file is owned by another user".to_string())
+            }
+            LinkMethod::Hardlink => std::fs::hard_link(base_file, workspace_file)
+                .map_err(|e| format!("hard link failed: {e}")),
+            LinkMethod::Reflink => crate::reflink::reflink(base_file, workspace_file)
+                .and_then(|()| {
+                    std::fs::set_permissions(
+                        workspace_file,
+                        std::fs::metadata(base_file)?.permissions(),
+                    )?;
+                    make_writable(workspace_file)
+                })
+                .map_err(|e| format!("reflink failed: {e}")),
+            LinkMethod::Symlink => {
+                let link = crate::paths::link_target(base_file, workspace_file);
+                #[cfg(unix)]
+                let symlinked = std::os::unix::fs::symlink(&link, workspace_file);
+                #[cfg(windows)]
+                let symlinked = std::os::windows::fs::symlink_file(&link, workspace_file);
+                symlinked.map_err(|e| format!("symlink failed: {e}"))
+            }
+            LinkMethod::Copy => {
+                copy_writable(base_file, workspace_file).map_err(|e| format!("copy failed: {e}"))
+            }
+        };
+        match placed {
+            Ok(()) => return Ok((method, failures)),
+            Err(reason) => {
+                tracing::debug!("{}: {reason}", workspace_file.display());
+                failures.push(reason);
+            }
+        }
+    }
+    Err(failures)
+}
+
+/// Journal what [`bring_in`] would do with `job`, assuming its first applicable method works
+fn record_planned(
+    journal: &mut Journal,
+    job: &LinkJob,
+    options: &CreateOptions,
+    same_owner: bool,
+    methods: &[LinkMethod],
+) -> Result<()> {
+    let (rel_path, role) = (job.rel_path, job.role);
+    let method = match job.kind {
+        LinkKind::Mkdir => {
+            journal.record(JournalAction::Mkdir, rel_path, "Ephemeral, starts empty");
+            return Ok(());
+        }
+        LinkKind::Managed => {
+            journal.record(
+                JournalAction::Copy,
+                rel_path,
+                "Managed, rewritten per workspace",
+            );
+            return Ok(());
+        }
+        LinkKind::Mutable if options.lazy_mutable && same_owner => {
+            journal.record(
+                JournalAction::Lazy,
+                rel_path,
+                "MutableData, shared with the base until changed",
+            );
+            return Ok(());
+        }
+        LinkKind::Place | LinkKind::Mutable => methods
+            .iter()
+            .find(|&&method| same_owner || method != LinkMethod::Hardlink)
+            .with_context(|| {
+                format!("Cannot link {rel_path}: base file is owned by another user")
+            })?,
+    };
+    let reason = match method {
+        LinkMethod::Hardlink | LinkMethod::Symlink => format!("{role:?}, shared with the base"),
+        LinkMethod::Reflink | LinkMethod::Copy => format!("{role:?}, own copy"),
+    };
+    match method {
+        LinkMethod::Hardlink => journal.record(JournalAction::Hardlink, rel_path, reason),
+        LinkMethod::Reflink => journal.record(JournalAction::Reflink, rel_path, reason),
+        LinkMethod::Copy => journal.record(JournalAction::Copy, rel_path, reason),
+        LinkMethod::Symlink => {
+            let link = crate::paths::link_target(&job.base_file, &job.workspace_file);
+            journal.record_symlink(rel_path, &link, reason);
+        }
+    }
+    Ok(())
+}
+
+/// Journal how [`place_file`] brought in a file, with the methods that failed before it
+fn record_placed(
+    journal: &mut Journal,
+    rel_path: &str,
+    workspace_file: &Path,
+    role: &FileRole,
+    placed: std::result::Result<(LinkMethod, Vec<String>), Vec<String>>,
+    copy_reason: &str,
+) -> Result<()> {
+    let (method, failures) = placed.map_err(|failures| {
+        anyhow::anyhow!("Failed to link {rel_path}: {}", failures.join("; "))
+    })?;
+    let mut reason = match method {
+        LinkMethod::Hardlink | LinkMethod::Symlink => format!("{role:?}, shared with the base"),
+        LinkMethod::Reflink | LinkMethod::Copy => format!("{role:?}, {copy_reason}"),
+    };
+    for failure in failures {
+        reason.push_str("; ");
+        reason.push_str(&failure);
+    }
+    match method {
+        LinkMethod::Hardlink => journal.record(JournalAction::Hardlink, rel_path, reason),
+        LinkMethod::Reflink => journal.record(JournalAction::Reflink, rel_path, reason),
+        LinkMethod::Copy => journal.record(JournalAction::Copy, rel_path, reason),
+        LinkMethod::Symlink => {
+            let link = std::fs::read_link(workspace_file).unwrap_or_default();
+            journal.record_symlink(rel_path, &link, reason);
+        }
+    }
+    Ok(())
+}
+
+fn make_writable(to: &Path) -> std::io::Result<()> {
+    let mut permissions = std::fs::metadata(to)?.permissions();
+    if !permissions.readonly() {
+        return Ok(());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    std::fs::set_permissions(to, permissions)
+}
+
+/// Modification time in seconds since the Unix epoch, 0 if unknown
+fn modified_secs(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Whether two paths are the same file (hard links of each other)
+#[cfg(unix)]
+pub(crate) fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+/// Whether two paths are the same file (hard links of each other)
+///
+/// Compares the volume serial number and file index, the NTFS counterpart of inodes.
+#[cfg(windows)]
+pub(crate) fn same_file(a: &Path, b: &Path) -> bool {
+    match (file_id(a), file_id(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Volume serial number and file index of a file
+#[cfg(windows)]
+fn file_id(path: &Path) -> Option<(u32, u32, u32)> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        BY_HANDLE_FILE_INFORMATION, GetFileInformationByHandle,
+    };
+
+    let file = std::fs::File::open(path).ok()?;
+    // SAFETY: the handle stays open for the call, and the struct is plain data
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    let ok = unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) };
+    (ok != 0).then_some((
+        info.dwVolumeSerialNumber,
+        info.nFileIndexHigh,
+        info.nFileIndexLow,
+    ))
+}
+
+/// Whether two paths are the same file; without a way to tell, never
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn same_file(_a: &Path, _b: &Path) -> bool {
+    false
+}
+
+/// Replace a hard link by a private copy of the file
+fn break_hard_link(path: &Path) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".realmctl-copy");
+    let temp_path = path.with_file_name(temp_name);
+    copy_writable(path, &temp_path)?;
+    std::fs::rename(&temp_path, path)
+}
+
+/// Settle lazily linked MutableData files before a launch
+///
+/// Files a patcher replaced are no longer shared and are dropped from the list. Files
+/// changed in place also changed the base; they get a private copy so further changes
+/// stay in the workspace, and a warning is returned for each.
+pub fn check_lazy_files(workspace_path: &Path) -> Result<Vec<String>> {
+    if !workspace_path.join("workspace.toml").is_file() {
+        return Ok(Vec::new());
+    }
+    let mut config = load_workspace_config(workspace_path)?;
+    if config.lazy_files.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut warnings = Vec::new();
+    let before = config.lazy_files.len();
+    let mut lazy_files = std::mem::take(&mut config.lazy_files);
+    lazy_files.retain(|rel_path, linked_mtime| {
+        let workspace_file = workspace_path.join(rel_path);
+        if !same_file(&config.base_path.join(rel_path), &workspace_file) {
+            return false;
+        }
+        if modified_secs(&workspace_file) == *linked_mtime {
+            return true;
+        }
+        match break_hard_link(&workspace_file) {
+            Ok(()) => warnings.push(format!(
+                "{rel_path} was changed in place, the base copy changed too; \
+                 the workspace now has its own copy. Run `realmctl init-base` on the base to refresh its manifest"
+            )),
+            Err(e) => warnings.push(format!("{rel_path} was changed in place, copying it failed: {e}")),
+        }
+        false
+    });
+    config.lazy_files = lazy_files;
+    if config.lazy_files.len() != before {
+        save_workspace_config(workspace_path, &config)?;
+    }
+    Ok(warnings)
+}
+
+/// Give a workspace private copies of lazily linked MutableData files
+///
+/// Run this before a patcher that edits files in place. With no `rel_paths`, every
+/// lazily linked file is copied. Returns the copied files.
+pub fn materialize_files(workspace_path: &Path, rel_paths: &[String]) -> Result<Vec<String>> {
+    let mut config = load_workspace_config(workspace_path)?;
+    for rel_path in rel_paths {
+        if !config.lazy_files.contains_key(rel_path) {
+            anyhow::bail!("{rel_path} is not a lazily linked file of {}", config.name);
+        }
+    }
+
+    let selected: Vec<String> = config
+        .lazy_files
+        .keys()
+        .filter(|rel_path| rel_paths.is_empty() || rel_paths.contains(rel_path))
+        .cloned()
+        .collect();
+    let mut copied = Vec::new();
+    for rel_path in selected {
+        crate::cancel::check()?;
+        let workspace_file = workspace_path.join(&rel_path);
+        if same_file(&config.base_path.join(&rel_path), &workspace_file) {
+            break_hard_link(&workspace_file)
+                .with_context(|| format!("Failed to copy {rel_path}"))?;
+            copied.push(rel_path.clone());
+        }
+        config.lazy_files.remove(&rel_path);
+        // Save as we go, an interrupted run keeps what it already copied
+        save_workspace_config(workspace_path, &config)?;
+    }
+    Ok(copied)
+}
+
+/// Links tying a workspace to its base and the shared roots, see [`detach_workspace`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DetachPlan {
+    /// Files hard-linked with the base, relative to the workspace
+    pub hard_links: Vec<String>,
+    /// Symlinks to base files or shared directories, relative to the workspace
+    pub symlinks: Vec<String>,
+    /// Size of the copies replacing them
+    pub bytes: u64,
+}
+
+impl DetachPlan {
+    pub fn is_empty(&self) -> bool {
+        self.hard_links.is_empty() && self.symlinks.is_empty()
+    }
+}
+
+/// Size of a file or of everything below a directory, following symlinks
+fn tree_size(path: &Path) -> u64 {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => std::fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| tree_size(&entry.path()))
+            .sum(),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Find what [`detach_workspace`] has to copy
+pub fn plan_detach(workspace_path: &Path) -> Result<DetachPlan> {
+    let config = load_workspace_config(workspace_path)?;
+    let mut plan = DetachPlan::default();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(rel_dir) = pending.pop() {
+        for entry in std::fs::read_dir(workspace_path.join(&rel_dir))? {
+            let entry = entry?;
+            let rel = rel_dir.join(entry.file_name());
+            let rel_path = rel.to_string_lossy().replace('\\', "/");
+            let file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                // A dangling link has nothing to copy
+                if entry.path().exists() {
+                    plan.bytes += tree_size(&entry.path());
+                    plan.symlinks.push(rel_path);
+                }
+            } else if file_type.is_dir() {
+                pending.push(rel);
+            } else if same_file(&config.base_path.join(&rel), &entry.path()) {
+                plan.bytes += entry.metadata()?.len();
+                plan.hard_links.push(rel_path);
+            }
+        }
+    }
+    plan.hard_links.sort();
+    plan.symlinks.sort();
+    Ok(plan)
+}
+
+/// Copy a file or directory tree, following symlinks, calling `progress` with each file's size
+fn copy_tree(from: &Path, to: &Path, progress: &mut impl FnMut(u64)) -> Result<()> {
+    crate::cancel::check()?;
+    let meta = std::fs::metadata(from)?;
+    if !meta.is_dir() {
+        copy_writable(from, to)?;
+        progress(meta.len());
+        return Ok(());
+    }
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_tree(&entry.path(), &to.join(entry.file_name()), progress)?;
+    }
+    Ok(())
+}
+
+/// Replace a symlink by a copy of what it points to
+fn replace_symlink(link: &Path, progress: &mut impl FnMut(u64)) -> Result<()> {
+    let mut temp_name = link.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".realmctl-copy");
+    let temp_path = link.with_file_name(temp_name);
+    if let Err(e) = copy_tree(link, &temp_path, progress) {
+        let _ = std::fs::remove_dir_all(&temp_path).or_else(|_| std::fs::remove_file(&temp_path));
+        return Err(e);
+    }
+    // Directory symlinks are directories to remove on Windows
+    std::fs::remove_file(link).or_else(|_| std::fs::remove_dir(link))?;
+    std::fs::rename(&temp_path, link)?;
+    Ok(())
+}
+
+/// Turn a workspace into a standalone install that works without its base
+///
+/// Hard links to the base and symlinks to base files or shared directories are replaced
+/// by copies; `progress` is called with the size of each copied file. The workspace is
+/// marked standalone, so `fix` and health checks no longer expect links. An interrupted
+/// run keeps what it copied and can be run again.
+pub fn detach_workspace(
+    workspace_path: &Path,
+    plan: &DetachPlan,
+    mut progress: impl FnMut(u64),
+) -> Result<()> {
+    let mut journal = Journal::new("materialize");
+    let mut copy_links = || -> Result<()> {
+        for rel_path in &plan.hard_links {
+            crate::cancel::check()?;
+            let path = workspace_path.join(rel_path);
+            break_hard_link(&path).with_context(|| format!("Failed to copy {rel_path}"))?;
+            progress(std::fs::metadata(&path)?.len());
+            journal.record(
+                JournalAction::Copy,
+                rel_path,
+                "materialized, no longer shared with the base",
+            );
+        }
+        for rel_path in &plan.symlinks {
+            replace_symlink(&workspace_path.join(rel_path), &mut progress)
+                .with_context(|| format!("Failed to copy {rel_path}"))?;
+            journal.record(
+                JournalAction::Copy,
+                rel_path,
+                "materialized, no longer shared",
+            );
+        }
+        Ok(())
+    };
+    let copied = copy_links();
+    journal
+        .append(workspace_path)
+        .context("Failed to write workspace.log")?;
+    copied?;
+
+    let mut config = load_workspace_config(workspace_path)?;
+    config.lazy_files.clear();
+    config.standalone = true;
+    save_workspace_config(workspace_path, &config)?;
+    Ok(())
+}
+
+/// The base files a workspace links, as they were when it linked them, saved as
+/// [`crate::base::BASE_SNAPSHOT`]
+///
+/// [`upgrade_workspace`] compares them with the base to tell files the base changed from
+/// files the workspace changed. Only checksums the base manifest already has are recorded,
+/// other files are hashed by the upgrade once their size or mtime show a change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BaseSnapshot {
+    /// Algorithm of `checksums`, that of the base manifest
+    pub hash_algorithm: String,
+    /// BaseData, Executable and MutableData files by path
+    pub checksums: BTreeMap<String, String>,
+    /// Size and mtime of the base files, missing in snapshots of older releases
+    #[serde(default)]
+    pub stamps: BTreeMap<String, FileStamp>,
+    /// Size and mtime of workspace copies (files not linked) as they were placed
+    #[serde(default)]
+    pub copies: BTreeMap<String, FileStamp>,
+}
+
+/// Size and modification time of a file, to notice changes without hashing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    /// Nanoseconds since the Unix epoch
+    pub mtime_ns: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let mtime = metadata.modified().ok()?;
+        let mtime_ns = mtime.duration_since(std::time::UNIX_EPOCH).ok()?.as_nanos();
+        Some(Self {
+            size: metadata.len(),
+            mtime_ns: u64::try_from(mtime_ns).ok()?,
+        })
+    }
+}
+
+/// Whether a role's files come from the base and follow its updates (patch MPQs are
+/// MutableData in most profiles)
+fn follows_base(role: &FileRole) -> bool {
+    matches!(
+        role,
+        FileRole::BaseData | FileRole::Executable | FileRole::MutableData
+    )
+}
+
+/// Snapshot of a base as its manifest records it, with the stamps of the base files and
+/// of the workspace's copies; nothing is hashed
+fn take_snapshot(base_path: &Path, workspace_path: &Path, manifest: &BaseManifest) -> BaseSnapshot {
+    let mut snapshot = BaseSnapshot {
+        hash_algorithm: manifest.checksum_algorithm().to_string(),
+        ..Default::default()
+    };
+    let base_path = long_path(base_path);
+    let ws_path = long_path(workspace_path);
+    for (rel_path, role) in &manifest.file_roles {
+        let base_file = base_path.join(rel_path);
+        if !follows_base(role) {
+            continue;
+        }
+        let Some(stamp) = FileStamp::of(&base_file) else {
+            continue;
+        };
+        snapshot.stamps.insert(rel_path.clone(), stamp);
+        if let Some(checksum) = manifest.checksums.get(rel_path) {
+            snapshot
+                .checksums
+                .insert(rel_path.clone(), checksum.clone());
+        }
+        let workspace_file = ws_path.join(rel_path);
+        if !same_file(&base_file, &workspace_file)
+            && let Some(copy) = FileStamp::of(&workspace_file)
+        {
+            snapshot.copies.insert(rel_path.clone(), copy);
+        }
+    }
+    snapshot
+}
+
+fn save_snapshot(workspace_path: &Path, snapshot: &BaseSnapshot) -> Result<()> {
+    let temp_path = workspace_path.join("base-snapshot.toml.tmp");
+    std::fs::write(&temp_path, toml::to_string_pretty(snapshot)?)?;
+    std::fs::rename(&temp_path, workspace_path.join(crate::base::BASE_SNAPSHOT))?;
+    Ok(())
+}
+
+/// The base snapshot of a workspace, None for workspaces created before it was recorded
+pub fn load_snapshot(workspace_path: &Path) -> Result<Option<BaseSnapshot>> {
+    let path = workspace_path.join(crate::base::BASE_SNAPSHOT);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => Ok(Some(crate::compat::parse_toml(&content, &path)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Changes [`upgrade_workspace`] made, or would make on a dry run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpgradeReport {
+    /// Files new in the base, linked into the workspace
+    pub added: Vec<String>,
+    /// Files the base changed, linked again
+    pub updated: Vec<String>,
+    /// Files the base changed that the workspace has its own version of, left alone
+    pub diverged: Vec<String>,
+    /// Files no longer in the base, left in the workspace
+    pub removed: Vec<String>,
+}
+
+impl UpgradeReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.diverged.is_empty()
+            && self.removed.is_empty()
+    }
+}
+
+/// Bring a workspace up to date with its base after the base got new or changed files
+///
+/// BaseData, Executable and MutableData files are compared with the snapshot taken when the
+/// workspace was created: new files are brought in, and files the base changed are brought
+/// in again where the workspace still has the old version. A workspace file that differs
+/// from both (the workspace was patched on its own) is left alone unless `force`. User data
+/// is never touched. Without a snapshot, only new files are brought in.
+pub fn upgrade_workspace(
+    workspace_path: &Path,
+    dry_run: bool,
+    force: bool,
+) -> Result<UpgradeReport> {
+    let mut config = load_workspace_config(workspace_path)?;
+    if config.standalone {
+        anyhow::bail!("{} is standalone, it no longer links its base", config.name);
+    }
+    let manifest = crate::base::load_manifest(&config.base_path).with_context(|| {
+        format!(
+            "No readable manifest.toml in {}",
+            config.base_path.display()
+        )
+    })?;
+    let snapshot = load_snapshot(workspace_path)?;
+    let algorithm = snapshot
+        .as_ref()
+        .map_or(manifest.checksum_algorithm(), |snapshot| {
+            &snapshot.hash_algorithm
+        })
+        .to_string();
+    // The manifest's checksums when it uses the snapshot's algorithm, else hashed here
+    let base_checksum = |rel_path: &str, base_file: &Path| -> Result<String> {
+        match manifest.checksums.get(rel_path) {
+            Some(checksum) if manifest.checksum_algorithm() == algorithm => Ok(checksum.clone()),
+            _ => crate::base::hash_file_with(base_file, &algorithm, None),
+        }
+    };
+
+    let base_path = long_path(&config.base_path);
+    let ws_path = long_path(workspace_path);
+    let mut report = UpgradeReport::default();
+    let mut to_link: Vec<(&String, &FileRole)> = Vec::new();
+    // Base checksums hashed here, kept in the new snapshot
+    let mut hashed: BTreeMap<String, String> = BTreeMap::new();
+    // Workspace copies that match the base again, their stamps are recorded anew
+    let mut caught_up: Vec<&String> = Vec::new();
+    for (rel_path, role) in &manifest.file_roles {
+        crate::cancel::check()?;
+        let base_file = base_path.join(rel_path);
+        let workspace_file = ws_path.join(rel_path);
+        if !follows_base(role) || !base_file.is_file() {
+            continue;
+        }
+        if workspace_file.symlink_metadata().is_err() {
+            report.added.push(rel_path.clone());
+            to_link.push((rel_path, role));
+            continue;
+        }
+        // Hard links and symlinks to the base see its changes already
+        if same_file(&base_file, &workspace_file) {
+            continue;
+        }
+        let recorded_stamp = snapshot.as_ref().and_then(|s| s.stamps.get(rel_path));
+        let copy_stamp = snapshot.as_ref().and_then(|s| s.copies.get(rel_path));
+        if recorded_stamp.is_some_and(|stamp| FileStamp::of(&base_file) == Some(*stamp)) {
+            // Unchanged in the base, the workspace's own copy is its business
+            continue;
+        }
+        let current = base_checksum(rel_path, &base_file)?;
+        hashed.insert(rel_path.clone(), current.clone());
+        let own = crate::base::hash_file_with(&workspace_file, &algorithm, None)?;
+        // A copy untouched since it was placed still holds the base's old version
+        let recorded = snapshot
+            .as_ref()
+            .and_then(|snapshot| snapshot.checksums.get(rel_path))
+            .cloned()
+            .or_else(|| {
+                copy_stamp
+                    .filter(|copy| FileStamp::of(&workspace_file) == Some(**copy))
+                    .map(|_| own.clone())
+            });
+        if recorded.as_ref() == Some(&current) {
+            continue;
+        }
+        if own == current {
+            caught_up.push(rel_path);
+            continue;
+        }
+        if recorded.as_ref() == Some(&own) || force {
+            report.updated.push(rel_path.clone());
+            to_link.push((rel_path, role));
+        } else {
+            report.diverged.push(rel_path.clone());
+        }
+    }
+    if let Some(snapshot) = &snapshot {
+        report.removed = snapshot
+            .checksums
+            .keys()
+            .filter(|rel_path| !base_path.join(rel_path).is_file())
+            .cloned()
+            .collect();
+    }
+    if dry_run {
+        return Ok(report);
+    }
+
+    let owner = file_owner(workspace_path);
+    let mut journal = Journal::new("upgrade");
+    let mut link_files = || -> Result<()> {
+        for (rel_path, role) in &to_link {
+            crate::cancel::check()?;
+            let base_file = base_path.join(rel_path);
+            let workspace_file = ws_path.join(rel_path);
+            let methods = config
+                .link_fallbacks
+                .get(&format!("{role:?}"))
+                .cloned()
+                .unwrap_or_else(|| default_fallbacks(role));
+            let same_owner = file_owner(&base_file) == owner;
+            // Link next to the old file and rename over it, the file is never missing
+            let mut temp_name = workspace_file
+                .file_name()
+                .unwrap_or_default()
+                .to_os_string();
+            temp_name.push(".realmctl-upgrade");
+            let temp_path = workspace_file.with_file_name(temp_name);
+            if let Some(parent) = workspace_file.parent() {
+                std::fs::create_dir_all(parent)?;
             }
-            FileRole::Ephemeral => {
-                // Create empty directories for ephemeral content
-                if base_file.is_dir() && !workspace_file.exists() {
-                    std::fs::create_dir_all(&workspace_file)?;
-                }
+            let _ = std::fs::remove_file(&temp_path);
+            let placed = place_file(&base_file, &temp_path, &methods, same_owner);
+            if placed.is_ok() {
+                std::fs::rename(&temp_path, &workspace_file)?;
             }
-            FileRole::Other => {
-                // Copy other files
-                if base_file.is_file() && !workspace_file.exists() {
-                    std::fs::copy(&base_file, &workspace_file)?;
-                }
+            let reason = if report.added.contains(rel_path) {
+                "new in the base"
+            } else {
+                "changed in the base"
+            };
+            record_placed(
+                &mut journal,
+                rel_path,
+                &workspace_file,
+                role,
+                placed,
+                reason,
+            )?;
+        }
+        Ok(())
+    };
+    let linked = link_files();
+    journal
+        .append(workspace_path)
+        .context("Failed to write workspace.log")?;
+    linked?;
+
+    let mut new_snapshot = take_snapshot(&config.base_path, workspace_path, &manifest);
+    if algorithm == new_snapshot.hash_algorithm {
+        for (rel_path, checksum) in hashed {
+            new_snapshot.checksums.entry(rel_path).or_insert(checksum);
+        }
+    }
+    // Only copies placed or caught up now are recorded as they are, others keep the stamp
+    // they were placed with so changes made in the workspace are still told apart
+    let placed: Vec<&String> = to_link.iter().map(|(rel_path, _)| *rel_path).collect();
+    let old_copies = snapshot.as_ref().map(|old| &old.copies);
+    new_snapshot.copies.retain(|rel_path, copy| {
+        if placed.contains(&rel_path) || caught_up.contains(&rel_path) {
+            return true;
+        }
+        match old_copies.and_then(|copies| copies.get(rel_path)) {
+            Some(old) => {
+                *copy = *old;
+                true
             }
-            _ => {}
+            None => false,
         }
+    });
+    // Diverged files keep their old record, so the next upgrade reports them again
+    for rel_path in &report.diverged {
+        let old = snapshot.as_ref();
+        match old.and_then(|old| old.checksums.get(rel_path)) {
+            Some(recorded) => new_snapshot
+                .checksums
+                .insert(rel_path.clone(), recorded.clone()),
+            None => new_snapshot.checksums.remove(rel_path),
+        };
+        match old.and_then(|old| old.stamps.get(rel_path)) {
+            Some(stamp) => new_snapshot.stamps.insert(rel_path.clone(), *stamp),
+            None => new_snapshot.stamps.remove(rel_path),
+        };
     }
+    save_snapshot(workspace_path, &new_snapshot)?;
+    config.base_state = Some(BaseState::of(&manifest));
+    save_workspace_config(workspace_path, &config)?;
+    Ok(report)
+}
 
+/// Check the current user can read every base file a workspace needs
+///
+/// Bases shared between users must be readable by all of them; a restrictive umask when
+/// the base was installed is the usual cause of failures here.
+pub fn check_base_readable(base_path: &Path, manifest: &BaseManifest) -> Result<()> {
+    let unreadable: Vec<&String> = manifest
+        .file_roles
+        .iter()
+        .filter(|(_, role)| !matches!(role, FileRole::UserMedia | FileRole::UserConfig))
+        .map(|(rel_path, _)| rel_path)
+        .filter(|rel_path| {
+            let path = base_path.join(rel_path);
+            path.is_file()
+                && std::fs::File::open(&path)
+                    .is_err_and(|e| e.kind() == std::io::ErrorKind::PermissionDenied)
+        })
+        .collect();
+    if let Some(first) = unreadable.first() {
+        anyhow::bail!(
+            "{} base file(s) are not readable by the current user, e.g. {first}. \
+             Make the base readable for everyone (chmod -R a+rX {}) \
+             or for a group (setfacl -R -m g:<group>:rX {})",
+            unreadable.len(),
+            base_path.display(),
+            base_path.display()
+        );
+    }
     Ok(())
 }
 
 /// Repair shared directories and symlinks for a workspace
-pub fn fix_workspace(workspace_path: &Path) -> Result<()> {
-    println!("Verifying workspace: {}", workspace_path.display());
+pub fn fix_workspace(
+    workspace_path: &Path,
+    resolve: impl FnMut(&ShareConflict) -> ConflictChoice,
+) -> Result<()> {
+    fix_workspace_with(workspace_path, resolve, false)
+}
+
+/// [`fix_workspace`], or with `dry_run` only print every repair it would make
+pub fn fix_workspace_with(
+    workspace_path: &Path,
+    mut resolve: impl FnMut(&ShareConflict) -> ConflictChoice,
+    dry_run: bool,
+) -> Result<()> {
+    tracing::info!("Verifying workspace: {}", workspace_path.display());
+    let workspace_path = &long_path(workspace_path);
 
     // Load workspace config
     let config = load_workspace_config(workspace_path)?;
+    if let Some(warning) = config.check_compatibility()? {
+        tracing::warn!("⚠ {warning}");
+    }
+    if config.standalone {
+        tracing::info!("Workspace is standalone (materialized), it has no links to repair");
+        return Ok(());
+    }
 
     // Determine workspace root (parent directory)
     let workspace_root = workspace_path
@@ -286,19 +1679,38 @@ pub fn fix_workspace(workspace_path: &Path) -> Result<()> {
     let per_base_shared_dir = workspace_root.join(".shared").join(&config.base_name);
 
     // Ensure shared roots exist
-    if !global_shared_dir.exists() {
-        println!("Creating missing global shared root: {}", global_shared_dir.display());
-        std::fs::create_dir_all(&global_shared_dir)?;
-    }
-    if !per_base_shared_dir.exists() {
-        println!("Creating missing base shared root: {}", per_base_shared_dir.display());
-        std::fs::create_dir_all(&per_base_shared_dir)?;
+    if dry_run {
+        for root in [&global_shared_dir, &per_base_shared_dir] {
+            if !root.exists() {
+                outln!("Would create missing shared root: {}", root.display());
+            }
+        }
+    } else {
+        let lock = lock_shared_root(workspace_root)?;
+        if !global_shared_dir.exists() {
+            tracing::info!(
+                "Creating missing global shared root: {}",
+                global_shared_dir.display()
+            );
+            std::fs::create_dir_all(&global_shared_dir)?;
+        }
+        if !per_base_shared_dir.exists() {
+            tracing::info!(
+                "Creating missing base shared root: {}",
+                per_base_shared_dir.display()
+            );
+            std::fs::create_dir_all(&per_base_shared_dir)?;
+        }
+        drop(lock);
     }
 
     // Load base manifest so we can find the paths expected to be shared
     let base_manifest = crate::base::load_manifest(&config.base_path)
         .context("Failed to load base manifest for workspace")?;
-    let base_path = &config.base_path;
+    if let Some(change) = config.base_change(&base_manifest) {
+        tracing::warn!("⚠ {change}");
+    }
+    let base_path = &long_path(&config.base_path);
 
     // Build list of candidate directories (like in link_workspace_files)
     let mut dir_entries: Vec<_> = base_manifest
@@ -311,7 +1723,12 @@ pub fn fix_workspace(workspace_path: &Path) -> Result<()> {
     // Sort by path depth
     dir_entries.sort_by_key(|(rel_path, _)| rel_path.matches('/').count());
 
+    let mut journal = Journal::new("fix");
+    let mut keep_local = Vec::new();
+    // Links a dry run would create, their contents are reached through them
+    let mut planned_links: Vec<PathBuf> = Vec::new();
     for (rel_path, role) in dir_entries {
+        crate::cancel::check()?;
         let ws_file = workspace_path.join(rel_path);
 
         let strategy = match role {
@@ -327,15 +1744,34 @@ pub fn fix_workspace(workspace_path: &Path) -> Result<()> {
                 // Should be a real directory inside workspace
                 if ws_file.exists() {
                     if ws_file.read_link().is_ok() {
-                        println!("⚠ Expected directory but found a symlink at {}. Leaving as-is.", ws_file.display());
+                        tracing::warn!(
+                            "⚠ Expected directory but found a symlink at {}. Leaving as-is.",
+                            ws_file.display()
+                        );
                     } else if ws_file.is_dir() {
-                        // OK
+                        tracing::debug!("{rel_path}: workspace directory present");
                     } else {
-                        println!("⚠ Expected directory at {}, but found a file. Leaving as-is.", ws_file.display());
+                        tracing::warn!(
+                            "⚠ Expected directory at {}, but found a file. Leaving as-is.",
+                            ws_file.display()
+                        );
                     }
+                } else if dry_run {
+                    outln!(
+                        "Would create missing workspace directory: {}",
+                        ws_file.display()
+                    );
                 } else {
-                    println!("Creating missing workspace directory: {}", ws_file.display());
+                    tracing::info!(
+                        "Creating missing workspace directory: {}",
+                        ws_file.display()
+                    );
                     std::fs::create_dir_all(&ws_file)?;
+                    journal.record(
+                        JournalAction::Mkdir,
+                        rel_path,
+                        format!("{role:?} directory was missing, shared per workspace"),
+                    );
                 }
             }
             SharingStrategy::Global | SharingStrategy::Base => {
@@ -358,69 +1794,473 @@ pub fn fix_workspace(workspace_path: &Path) -> Result<()> {
                                 let resolved = if link_target.is_absolute() {
                                     link_target
                                 } else {
-                                    ws_file.parent().unwrap_or_else(|| Path::new(".")).join(link_target)
+                                    ws_file
+                                        .parent()
+                                        .unwrap_or_else(|| Path::new("."))
+                                        .join(link_target)
                                 };
 
                                 if resolved.exists() {
-                                    // All good
+                                    tracing::debug!("{rel_path}: linked to {}", resolved.display());
+                                } else if dry_run {
+                                    outln!(
+                                        "Would recreate {}, the missing target of {}",
+                                        target.display(),
+                                        ws_file.display()
+                                    );
                                 } else {
                                     // Target missing: recreate target directory
-                                    println!("Target missing for symlink {} -> {}. Recreating {}.", ws_file.display(), resolved.display(), target.display());
+                                    tracing::info!(
+                                        "Target missing for symlink {} -> {}. Recreating {}.",
+                                        ws_file.display(),
+                                        resolved.display(),
+                                        target.display()
+                                    );
                                     std::fs::create_dir_all(&target)?;
                                 }
+                            } else if dry_run {
+                                outln!(
+                                    "Would recreate {}, the target of dangling symlink {}",
+                                    target.display(),
+                                    ws_file.display()
+                                );
                             } else {
                                 // Shouldn't happen, but treat as dangling; recreate target
-                                println!("Dangling symlink detected at {}. Recreating target {}.", ws_file.display(), target.display());
+                                tracing::info!(
+                                    "Dangling symlink detected at {}. Recreating target {}.",
+                                    ws_file.display(),
+                                    target.display()
+                                );
                                 std::fs::create_dir_all(&target)?;
                             }
+                        } else if meta.is_dir() && reached_through_link(workspace_path, &ws_file) {
+                            // Inside a directory that is already shared, e.g. Interface/AddOns/X
+                            tracing::debug!("{rel_path}: inside an already shared directory");
+                        } else if meta.is_dir() {
+                            // User replaced the symlink with a real directory, ask what to do with it
+                            let conflict = ShareConflict {
+                                rel_path: rel_path.clone(),
+                                local: ws_file.clone(),
+                                shared: target.clone(),
+                                strategy: strategy.clone(),
+                            };
+                            match resolve(&conflict) {
+                                ConflictChoice::KeepLocal if dry_run => {
+                                    outln!(
+                                        "Would keep {} as a workspace directory and stop sharing it",
+                                        ws_file.display()
+                                    );
+                                }
+                                ConflictChoice::KeepLocal => {
+                                    tracing::info!(
+                                        "Keeping {} as a workspace directory, it is no longer shared",
+                                        ws_file.display()
+                                    );
+                                    keep_local.push(rel_path.clone());
+                                }
+                                ConflictChoice::Adopt => {
+                                    if let Some(link) = adopt_into_shared(&conflict, dry_run)? {
+                                        if dry_run {
+                                            planned_links.push(ws_file.clone());
+                                        }
+                                        journal.record_symlink(
+                                            rel_path,
+                                            &link,
+                                            format!(
+                                                "workspace {role:?} directory adopted into the {} shared directory",
+                                                strategy.as_str()
+                                            ),
+                                        );
+                                    }
+                                }
+                                ConflictChoice::Skip => {
+                                    tracing::warn!(
+                                        "⚠ Detected real directory at {} which seems to replace an expected symlink. Will NOT overwrite or remove user data.",
+                                        ws_file.display()
+                                    );
+                                }
+                            }
                         } else {
-                            // Not a symlink - user replaced symlink with real directory or file
-                            println!("⚠ Detected real file/directory at {} which seems to replace an expected symlink. Will NOT overwrite or remove user data.", ws_file.display());
+                            // Not a symlink - user replaced symlink with a file
+                            tracing::warn!(
+                                "⚠ Detected real file at {} which seems to replace an expected symlink. Will NOT overwrite or remove user data.",
+                                ws_file.display()
+                            );
+                        }
+                    }
+                    Err(_) if dry_run => {
+                        if planned_links.iter().any(|link| ws_file.starts_with(link)) {
+                            continue;
                         }
+                        planned_links.push(ws_file.clone());
+                        if !target.exists() {
+                            outln!(
+                                "Would create missing target shared directory: {}",
+                                target.display()
+                            );
+                        }
+                        let link = crate::paths::link_target(&target, &ws_file);
+                        outln!(
+                            "Would create symlink: {} -> {}",
+                            ws_file.display(),
+                            link.display()
+                        );
                     }
                     Err(_) => {
                         // Path doesn't exist - create target and symlink
                         if !target.exists() {
-                            println!("Creating missing target shared directory: {}", target.display());
+                            tracing::info!(
+                                "Creating missing target shared directory: {}",
+                                target.display()
+                            );
                             std::fs::create_dir_all(&target)?;
                         }
                         // Ensure parent exists
-                        if let Some(parent) = ws_file.parent() {
-                            if !parent.exists() {
-                                std::fs::create_dir_all(parent)?;
-                            }
+                        if let Some(parent) = ws_file.parent()
+                            && !parent.exists()
+                        {
+                            std::fs::create_dir_all(parent)?;
                         }
 
                         // It's possible that creating the target (under the per-base/global shared dir)
                         // made the corresponding path accessible via an existing parent symlink in the workspace.
                         // If the workspace path now exists, do not attempt to create another symlink (would EEXIST).
                         if ws_file.exists() {
-                            #[cfg(test)]
-                            println!("  -> workspace path {} already exists after creating target, skipping symlink", ws_file.display());
+                            tracing::debug!(
+                                "Workspace path {} already exists after creating target, skipping symlink",
+                                ws_file.display()
+                            );
                             continue;
                         }
 
-                        #[cfg(unix)]
-                        {
-                            use std::os::unix::fs::symlink;
-                            println!("Creating symlink: {} -> {}", ws_file.display(), target.display());
-                            symlink(&target, &ws_file)?;
-                        }
-                        #[cfg(windows)]
-                        {
-                            use std::os::windows::fs::symlink_dir;
-                            println!("Creating symlink: {} -> {}", ws_file.display(), target.display());
-                            symlink_dir(&target, &ws_file)?;
-                        }
+                        let link = crate::paths::link_target(&target, &ws_file);
+                        tracing::info!(
+                            "Creating symlink: {} -> {}",
+                            ws_file.display(),
+                            link.display()
+                        );
+                        symlink_dir(&link, &ws_file)?;
+                        journal.record_symlink(
+                            rel_path,
+                            &link,
+                            format!(
+                                "{role:?} directory was missing, shared per {}",
+                                strategy.as_str()
+                            ),
+                        );
                     }
                 }
             }
         }
     }
 
+    if dry_run {
+        return Ok(());
+    }
+    if !keep_local.is_empty() {
+        let mut config = config;
+        for rel_path in keep_local {
+            config
+                .sharing_rules
+                .retain(|key, _| !key.eq_ignore_ascii_case(&rel_path));
+            config
+                .sharing_rules
+                .insert(rel_path, SharingStrategy::Workspace);
+        }
+        save_workspace_config(workspace_path, &config)?;
+    }
+
+    journal
+        .append(workspace_path)
+        .context("Failed to write workspace.log")?;
     Ok(())
 }
 
+/// A real directory in a workspace where `fix` expects a link to a shared directory
+#[derive(Debug, Clone)]
+pub struct ShareConflict {
+    pub rel_path: String,
+    pub local: PathBuf,
+    /// Shared directory the link should point to
+    pub shared: PathBuf,
+    pub strategy: SharingStrategy,
+}
+
+/// What `fix` does with a [`ShareConflict`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictChoice {
+    /// Keep the directory and share the path per workspace from now on (workspace.toml)
+    KeepLocal,
+    /// Move its files into the shared directory and link it, see [`adopt_into_shared`]
+    Adopt,
+    /// Leave it as it is for now
+    Skip,
+}
+
+impl std::str::FromStr for ConflictChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "keep-local" => Ok(ConflictChoice::KeepLocal),
+            "adopt" => Ok(ConflictChoice::Adopt),
+            "skip" => Ok(ConflictChoice::Skip),
+            _ => Err(format!("expected keep-local, adopt or skip, got {s}")),
+        }
+    }
+}
+
+/// How the files of two directories compare, by path relative to them
+#[derive(Debug, Default, PartialEq)]
+pub struct DirDiff {
+    pub only_local: Vec<String>,
+    pub only_shared: Vec<String>,
+    /// Present in both with different contents
+    pub differing: Vec<String>,
+    pub identical: usize,
+}
+
+/// Files (and links) below `dir` by relative path, not following symlinks
+fn dir_files(dir: &Path) -> BTreeMap<String, PathBuf> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                pending.push(path);
+            } else if let Ok(rel) = path.strip_prefix(dir) {
+                files.insert(rel.to_string_lossy().replace('\\', "/"), path);
+            }
+        }
+    }
+    files
+}
+
+fn same_contents(a: &Path, b: &Path) -> Result<bool> {
+    let (meta_a, meta_b) = (std::fs::symlink_metadata(a)?, std::fs::symlink_metadata(b)?);
+    if meta_a.is_symlink() || meta_b.is_symlink() {
+        return Ok(meta_a.is_symlink()
+            && meta_b.is_symlink()
+            && std::fs::read_link(a)? == std::fs::read_link(b)?);
+    }
+    Ok(meta_a.len() == meta_b.len()
+        && crate::base::compute_file_hash(a, None)? == crate::base::compute_file_hash(b, None)?)
+}
+
+/// Compare a workspace directory with the shared directory it should link to
+pub fn diff_dirs(local: &Path, shared: &Path) -> Result<DirDiff> {
+    let local_files = dir_files(local);
+    let shared_files = dir_files(shared);
+    let mut diff = DirDiff::default();
+    for (rel, path) in &local_files {
+        match shared_files.get(rel) {
+            None => diff.only_local.push(rel.clone()),
+            Some(shared_path) if same_contents(path, shared_path)? => diff.identical += 1,
+            Some(_) => diff.differing.push(rel.clone()),
+        }
+    }
+    diff.only_shared = shared_files
+        .into_keys()
+        .filter(|rel| !local_files.contains_key(rel))
+        .collect();
+    Ok(diff)
+}
+
+/// Move the files of a conflicting directory into the shared directory and replace it
+/// with a link, returning the link target
+///
+/// Nothing is moved when a file exists in both with different contents; those are
+/// listed and `None` is returned. With `dry_run` the moves are only printed.
+pub fn adopt_into_shared(conflict: &ShareConflict, dry_run: bool) -> Result<Option<PathBuf>> {
+    if let (Ok(local), Ok(shared)) = (
+        conflict.local.canonicalize(),
+        conflict.shared.canonicalize(),
+    ) && local == shared
+    {
+        anyhow::bail!(
+            "{} already is the shared directory",
+            conflict.local.display()
+        );
+    }
+    let diff = diff_dirs(&conflict.local, &conflict.shared)?;
+    if !diff.differing.is_empty() {
+        tracing::warn!(
+            "⚠ Not adopting {}, these files differ from the shared copies:",
+            conflict.local.display()
+        );
+        for rel in &diff.differing {
+            tracing::warn!("\t{rel}");
+        }
+        return Ok(None);
+    }
+    if dry_run {
+        for rel in &diff.only_local {
+            outln!(
+                "Would move {} to {}",
+                conflict.local.join(rel).display(),
+                conflict.shared.join(rel).display()
+            );
+        }
+        let link = crate::paths::link_target(&conflict.shared, &conflict.local);
+        outln!("Would remove {}", conflict.local.display());
+        outln!(
+            "Would create symlink: {} -> {}",
+            conflict.local.display(),
+            link.display()
+        );
+        return Ok(Some(link));
+    }
+    for rel in &diff.only_local {
+        crate::cancel::check()?;
+        let from = conflict.local.join(rel);
+        let to = conflict.shared.join(rel);
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        tracing::debug!("Moving {} to {}", from.display(), to.display());
+        if std::fs::rename(&from, &to).is_err() {
+            // Shared directory on another filesystem
+            std::fs::copy(&from, &to).with_context(|| {
+                format!("Failed to copy {} to {}", from.display(), to.display())
+            })?;
+            std::fs::remove_file(&from)?;
+        }
+    }
+    std::fs::create_dir_all(&conflict.shared)?;
+    // Only files with an identical shared copy are left
+    std::fs::remove_dir_all(&conflict.local)?;
+    let link = crate::paths::link_target(&conflict.shared, &conflict.local);
+    symlink_dir(&link, &conflict.local)?;
+    tracing::info!(
+        "✓ Adopted {} files into {}, {} now links to it",
+        diff.only_local.len(),
+        conflict.shared.display(),
+        conflict.rel_path
+    );
+    Ok(Some(link))
+}
+
+/// Whether `path` lies outside the workspace once links of its parents are followed
+fn reached_through_link(workspace_path: &Path, path: &Path) -> bool {
+    match (workspace_path.canonicalize(), path.canonicalize()) {
+        (Ok(workspace), Ok(resolved)) => !resolved.starts_with(workspace),
+        _ => false,
+    }
+}
+
+fn symlink_dir(link: &Path, path: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(link, path);
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_dir(link, path);
+}
+
+/// Result of a workspace health check
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    pub warnings: Vec<String>,
+    /// False when the time budget ran out before every check was done
+    pub complete: bool,
+}
+
+/// Check a workspace's base, shared links and base files without changing anything
+///
+/// With a `budget`, checking stops once it is used up and the report is marked incomplete.
+pub fn check_health(
+    workspace_path: &Path,
+    budget: Option<std::time::Duration>,
+) -> Result<HealthReport> {
+    let started = std::time::Instant::now();
+    let out_of_time = || budget.is_some_and(|budget| started.elapsed() > budget);
+    let mut report = HealthReport::default();
+
+    let config = load_workspace_config(workspace_path)?;
+    if let Err(e) = config.check_compatibility() {
+        report.warnings.push(e.to_string());
+    }
+    if config.standalone {
+        report.complete = true;
+        return Ok(report);
+    }
+    let manifest = match crate::base::load_manifest(&config.base_path) {
+        Ok(manifest) => manifest,
+        Err(_) => {
+            report.warnings.push(format!(
+                "base manifest missing at {}",
+                config.base_path.display()
+            ));
+            report.complete = true;
+            return Ok(report);
+        }
+    };
+
+    // Shared directories must be symlinks with an existing target
+    let mut shared: Vec<&String> = Vec::new();
+    let mut dirs: Vec<(&String, &FileRole)> = manifest
+        .file_roles
+        .iter()
+        .filter(|(_, role)| matches!(role, FileRole::UserMedia | FileRole::UserConfig))
+        .collect();
+    dirs.sort_by_key(|(rel_path, _)| rel_path.matches('/').count());
+    for (rel_path, role) in dirs {
+        if out_of_time() {
+            return Ok(report);
+        }
+        if shared
+            .iter()
+            .any(|dir| rel_path.starts_with(&format!("{dir}/")))
+        {
+            continue;
+        }
+        let default = match role {
+            FileRole::UserMedia => SharingStrategy::Global,
+            _ => SharingStrategy::Workspace,
+        };
+        if determine_strategy(rel_path, &config.sharing_rules, default)
+            == SharingStrategy::Workspace
+        {
+            continue;
+        }
+        shared.push(rel_path);
+        let ws_file = workspace_path.join(rel_path);
+        match std::fs::symlink_metadata(&ws_file) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                if !ws_file.exists() {
+                    report
+                        .warnings
+                        .push(format!("{rel_path} links to a missing shared directory"));
+                }
+            }
+            Ok(_) => report
+                .warnings
+                .push(format!("{rel_path} is not linked to its shared directory")),
+            Err(_) => report.warnings.push(format!("{rel_path} is missing")),
+        }
+    }
+
+    // Hard-linked base files
+    let mut missing = 0;
+    for (rel_path, role) in &manifest.file_roles {
+        if out_of_time() {
+            return Ok(report);
+        }
+        if matches!(role, FileRole::BaseData | FileRole::Executable)
+            && !workspace_path.join(rel_path).exists()
+        {
+            missing += 1;
+        }
+    }
+    if missing > 0 {
+        report
+            .warnings
+            .push(format!("{missing} base file(s) missing"));
+    }
+
+    report.complete = true;
+    Ok(report)
+}
+
 fn determine_strategy(
     rel_path: &str,
     sharing_rules: &HashMap<String, SharingStrategy>,
@@ -432,6 +2272,14 @@ fn determine_strategy(
     let normalized_path = rel_path.to_lowercase();
     let components: Vec<&str> = normalized_path.split('/').collect();
 
+    // A rule for exactly this path (e.g. kept local by `fix`) wins over broader ones
+    if let Some((_, strategy)) = sharing_rules
+        .iter()
+        .find(|(key, _)| key.to_lowercase() == normalized_path)
+    {
+        return strategy.clone();
+    }
+
     for (key, strategy) in sharing_rules {
         let normalized_key = key.to_lowercase();
 
@@ -446,6 +2294,26 @@ fn determine_strategy(
     default
 }
 
+/// Take the advisory lock on `<workspace root>/.shared`, released when the file is dropped
+///
+/// Serializes shared root setup between concurrent `create` and `fix` runs.
+fn lock_shared_root(workspace_root: &Path) -> Result<std::fs::File> {
+    let shared_root = workspace_root.join(".shared");
+    std::fs::create_dir_all(&shared_root)?;
+    let lock_path = shared_root.join(SHARED_LOCK_FILE);
+    // A lock file created by another user of a shared root may only be readable
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .or_else(|_| std::fs::File::open(&lock_path))
+        .with_context(|| format!("Failed to open lock file {}", lock_path.display()))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+    Ok(file)
+}
+
 fn create_shared_link(
     rel_path: &str,
     workspace_file: &Path,
@@ -465,8 +2333,7 @@ fn create_shared_link(
             if !workspace_file.exists() {
                 std::fs::create_dir_all(workspace_file)?;
             }
-            #[cfg(test)]
-            println!("  -> created workspace-specific directory");
+            tracing::debug!("Created workspace directory {}", workspace_file.display());
             return Ok(());
         }
     };
@@ -474,49 +2341,266 @@ fn create_shared_link(
     // Ensure target directory exists (create it if it doesn't)
     if !target.exists() {
         std::fs::create_dir_all(&target)?;
-        #[cfg(test)]
-        println!("  -> created target directory: {}", target.display());
+        tracing::debug!("Created shared directory {}", target.display());
+    }
+
+    // Create symlink
+    let link = crate::paths::link_target(&target, workspace_file);
+    tracing::debug!("Linking {} -> {}", workspace_file.display(), link.display());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::symlink;
+        let linked = symlink(&link, workspace_file);
+        ignore_same_link(linked, workspace_file, &link)
+            .with_context(|| format!("Failed to create symlink for {}", rel_path))?;
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::{symlink_dir, symlink_file};
+        let linked = if target.is_dir() {
+            symlink_dir(&link, workspace_file)
+        } else {
+            symlink_file(&link, workspace_file)
+        };
+        ignore_same_link(linked, workspace_file, &link)?;
+    }
+
+    Ok(())
+}
+
+/// Treat EEXIST as success when a concurrent run already created the same link
+fn ignore_same_link(
+    result: std::io::Result<()>,
+    link: &Path,
+    target: &Path,
+) -> std::io::Result<()> {
+    match result {
+        Err(e)
+            if e.kind() == std::io::ErrorKind::AlreadyExists
+                && std::fs::read_link(link).is_ok_and(|existing| existing == target) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Write workspace.toml of a workspace
+pub fn save_workspace_config(workspace_path: &Path, config: &WorkspaceConfig) -> Result<()> {
+    let config_path = workspace_path.join("workspace.toml");
+    let mut config = config.clone();
+    // Portable workspaces find their base relative to themselves and their own path from
+    // where they are loaded
+    config.base_path = crate::paths::portable_relative(&config.base_path, workspace_path);
+    if config.base_path.is_relative() {
+        config.workspace_path = PathBuf::new();
+    }
+    let toml_string = toml::to_string_pretty(&config)?;
+    // Write then rename, a crash never leaves a workspace without its config
+    let temp_path = workspace_path.join("workspace.toml.tmp");
+    std::fs::write(&temp_path, toml_string)?;
+    std::fs::rename(&temp_path, config_path)?;
+    Ok(())
+}
+
+/// Load workspace config
+pub fn load_workspace_config(workspace_path: &Path) -> Result<WorkspaceConfig> {
+    let config_path = workspace_path.join("workspace.toml");
+    let content = std::fs::read_to_string(&config_path)?;
+    let mut config: WorkspaceConfig = crate::compat::parse_toml(&content, &config_path)?;
+    // Portable workspaces store their base relative to themselves; older releases stored
+    // a relative --base as given, relative to where create ran
+    let relative_base = crate::paths::normalize(&workspace_path.join(&config.base_path));
+    if config.base_path.is_relative() && relative_base.is_dir() {
+        config.base_path = relative_base;
+    }
+    if config.workspace_path.as_os_str().is_empty() {
+        config.workspace_path = workspace_path.to_path_buf();
+    }
+    if config.name.is_empty() {
+        config.name = workspace_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+    }
+    Ok(config)
+}
+
+/// List all workspaces (directories containing a workspace.toml) under a workspace root
+pub fn list_workspaces(workspace_root: &Path) -> Result<Vec<WorkspaceConfig>> {
+    let mut workspaces = Vec::new();
+    if !workspace_root.is_dir() {
+        return Ok(workspaces);
+    }
+
+    for entry in std::fs::read_dir(workspace_root)? {
+        let path = entry?.path();
+        if !path.join("workspace.toml").is_file() {
+            continue;
+        }
+        match load_workspace_config(&path) {
+            Ok(config) => workspaces.push(config),
+            Err(e) => tracing::warn!("⚠ Skipping {}: {}", path.display(), e),
+        }
+    }
+
+    workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(workspaces)
+}
+
+/// What deleting a workspace would lose, by where each file's data lives
+#[derive(Debug, Clone, Default)]
+pub struct DeletionReport {
+    /// Symlinks and hard links into the base or a shared root, their data stays
+    pub linked: usize,
+    /// Private copies of base files that a new workspace gets back
+    pub copied: usize,
+    /// Caches, logs and files realmctl rewrites anyway
+    pub disposable: usize,
+    /// Files only this workspace has (WTF configs, local addons, ...), with their size
+    pub unique: Vec<(String, u64)>,
+}
+
+/// Classify every file of a workspace before deleting it, without following symlinks
+pub fn plan_deletion(workspace_path: &Path) -> Result<DeletionReport> {
+    let config = load_workspace_config(workspace_path)?;
+    let manifest = crate::base::load_manifest(&config.base_path).ok();
+    let profile = manifest
+        .as_ref()
+        .and_then(|manifest| crate::base::Profile::builtin(&manifest.profile))
+        .unwrap_or_else(crate::base::Profile::chromie_335a);
+
+    let mut report = DeletionReport::default();
+    let mut pending = vec![workspace_path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let meta = std::fs::symlink_metadata(&path)?;
+            if meta.file_type().is_symlink() {
+                report.linked += 1;
+                continue;
+            }
+            let rel_path = path
+                .strip_prefix(workspace_path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if meta.is_dir() {
+                // Ephemeral patterns name whole directories like Cache
+                if profile.classify_path(&rel_path) == FileRole::Ephemeral {
+                    report.disposable += count_files(&path);
+                } else {
+                    pending.push(path);
+                }
+                continue;
+            }
+            let base_file = config.base_path.join(&rel_path);
+            if same_file(&path, &base_file) {
+                report.linked += 1;
+                continue;
+            }
+            match profile.classify_path(&rel_path) {
+                FileRole::Ephemeral | FileRole::Managed => report.disposable += 1,
+                FileRole::Executable | FileRole::BaseData | FileRole::MutableData
+                    if same_content(&path, &base_file, &rel_path, manifest.as_ref())? =>
+                {
+                    report.copied += 1
+                }
+                _ => report.unique.push((rel_path, meta.len())),
+            }
+        }
     }
+    report.unique.sort();
+    Ok(report)
+}
 
-    // Create symlink
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::symlink;
-        #[cfg(test)]
-        println!(
-            "  -> creating symlink: {} -> {}",
-            workspace_file.display(),
-            target.display()
-        );
-        symlink(&target, workspace_file)
-            .with_context(|| format!("Failed to create symlink for {}", rel_path))?;
+/// Whether a workspace copy holds the bytes of its base file, by the manifest checksum or,
+/// for files without one, by hashing the base file too
+fn same_content(
+    path: &Path,
+    base_file: &Path,
+    rel_path: &str,
+    manifest: Option<&BaseManifest>,
+) -> Result<bool> {
+    use crate::base::hash_file_with;
+
+    let (Ok(meta), Ok(base)) = (std::fs::metadata(path), std::fs::metadata(base_file)) else {
+        return Ok(false);
+    };
+    if meta.len() != base.len() {
+        return Ok(false);
     }
+    let algorithm = manifest.map_or(crate::base::HASH_ALGORITHM, |m| m.checksum_algorithm());
+    let hash = hash_file_with(path, algorithm, None)?;
+    Ok(match manifest.and_then(|m| m.checksums.get(rel_path)) {
+        Some(expected) => *expected == hash,
+        None => hash_file_with(base_file, algorithm, None)? == hash,
+    })
+}
 
-    #[cfg(windows)]
+fn count_files(dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => count_files(&entry.path()),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Pack files of a workspace into a gzipped tarball, paths relative to the workspace
+pub fn archive_files(workspace_path: &Path, rel_paths: &[String], archive: &Path) -> Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    if let Some(parent) = archive
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
     {
-        use std::os::windows::fs::{symlink_dir, symlink_file};
-        if target.is_dir() {
-            symlink_dir(&target, workspace_file)?;
-        } else {
-            symlink_file(&target, workspace_file)?;
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut child = Command::new("tar")
+        .arg("-czf")
+        .arg(archive)
+        .arg("-C")
+        .arg(workspace_path)
+        .args(["--null", "-T", "-"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Failed to run tar")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        for rel_path in rel_paths {
+            write!(stdin, "{rel_path}\0")?;
         }
     }
-
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("tar failed to write {} ({status})", archive.display());
+    }
     Ok(())
 }
 
-/// Load workspace config
-pub fn load_workspace_config(workspace_path: &Path) -> Result<WorkspaceConfig> {
-    let config_path = workspace_path.join("workspace.toml");
-    let content = std::fs::read_to_string(config_path)?;
-    let config: WorkspaceConfig = toml::from_str(&content)?;
-    Ok(config)
+/// Remove a workspace directory; links are removed, never the base or shared data they point to
+pub fn delete_workspace(workspace_path: &Path) -> Result<()> {
+    if !workspace_path.join("workspace.toml").is_file() {
+        anyhow::bail!(
+            "{} is not a workspace (no workspace.toml)",
+            workspace_path.display()
+        );
+    }
+    std::fs::remove_dir_all(long_path(workspace_path))
+        .with_context(|| format!("Failed to remove {}", workspace_path.display()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::base::{Profile, write_manifest, scan_and_build_manifest};
+    use crate::base::{Profile, load_manifest, scan_and_build_manifest, write_manifest};
     use std::fs;
     use tempfile::TempDir;
 
@@ -524,14 +2608,7 @@ mod tests {
     #[allow(dead_code)]
     pub fn print_dir_tree(path: &Path) {
         println!("\nDirectory tree under {}:", path.display());
-        let output = std::process::Command::new("tree")
-            .arg("-a") // include hidden files
-            .arg("-L")
-            .arg("4") // limit depth to 4 levels
-            .arg(path)
-            .output()
-            .expect("failed to execute tree");
-        println!("{}", String::from_utf8_lossy(&output.stdout));
+        print!("{}", crate::status::render_tree(path, 4));
     }
 
     /// Helper function to create a mock WoW base directory for testing
@@ -628,7 +2705,7 @@ mod tests {
         assert!(!global_shared.exists());
 
         // Run fix
-        fix_workspace(&config.workspace_path)?;
+        fix_workspace(&config.workspace_path, |_| ConflictChoice::Skip)?;
 
         // Target should be recreated
         assert!(global_shared.exists());
@@ -663,7 +2740,7 @@ mod tests {
         fs::write(ws_screenshots.join("user.jpg"), b"user data")?;
 
         // Run fix
-        fix_workspace(&config.workspace_path)?;
+        fix_workspace(&config.workspace_path, |_| ConflictChoice::Skip)?;
 
         // Ensure we didn't remove the user's file and we didn't replace the directory with a symlink
         assert!(ws_screenshots.exists());
@@ -673,6 +2750,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_fix_resolves_share_conflicts() -> Result<()> {
+        let tmp = TempDir::new()?;
+        let base_dir = tmp.path().join("base");
+        fs::create_dir_all(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+        let ws_root = tmp.path().join("workspaces");
+        let config = create_workspace("ws", &base_dir, &ws_root, default_sharing_rules())?;
+        let screenshots = config.workspace_path.join("Screenshots");
+        let shared = ws_root.join(".shared/global/Screenshots");
+        fs::write(shared.join("same.jpg"), b"same")?;
+        let replace_link = || -> Result<()> {
+            fs::remove_file(&screenshots)?;
+            fs::create_dir(&screenshots)?;
+            fs::write(screenshots.join("same.jpg"), b"same")?;
+            fs::write(screenshots.join("new.jpg"), b"new")?;
+            Ok(())
+        };
+
+        // Adopting moves new files into the shared directory and links it again
+        replace_link()?;
+        let diff = diff_dirs(&screenshots, &shared)?;
+        assert_eq!(diff.only_local, vec!["new.jpg"]);
+        assert_eq!(diff.identical, 1);
+        fix_workspace(&config.workspace_path, |_| ConflictChoice::Adopt)?;
+        assert!(screenshots.read_link().is_ok());
+        assert!(shared.join("new.jpg").is_file());
+
+        // A file differing from its shared copy blocks adoption
+        replace_link()?;
+        fs::write(screenshots.join("new.jpg"), b"changed")?;
+        fix_workspace(&config.workspace_path, |_| ConflictChoice::Adopt)?;
+        assert!(screenshots.read_link().is_err());
+
+        // Keeping it local makes the path per workspace, so fix no longer asks
+        fix_workspace(&config.workspace_path, |_| ConflictChoice::KeepLocal)?;
+        let config = load_workspace_config(&config.workspace_path)?;
+        assert_eq!(
+            determine_strategy(
+                "Screenshots",
+                &config.sharing_rules,
+                SharingStrategy::Global
+            ),
+            SharingStrategy::Workspace
+        );
+        fix_workspace(&config.workspace_path, |_| panic!("asked again"))?;
+        assert!(screenshots.join("new.jpg").is_file());
+
+        Ok(())
+    }
 
     #[test]
     fn test_workspace_creation_basic() -> Result<()> {
@@ -712,6 +2839,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_dry_runs_change_nothing() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+
+        let plan = plan_workspace(
+            "ws",
+            &base_dir,
+            &workspace_root,
+            &default_sharing_rules(),
+            &CreateOptions::default(),
+        )?;
+        assert!(!workspace_root.exists());
+
+        // The plan lists what create journals; a planned reflink may become a copy
+        let config = create_workspace("ws", &base_dir, &workspace_root, default_sharing_rules())?;
+        let created = crate::journal::load_journal(&config.workspace_path)?;
+        assert_eq!(plan.entries().len(), created.len());
+        for (planned, done) in plan.entries().iter().zip(&created) {
+            assert_eq!(planned.rel_path, done.rel_path);
+            if planned.action != JournalAction::Reflink {
+                assert_eq!(planned.action, done.action, "{}", planned.rel_path);
+                assert_eq!(planned.target, done.target, "{}", planned.rel_path);
+            }
+        }
+
+        let shared = workspace_root.join(".shared/global/Screenshots");
+        fs::remove_dir_all(&shared)?;
+        fix_workspace_with(&config.workspace_path, |_| ConflictChoice::Adopt, true)?;
+        assert!(!shared.exists());
+        assert_eq!(
+            crate::journal::load_journal(&config.workspace_path)?,
+            created
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_workspace_creation_112() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -791,6 +2958,221 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_concurrent_workspace_creation() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+
+        // Same base and root: shared roots and links race, every run must still succeed
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let base_dir = base_dir.clone();
+                let workspace_root = workspace_root.clone();
+                std::thread::spawn(move || {
+                    create_workspace(
+                        &format!("workspace{i}"),
+                        &base_dir,
+                        &workspace_root,
+                        default_sharing_rules(),
+                    )
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap()?;
+        }
+
+        // Racing runs for one name: exactly one wins
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let base_dir = base_dir.clone();
+                let workspace_root = workspace_root.clone();
+                std::thread::spawn(move || {
+                    create_workspace("same", &base_dir, &workspace_root, default_sharing_rules())
+                })
+            })
+            .collect();
+        let created = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(Result::is_ok)
+            .count();
+        assert_eq!(created, 1);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_workspace_from_foreign_read_only_base() -> Result<()> {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+
+        // A base installed read-only, as a system-wide base would be
+        let patch = base_dir.join("Data/patch-A.MPQ");
+        fs::write(&patch, "custom patch")?;
+        let manifest = scan_and_build_manifest(&base_dir, &Profile::chromie_335a())?;
+        write_manifest(&manifest, &base_dir)?;
+        fs::set_permissions(&patch, fs::Permissions::from_mode(0o444))?;
+        assert_eq!(
+            fs::metadata(base_dir.join("manifest.toml"))?.mode() & 0o777,
+            0o644
+        );
+
+        // Only root can hand the base to another user
+        let foreign = std::os::unix::fs::chown(base_dir.join("Data/common.MPQ"), Some(12345), None);
+
+        let ws = create_workspace("ws", &base_dir, &workspace_root, default_sharing_rules())?;
+        let copied = ws.workspace_path.join("Data/patch-A.MPQ");
+        assert!(!fs::metadata(&copied)?.permissions().readonly());
+        assert!(!same_file(&copied, &patch));
+        // Cloned where the temp directory's filesystem supports reflinks, else copied
+        let journal = crate::journal::load_journal(&ws.workspace_path)?;
+        let action = journal
+            .iter()
+            .find(|entry| entry.rel_path == "Data/patch-A.MPQ")
+            .map(|entry| entry.action);
+        assert!(matches!(
+            action,
+            Some(crate::journal::JournalAction::Reflink | crate::journal::JournalAction::Copy)
+        ));
+
+        let common = ws.workspace_path.join("Data/common.MPQ");
+        if foreign.is_ok() {
+            assert!(common.symlink_metadata()?.file_type().is_symlink());
+        }
+        assert!(fs::metadata(&common)?.is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lazy_mutable_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+
+        let options = CreateOptions {
+            lazy_mutable: true,
+            ..Default::default()
+        };
+        let ws = create_workspace_with(
+            "lazy",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+            &options,
+        )?;
+        let ws_path = &ws.workspace_path;
+        let patch = ws_path.join("Data/patch.MPQ");
+        assert!(
+            load_workspace_config(ws_path)?
+                .lazy_files
+                .contains_key("Data/patch.MPQ")
+        );
+        assert!(same_file(&base_dir.join("Data/patch.MPQ"), &patch));
+
+        // Untouched files stay shared
+        assert!(check_lazy_files(ws_path)?.is_empty());
+        assert!(same_file(&base_dir.join("Data/patch.MPQ"), &patch));
+
+        // A patcher replacing the file leaves the base alone
+        fs::remove_file(&patch)?;
+        fs::write(&patch, "new patch")?;
+        assert!(check_lazy_files(ws_path)?.is_empty());
+        assert!(load_workspace_config(ws_path)?.lazy_files.is_empty());
+        assert_eq!(
+            fs::read(base_dir.join("Data/patch.MPQ"))?,
+            b"mock patch file"
+        );
+
+        // Explicit materialize breaks the link up front
+        let ws2 = create_workspace_with(
+            "lazy2",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+            &options,
+        )?;
+        let copied = materialize_files(&ws2.workspace_path, &[])?;
+        assert_eq!(copied, vec!["Data/patch.MPQ".to_string()]);
+        assert!(!same_file(
+            &base_dir.join("Data/patch.MPQ"),
+            &ws2.workspace_path.join("Data/patch.MPQ")
+        ));
+        assert!(materialize_files(&ws2.workspace_path, &["Data/patch.MPQ".to_string()]).is_err());
+
+        // A change in place went to the base too, the workspace is detached with a warning
+        let ws3 = create_workspace_with(
+            "lazy3",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+            &options,
+        )?;
+        let patch = ws3.workspace_path.join("Data/patch.MPQ");
+        let file = fs::OpenOptions::new().append(true).open(&patch)?;
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))?;
+        drop(file);
+        assert_eq!(check_lazy_files(&ws3.workspace_path)?.len(), 1);
+        assert!(!same_file(&base_dir.join("Data/patch.MPQ"), &patch));
+        Ok(())
+    }
+
+    #[test]
+    fn test_detach_workspace() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+
+        let config = create_workspace(
+            "standalone",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+        )?;
+        let ws_path = &config.workspace_path;
+        fs::write(ws_path.join("Screenshots/shot.jpg"), "shot")?;
+        let plan = plan_detach(ws_path)?;
+        assert!(plan.hard_links.contains(&"Wow.exe".to_string()));
+        assert!(plan.symlinks.contains(&"Screenshots".to_string()));
+
+        let mut copied = 0;
+        detach_workspace(ws_path, &plan, |bytes| copied += bytes)?;
+        assert_eq!(copied, plan.bytes);
+        assert!(!same_file(
+            &base_dir.join("Wow.exe"),
+            &ws_path.join("Wow.exe")
+        ));
+        let screenshots = ws_path.join("Screenshots");
+        assert!(!fs::symlink_metadata(&screenshots)?.file_type().is_symlink());
+        assert_eq!(fs::read(screenshots.join("shot.jpg"))?, b"shot");
+        // Shared data stays where it was for the other workspaces
+        assert!(
+            workspace_root
+                .join(".shared/global/Screenshots/shot.jpg")
+                .is_file()
+        );
+
+        assert!(plan_detach(ws_path)?.is_empty());
+        assert!(load_workspace_config(ws_path)?.standalone);
+        fs::remove_dir_all(&base_dir)?;
+        let report = check_health(ws_path, None)?;
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+        assert!(crate::journal::audit_workspace(ws_path)?.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_duplicate_workspace_fails() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -1237,10 +3619,7 @@ mod tests {
         assert!(global_dir.exists(), "Global shared directory doesn't exist");
 
         let per_base_dir = shared_dir.join(&profile.name);
-        assert!(
-            per_base_dir.exists(),
-            "Base shared directory doesn't exist"
-        );
+        assert!(per_base_dir.exists(), "Base shared directory doesn't exist");
 
         Ok(())
     }
@@ -1270,6 +3649,276 @@ mod tests {
         assert_eq!(loaded_config.base_name, created_config.base_name);
         assert_eq!(loaded_config.base_path, created_config.base_path);
         assert_eq!(loaded_config.sharing_rules, created_config.sharing_rules);
+        assert_eq!(loaded_config.schema_version, WORKSPACE_SCHEMA_VERSION);
+        assert_eq!(
+            loaded_config.tool_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(loaded_config.link_strategy, Some(LinkStrategy::Hardlink));
+        assert!(loaded_config.check_compatibility()?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_health() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+
+        let config = create_workspace(
+            "healthy",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+        )?;
+        let report = check_health(&config.workspace_path, None)?;
+        assert!(report.complete);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+
+        // Replace the shared Screenshots link with a real directory
+        let screenshots = config.workspace_path.join("Screenshots");
+        fs::remove_file(&screenshots)?;
+        fs::create_dir(&screenshots)?;
+        fs::remove_file(config.workspace_path.join("Wow.exe"))?;
+        let report = check_health(&config.workspace_path, None)?;
+        assert_eq!(report.warnings.len(), 2, "{:?}", report.warnings);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_journal_audit() -> Result<()> {
+        use crate::journal::{JournalAction, audit_workspace, load_journal};
+
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+
+        let config = create_workspace(
+            "audited",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+        )?;
+        let journal = load_journal(&config.workspace_path)?;
+        let action = |rel_path: &str| {
+            journal
+                .iter()
+                .find(|entry| entry.rel_path == rel_path)
+                .map(|entry| entry.action)
+        };
+        assert_eq!(action("Wow.exe"), Some(JournalAction::Hardlink));
+        assert_eq!(action("Screenshots"), Some(JournalAction::Symlink));
+        assert_eq!(action("WTF"), Some(JournalAction::Mkdir));
+        assert!(journal.iter().all(|entry| entry.operation == "create"));
+        assert!(audit_workspace(&config.workspace_path)?.is_empty());
+
+        // Replace a hard link by a copy and drop a shared link; fix only restores the link
+        fs::remove_file(config.workspace_path.join("Wow.exe"))?;
+        fs::write(config.workspace_path.join("Wow.exe"), b"mock executable")?;
+        fs::remove_file(config.workspace_path.join("Screenshots"))?;
+        let findings = audit_workspace(&config.workspace_path)?;
+        let paths: Vec<&str> = findings.iter().map(|f| f.rel_path.as_str()).collect();
+        assert_eq!(paths, ["Screenshots", "Wow.exe"]);
+
+        fix_workspace(&config.workspace_path, |_| ConflictChoice::Skip)?;
+        let journal = load_journal(&config.workspace_path)?;
+        assert_eq!(
+            journal.last().map(|entry| entry.operation.as_str()),
+            Some("fix")
+        );
+        let findings = audit_workspace(&config.workspace_path)?;
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rel_path, "Wow.exe");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_parallel_keeps_job_order() {
+        let jobs: Vec<u32> = (0..500).collect();
+        let doubled = run_parallel(&jobs, |n| Ok(n * 2)).unwrap();
+        assert_eq!(doubled, jobs.iter().map(|n| n * 2).collect::<Vec<_>>());
+
+        let failed = run_parallel(&jobs, |&n| match n {
+            7 | 300 => anyhow::bail!("job {n} failed"),
+            n => Ok(n),
+        });
+        assert_eq!(failed.unwrap_err().to_string(), "job 7 failed");
+    }
+
+    #[test]
+    fn test_link_fallbacks_per_role() -> Result<()> {
+        use crate::journal::{JournalAction, load_journal};
+
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+
+        assert!(parse_fallback("basedata=hardlink,move").is_err());
+        assert!(parse_fallback("Ephemeral=copy").is_err());
+        let options = CreateOptions {
+            link_fallbacks: ["executable=symlink,copy", "BaseData=copy"]
+                .into_iter()
+                .map(|arg| parse_fallback(arg).unwrap())
+                .collect(),
+            ..Default::default()
+        };
+        let config = create_workspace_with(
+            "fallbacks",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+            &options,
+        )?;
+        let journal = load_journal(&config.workspace_path)?;
+        let action = |rel_path: &str| {
+            journal
+                .iter()
+                .find(|entry| entry.rel_path == rel_path)
+                .map(|entry| entry.action)
+        };
+        assert_eq!(action("Wow.exe"), Some(JournalAction::Symlink));
+        assert_eq!(action("Data/common.MPQ"), Some(JournalAction::Copy));
+        assert!(!same_file(
+            &config.workspace_path.join("Data/common.MPQ"),
+            &base_dir.join("Data/common.MPQ")
+        ));
+
+        let loaded = load_workspace_config(&config.workspace_path)?;
+        assert_eq!(
+            loaded.link_fallbacks.get("Executable"),
+            Some(&vec![LinkMethod::Symlink, LinkMethod::Copy])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_workspace_after_base_update() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        let profile = Profile::chromie_335a();
+        create_mock_base(&base_dir, &profile)?;
+        let config = create_workspace(
+            "upgrade",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+        )?;
+        let ws = &config.workspace_path;
+        assert!(config.base_change(&load_manifest(&base_dir)?).is_none());
+        // Executables have no manifest checksum, creating the workspace does not hash them
+        let snapshot = load_snapshot(ws)?.unwrap();
+        assert!(snapshot.stamps.contains_key("Wow.exe"));
+        assert!(!snapshot.checksums.contains_key("Wow.exe"));
+
+        // A patcher replaces two files and adds one; the workspace patched one on its own
+        fs::remove_file(ws.join("Data/lichking.MPQ"))?;
+        fs::write(ws.join("Data/lichking.MPQ"), b"workspace patch")?;
+        for (rel_path, content) in [
+            ("Data/patch.MPQ", "new patch"),
+            ("Data/lichking.MPQ", "new expansion"),
+            ("Data/patch-4.MPQ", "custom patch"),
+        ] {
+            let _ = fs::remove_file(base_dir.join(rel_path));
+            fs::write(base_dir.join(rel_path), content)?;
+        }
+        fs::remove_file(base_dir.join("Data/common.MPQ"))?;
+        let rescanned = scan_and_build_manifest(&base_dir, &profile)?;
+        write_manifest(&rescanned, &base_dir)?;
+        assert!(config.base_change(&rescanned).is_some());
+
+        let planned = upgrade_workspace(ws, true, false)?;
+        assert_eq!(planned.added, vec!["Data/patch-4.MPQ"]);
+        assert_eq!(planned.updated, vec!["Data/patch.MPQ"]);
+        assert_eq!(planned.diverged, vec!["Data/lichking.MPQ"]);
+        assert_eq!(planned.removed, vec!["Data/common.MPQ"]);
+        assert!(!ws.join("Data/patch-4.MPQ").exists());
+
+        assert_eq!(upgrade_workspace(ws, false, false)?, planned);
+        assert_eq!(
+            load_workspace_config(ws)?.base_state,
+            Some(BaseState::of(&rescanned))
+        );
+        // Patch MPQs are MutableData, the workspace gets its own copies
+        assert_eq!(fs::read(ws.join("Data/patch.MPQ"))?, b"new patch");
+        assert_eq!(fs::read(ws.join("Data/patch-4.MPQ"))?, b"custom patch");
+        assert_eq!(fs::read(ws.join("Data/lichking.MPQ"))?, b"workspace patch");
+        assert!(ws.join("Data/common.MPQ").exists());
+
+        // Diverged files are reported until forced
+        let again = upgrade_workspace(ws, false, false)?;
+        assert_eq!(again.diverged, vec!["Data/lichking.MPQ"]);
+        assert_eq!(
+            upgrade_workspace(ws, false, true)?.updated,
+            vec!["Data/lichking.MPQ"]
+        );
+        assert!(same_file(
+            &base_dir.join("Data/lichking.MPQ"),
+            &ws.join("Data/lichking.MPQ")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_compatibility() -> Result<()> {
+        // workspace.toml as written before versions were recorded
+        let legacy: WorkspaceConfig = toml::from_str(
+            r#"
+name = "old"
+base_name = "chromie-3.3.5a"
+base_path = "/base"
+workspace_path = "/workspaces/old"
+created_at = "0"
+
+[sharing_rules]
+wtf = "workspace"
+"#,
+        )?;
+        assert_eq!(legacy.schema_version, 0);
+        assert!(legacy.check_compatibility()?.is_some());
+
+        let newer = WorkspaceConfig {
+            schema_version: WORKSPACE_SCHEMA_VERSION + 1,
+            ..legacy
+        };
+        assert!(newer.check_compatibility().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_workspaces() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+
+        // Create mock base
+        fs::create_dir(&base_dir)?;
+        let profile = Profile::chromie_335a();
+        create_mock_base(&base_dir, &profile)?;
+
+        // Missing root is not an error
+        assert!(list_workspaces(&workspace_root)?.is_empty());
+
+        create_workspace("beta", &base_dir, &workspace_root, default_sharing_rules())?;
+        create_workspace("alpha", &base_dir, &workspace_root, default_sharing_rules())?;
+
+        // .shared is not a workspace and must be skipped
+        let names: Vec<_> = list_workspaces(&workspace_root)?
+            .into_iter()
+            .map(|ws| ws.name)
+            .collect();
+        assert_eq!(names, vec!["alpha", "beta"]);
 
         Ok(())
     }
@@ -1356,53 +4005,14 @@ mod tests {
         assert_eq!(rules.get("wtf"), Some(&SharingStrategy::Workspace));
     }
 
-    /// Helper function to calculate actual disk usage using du command
+    /// Helper function to calculate actual disk usage, hard links counted once
     fn get_disk_usage(path: &Path) -> Result<u64> {
-        let output = std::process::Command::new("du")
-            .arg("-sb") // -s for summary, -b for bytes
-            .arg(path)
-            .output()
-            .context("Failed to run du command")?;
-
-        if !output.status.success() {
-            anyhow::bail!("du command failed");
-        }
-
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let size_str = output_str
-            .split_whitespace()
-            .next()
-            .context("Failed to parse du output")?;
-        let size = size_str
-            .parse::<u64>()
-            .context("Failed to parse size as u64")?;
-
-        Ok(size)
+        Ok(crate::status::directory_size(path).on_disk)
     }
 
     /// Helper function to calculate directory size by summing file sizes (not accounting for hard links)
     fn get_apparent_size(path: &Path) -> Result<u64> {
-        let output = std::process::Command::new("du")
-            .arg("-sb")
-            .arg("--apparent-size") // Show apparent size (logical size) not disk usage
-            .arg(path)
-            .output()
-            .context("Failed to run du command")?;
-
-        if !output.status.success() {
-            anyhow::bail!("du command failed");
-        }
-
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let size_str = output_str
-            .split_whitespace()
-            .next()
-            .context("Failed to parse du output")?;
-        let size = size_str
-            .parse::<u64>()
-            .context("Failed to parse size as u64")?;
-
-        Ok(size)
+        Ok(crate::status::directory_size(path).apparent)
     }
 
     #[test]
@@ -1673,4 +4283,96 @@ mod tests {
 
         Ok(())
     }
+    #[test]
+    fn test_delete_workspace_keeps_shared_data() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base_dir = temp_dir.path().join("base");
+        let workspace_root = temp_dir.path().join("workspaces");
+        fs::create_dir(&base_dir)?;
+        create_mock_base(&base_dir, &Profile::chromie_335a())?;
+        let ws = create_workspace(
+            "doomed",
+            &base_dir,
+            &workspace_root,
+            default_sharing_rules(),
+        )?;
+        let ws_path = &ws.workspace_path;
+
+        fs::create_dir_all(ws_path.join("WTF/Account/PLAYER"))?;
+        fs::write(
+            ws_path.join("WTF/Account/PLAYER/bindings-cache.wtf"),
+            "bindings",
+        )?;
+        fs::create_dir_all(ws_path.join("Interface/AddOns/Local"))?;
+        fs::write(
+            ws_path.join("Interface/AddOns/Local/Local.toc"),
+            "## Title: Local",
+        )?;
+        fs::create_dir_all(ws_path.join("Cache"))?;
+        fs::write(ws_path.join("Cache/creaturecache.wdb"), "cache")?;
+
+        let report = plan_deletion(ws_path)?;
+        let copied = report.copied;
+        assert!(copied > 0);
+
+        // A custom patch of the same size as the base's is the workspace's own
+        fs::remove_file(ws_path.join("Data/patch.MPQ"))?;
+        fs::write(ws_path.join("Data/patch.MPQ"), b"mock PATCH file")?;
+        let report = plan_deletion(ws_path)?;
+        assert_eq!(report.copied, copied - 1);
+        let unique: Vec<&str> = report.unique.iter().map(|(p, _)| p.as_str()).collect();
+        assert!(unique.contains(&"Data/patch.MPQ"));
+        assert!(unique.contains(&"WTF/Account/PLAYER/bindings-cache.wtf"));
+        // AddOns is shared per base by default, the addon lives behind the link
+        assert!(!unique.iter().any(|p| {
+            p.starts_with("Interface/") || p.starts_with("Cache/") || p.ends_with("common.MPQ")
+        }));
+        assert!(report.linked > 0);
+        assert!(report.disposable > 0);
+
+        let archive = temp_dir.path().join("doomed.tar.gz");
+        let paths: Vec<String> = report.unique.iter().map(|(p, _)| p.clone()).collect();
+        archive_files(ws_path, &paths, &archive)?;
+        assert!(fs::metadata(&archive)?.len() > 0);
+
+        delete_workspace(ws_path)?;
+        assert!(!ws_path.exists());
+        assert_eq!(
+            fs::read(base_dir.join("Data/common.MPQ"))?,
+            b"mock data file"
+        );
+        assert!(
+            workspace_root
+                .join(".shared/chromie-3.3.5a/Interface/AddOns/Local/Local.toc")
+                .is_file()
+        );
+        assert!(delete_workspace(&base_dir).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_files_odd_names() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let names = ["-v", "new\nline.txt"];
+        for name in names {
+            fs::write(temp_dir.path().join(name), name)?;
+        }
+        let archive = temp_dir.path().join("out/odd.tar.gz");
+        let paths: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        archive_files(temp_dir.path(), &paths, &archive)?;
+
+        let restored = temp_dir.path().join("restored");
+        fs::create_dir(&restored)?;
+        let status = std::process::Command::new("tar")
+            .arg("-xzf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&restored)
+            .status()?;
+        assert!(status.success());
+        for name in names {
+            assert_eq!(fs::read_to_string(restored.join(name))?, name);
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,132 @@
+use std::path::{Path, PathBuf};
+
+/// WTF/Config.wtf of a client, next to the executable (the product directory of CASC clients)
+pub fn config_wtf_path(game_dir: &Path, executable: &str) -> PathBuf {
+    let client_dir = game_dir
+        .join(executable)
+        .parent()
+        .map_or_else(|| game_dir.to_path_buf(), Path::to_path_buf);
+    client_dir.join("WTF").join("Config.wtf")
+}
+
+/// Set `SET <key> "<value>"` lines in Config.wtf content, replacing existing ones
+/// (case-insensitively) in place and appending the others
+pub fn set_values(content: &str, values: &[(&str, String)]) -> String {
+    let mut pending: Vec<&(&str, String)> = values.iter().collect();
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let is_set = parts
+                .next()
+                .is_some_and(|set| set.eq_ignore_ascii_case("set"));
+            let key = parts.next().unwrap_or("");
+            match pending
+                .iter()
+                .position(|(k, _)| is_set && k.eq_ignore_ascii_case(key))
+            {
+                Some(i) => {
+                    let (key, value) = pending.remove(i);
+                    format!("SET {key} \"{value}\"")
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    lines.extend(
+        pending
+            .into_iter()
+            .map(|(key, value)| format!("SET {key} \"{value}\"")),
+    );
+    let mut updated = lines.join("\r\n");
+    updated.push_str("\r\n");
+    updated
+}
+
+/// Remove the `SET <key>` lines of `keys` (matched case-insensitively) from Config.wtf content
+pub fn remove_values(content: &str, keys: &[&str]) -> String {
+    let mut updated: String = content
+        .lines()
+        .filter(|line| {
+            let mut parts = line.split_whitespace();
+            let is_set = parts
+                .next()
+                .is_some_and(|set| set.eq_ignore_ascii_case("set"));
+            let key = parts.next().unwrap_or("");
+            !(is_set && keys.iter().any(|k| k.eq_ignore_ascii_case(key)))
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    if !updated.is_empty() {
+        updated.push_str("\r\n");
+    }
+    updated
+}
+
+/// Value of a `SET <key> "<value>"` line in Config.wtf content, matching the key
+/// case-insensitively
+pub fn get_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (set, rest) = line.trim().split_once(char::is_whitespace)?;
+        let (name, value) = rest.trim_start().split_once(char::is_whitespace)?;
+        (set.eq_ignore_ascii_case("set") && name.eq_ignore_ascii_case(key))
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Prefill the login screen: account name, and the realm and character to select if given
+pub fn apply_auto_login(
+    config_wtf: &Path,
+    account: &str,
+    realm_name: Option<&str>,
+    character_index: Option<u32>,
+) -> std::io::Result<()> {
+    let mut values = vec![("accountName", account.to_string())];
+    if let Some(realm_name) = realm_name {
+        values.push(("realmName", realm_name.to_string()));
+    }
+    if let Some(index) = character_index {
+        values.push(("lastCharacterIndex", index.to_string()));
+    }
+    apply_values(config_wtf, &values)
+}
+
+/// Set values in a Config.wtf file, creating it if the client has not written one yet
+pub fn apply_values(config_wtf: &Path, values: &[(&str, String)]) -> std::io::Result<()> {
+    let content = match std::fs::read_to_string(config_wtf) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    if let Some(dir) = config_wtf.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(config_wtf, set_values(&content, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_values_keeps_other_settings() {
+        let content = "SET gxResolution \"1920x1080\"\r\nset ACCOUNTNAME \"old\"\r\nSET realmName \"Old\"\r\n";
+        let updated = set_values(
+            content,
+            &[
+                ("accountName", "me".to_string()),
+                ("lastCharacterIndex", "2".to_string()),
+            ],
+        );
+        assert_eq!(
+            updated,
+            "SET gxResolution \"1920x1080\"\r\nSET accountName \"me\"\r\nSET realmName \"Old\"\r\nSET lastCharacterIndex \"2\"\r\n"
+        );
+        assert_eq!(get_value(&updated, "ACCOUNTNAME").as_deref(), Some("me"));
+        assert_eq!(get_value(&updated, "portal"), None);
+        assert_eq!(
+            config_wtf_path(Path::new("/games/wow"), "_retail_/Wow.exe"),
+            Path::new("/games/wow/_retail_/WTF/Config.wtf")
+        );
+    }
+}